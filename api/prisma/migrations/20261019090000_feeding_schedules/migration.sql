@@ -0,0 +1,122 @@
+-- AlterTable
+ALTER TABLE "Plant" ADD COLUMN     "batchId" TEXT,
+ADD COLUMN     "feedScheduleId" TEXT;
+
+-- AlterTable
+ALTER TABLE "PlantHistory" ADD COLUMN     "stage" "PlantStage";
+
+-- CreateTable
+CREATE TABLE "Batch" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "feedScheduleId" TEXT,
+
+    CONSTRAINT "Batch_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "NutrientProduct" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "manufacturer" TEXT,
+    "unit" TEXT NOT NULL DEFAULT 'ml',
+
+    CONSTRAINT "NutrientProduct_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "FeedRecipe" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+
+    CONSTRAINT "FeedRecipe_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "FeedRecipeItem" (
+    "recipeId" TEXT NOT NULL,
+    "productId" TEXT NOT NULL,
+    "dosePerLitre" DOUBLE PRECISION NOT NULL,
+
+    CONSTRAINT "FeedRecipeItem_pkey" PRIMARY KEY ("recipeId","productId")
+);
+
+-- CreateTable
+CREATE TABLE "FeedSchedule" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+
+    CONSTRAINT "FeedSchedule_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "FeedScheduleEntry" (
+    "id" TEXT NOT NULL,
+    "scheduleId" TEXT NOT NULL,
+    "stage" "PlantStage" NOT NULL,
+    "week" INTEGER NOT NULL,
+    "recipeId" TEXT NOT NULL,
+
+    CONSTRAINT "FeedScheduleEntry_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "FeedingLog" (
+    "id" TEXT NOT NULL,
+    "plantId" TEXT NOT NULL,
+    "recipeId" TEXT,
+    "volume" DOUBLE PRECISION NOT NULL,
+    "ec" DOUBLE PRECISION,
+    "ph" DOUBLE PRECISION,
+    "note" TEXT,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "userId" TEXT NOT NULL,
+
+    CONSTRAINT "FeedingLog_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Batch_name_key" ON "Batch"("name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "NutrientProduct_name_key" ON "NutrientProduct"("name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "FeedRecipe_name_key" ON "FeedRecipe"("name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "FeedSchedule_name_key" ON "FeedSchedule"("name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "FeedScheduleEntry_scheduleId_stage_week_key" ON "FeedScheduleEntry"("scheduleId", "stage", "week");
+
+-- AddForeignKey
+ALTER TABLE "Plant" ADD CONSTRAINT "Plant_batchId_fkey" FOREIGN KEY ("batchId") REFERENCES "Batch"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Plant" ADD CONSTRAINT "Plant_feedScheduleId_fkey" FOREIGN KEY ("feedScheduleId") REFERENCES "FeedSchedule"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Batch" ADD CONSTRAINT "Batch_feedScheduleId_fkey" FOREIGN KEY ("feedScheduleId") REFERENCES "FeedSchedule"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "FeedRecipeItem" ADD CONSTRAINT "FeedRecipeItem_recipeId_fkey" FOREIGN KEY ("recipeId") REFERENCES "FeedRecipe"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "FeedRecipeItem" ADD CONSTRAINT "FeedRecipeItem_productId_fkey" FOREIGN KEY ("productId") REFERENCES "NutrientProduct"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "FeedScheduleEntry" ADD CONSTRAINT "FeedScheduleEntry_scheduleId_fkey" FOREIGN KEY ("scheduleId") REFERENCES "FeedSchedule"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "FeedScheduleEntry" ADD CONSTRAINT "FeedScheduleEntry_recipeId_fkey" FOREIGN KEY ("recipeId") REFERENCES "FeedRecipe"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "FeedingLog" ADD CONSTRAINT "FeedingLog_plantId_fkey" FOREIGN KEY ("plantId") REFERENCES "Plant"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "FeedingLog" ADD CONSTRAINT "FeedingLog_recipeId_fkey" FOREIGN KEY ("recipeId") REFERENCES "FeedRecipe"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "FeedingLog" ADD CONSTRAINT "FeedingLog_userId_fkey" FOREIGN KEY ("userId") REFERENCES "User"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
@@ -0,0 +1,172 @@
+-- CreateEnum
+CREATE TYPE "TaskStatus" AS ENUM ('OPEN', 'IN_PROGRESS', 'DONE', 'CANCELLED');
+
+-- AlterTable
+ALTER TABLE "Plant" ADD COLUMN     "locationId" TEXT;
+
+-- CreateTable
+CREATE TABLE "Location" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "description" TEXT,
+
+    CONSTRAINT "Location_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "Task" (
+    "id" TEXT NOT NULL,
+    "title" TEXT NOT NULL,
+    "description" TEXT,
+    "dueDate" TIMESTAMP(3) NOT NULL,
+    "status" "TaskStatus" NOT NULL DEFAULT 'OPEN',
+    "assigneeId" TEXT,
+    "createdById" TEXT,
+    "templateId" TEXT,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "completedAt" TIMESTAMP(3),
+
+    CONSTRAINT "Task_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "TaskTemplate" (
+    "id" TEXT NOT NULL,
+    "title" TEXT NOT NULL,
+    "description" TEXT,
+    "assigneeId" TEXT,
+    "intervalDays" INTEGER,
+    "nextDueDate" TIMESTAMP(3),
+    "triggerStage" "PlantStage",
+    "offsetDays" INTEGER NOT NULL DEFAULT 0,
+    "active" BOOLEAN NOT NULL DEFAULT true,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "TaskTemplate_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "_PlantToTask" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL
+);
+
+-- CreateTable
+CREATE TABLE "_PlantToTaskTemplate" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL
+);
+
+-- CreateTable
+CREATE TABLE "_BatchToTask" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL
+);
+
+-- CreateTable
+CREATE TABLE "_BatchToTaskTemplate" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL
+);
+
+-- CreateTable
+CREATE TABLE "_LocationToTask" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL
+);
+
+-- CreateTable
+CREATE TABLE "_LocationToTaskTemplate" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Location_name_key" ON "Location"("name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_PlantToTask_AB_unique" ON "_PlantToTask"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_PlantToTask_B_index" ON "_PlantToTask"("B");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_PlantToTaskTemplate_AB_unique" ON "_PlantToTaskTemplate"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_PlantToTaskTemplate_B_index" ON "_PlantToTaskTemplate"("B");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_BatchToTask_AB_unique" ON "_BatchToTask"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_BatchToTask_B_index" ON "_BatchToTask"("B");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_BatchToTaskTemplate_AB_unique" ON "_BatchToTaskTemplate"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_BatchToTaskTemplate_B_index" ON "_BatchToTaskTemplate"("B");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_LocationToTask_AB_unique" ON "_LocationToTask"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_LocationToTask_B_index" ON "_LocationToTask"("B");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_LocationToTaskTemplate_AB_unique" ON "_LocationToTaskTemplate"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_LocationToTaskTemplate_B_index" ON "_LocationToTaskTemplate"("B");
+
+-- AddForeignKey
+ALTER TABLE "Plant" ADD CONSTRAINT "Plant_locationId_fkey" FOREIGN KEY ("locationId") REFERENCES "Location"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Task" ADD CONSTRAINT "Task_assigneeId_fkey" FOREIGN KEY ("assigneeId") REFERENCES "User"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Task" ADD CONSTRAINT "Task_createdById_fkey" FOREIGN KEY ("createdById") REFERENCES "User"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Task" ADD CONSTRAINT "Task_templateId_fkey" FOREIGN KEY ("templateId") REFERENCES "TaskTemplate"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "TaskTemplate" ADD CONSTRAINT "TaskTemplate_assigneeId_fkey" FOREIGN KEY ("assigneeId") REFERENCES "User"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_PlantToTask" ADD CONSTRAINT "_PlantToTask_A_fkey" FOREIGN KEY ("A") REFERENCES "Plant"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_PlantToTask" ADD CONSTRAINT "_PlantToTask_B_fkey" FOREIGN KEY ("B") REFERENCES "Task"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_PlantToTaskTemplate" ADD CONSTRAINT "_PlantToTaskTemplate_A_fkey" FOREIGN KEY ("A") REFERENCES "Plant"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_PlantToTaskTemplate" ADD CONSTRAINT "_PlantToTaskTemplate_B_fkey" FOREIGN KEY ("B") REFERENCES "TaskTemplate"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_BatchToTask" ADD CONSTRAINT "_BatchToTask_A_fkey" FOREIGN KEY ("A") REFERENCES "Batch"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_BatchToTask" ADD CONSTRAINT "_BatchToTask_B_fkey" FOREIGN KEY ("B") REFERENCES "Task"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_BatchToTaskTemplate" ADD CONSTRAINT "_BatchToTaskTemplate_A_fkey" FOREIGN KEY ("A") REFERENCES "Batch"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_BatchToTaskTemplate" ADD CONSTRAINT "_BatchToTaskTemplate_B_fkey" FOREIGN KEY ("B") REFERENCES "TaskTemplate"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_LocationToTask" ADD CONSTRAINT "_LocationToTask_A_fkey" FOREIGN KEY ("A") REFERENCES "Location"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_LocationToTask" ADD CONSTRAINT "_LocationToTask_B_fkey" FOREIGN KEY ("B") REFERENCES "Task"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_LocationToTaskTemplate" ADD CONSTRAINT "_LocationToTaskTemplate_A_fkey" FOREIGN KEY ("A") REFERENCES "Location"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_LocationToTaskTemplate" ADD CONSTRAINT "_LocationToTaskTemplate_B_fkey" FOREIGN KEY ("B") REFERENCES "TaskTemplate"("id") ON DELETE CASCADE ON UPDATE CASCADE;
@@ -0,0 +1,5 @@
+-- AlterTable
+ALTER TABLE "User" ADD COLUMN     "calendarToken" TEXT;
+
+-- CreateIndex
+CREATE UNIQUE INDEX "User_calendarToken_key" ON "User"("calendarToken");
@@ -0,0 +1,24 @@
+-- AlterTable
+ALTER TABLE "Genetic" ADD COLUMN     "code" TEXT,
+ADD COLUMN     "namingTemplate" TEXT;
+
+-- CreateTable
+CREATE TABLE "Setting" (
+    "key" TEXT NOT NULL,
+    "value" TEXT NOT NULL,
+    "updatedAt" TIMESTAMP(3) NOT NULL,
+
+    CONSTRAINT "Setting_pkey" PRIMARY KEY ("key")
+);
+
+-- CreateTable
+CREATE TABLE "NameSequence" (
+    "key" TEXT NOT NULL,
+    "value" INTEGER NOT NULL DEFAULT 0,
+
+    CONSTRAINT "NameSequence_pkey" PRIMARY KEY ("key")
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Genetic_code_key" ON "Genetic"("code");
+
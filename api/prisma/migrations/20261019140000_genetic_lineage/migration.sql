@@ -0,0 +1,12 @@
+-- AlterTable
+ALTER TABLE "Genetic" ADD COLUMN     "breeder" TEXT,
+ADD COLUMN     "femaleParentId" TEXT,
+ADD COLUMN     "generation" TEXT,
+ADD COLUMN     "maleParentId" TEXT,
+ADD COLUMN     "source" TEXT;
+
+-- AddForeignKey
+ALTER TABLE "Genetic" ADD CONSTRAINT "Genetic_femaleParentId_fkey" FOREIGN KEY ("femaleParentId") REFERENCES "Genetic"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Genetic" ADD CONSTRAINT "Genetic_maleParentId_fkey" FOREIGN KEY ("maleParentId") REFERENCES "Genetic"("id") ON DELETE SET NULL ON UPDATE CASCADE;
@@ -0,0 +1,14 @@
+-- CreateEnum
+CREATE TYPE "GeneticType" AS ENUM ('INDICA', 'SATIVA', 'HYBRID');
+
+-- AlterTable
+ALTER TABLE "Genetic" ADD COLUMN     "autoflower" BOOLEAN NOT NULL DEFAULT false,
+ADD COLUMN     "cbdMax" DOUBLE PRECISION,
+ADD COLUMN     "cbdMin" DOUBLE PRECISION,
+ADD COLUMN     "expectedYield" DOUBLE PRECISION,
+ADD COLUMN     "geneticType" "GeneticType",
+ADD COLUMN     "stretchFactor" DOUBLE PRECISION,
+ADD COLUMN     "tags" TEXT[],
+ADD COLUMN     "terpenes" TEXT[],
+ADD COLUMN     "thcMax" DOUBLE PRECISION,
+ADD COLUMN     "thcMin" DOUBLE PRECISION;
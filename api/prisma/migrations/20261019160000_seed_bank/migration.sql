@@ -0,0 +1,53 @@
+-- CreateEnum
+CREATE TYPE "SeedType" AS ENUM ('FEMINIZED', 'REGULAR');
+
+-- AlterTable
+ALTER TABLE "Plant" ADD COLUMN     "germinationId" TEXT,
+ADD COLUMN     "seedPackId" TEXT;
+
+-- CreateTable
+CREATE TABLE "SeedPack" (
+    "id" TEXT NOT NULL,
+    "geneticId" TEXT NOT NULL,
+    "label" TEXT,
+    "source" TEXT,
+    "purchasedAt" TIMESTAMP(3),
+    "seedType" "SeedType" NOT NULL DEFAULT 'FEMINIZED',
+    "count" INTEGER NOT NULL,
+    "remaining" INTEGER NOT NULL,
+    "locationId" TEXT,
+    "note" TEXT,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "SeedPack_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "SeedGermination" (
+    "id" TEXT NOT NULL,
+    "seedPackId" TEXT NOT NULL,
+    "sown" INTEGER NOT NULL,
+    "germinated" INTEGER NOT NULL,
+    "userId" TEXT NOT NULL,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "SeedGermination_pkey" PRIMARY KEY ("id")
+);
+
+-- AddForeignKey
+ALTER TABLE "Plant" ADD CONSTRAINT "Plant_seedPackId_fkey" FOREIGN KEY ("seedPackId") REFERENCES "SeedPack"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Plant" ADD CONSTRAINT "Plant_germinationId_fkey" FOREIGN KEY ("germinationId") REFERENCES "SeedGermination"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "SeedPack" ADD CONSTRAINT "SeedPack_geneticId_fkey" FOREIGN KEY ("geneticId") REFERENCES "Genetic"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "SeedPack" ADD CONSTRAINT "SeedPack_locationId_fkey" FOREIGN KEY ("locationId") REFERENCES "Location"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "SeedGermination" ADD CONSTRAINT "SeedGermination_seedPackId_fkey" FOREIGN KEY ("seedPackId") REFERENCES "SeedPack"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "SeedGermination" ADD CONSTRAINT "SeedGermination_userId_fkey" FOREIGN KEY ("userId") REFERENCES "User"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
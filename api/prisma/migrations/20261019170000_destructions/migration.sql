@@ -0,0 +1,50 @@
+-- CreateEnum
+CREATE TYPE "DestructionReason" AS ENUM ('PEST', 'MOLD', 'MALE', 'COMPLIANCE', 'OTHER');
+
+-- AlterTable
+ALTER TABLE "Attachment" ADD COLUMN     "destructionId" TEXT;
+
+-- CreateTable
+CREATE TABLE "Destruction" (
+    "id" TEXT NOT NULL,
+    "plantId" TEXT NOT NULL,
+    "reason" "DestructionReason" NOT NULL,
+    "method" TEXT NOT NULL,
+    "weight" DOUBLE PRECISION NOT NULL,
+    "note" TEXT,
+    "destroyedAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "recordedById" TEXT NOT NULL,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "Destruction_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "_witnessedDestructions" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Destruction_plantId_key" ON "Destruction"("plantId");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_witnessedDestructions_AB_unique" ON "_witnessedDestructions"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_witnessedDestructions_B_index" ON "_witnessedDestructions"("B");
+
+-- AddForeignKey
+ALTER TABLE "Attachment" ADD CONSTRAINT "Attachment_destructionId_fkey" FOREIGN KEY ("destructionId") REFERENCES "Destruction"("id") ON DELETE SET NULL ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Destruction" ADD CONSTRAINT "Destruction_plantId_fkey" FOREIGN KEY ("plantId") REFERENCES "Plant"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Destruction" ADD CONSTRAINT "Destruction_recordedById_fkey" FOREIGN KEY ("recordedById") REFERENCES "User"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_witnessedDestructions" ADD CONSTRAINT "_witnessedDestructions_A_fkey" FOREIGN KEY ("A") REFERENCES "Destruction"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_witnessedDestructions" ADD CONSTRAINT "_witnessedDestructions_B_fkey" FOREIGN KEY ("B") REFERENCES "User"("id") ON DELETE CASCADE ON UPDATE CASCADE;
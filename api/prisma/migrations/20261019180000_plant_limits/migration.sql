@@ -0,0 +1,20 @@
+-- CreateTable
+CREATE TABLE "PlantLimit" (
+    "id" TEXT NOT NULL,
+    "locationId" TEXT,
+    "stage" "PlantStage",
+    "maxPlants" INTEGER NOT NULL,
+    "note" TEXT,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "PlantLimit_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "PlantLimit_locationId_stage_key" ON "PlantLimit"("locationId", "stage");
+
+-- Postgres treats NULLs as distinct, so also keep facility wide and total limits unique
+CREATE UNIQUE INDEX "PlantLimit_scope_key" ON "PlantLimit"(COALESCE("locationId", ''), COALESCE("stage"::TEXT, ''));
+
+-- AddForeignKey
+ALTER TABLE "PlantLimit" ADD CONSTRAINT "PlantLimit_locationId_fkey" FOREIGN KEY ("locationId") REFERENCES "Location"("id") ON DELETE CASCADE ON UPDATE CASCADE;
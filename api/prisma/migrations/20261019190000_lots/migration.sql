@@ -0,0 +1,52 @@
+-- CreateTable
+CREATE TABLE "Lot" (
+    "id" TEXT NOT NULL,
+    "code" TEXT NOT NULL,
+    "weight" DOUBLE PRECISION NOT NULL,
+    "remaining" DOUBLE PRECISION NOT NULL,
+    "note" TEXT,
+    "packagedAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "packagedById" TEXT NOT NULL,
+
+    CONSTRAINT "Lot_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "LotHarvest" (
+    "lotId" TEXT NOT NULL,
+    "harvestId" TEXT NOT NULL,
+    "weight" DOUBLE PRECISION NOT NULL,
+
+    CONSTRAINT "LotHarvest_pkey" PRIMARY KEY ("lotId","harvestId")
+);
+
+-- CreateTable
+CREATE TABLE "Sale" (
+    "id" TEXT NOT NULL,
+    "lotId" TEXT NOT NULL,
+    "weight" DOUBLE PRECISION NOT NULL,
+    "customer" TEXT,
+    "reference" TEXT,
+    "soldAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "userId" TEXT NOT NULL,
+
+    CONSTRAINT "Sale_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Lot_code_key" ON "Lot"("code");
+
+-- AddForeignKey
+ALTER TABLE "Lot" ADD CONSTRAINT "Lot_packagedById_fkey" FOREIGN KEY ("packagedById") REFERENCES "User"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "LotHarvest" ADD CONSTRAINT "LotHarvest_lotId_fkey" FOREIGN KEY ("lotId") REFERENCES "Lot"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "LotHarvest" ADD CONSTRAINT "LotHarvest_harvestId_fkey" FOREIGN KEY ("harvestId") REFERENCES "Harvest"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Sale" ADD CONSTRAINT "Sale_lotId_fkey" FOREIGN KEY ("lotId") REFERENCES "Lot"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Sale" ADD CONSTRAINT "Sale_userId_fkey" FOREIGN KEY ("userId") REFERENCES "User"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
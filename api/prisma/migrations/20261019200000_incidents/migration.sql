@@ -0,0 +1,102 @@
+-- CreateEnum
+CREATE TYPE "IncidentKind" AS ENUM ('PEST', 'DISEASE', 'DEFICIENCY', 'OTHER');
+
+-- CreateEnum
+CREATE TYPE "IncidentSeverity" AS ENUM ('LOW', 'MEDIUM', 'HIGH', 'CRITICAL');
+
+-- CreateEnum
+CREATE TYPE "IncidentStatus" AS ENUM ('OPEN', 'MONITORING', 'RESOLVED');
+
+-- CreateTable
+CREATE TABLE "Incident" (
+    "id" TEXT NOT NULL,
+    "issue" TEXT NOT NULL,
+    "kind" "IncidentKind" NOT NULL,
+    "severity" "IncidentSeverity" NOT NULL,
+    "status" "IncidentStatus" NOT NULL DEFAULT 'OPEN',
+    "description" TEXT,
+    "observedAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "resolvedAt" TIMESTAMP(3),
+    "resolution" TEXT,
+    "reportedById" TEXT NOT NULL,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "Incident_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "Treatment" (
+    "id" TEXT NOT NULL,
+    "incidentId" TEXT NOT NULL,
+    "product" TEXT NOT NULL,
+    "dose" TEXT NOT NULL,
+    "preHarvestDays" INTEGER NOT NULL DEFAULT 0,
+    "appliedAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "appliedById" TEXT NOT NULL,
+    "note" TEXT,
+
+    CONSTRAINT "Treatment_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "IncidentFollowUp" (
+    "id" TEXT NOT NULL,
+    "incidentId" TEXT NOT NULL,
+    "note" TEXT NOT NULL,
+    "status" "IncidentStatus",
+    "userId" TEXT NOT NULL,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "IncidentFollowUp_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateTable
+CREATE TABLE "_IncidentToPlant" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL
+);
+
+-- CreateTable
+CREATE TABLE "_IncidentToLocation" (
+    "A" TEXT NOT NULL,
+    "B" TEXT NOT NULL
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_IncidentToPlant_AB_unique" ON "_IncidentToPlant"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_IncidentToPlant_B_index" ON "_IncidentToPlant"("B");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "_IncidentToLocation_AB_unique" ON "_IncidentToLocation"("A", "B");
+
+-- CreateIndex
+CREATE INDEX "_IncidentToLocation_B_index" ON "_IncidentToLocation"("B");
+
+-- AddForeignKey
+ALTER TABLE "Incident" ADD CONSTRAINT "Incident_reportedById_fkey" FOREIGN KEY ("reportedById") REFERENCES "User"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Treatment" ADD CONSTRAINT "Treatment_incidentId_fkey" FOREIGN KEY ("incidentId") REFERENCES "Incident"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Treatment" ADD CONSTRAINT "Treatment_appliedById_fkey" FOREIGN KEY ("appliedById") REFERENCES "User"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "IncidentFollowUp" ADD CONSTRAINT "IncidentFollowUp_incidentId_fkey" FOREIGN KEY ("incidentId") REFERENCES "Incident"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "IncidentFollowUp" ADD CONSTRAINT "IncidentFollowUp_userId_fkey" FOREIGN KEY ("userId") REFERENCES "User"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_IncidentToPlant" ADD CONSTRAINT "_IncidentToPlant_A_fkey" FOREIGN KEY ("A") REFERENCES "Incident"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_IncidentToPlant" ADD CONSTRAINT "_IncidentToPlant_B_fkey" FOREIGN KEY ("B") REFERENCES "Plant"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_IncidentToLocation" ADD CONSTRAINT "_IncidentToLocation_A_fkey" FOREIGN KEY ("A") REFERENCES "Incident"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "_IncidentToLocation" ADD CONSTRAINT "_IncidentToLocation_B_fkey" FOREIGN KEY ("B") REFERENCES "Location"("id") ON DELETE CASCADE ON UPDATE CASCADE;
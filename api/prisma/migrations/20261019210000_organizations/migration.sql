@@ -0,0 +1,69 @@
+/*
+  Warnings:
+
+  - Existing genetics, plants, history and role assignments are moved to the organization `Default`.
+  - The unique constraints on `Genetic.name`, `Genetic.code` and `Plant.name` now apply per organization.
+
+*/
+-- CreateTable
+CREATE TABLE "Organization" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "createdAt" TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    CONSTRAINT "Organization_pkey" PRIMARY KEY ("id")
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Organization_name_key" ON "Organization"("name");
+
+-- Organization of the data created before organizations existed
+INSERT INTO "Organization" ("id", "name") VALUES ('00000000-0000-0000-0000-000000000001', 'Default');
+
+-- AlterTable
+ALTER TABLE "UsersInRoles" DROP CONSTRAINT "UsersInRoles_pkey",
+ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001',
+ADD CONSTRAINT "UsersInRoles_pkey" PRIMARY KEY ("userId", "roleId", "organizationId");
+ALTER TABLE "UsersInRoles" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- AlterTable
+ALTER TABLE "Genetic" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "Genetic" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- AlterTable
+ALTER TABLE "Plant" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "Plant" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- AlterTable
+ALTER TABLE "PlantHistory" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "PlantHistory" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- DropIndex
+DROP INDEX "Genetic_name_key";
+
+-- DropIndex
+DROP INDEX "Genetic_code_key";
+
+-- DropIndex
+DROP INDEX "Plant_name_key";
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Genetic_organizationId_name_key" ON "Genetic"("organizationId", "name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Genetic_organizationId_code_key" ON "Genetic"("organizationId", "code");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Plant_organizationId_name_key" ON "Plant"("organizationId", "name");
+
+-- AddForeignKey
+ALTER TABLE "UsersInRoles" ADD CONSTRAINT "UsersInRoles_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE CASCADE ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Genetic" ADD CONSTRAINT "Genetic_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Plant" ADD CONSTRAINT "Plant_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "PlantHistory" ADD CONSTRAINT "PlantHistory_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
@@ -0,0 +1,19 @@
+/*
+  Warnings:
+
+  - Existing tasks and task templates are moved to the organization `Default`.
+
+*/
+-- AlterTable
+ALTER TABLE "Task" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "Task" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- AlterTable
+ALTER TABLE "TaskTemplate" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "TaskTemplate" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- AddForeignKey
+ALTER TABLE "Task" ADD CONSTRAINT "Task_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "TaskTemplate" ADD CONSTRAINT "TaskTemplate_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
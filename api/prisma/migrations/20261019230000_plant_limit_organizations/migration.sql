@@ -0,0 +1,25 @@
+/*
+  Warnings:
+
+  - Existing plant limits are moved to the organization `Default`.
+  - The unique constraints on the scope of a limit now apply per organization.
+
+*/
+-- AlterTable
+ALTER TABLE "PlantLimit" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "PlantLimit" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- DropIndex
+DROP INDEX "PlantLimit_locationId_stage_key";
+
+-- DropIndex
+DROP INDEX "PlantLimit_scope_key";
+
+-- CreateIndex
+CREATE UNIQUE INDEX "PlantLimit_organizationId_locationId_stage_key" ON "PlantLimit"("organizationId", "locationId", "stage");
+
+-- Postgres treats NULLs as distinct, so also keep facility wide and total limits unique
+CREATE UNIQUE INDEX "PlantLimit_scope_key" ON "PlantLimit"("organizationId", COALESCE("locationId", ''), COALESCE("stage"::TEXT, ''));
+
+-- AddForeignKey
+ALTER TABLE "PlantLimit" ADD CONSTRAINT "PlantLimit_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
@@ -0,0 +1,12 @@
+/*
+  Warnings:
+
+  - The existing naming template and plant name sequences are moved to the organization `Default`.
+*/
+-- UpdateData
+UPDATE "Setting" SET "key" = 'plantNameTemplate:00000000-0000-0000-0000-000000000001' WHERE "key" = 'plantNameTemplate';
+
+-- UpdateData
+UPDATE "NameSequence"
+SET "key" = 'plant:00000000-0000-0000-0000-000000000001:' || substring("key" from 7)
+WHERE "key" LIKE 'plant:%';
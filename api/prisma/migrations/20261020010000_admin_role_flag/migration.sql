@@ -0,0 +1,11 @@
+/*
+  Warnings:
+
+  - Admin rights are granted by the `isAdmin` flag instead of the role name, the existing `Admin` role keeps them.
+
+*/
+-- AlterTable
+ALTER TABLE "Role" ADD COLUMN "isAdmin" BOOLEAN NOT NULL DEFAULT false;
+
+-- UpdateData
+UPDATE "Role" SET "isAdmin" = true WHERE "id" = (SELECT MIN("id") FROM "Role" WHERE "name" = 'Admin');
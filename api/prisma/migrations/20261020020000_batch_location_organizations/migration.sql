@@ -0,0 +1,32 @@
+/*
+  Warnings:
+
+  - Existing batches and locations are moved to the organization `Default`.
+  - Batch and location names are unique per organization.
+
+*/
+-- DropIndex
+DROP INDEX "Batch_name_key";
+
+-- DropIndex
+DROP INDEX "Location_name_key";
+
+-- AlterTable
+ALTER TABLE "Batch" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "Batch" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- AlterTable
+ALTER TABLE "Location" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "Location" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Batch_organizationId_name_key" ON "Batch"("organizationId", "name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Location_organizationId_name_key" ON "Location"("organizationId", "name");
+
+-- AddForeignKey
+ALTER TABLE "Batch" ADD CONSTRAINT "Batch_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "Location" ADD CONSTRAINT "Location_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
@@ -0,0 +1,45 @@
+/*
+  Warnings:
+
+  - Existing nutrient products, feed recipes and feed schedules are moved to the organization `Default`.
+  - Their names are unique per organization.
+
+*/
+-- DropIndex
+DROP INDEX "NutrientProduct_name_key";
+
+-- DropIndex
+DROP INDEX "FeedRecipe_name_key";
+
+-- DropIndex
+DROP INDEX "FeedSchedule_name_key";
+
+-- AlterTable
+ALTER TABLE "NutrientProduct" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "NutrientProduct" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- AlterTable
+ALTER TABLE "FeedRecipe" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "FeedRecipe" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- AlterTable
+ALTER TABLE "FeedSchedule" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "FeedSchedule" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- CreateIndex
+CREATE UNIQUE INDEX "NutrientProduct_organizationId_name_key" ON "NutrientProduct"("organizationId", "name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "FeedRecipe_organizationId_name_key" ON "FeedRecipe"("organizationId", "name");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "FeedSchedule_organizationId_name_key" ON "FeedSchedule"("organizationId", "name");
+
+-- AddForeignKey
+ALTER TABLE "NutrientProduct" ADD CONSTRAINT "NutrientProduct_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "FeedRecipe" ADD CONSTRAINT "FeedRecipe_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
+
+-- AddForeignKey
+ALTER TABLE "FeedSchedule" ADD CONSTRAINT "FeedSchedule_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
@@ -0,0 +1,33 @@
+/*
+  Warnings:
+
+  - Existing lots are moved to the organization of the plants they were packaged from.
+  - Lot codes are unique per organization.
+  - The existing lot code sequences are moved to the organization `Default`.
+
+*/
+-- DropIndex
+DROP INDEX "Lot_code_key";
+
+-- AlterTable
+ALTER TABLE "Lot" ADD COLUMN "organizationId" TEXT NOT NULL DEFAULT '00000000-0000-0000-0000-000000000001';
+ALTER TABLE "Lot" ALTER COLUMN "organizationId" DROP DEFAULT;
+
+-- UpdateData
+UPDATE "Lot"
+SET "organizationId" = "Plant"."organizationId"
+FROM "LotHarvest"
+JOIN "Harvest" ON "Harvest"."id" = "LotHarvest"."harvestId"
+JOIN "Plant" ON "Plant"."id" = "Harvest"."plantId"
+WHERE "LotHarvest"."lotId" = "Lot"."id";
+
+-- UpdateData
+UPDATE "NameSequence"
+SET "key" = 'lot:00000000-0000-0000-0000-000000000001:' || substring("key" from 5)
+WHERE "key" LIKE 'lot:%';
+
+-- CreateIndex
+CREATE UNIQUE INDEX "Lot_organizationId_code_key" ON "Lot"("organizationId", "code");
+
+-- AddForeignKey
+ALTER TABLE "Lot" ADD CONSTRAINT "Lot_organizationId_fkey" FOREIGN KEY ("organizationId") REFERENCES "Organization"("id") ON DELETE RESTRICT ON UPDATE CASCADE;
@@ -0,0 +1,292 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::{env, fs, net::TcpListener, path::PathBuf};
+
+use actix_web::web;
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use uuid::Uuid;
+
+use crate::{
+    model::dto::{
+        auth::{RegisterRequest, RoleRegisterRequest},
+        backup::{ImportReport, SnapshotSummary},
+    },
+    prisma::{organization, PrismaClient},
+    server::run,
+    service::{
+        authentication::change_password,
+        backup::{
+            export_snapshot, import_snapshot, read_archive, summary, write_archive, SYSTEM_USER_ID,
+        },
+        demo::seed_demo,
+        metrics::TimedQuery,
+        organization::{
+            admin_role, first_organization, organization_named, DEFAULT_ORGANIZATION_ID,
+        },
+        user::{create_new_user, find_by_identifier},
+    },
+};
+
+const DEFAULT_BIND: &str = "127.0.0.1:8004";
+
+#[doc = "The easy way to manage all your plants.
+All commands read the same environment as the server, e.g. `DATABASE_URL`."]
+#[derive(Parser, Debug)]
+#[command(name = "planters_cycle", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, PartialEq, Eq)]
+pub enum Command {
+    #[doc = "Run the api server, the default without a command"]
+    Serve {
+        #[doc = "Address to listen on"]
+        #[arg(long, env = "BIND_ADDRESS", default_value = DEFAULT_BIND)]
+        bind: String,
+    },
+    #[doc = "Apply pending migrations and exit"]
+    Migrate,
+    #[doc = "Create a user with the admin role"]
+    CreateAdmin {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        #[doc = "Generated and printed when not given"]
+        #[arg(long, env = "ADMIN_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+        #[doc = "Organization the admin manages, created when missing, `Default` when not given"]
+        #[arg(long)]
+        organization: Option<String>,
+    },
+    #[doc = "Set a new password for a user"]
+    ResetPassword {
+        #[doc = "Display name or email of the user"]
+        identifier: String,
+        #[doc = "Generated and printed when not given"]
+        #[arg(long, env = "NEW_PASSWORD", hide_env_values = true)]
+        password: Option<String>,
+    },
+    #[doc = "Fill an empty organization with demo genetics, plants and their history"]
+    SeedDemo {
+        #[doc = "Display name or email of the user the history is recorded for"]
+        #[arg(long)]
+        user: String,
+        #[doc = "Name of the organization, the first one the user joined when not given"]
+        #[arg(long)]
+        organization: Option<String>,
+    },
+    #[doc = "Write roles, users, genetics, plants and history to a zip archive
+holding them as JSON and CSV"]
+    Export {
+        output: PathBuf,
+        #[doc = "Include the password hashes so users can log in after an import"]
+        #[arg(long)]
+        with_passwords: bool,
+    },
+    #[doc = "Merge an archive written by `export` into this instance"]
+    Import {
+        input: PathBuf,
+        #[doc = "Only report what would be written"]
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+impl Cli {
+    #[doc = "The command to run, `serve` when none was given"]
+    pub fn command(self) -> Command {
+        self.command.unwrap_or_else(|| Command::Serve {
+            bind: env::var("BIND_ADDRESS").unwrap_or(DEFAULT_BIND.to_string()),
+        })
+    }
+}
+
+#[doc = "Passwords of at least 8 characters are accepted, a random one is generated otherwise"]
+pub fn password_or_generated(password: Option<String>) -> Result<(String, bool), String> {
+    match password {
+        Some(password) if password.chars().count() < 8 => {
+            Err("Password must be at least 8 characters long".to_string())
+        }
+        Some(password) => Ok((password, false)),
+        None => Ok((Uuid::new_v4().simple().to_string(), true)),
+    }
+}
+
+fn print_summary(verb: &str, summary: &SnapshotSummary) {
+    println!(
+        "{} {} organizations, {} roles, {} users, {} genetics, {} plants and {} history entries",
+        verb,
+        summary.organizations,
+        summary.roles,
+        summary.users,
+        summary.genetics,
+        summary.plants,
+        summary.history
+    );
+}
+
+fn print_report(report: &ImportReport) {
+    if report.dry_run {
+        print_summary("Would create", &report.created);
+        print_summary("Would keep present", &report.existing);
+    } else {
+        print_summary("Created", &report.created);
+        print_summary("Kept present", &report.existing);
+    }
+    if report.without_password > 0 {
+        println!(
+            "{} users have no password and need a `reset-password` before they can log in",
+            report.without_password
+        );
+    }
+}
+
+#[doc = "Run a command against the connected and migrated database"]
+pub async fn execute(command: Command, data: PrismaClient) -> Result<(), String> {
+    match command {
+        Command::Serve { bind } => {
+            let listener =
+                TcpListener::bind(&bind).map_err(|e| format!("Failed to bind {}: {}", bind, e))?;
+            tracing::info!(address = %bind, "Serving");
+
+            // Runs until SIGTERM or SIGINT, in-flight requests get `SHUTDOWN_TIMEOUT` to finish
+            run(listener, data)
+                .await
+                .map_err(|e| e.to_string())?
+                .await
+                .map_err(|e| e.to_string())?;
+            tracing::info!("Server stopped");
+            Ok(())
+        }
+        // Pending migrations were applied before the command ran
+        Command::Migrate => Ok(()),
+        Command::CreateAdmin {
+            name,
+            email,
+            password,
+            organization,
+        } => {
+            let (password, generated) = password_or_generated(password)?;
+            let data = web::Data::new(data);
+            let admin = admin_role(&data).await.map_err(|e| format!("{:?}", e))?;
+            let organization_id = match organization {
+                Some(organization) => {
+                    organization_named(&data, &organization)
+                        .await
+                        .map_err(|e| format!("{:?}", e))?
+                        .id
+                }
+                None => DEFAULT_ORGANIZATION_ID.to_string(),
+            };
+            if find_by_identifier(&name, &data)
+                .await
+                .ok()
+                .flatten()
+                .is_some()
+                || find_by_identifier(&email, &data)
+                    .await
+                    .ok()
+                    .flatten()
+                    .is_some()
+            {
+                return Err(format!("A user named {} or {} already exists", name, email));
+            }
+
+            let request = RegisterRequest {
+                display_name: name,
+                email,
+                password: password.clone(),
+                roles: Some(vec![RoleRegisterRequest {
+                    role_id: admin.id,
+                    assigned_at: Utc::now().fixed_offset(),
+                    assigned_by: SYSTEM_USER_ID.to_string(),
+                }]),
+            };
+            let user =
+                create_new_user(SYSTEM_USER_ID.to_string(), &organization_id, request, &data)
+                    .await
+                    .map_err(|e| format!("Creating the admin failed: {:?}", e))?;
+            println!("Created admin {} ({})", user.display_name, user.id);
+            if generated {
+                println!("Password: {}", password);
+            }
+            Ok(())
+        }
+        Command::ResetPassword {
+            identifier,
+            password,
+        } => {
+            let (password, generated) = password_or_generated(password)?;
+            let data = web::Data::new(data);
+            let user = find_by_identifier(&identifier, &data)
+                .await
+                .map_err(|e| format!("{:?}", e))?
+                .ok_or_else(|| format!("No user named {}", identifier))?;
+            change_password(&user.id, &password, &data)
+                .await
+                .map_err(|e| format!("{:?}", e))?;
+            println!("Password of {} was reset", user.display_name);
+            if generated {
+                println!("Password: {}", password);
+            }
+            Ok(())
+        }
+        Command::SeedDemo { user, organization } => {
+            let data = web::Data::new(data);
+            let user = find_by_identifier(&user, &data)
+                .await
+                .map_err(|e| format!("{:?}", e))?
+                .ok_or_else(|| format!("No user named {}", user))?;
+            let organization_id = match organization {
+                Some(name) => data
+                    .organization()
+                    .find_unique(organization::name::equals(name.clone()))
+                    .exec()
+                    .timed("organization", "find_unique")
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .map(|organization| organization.id)
+                    .ok_or_else(|| format!("No organization named {}", name))?,
+                None => first_organization(&data, &user.id)
+                    .await
+                    .map_err(|e| format!("{:?}", e))?
+                    .ok_or_else(|| format!("{} belongs to no organization", user.display_name))?,
+            };
+            let summary = seed_demo(&data, &organization_id, &user.id, Utc::now().fixed_offset())
+                .await
+                .map_err(|e| format!("Seeding failed: {:?}", e))?;
+            print_summary("Seeded", &summary);
+            Ok(())
+        }
+        Command::Export {
+            output,
+            with_passwords,
+        } => {
+            let snapshot = export_snapshot(&data, with_passwords)
+                .await
+                .map_err(|e| format!("Export failed: {:?}", e))?;
+            let archive = write_archive(&snapshot)?;
+            fs::write(&output, archive)
+                .map_err(|e| format!("Writing {} failed: {}", output.display(), e))?;
+            print_summary("Exported", &summary(&snapshot));
+            Ok(())
+        }
+        Command::Import { input, dry_run } => {
+            let bytes = fs::read(&input)
+                .map_err(|e| format!("Reading {} failed: {}", input.display(), e))?;
+            let snapshot = read_archive(&bytes)
+                .map_err(|e| format!("{} is not an export: {}", input.display(), e))?;
+            let report = import_snapshot(&data, snapshot, dry_run)
+                .await
+                .map_err(|e| format!("Import failed: {:?}", e))?;
+            print_report(&report);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,149 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::{env, time::Duration};
+
+use include_dir::{include_dir, Dir};
+use prisma_client_rust::{raw, NewClientError};
+use serde::Deserialize;
+
+use crate::prisma::PrismaClient;
+
+#[doc = "Migrations shipped with the binary, applied by `_migrate_deploy`"]
+static MIGRATIONS: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/prisma/migrations");
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[doc = "What to do with pending migrations at startup, set by `DATABASE_MIGRATIONS`"]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationMode {
+    #[doc = "Apply pending migrations before serving"]
+    Apply,
+    #[doc = "Refuse to start while migrations are pending"]
+    Check,
+    #[doc = "Do not look at the schema"]
+    Skip,
+}
+
+impl MigrationMode {
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(str::to_lowercase).as_deref() {
+            Some("apply") => Ok(MigrationMode::Apply),
+            None | Some("") | Some("check") => Ok(MigrationMode::Check),
+            Some("skip") => Ok(MigrationMode::Skip),
+            Some(other) => Err(format!(
+                "DATABASE_MIGRATIONS must be apply, check or skip, not {:?}",
+                other
+            )),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(env::var("DATABASE_MIGRATIONS").ok().as_deref())
+    }
+}
+
+#[doc = "Delay before the next connection attempt, doubling from 500 ms up to 30 s"]
+pub fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(500)
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF)
+}
+
+#[doc = "Connect to `DATABASE_URL`, retrying `DATABASE_CONNECT_ATTEMPTS` times (default 8)
+so the api can start before the database is up."]
+pub async fn connect() -> Result<PrismaClient, NewClientError> {
+    let attempts: u32 = env::var("DATABASE_CONNECT_ATTEMPTS")
+        .ok()
+        .and_then(|attempts| attempts.parse().ok())
+        .unwrap_or(8)
+        .max(1);
+
+    let mut attempt = 0;
+    loop {
+        match PrismaClient::_builder().build().await {
+            Ok(client) => return Ok(client),
+            Err(e) if attempt + 1 < attempts => {
+                let delay = backoff(attempt);
+                tracing::warn!(
+                    error = %e,
+                    attempt = attempt + 1,
+                    attempts,
+                    "Connecting to the database failed, retrying in {:?}",
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[doc = "Names of the migrations shipped with the binary, oldest first"]
+pub fn bundled_migrations() -> Vec<&'static str> {
+    let mut names: Vec<&str> = MIGRATIONS
+        .dirs()
+        .filter_map(|dir| dir.path().file_name()?.to_str())
+        .collect();
+    names.sort();
+    names
+}
+
+#[doc = "Bundled migrations the database has not finished yet"]
+pub fn pending_migrations(bundled: &[&str], applied: &[String]) -> Vec<String> {
+    bundled
+        .iter()
+        .filter(|name| !applied.iter().any(|applied| applied == *name))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct AppliedMigration {
+    migration_name: String,
+}
+
+#[doc = "Migrations recorded by prisma, none when the database was never migrated"]
+async fn applied_migrations(client: &PrismaClient) -> Vec<String> {
+    let applied: Result<Vec<AppliedMigration>, _> = client
+        ._query_raw(raw!(
+            r#"SELECT "migration_name" FROM "_prisma_migrations"
+            WHERE "finished_at" IS NOT NULL AND "rolled_back_at" IS NULL"#
+        ))
+        .exec()
+        .await;
+    match applied {
+        Ok(applied) => applied.into_iter().map(|m| m.migration_name).collect(),
+        Err(e) => {
+            tracing::warn!(error = %e, "Reading the applied migrations failed");
+            vec![]
+        }
+    }
+}
+
+#[doc = "Bring the schema up to date or make sure it is, depending on the mode"]
+pub async fn migrate(client: &PrismaClient, mode: MigrationMode) -> Result<(), String> {
+    if mode == MigrationMode::Skip {
+        return Ok(());
+    }
+
+    let pending = pending_migrations(&bundled_migrations(), &applied_migrations(client).await);
+    if pending.is_empty() {
+        tracing::info!("Database schema is up to date");
+        return Ok(());
+    }
+
+    match mode {
+        MigrationMode::Apply => {
+            tracing::info!(?pending, "Applying pending migrations");
+            client._migrate_deploy().await.map_err(|e| e.to_string())
+        }
+        _ => Err(format!(
+            "Database schema is behind, pending migrations: {}. \
+            Run `cargo prisma migrate deploy` or start with DATABASE_MIGRATIONS=apply",
+            pending.join(", ")
+        )),
+    }
+}
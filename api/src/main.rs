@@ -2,15 +2,20 @@
  * Copyright (c) Johannes Grimm 2024.
  */
 
-use std::{env, net::TcpListener};
+use std::{
+    env,
+    io::{Error, ErrorKind},
+};
 
-use actix_web::Result;
-use prisma_client_rust::NewClientError;
+use clap::Parser;
 
-use prisma::PrismaClient;
-
-use crate::server::run;
+use crate::{
+    cli::{Cli, Command},
+    database::MigrationMode,
+};
 
+mod cli;
+mod database;
 mod middleware;
 mod model;
 #[allow(warnings, unused)]
@@ -18,22 +23,27 @@ mod prisma;
 mod route;
 mod server;
 mod service;
+mod storage;
+mod telemetry;
 mod tests;
 
 #[tokio::main]
 async fn main() -> Result<(), std::io::Error> {
-    // Prepare database connection
-    let data: Result<PrismaClient, NewClientError> = PrismaClient::_builder().build().await;
-    let data = data.unwrap();
+    let command = Cli::parse().command();
 
     // enable logging
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or("debug"));
+    let _telemetry = telemetry::init();
     env::set_var("RUST_BACKTRACE", "1");
-    env::set_var("RUST_LOG", "actix_web=debug");
-
-    let listener = TcpListener::bind("127.0.0.1:8004").expect("Failed to bind address");
 
-    run(listener, data).await?.await?;
-
-    Ok(())
+    // Prepare database connection
+    let mode = match command {
+        Command::Migrate => MigrationMode::Apply,
+        _ => MigrationMode::from_env().map_err(|e| Error::new(ErrorKind::InvalidInput, e))?,
+    };
+    let data = database::connect()
+        .await
+        .map_err(|e| Error::new(ErrorKind::ConnectionRefused, e.to_string()))?;
+    database::migrate(&data, mode).await.map_err(Error::other)?;
+
+    cli::execute(command, data).await.map_err(Error::other)
 }
@@ -9,6 +9,8 @@ use actix_web::dev::Payload;
 use actix_web::guard::GuardContext;
 use actix_web::{FromRequest, HttpRequest, HttpResponse};
 
+use crate::model::error::ErrorCode;
+
 #[derive(Debug)]
 pub struct AuthDetails {
     pub user_id: String,
@@ -42,3 +44,8 @@ pub fn verify_token(ctx: &GuardContext) -> bool {
         return true;
     }
 }
+
+#[doc = "Read the user id of the logged in identity"]
+pub fn identity_id(identity: &Identity) -> Result<String, ErrorCode> {
+    identity.id().map_err(|_| ErrorCode::INTERNAL001)
+}
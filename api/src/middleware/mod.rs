@@ -2,3 +2,6 @@
  * Copyright (c) Johannes Grimm 2024.
  */
 pub(crate) mod auth;
+pub(crate) mod metrics;
+pub(crate) mod request_id;
+pub(crate) mod tenant;
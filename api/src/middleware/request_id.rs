@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::future::{ready, Ready};
+use std::time::Instant;
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::Error;
+use futures_util::future::LocalBoxFuture;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::service::metrics::UNMATCHED_ROUTE;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+#[doc = "Id of the request handled by the current task, `None` outside of `RequestTracing`"]
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+#[doc = "Ids sent by clients are only taken over when they are safe to log and echo"]
+pub fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+#[doc = "Take over the `X-Request-Id` of the client or generate one.
+The request is handled in a span carrying the id, the id is echoed in the response
+and available to `ErrorResponse` through `current_request_id`."]
+pub struct RequestTracing;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTracing
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestTracingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTracingMiddleware { service }))
+    }
+}
+
+pub struct RequestTracingMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTracingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| is_valid_request_id(id))
+            .map(str::to_owned)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let span = tracing::info_span!(
+            "request",
+            request_id = %id,
+            method = %req.method(),
+            route = req.match_pattern().as_deref().unwrap_or(UNMATCHED_ROUTE),
+            path = req.path(),
+        );
+
+        let started = Instant::now();
+        let response = span.in_scope(|| self.service.call(req));
+        let response = REQUEST_ID.scope(id.clone(), response);
+
+        Box::pin(
+            async move {
+                let mut response = response.await;
+                let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+                match &mut response {
+                    Ok(response) => {
+                        tracing::info!(
+                            status = response.status().as_u16(),
+                            latency_ms,
+                            "Request finished"
+                        );
+                        if let Ok(value) = HeaderValue::from_str(&id) {
+                            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+                        }
+                    }
+                    Err(e) => tracing::error!(error = %e, latency_ms, "Request failed"),
+                }
+                response
+            }
+            .instrument(span),
+        )
+    }
+}
@@ -0,0 +1,64 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_identity::IdentityExt;
+use actix_session::SessionExt;
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::{web, FromRequest, HttpRequest};
+use futures_util::future::LocalBoxFuture;
+
+use crate::model::error::{ErrorCode, ErrorResponse};
+use crate::prisma::PrismaClient;
+use crate::service::organization::is_member;
+
+#[doc = "Session key of the organization the user works in"]
+pub const ORGANIZATION_KEY: &str = "organization_id";
+
+#[doc = "Logged in user and the organization selected in their session.
+Extracting it checks the user still belongs to the organization. Handlers of tenant
+data take it and pass the organization to every query."]
+#[derive(Debug, Clone)]
+pub struct Tenant {
+    pub user_id: String,
+    pub organization_id: String,
+}
+
+fn rejected(code: ErrorCode) -> actix_web::Error {
+    InternalError::from_response(format!("{:?}", code), ErrorResponse::build(code)).into()
+}
+
+impl FromRequest for Tenant {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let user_id = req
+            .get_identity()
+            .ok()
+            .and_then(|identity| identity.id().ok());
+        let organization_id = req
+            .get_session()
+            .get::<String>(ORGANIZATION_KEY)
+            .ok()
+            .flatten();
+        let data = req.app_data::<web::Data<PrismaClient>>().cloned();
+
+        Box::pin(async move {
+            let (Some(user_id), Some(organization_id), Some(data)) =
+                (user_id, organization_id, data)
+            else {
+                return Err(rejected(ErrorCode::ORG001));
+            };
+            match is_member(&data, &user_id, &organization_id).await {
+                Ok(true) => Ok(Tenant {
+                    user_id,
+                    organization_id,
+                }),
+                Ok(false) => Err(rejected(ErrorCode::ORG001)),
+                Err(e) => Err(rejected(e)),
+            }
+        })
+    }
+}
@@ -2,21 +2,26 @@
  * Copyright (c) Johannes Grimm 2024.
  */
 
-use crate::prisma::{genetic, plant, role};
+use crate::prisma::{
+    self, batch, genetic, harvest, location, nutrient_product, plant, plant_limit, role, seed_pack,
+    PlantStage,
+};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 pub mod auth {
     use serde::{Deserialize, Serialize};
+    use utoipa::ToSchema;
 
     #[doc = "User Login"]
-    #[derive(Serialize, Debug, Deserialize)]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
     pub struct LoginRequest {
         pub identifier: String,
         pub password: String,
     }
 
     #[doc = "User Register Model"]
-    #[derive(Serialize, Debug, Deserialize)]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
     pub struct RegisterRequest {
         #[serde(rename = "displayName")]
         pub display_name: String,
@@ -28,7 +33,7 @@ pub mod auth {
     }
 
     #[doc = "Roles Register Model"]
-    #[derive(Serialize, Debug, Deserialize)]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
     pub struct RoleRegisterRequest {
         #[serde(rename = "roleId")]
         pub role_id: i32,
@@ -40,8 +45,1195 @@ pub mod auth {
     }
 }
 
+pub mod organization {
+    use serde::{Deserialize, Serialize};
+    use utoipa::ToSchema;
+
+    #[doc = "Organization Create Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct OrganizationRequest {
+        pub name: String,
+    }
+
+    #[doc = "Member Model, assigns a role of the current organization to a user"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct MemberRequest {
+        #[serde(rename = "userId")]
+        pub user_id: String,
+        #[serde(rename = "roleId")]
+        pub role_id: i32,
+    }
+}
+
+pub mod feeding {
+    use crate::prisma::{feed_recipe, PlantStage};
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    #[doc = "Feed Recipe Create Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct RecipeRequest {
+        pub name: String,
+        pub items: Vec<RecipeItemRequest>,
+    }
+
+    #[doc = "Feed Recipe Item Model, dose is given per litre of water"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct RecipeItemRequest {
+        #[serde(rename = "productId")]
+        pub product_id: String,
+        #[serde(rename = "dosePerLitre")]
+        pub dose_per_litre: f64,
+    }
+
+    #[doc = "Feed Schedule Create Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct ScheduleRequest {
+        pub name: String,
+        pub entries: Vec<ScheduleEntryRequest>,
+    }
+
+    #[doc = "Feed Schedule Entry Model, keyed by stage and week in stage"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct ScheduleEntryRequest {
+        pub stage: PlantStage,
+        pub week: i32,
+        #[serde(rename = "recipeId")]
+        pub recipe_id: String,
+    }
+
+    #[doc = "Feeding Log Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct FeedingLogRequest {
+        #[serde(rename = "plantId")]
+        pub plant_id: String,
+        #[serde(rename = "recipeId")]
+        pub recipe_id: Option<String>,
+        pub volume: f64,
+        pub ec: Option<f64>,
+        pub ph: Option<f64>,
+        pub note: Option<String>,
+    }
+
+    #[doc = "Feeding Log Query"]
+    #[derive(Serialize, Debug, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct FeedingLogQuery {
+        #[serde(rename = "plantId")]
+        pub plant_id: String,
+    }
+
+    #[doc = "What a plant should receive today"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct FeedingPlan {
+        #[serde(rename = "plantId")]
+        pub plant_id: String,
+        #[serde(rename = "plantName")]
+        pub plant_name: String,
+        pub stage: PlantStage,
+        #[serde(rename = "stageEnteredAt")]
+        pub stage_entered_at:
+            ::prisma_client_rust::chrono::DateTime<::prisma_client_rust::chrono::FixedOffset>,
+        #[serde(rename = "dayInStage")]
+        pub day_in_stage: i64,
+        #[serde(rename = "weekInStage")]
+        pub week_in_stage: i32,
+        #[serde(rename = "scheduleId")]
+        pub schedule_id: Option<String>,
+        pub recipe: Option<feed_recipe::Data>,
+    }
+}
+
+pub mod task {
+    use crate::prisma::{PlantStage, TaskStatus};
+    use prisma_client_rust::chrono::{DateTime, FixedOffset};
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    #[doc = "Task Create Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct TaskRequest {
+        pub title: String,
+        pub description: Option<String>,
+        #[serde(rename = "dueDate")]
+        pub due_date: DateTime<FixedOffset>,
+        #[serde(rename = "assigneeId")]
+        pub assignee_id: Option<String>,
+        #[serde(rename = "plantIds", default)]
+        pub plant_ids: Vec<String>,
+        #[serde(rename = "batchIds", default)]
+        pub batch_ids: Vec<String>,
+        #[serde(rename = "locationIds", default)]
+        pub location_ids: Vec<String>,
+    }
+
+    #[doc = "Task Update Model, only given fields are changed"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct TaskUpdateRequest {
+        pub title: Option<String>,
+        pub description: Option<String>,
+        #[serde(rename = "dueDate")]
+        pub due_date: Option<DateTime<FixedOffset>>,
+        pub status: Option<TaskStatus>,
+        #[serde(rename = "assigneeId")]
+        pub assignee_id: Option<String>,
+        #[serde(rename = "plantIds")]
+        pub plant_ids: Option<Vec<String>>,
+        #[serde(rename = "batchIds")]
+        pub batch_ids: Option<Vec<String>>,
+        #[serde(rename = "locationIds")]
+        pub location_ids: Option<Vec<String>>,
+    }
+
+    #[doc = "Task Search Query"]
+    #[derive(Serialize, Debug, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct TaskQuery {
+        pub status: Option<TaskStatus>,
+        #[serde(rename = "assigneeId")]
+        pub assignee_id: Option<String>,
+        #[serde(rename = "plantId")]
+        pub plant_id: Option<String>,
+    }
+
+    #[doc = "Task Template Create Model.
+Recurring templates need `intervalDays`, stage templates need `triggerStage`."]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct TaskTemplateRequest {
+        pub title: String,
+        pub description: Option<String>,
+        #[serde(rename = "assigneeId")]
+        pub assignee_id: Option<String>,
+        #[serde(rename = "intervalDays")]
+        pub interval_days: Option<i32>,
+        #[serde(rename = "startDate")]
+        pub start_date: Option<DateTime<FixedOffset>>,
+        #[serde(rename = "triggerStage")]
+        pub trigger_stage: Option<PlantStage>,
+        #[serde(rename = "offsetDays", default)]
+        pub offset_days: i32,
+        #[serde(rename = "plantIds", default)]
+        pub plant_ids: Vec<String>,
+        #[serde(rename = "batchIds", default)]
+        pub batch_ids: Vec<String>,
+        #[serde(rename = "locationIds", default)]
+        pub location_ids: Vec<String>,
+    }
+}
+
+pub mod attachment {
+    use serde::{Deserialize, Serialize};
+    use utoipa::IntoParams;
+
+    #[doc = "Attachment Search Query"]
+    #[derive(Serialize, Debug, Default, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct AttachmentQuery {
+        #[serde(rename = "plantId")]
+        pub plant_id: Option<String>,
+        #[serde(rename = "geneticId")]
+        pub genetic_id: Option<String>,
+        #[serde(rename = "harvestId")]
+        pub harvest_id: Option<String>,
+        #[serde(rename = "historyId")]
+        pub history_id: Option<String>,
+        #[serde(rename = "destructionId")]
+        pub destruction_id: Option<String>,
+    }
+
+    #[doc = "Uploaded file with the entities it is attached to"]
+    #[derive(Debug, Default)]
+    pub struct UploadRequest {
+        pub file_name: String,
+        pub content_type: Option<String>,
+        pub bytes: Vec<u8>,
+        pub links: AttachmentQuery,
+    }
+}
+
+pub mod label {
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    #[doc = "Output format of printed labels"]
+    #[derive(Serialize, Debug, Default, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+    #[serde(rename_all = "lowercase")]
+    pub enum LabelFormat {
+        #[default]
+        Pdf,
+        Zpl,
+    }
+
+    #[doc = "Label sheet layouts for the pdf format"]
+    #[derive(Serialize, Debug, Default, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+    pub enum LabelLayout {
+        #[doc = "A4, 3 x 7 labels of 63.5 x 38.1 mm"]
+        #[default]
+        L7160,
+        #[doc = "A4, 2 x 7 labels of 99.1 x 38.1 mm"]
+        L7163,
+        #[doc = "US Letter, 3 x 10 labels of 66.7 x 25.4 mm"]
+        #[serde(rename = "5160")]
+        Avery5160,
+    }
+
+    #[doc = "Label Render Query"]
+    #[derive(Serialize, Debug, Default, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct LabelQuery {
+        #[serde(default)]
+        pub format: LabelFormat,
+        #[serde(default)]
+        pub layout: LabelLayout,
+        #[doc = "Number of labels already used on the first sheet"]
+        #[serde(default)]
+        pub skip: usize,
+    }
+
+    #[doc = "Scanned QR Code Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    #[schema(as = LabelResolveRequest)]
+    pub struct ResolveRequest {
+        pub payload: String,
+    }
+}
+
+pub mod naming {
+    use serde::{Deserialize, Serialize};
+    use utoipa::ToSchema;
+
+    #[doc = "Naming Template Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct NamingTemplateRequest {
+        pub template: String,
+    }
+
+    #[doc = "Plant Name Generation Model"]
+    #[derive(Serialize, Debug, Default, Deserialize, ToSchema)]
+    pub struct NamingRequest {
+        #[serde(rename = "geneticId")]
+        pub genetic_id: String,
+        #[serde(rename = "motherId")]
+        pub mother_id: Option<String>,
+        #[serde(rename = "batchId")]
+        pub batch_id: Option<String>,
+        #[doc = "Template to try instead of the configured one, only used by previews"]
+        pub template: Option<String>,
+    }
+
+    #[doc = "Plant Name Preview Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct NamingPreview {
+        pub template: String,
+        pub name: String,
+    }
+}
+
+pub mod genetic_search {
+    use serde::{Deserialize, Serialize};
+    use utoipa::IntoParams;
+
+    use crate::prisma::GeneticType;
+
+    #[doc = "Genetic Search Query, lists are comma separated"]
+    #[derive(Serialize, Debug, Default, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct GeneticQuery {
+        #[doc = "Part of the name, case insensitive"]
+        pub search: Option<String>,
+        #[serde(rename = "geneticType")]
+        pub genetic_type: Option<GeneticType>,
+        pub autoflower: Option<bool>,
+        #[doc = "Genetics which can reach at least this THC percentage"]
+        #[serde(rename = "thcMin")]
+        pub thc_min: Option<f64>,
+        #[doc = "Genetics which can stay below this THC percentage"]
+        #[serde(rename = "thcMax")]
+        pub thc_max: Option<f64>,
+        #[serde(rename = "cbdMin")]
+        pub cbd_min: Option<f64>,
+        #[serde(rename = "cbdMax")]
+        pub cbd_max: Option<f64>,
+        #[doc = "Genetics having all of these terpenes"]
+        pub terpenes: Option<String>,
+        #[doc = "Genetics having all of these tags"]
+        pub tags: Option<String>,
+        #[serde(rename = "minYield")]
+        pub min_yield: Option<f64>,
+        pub breeder: Option<String>,
+    }
+}
+
+pub mod seed {
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    use crate::prisma::{plant, seed_germination, seed_pack};
+
+    #[doc = "Seed Pack Search Query"]
+    #[derive(Serialize, Debug, Default, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct SeedPackQuery {
+        #[serde(rename = "geneticId")]
+        pub genetic_id: Option<String>,
+        #[doc = "Only packs with seeds left"]
+        pub available: Option<bool>,
+    }
+
+    #[doc = "Germinate Seeds Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct GerminateRequest {
+        #[doc = "Seeds taken from the pack"]
+        pub count: i32,
+        #[doc = "Seeds that germinated, defaults to all of them"]
+        pub germinated: Option<i32>,
+        #[serde(rename = "batchId")]
+        pub batch_id: Option<String>,
+        #[serde(rename = "locationId")]
+        pub location_id: Option<String>,
+    }
+
+    #[doc = "Seed pack with its germination success rate"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct SeedPackSummary {
+        #[serde(flatten)]
+        pub pack: seed_pack::Data,
+        pub sown: i32,
+        pub germinated: i32,
+        #[serde(rename = "germinationRate")]
+        pub germination_rate: Option<f64>,
+    }
+
+    #[doc = "Germination with the seedlings created for it"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct GerminationResult {
+        pub germination: seed_germination::Data,
+        pub plants: Vec<plant::Data>,
+    }
+}
+
+pub mod incident {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    use crate::prisma::{IncidentKind, IncidentSeverity, IncidentStatus};
+
+    #[doc = "Incident Create Model, an incident affects at least one plant or location"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct IncidentRequest {
+        #[doc = "Observed issue, e.g. spider mites, powdery mildew or botrytis"]
+        pub issue: String,
+        pub kind: IncidentKind,
+        pub severity: IncidentSeverity,
+        pub description: Option<String>,
+        #[serde(rename = "observedAt")]
+        pub observed_at: Option<DateTime<FixedOffset>>,
+        #[serde(rename = "plantIds", default)]
+        pub plant_ids: Vec<String>,
+        #[serde(rename = "locationIds", default)]
+        pub location_ids: Vec<String>,
+    }
+
+    #[doc = "Incident Update Model, only given fields are changed"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct IncidentUpdateRequest {
+        pub issue: Option<String>,
+        pub kind: Option<IncidentKind>,
+        pub severity: Option<IncidentSeverity>,
+        pub description: Option<String>,
+        #[serde(rename = "plantIds")]
+        pub plant_ids: Option<Vec<String>>,
+        #[serde(rename = "locationIds")]
+        pub location_ids: Option<Vec<String>>,
+    }
+
+    #[doc = "Incident Search Query"]
+    #[derive(Serialize, Debug, Default, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct IncidentQuery {
+        pub status: Option<IncidentStatus>,
+        #[serde(rename = "plantId")]
+        pub plant_id: Option<String>,
+        #[serde(rename = "locationId")]
+        pub location_id: Option<String>,
+    }
+
+    #[doc = "Treatment Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct TreatmentRequest {
+        pub product: String,
+        #[doc = "Applied dose, e.g. `2 ml/l`"]
+        pub dose: String,
+        #[doc = "Days after the application before the plant may be harvested"]
+        #[serde(rename = "preHarvestDays", default)]
+        pub pre_harvest_days: i32,
+        #[serde(rename = "appliedAt")]
+        pub applied_at: Option<DateTime<FixedOffset>>,
+        pub note: Option<String>,
+    }
+
+    #[doc = "Follow-up Model, can move the incident to OPEN or MONITORING"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct FollowUpRequest {
+        pub note: String,
+        pub status: Option<IncidentStatus>,
+    }
+
+    #[doc = "Incident Resolution Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct ResolveRequest {
+        pub resolution: String,
+        #[serde(rename = "resolvedAt")]
+        pub resolved_at: Option<DateTime<FixedOffset>>,
+    }
+
+    #[doc = "Running pre-harvest interval of a treatment"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq, ToSchema)]
+    pub struct PreHarvestInterval {
+        #[serde(rename = "treatmentId")]
+        pub treatment_id: String,
+        #[serde(rename = "incidentId")]
+        pub incident_id: String,
+        pub product: String,
+        #[serde(rename = "appliedAt")]
+        pub applied_at: DateTime<FixedOffset>,
+        #[serde(rename = "endsAt")]
+        pub ends_at: DateTime<FixedOffset>,
+    }
+}
+
+pub mod lot {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    #[doc = "Lot Search Query"]
+    #[derive(Serialize, Debug, Default, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct LotQuery {
+        #[doc = "Only lots with product left"]
+        pub available: Option<bool>,
+    }
+
+    #[doc = "Weight taken from a harvest into a lot"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct LotHarvestRequest {
+        #[serde(rename = "harvestId")]
+        pub harvest_id: String,
+        #[doc = "Packaged weight in grams"]
+        pub weight: f64,
+    }
+
+    #[doc = "Create Lot Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct CreateLotRequest {
+        #[doc = "Lot code, generated as `LOT-YYYYMMDD-NNN` when missing"]
+        pub code: Option<String>,
+        pub harvests: Vec<LotHarvestRequest>,
+        pub note: Option<String>,
+    }
+
+    #[doc = "Sale Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct SaleRequest {
+        #[doc = "Sold weight in grams"]
+        pub weight: f64,
+        pub customer: Option<String>,
+        #[doc = "Invoice or delivery note number"]
+        pub reference: Option<String>,
+        #[serde(rename = "soldAt")]
+        pub sold_at: Option<DateTime<FixedOffset>>,
+    }
+}
+
+pub mod analytics {
+    use chrono::NaiveDate;
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    use crate::prisma::PlantStage;
+
+    #[doc = "Analytics Time Range Query, filters plants by their creation date"]
+    #[derive(Serialize, Debug, Default, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct AnalyticsQuery {
+        pub from: Option<NaiveDate>,
+        pub to: Option<NaiveDate>,
+    }
+
+    #[doc = "Average time plants spent in a stage they already left"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq, ToSchema)]
+    pub struct StageDays {
+        pub stage: PlantStage,
+        #[serde(rename = "averageDays")]
+        pub average_days: f64,
+        pub samples: usize,
+    }
+
+    #[doc = "Harvested weight in grams"]
+    #[derive(Serialize, Debug, Clone, Default, Deserialize, PartialEq, ToSchema)]
+    pub struct YieldStats {
+        #[serde(rename = "harvestedPlants")]
+        pub harvested_plants: usize,
+        #[serde(rename = "totalDryWeight")]
+        pub total_dry_weight: f64,
+        #[serde(rename = "averageDryWeight")]
+        pub average_dry_weight: Option<f64>,
+        #[serde(rename = "averageWetWeight")]
+        pub average_wet_weight: Option<f64>,
+    }
+
+    #[doc = "Clones taken from mothers of the genetic"]
+    #[derive(Serialize, Debug, Clone, Default, Deserialize, PartialEq, ToSchema)]
+    pub struct CloneStats {
+        pub taken: usize,
+        pub rooted: usize,
+        pub failed: usize,
+        #[serde(rename = "successRate")]
+        pub success_rate: Option<f64>,
+    }
+
+    #[doc = "Performance of the plants of a genetic"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq, ToSchema)]
+    pub struct GeneticAnalytics {
+        #[serde(rename = "geneticId")]
+        pub genetic_id: String,
+        #[serde(rename = "geneticName")]
+        pub genetic_name: String,
+        pub plants: usize,
+        #[serde(rename = "stageDays")]
+        pub stage_days: Vec<StageDays>,
+        #[serde(rename = "expectedFlowerDays")]
+        pub expected_flower_days: i32,
+        #[doc = "Days in flower, counted from seed for autoflowers"]
+        #[serde(rename = "actualFlowerDays")]
+        pub actual_flower_days: Option<f64>,
+        #[serde(rename = "yield")]
+        pub yield_stats: YieldStats,
+        pub destroyed: usize,
+        #[serde(rename = "lossRate")]
+        pub loss_rate: Option<f64>,
+        pub clones: CloneStats,
+    }
+}
+
+pub mod report {
+    use serde::{Deserialize, Serialize};
+    use utoipa::ToSchema;
+
+    #[doc = "Output format of exported reports"]
+    #[derive(Serialize, Debug, Default, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+    #[serde(rename_all = "lowercase")]
+    pub enum ReportFormat {
+        #[default]
+        Json,
+        Csv,
+    }
+}
+
+pub mod trace {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    use crate::prisma::{DestructionReason, PlantStage};
+
+    #[doc = "Output format of traceability reports"]
+    #[derive(Serialize, Debug, Default, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+    #[serde(rename_all = "lowercase")]
+    pub enum TraceFormat {
+        #[default]
+        Json,
+        Csv,
+        Pdf,
+    }
+
+    #[doc = "Traceability Report Query"]
+    #[derive(Serialize, Debug, Default, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct TraceQuery {
+        #[serde(default)]
+        pub format: TraceFormat,
+    }
+
+    #[doc = "Genetic of a traced plant"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct TraceGenetic {
+        pub id: String,
+        pub name: String,
+        pub code: Option<String>,
+        pub breeder: Option<String>,
+    }
+
+    #[doc = "Mother plant in the clone chain of a traced plant"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct TraceAncestor {
+        pub id: String,
+        pub name: String,
+        pub genetic: String,
+    }
+
+    #[doc = "History event of a traced plant"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct TraceEvent {
+        pub at: DateTime<FixedOffset>,
+        pub action: String,
+        pub stage: Option<PlantStage>,
+        pub user: String,
+    }
+
+    #[doc = "Harvest of a traced plant"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct TraceHarvest {
+        pub id: String,
+        #[serde(rename = "harvestedAt")]
+        pub harvested_at: DateTime<FixedOffset>,
+        #[serde(rename = "wetWeight")]
+        pub wet_weight: Option<f64>,
+        #[serde(rename = "dryWeight")]
+        pub dry_weight: Option<f64>,
+        pub user: String,
+        #[doc = "Codes of the lots the harvest was packaged into"]
+        pub lots: Vec<String>,
+    }
+
+    #[doc = "Destruction of a traced plant"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct TraceDestruction {
+        pub id: String,
+        #[serde(rename = "destroyedAt")]
+        pub destroyed_at: DateTime<FixedOffset>,
+        pub reason: DestructionReason,
+        pub method: String,
+        pub weight: f64,
+        #[serde(rename = "recordedBy")]
+        pub recorded_by: String,
+        pub witnesses: Vec<String>,
+    }
+
+    #[doc = "Sale of a traced lot"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct TraceSale {
+        pub id: String,
+        #[serde(rename = "soldAt")]
+        pub sold_at: DateTime<FixedOffset>,
+        pub weight: f64,
+        pub customer: Option<String>,
+        pub reference: Option<String>,
+        pub user: String,
+    }
+
+    #[doc = "Traced lot with its sales"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct TraceLot {
+        pub id: String,
+        pub code: String,
+        pub weight: f64,
+        pub remaining: f64,
+        #[serde(rename = "packagedAt")]
+        pub packaged_at: DateTime<FixedOffset>,
+        #[serde(rename = "packagedBy")]
+        pub packaged_by: String,
+        pub sales: Vec<TraceSale>,
+    }
+
+    #[doc = "Full trail of a single plant"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct PlantTrace {
+        pub id: String,
+        pub name: String,
+        pub stage: PlantStage,
+        #[serde(rename = "createdAt")]
+        pub created_at: DateTime<FixedOffset>,
+        pub genetic: TraceGenetic,
+        pub batch: Option<String>,
+        pub location: Option<String>,
+        #[serde(rename = "seedPack")]
+        pub seed_pack: Option<String>,
+        #[doc = "Mother chain, starting with the direct mother"]
+        pub mothers: Vec<TraceAncestor>,
+        pub events: Vec<TraceEvent>,
+        pub harvests: Vec<TraceHarvest>,
+        pub destruction: Option<TraceDestruction>,
+    }
+
+    #[doc = "Seed-to-sale traceability report"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct TraceReport {
+        #[doc = "Kind of the traced subject, `plant`, `batch` or `lot`"]
+        pub subject: String,
+        #[serde(rename = "subjectId")]
+        pub subject_id: String,
+        #[serde(rename = "subjectName")]
+        pub subject_name: String,
+        #[serde(rename = "generatedAt")]
+        pub generated_at: DateTime<FixedOffset>,
+        #[doc = "Traced lot, only set for lot reports"]
+        pub lot: Option<TraceLot>,
+        pub plants: Vec<PlantTrace>,
+        #[doc = "Display names of every user involved in the trail"]
+        pub users: Vec<String>,
+    }
+}
+
+pub mod destruction {
+    use chrono::{DateTime, FixedOffset, NaiveDate};
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    use super::report::ReportFormat;
+    use crate::prisma::DestructionReason;
+
+    #[doc = "Plant Destruction Model"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct DestroyRequest {
+        pub reason: DestructionReason,
+        #[doc = "How the plant material was made unusable, e.g. mixed with soil and composted"]
+        pub method: String,
+        #[doc = "Destroyed weight in grams"]
+        pub weight: f64,
+        #[serde(rename = "witnessIds")]
+        pub witness_ids: Vec<String>,
+        #[serde(rename = "destroyedAt")]
+        pub destroyed_at: Option<DateTime<FixedOffset>>,
+        pub note: Option<String>,
+        #[doc = "Uploaded photos documenting the destruction"]
+        #[serde(rename = "attachmentIds", default)]
+        pub attachment_ids: Vec<String>,
+    }
+
+    #[doc = "Waste Log Query, the range is inclusive"]
+    #[derive(Serialize, Debug, Default, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct WasteLogQuery {
+        pub from: Option<NaiveDate>,
+        pub to: Option<NaiveDate>,
+        pub reason: Option<DestructionReason>,
+        #[serde(default)]
+        pub format: ReportFormat,
+    }
+
+    #[doc = "Row of the waste log"]
+    #[derive(Serialize, Debug, Clone, Deserialize, ToSchema)]
+    pub struct WasteLogEntry {
+        pub id: String,
+        #[serde(rename = "destroyedAt")]
+        pub destroyed_at: DateTime<FixedOffset>,
+        #[serde(rename = "plantId")]
+        pub plant_id: String,
+        #[serde(rename = "plantName")]
+        pub plant_name: String,
+        pub genetic: String,
+        pub reason: DestructionReason,
+        pub method: String,
+        pub weight: f64,
+        pub note: Option<String>,
+        #[serde(rename = "recordedBy")]
+        pub recorded_by: String,
+        pub witnesses: Vec<String>,
+        #[serde(rename = "attachmentIds")]
+        pub attachment_ids: Vec<String>,
+    }
+}
+
+pub mod compliance {
+    use serde::{Deserialize, Serialize};
+    use utoipa::ToSchema;
+
+    use crate::prisma::PlantStage;
+
+    #[doc = "Current usage of a plant limit"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq, ToSchema)]
+    pub struct LimitUsage {
+        #[serde(rename = "limitId")]
+        pub limit_id: String,
+        #[serde(rename = "locationId")]
+        pub location_id: Option<String>,
+        #[serde(rename = "locationName")]
+        pub location_name: Option<String>,
+        #[doc = "Limited stage, `None` limits all living plants"]
+        pub stage: Option<PlantStage>,
+        #[serde(rename = "maxPlants")]
+        pub max_plants: i32,
+        pub current: i64,
+        pub remaining: i64,
+        #[doc = "Share of the limit in use, `None` for limits of 0 plants"]
+        pub utilization: Option<f64>,
+        pub exceeded: bool,
+    }
+}
+
+pub mod pedigree {
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoParams, ToSchema};
+
+    #[doc = "Pedigree Query"]
+    #[derive(Serialize, Debug, Deserialize, IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct PedigreeQuery {
+        #[doc = "Number of ancestor generations, defaults to 5"]
+        pub depth: Option<usize>,
+    }
+
+    #[doc = "Genetic with its parents as a tree"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq, ToSchema)]
+    pub struct PedigreeNode {
+        pub id: String,
+        pub name: String,
+        pub generation: Option<String>,
+        pub breeder: Option<String>,
+        #[doc = "Cross notation like `Gelato × OG Kush`"]
+        pub cross: Option<String>,
+        #[schema(no_recursion)]
+        pub female: Option<Box<PedigreeNode>>,
+        #[schema(no_recursion)]
+        pub male: Option<Box<PedigreeNode>>,
+    }
+
+    #[doc = "Family tree of a genetic"]
+    #[derive(Serialize, Debug, Deserialize, ToSchema)]
+    pub struct Pedigree {
+        pub genetic: PedigreeNode,
+        pub offspring: Vec<PedigreeNode>,
+    }
+}
+
+pub mod health {
+    use serde::{Deserialize, Serialize};
+    use utoipa::ToSchema;
+
+    #[doc = "Status of a dependency or of the whole service"]
+    #[derive(Serialize, Debug, Clone, Copy, Deserialize, PartialEq, ToSchema)]
+    #[serde(rename_all = "lowercase")]
+    pub enum HealthStatus {
+        Up,
+        #[doc = "Only dependencies the service can run without failed"]
+        Degraded,
+        Down,
+    }
+
+    #[doc = "Result of probing a single dependency"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq, ToSchema)]
+    pub struct DependencyHealth {
+        pub name: String,
+        pub status: HealthStatus,
+        #[doc = "Failed critical dependencies make the service unready"]
+        pub critical: bool,
+        #[serde(rename = "latencyMs")]
+        pub latency_ms: f64,
+    }
+
+    #[doc = "Readiness of the service with the probed dependencies"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq, ToSchema)]
+    pub struct Readiness {
+        pub status: HealthStatus,
+        pub checks: Vec<DependencyHealth>,
+    }
+}
+
+pub mod backup {
+    use crate::prisma::{GeneticType, PlantStage};
+    use crate::service::organization::DEFAULT_ORGANIZATION_ID;
+    use serde::{Deserialize, Serialize};
+
+    #[doc = "Format version written by this build, imports of newer versions are refused.
+Version 2 added the optional password hashes, version 3 the organizations."]
+    pub const SNAPSHOT_VERSION: u32 = 3;
+
+    fn default_organization() -> String {
+        DEFAULT_ORGANIZATION_ID.to_string()
+    }
+
+    #[doc = "Organization with its original id"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
+    pub struct OrganizationRecord {
+        pub id: String,
+        pub name: String,
+    }
+
+    #[doc = "Role a user holds in an organization. Snapshots before version 3 list
+plain role ids, those are read as roles in the default organization."]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
+    #[serde(from = "RoleAssignmentFormat")]
+    pub struct RoleAssignment {
+        #[serde(rename = "roleId")]
+        pub role_id: i32,
+        #[serde(rename = "organizationId")]
+        pub organization_id: String,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RoleAssignmentFormat {
+        RoleId(i32),
+        Assignment {
+            #[serde(rename = "roleId")]
+            role_id: i32,
+            #[serde(rename = "organizationId")]
+            organization_id: String,
+        },
+    }
+
+    impl From<RoleAssignmentFormat> for RoleAssignment {
+        fn from(format: RoleAssignmentFormat) -> Self {
+            match format {
+                RoleAssignmentFormat::RoleId(role_id) => Self {
+                    role_id,
+                    organization_id: default_organization(),
+                },
+                RoleAssignmentFormat::Assignment {
+                    role_id,
+                    organization_id,
+                } => Self {
+                    role_id,
+                    organization_id,
+                },
+            }
+        }
+    }
+
+    #[doc = "Role with its original id"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
+    pub struct RoleRecord {
+        pub id: i32,
+        pub name: String,
+        #[serde(rename = "isDefault")]
+        pub is_default: bool,
+        #[serde(rename = "isAdmin", default)]
+        pub is_admin: bool,
+    }
+
+    #[doc = "User, the password hash is only exported on request"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
+    pub struct UserRecord {
+        pub id: String,
+        #[serde(rename = "displayName")]
+        pub display_name: String,
+        pub email: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub password: Option<String>,
+        #[serde(rename = "createdAt")]
+        pub created_at:
+            ::prisma_client_rust::chrono::DateTime<::prisma_client_rust::chrono::FixedOffset>,
+        #[serde(alias = "roleIds")]
+        pub roles: Vec<RoleAssignment>,
+    }
+
+    #[doc = "Genetic with its lineage"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
+    pub struct GeneticRecord {
+        pub id: String,
+        pub name: String,
+        #[serde(rename = "organizationId", default = "default_organization")]
+        pub organization_id: String,
+        #[serde(rename = "flowerDays")]
+        pub flower_days: i32,
+        pub code: Option<String>,
+        #[serde(rename = "namingTemplate")]
+        pub naming_template: Option<String>,
+        #[serde(rename = "femaleParentId")]
+        pub female_parent_id: Option<String>,
+        #[serde(rename = "maleParentId")]
+        pub male_parent_id: Option<String>,
+        pub generation: Option<String>,
+        pub breeder: Option<String>,
+        pub source: Option<String>,
+        #[serde(rename = "geneticType")]
+        pub genetic_type: Option<GeneticType>,
+        #[serde(rename = "thcMin")]
+        pub thc_min: Option<f64>,
+        #[serde(rename = "thcMax")]
+        pub thc_max: Option<f64>,
+        #[serde(rename = "cbdMin")]
+        pub cbd_min: Option<f64>,
+        #[serde(rename = "cbdMax")]
+        pub cbd_max: Option<f64>,
+        pub terpenes: Vec<String>,
+        #[serde(rename = "expectedYield")]
+        pub expected_yield: Option<f64>,
+        #[serde(rename = "stretchFactor")]
+        pub stretch_factor: Option<f64>,
+        pub autoflower: bool,
+        pub tags: Vec<String>,
+    }
+
+    #[doc = "Plant with its mother"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
+    pub struct PlantRecord {
+        pub id: String,
+        pub name: String,
+        #[serde(rename = "organizationId", default = "default_organization")]
+        pub organization_id: String,
+        #[serde(rename = "geneticId")]
+        pub genetic_id: String,
+        pub stage: PlantStage,
+        #[serde(rename = "motherId")]
+        pub mother_id: Option<String>,
+        #[serde(rename = "createdAt")]
+        pub created_at:
+            ::prisma_client_rust::chrono::DateTime<::prisma_client_rust::chrono::FixedOffset>,
+    }
+
+    #[doc = "Entry of the plant history"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
+    pub struct HistoryRecord {
+        pub id: String,
+        #[serde(rename = "plantId")]
+        pub plant_id: String,
+        pub action: String,
+        pub stage: Option<PlantStage>,
+        #[serde(rename = "userId")]
+        pub user_id: String,
+        #[serde(rename = "organizationId", default = "default_organization")]
+        pub organization_id: String,
+        #[serde(rename = "createdAt")]
+        pub created_at:
+            ::prisma_client_rust::chrono::DateTime<::prisma_client_rust::chrono::FixedOffset>,
+    }
+
+    #[doc = "Everything moved between instances by `export` and `import`"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
+    pub struct Snapshot {
+        pub version: u32,
+        #[serde(rename = "exportedAt")]
+        pub exported_at:
+            ::prisma_client_rust::chrono::DateTime<::prisma_client_rust::chrono::FixedOffset>,
+        #[serde(default)]
+        pub organizations: Vec<OrganizationRecord>,
+        pub roles: Vec<RoleRecord>,
+        pub users: Vec<UserRecord>,
+        pub genetics: Vec<GeneticRecord>,
+        pub plants: Vec<PlantRecord>,
+        pub history: Vec<HistoryRecord>,
+    }
+
+    #[doc = "Number of records per kind"]
+    #[derive(Serialize, Debug, Clone, Default, Deserialize, PartialEq)]
+    pub struct SnapshotSummary {
+        #[serde(default)]
+        pub organizations: usize,
+        pub roles: usize,
+        pub users: usize,
+        pub genetics: usize,
+        pub plants: usize,
+        pub history: usize,
+    }
+
+    #[doc = "Describes the archive, written next to the snapshot"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq)]
+    pub struct ArchiveManifest {
+        pub version: u32,
+        #[serde(rename = "exportedAt")]
+        pub exported_at:
+            ::prisma_client_rust::chrono::DateTime<::prisma_client_rust::chrono::FixedOffset>,
+        #[doc = "Whether the users carry their password hashes"]
+        pub passwords: bool,
+        pub counts: SnapshotSummary,
+    }
+
+    #[doc = "What an import wrote, or would write in a dry run"]
+    #[derive(Serialize, Debug, Clone, Default, Deserialize, PartialEq)]
+    pub struct ImportReport {
+        #[serde(rename = "dryRun")]
+        pub dry_run: bool,
+        #[doc = "Records not present before"]
+        pub created: SnapshotSummary,
+        #[doc = "Records matched to present ones by name or email and left as they are"]
+        pub existing: SnapshotSummary,
+        #[doc = "Created users without a password, they need a `reset-password`"]
+        #[serde(rename = "withoutPassword")]
+        pub without_password: usize,
+    }
+}
+
+pub mod bulk_import {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+    use utoipa::ToSchema;
+
+    use crate::prisma::{GeneticType, PlantStage};
+
+    #[doc = "Genetic read from a CSV row, parents are referenced by name"]
+    #[derive(Serialize, Debug, Clone, Default, Deserialize, PartialEq, ToSchema)]
+    pub struct GeneticImportRow {
+        #[doc = "Line of the row in the file, the header is line 1"]
+        pub line: usize,
+        pub name: String,
+        #[serde(rename = "flowerDays")]
+        pub flower_days: i32,
+        pub code: Option<String>,
+        pub generation: Option<String>,
+        pub breeder: Option<String>,
+        pub source: Option<String>,
+        #[serde(rename = "geneticType")]
+        pub genetic_type: Option<GeneticType>,
+        #[serde(rename = "thcMin")]
+        pub thc_min: Option<f64>,
+        #[serde(rename = "thcMax")]
+        pub thc_max: Option<f64>,
+        #[serde(rename = "cbdMin")]
+        pub cbd_min: Option<f64>,
+        #[serde(rename = "cbdMax")]
+        pub cbd_max: Option<f64>,
+        pub terpenes: Vec<String>,
+        #[serde(rename = "expectedYield")]
+        pub expected_yield: Option<f64>,
+        #[serde(rename = "stretchFactor")]
+        pub stretch_factor: Option<f64>,
+        pub autoflower: bool,
+        pub tags: Vec<String>,
+        #[serde(rename = "femaleParent")]
+        pub female_parent: Option<String>,
+        #[serde(rename = "maleParent")]
+        pub male_parent: Option<String>,
+    }
+
+    #[doc = "Plant read from a CSV row, related records are referenced by name"]
+    #[derive(Serialize, Debug, Clone, Deserialize, PartialEq, ToSchema)]
+    pub struct PlantImportRow {
+        #[doc = "Line of the row in the file, the header is line 1"]
+        pub line: usize,
+        pub name: String,
+        pub genetic: String,
+        pub stage: PlantStage,
+        #[doc = "Plant of the instance or of an earlier or later row"]
+        pub mother: Option<String>,
+        pub location: Option<String>,
+    }
+
+    #[doc = "Genetics a CSV file would create. Messages are keyed by `rows.<line>.<column>`,
+nothing is created while there are any."]
+    #[derive(Serialize, Debug, Clone, Default, Deserialize, ToSchema)]
+    pub struct GeneticImportPreview {
+        pub rows: Vec<GeneticImportRow>,
+        pub errors: BTreeMap<String, String>,
+    }
+
+    #[doc = "Plants a CSV file would create. Messages are keyed by `rows.<line>.<column>`,
+nothing is created while there are any."]
+    #[derive(Serialize, Debug, Clone, Default, Deserialize, ToSchema)]
+    pub struct PlantImportPreview {
+        pub rows: Vec<PlantImportRow>,
+        pub errors: BTreeMap<String, String>,
+    }
+}
+
+#[doc = "Harvest Create Model"]
+#[derive(Serialize, Debug, Deserialize, ToSchema)]
+pub struct HarvestRequest {
+    #[serde(rename = "plantId")]
+    pub plant_id: String,
+    #[serde(rename = "wetWeight")]
+    pub wet_weight: Option<f64>,
+    #[serde(rename = "dryWeight")]
+    pub dry_weight: Option<f64>,
+    pub note: Option<String>,
+}
+
 #[doc = "Plain Id Model"]
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, ToSchema)]
 pub struct IdModel {
     pub id: String,
 }
@@ -54,9 +1246,90 @@ role::partial_unchecked!(Role{
 genetic::partial_unchecked!(Genetic{
     name
     flower_days
+    code
+    naming_template
+    female_parent_id
+    male_parent_id
+    generation
+    breeder
+    source
+    genetic_type
+    thc_min
+    thc_max
+    cbd_min
+    cbd_max
+    terpenes
+    expected_yield
+    stretch_factor
+    autoflower
+    tags
 });
 
 plant::partial_unchecked!(Plant{
     name
     genetic_id
+    mother_id
+    batch_id
+    feed_schedule_id
+    location_id
+});
+
+batch::partial_unchecked!(Batch{
+    name
+    feed_schedule_id
+});
+
+location::partial_unchecked!(Location{
+    name
+    description
+});
+
+nutrient_product::partial_unchecked!(NutrientProduct{
+    name
+    manufacturer
+    unit
+});
+
+#[doc = "Plant Stage Change Model"]
+#[derive(Serialize, Debug, Deserialize, ToSchema)]
+pub struct StageChangeRequest {
+    pub stage: PlantStage,
+}
+
+seed_pack::partial_unchecked!(SeedPack{
+    genetic_id
+    label
+    source
+    purchased_at
+    seed_type
+    count
+    remaining
+    location_id
+    note
+});
+
+prisma::lot::partial_unchecked!(Lot{
+    code
+    note
+});
+
+plant_limit::partial_unchecked!(PlantLimit{
+    location_id
+    stage
+    max_plants
+    note
+});
+
+harvest::partial_unchecked!(Harvest{
+    wet_weight
+    dry_weight
+    note
+});
+
+prisma::attachment::partial_unchecked!(AttachmentLinks{
+    plant_id
+    genetic_id
+    harvest_id
+    history_id
+    destruction_id
 });
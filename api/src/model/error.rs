@@ -2,12 +2,17 @@
  * Copyright (c) Johannes Grimm 2024.
  */
 
+use std::collections::BTreeMap;
+
 use actix_web::HttpResponse;
 use prisma_client_rust::{prisma_errors::query_engine::RecordNotFound, QueryError};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use utoipa::ToSchema;
+
+use crate::middleware::request_id::current_request_id;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
 pub enum ErrorCode {
     #[doc = "User not found"]
     AUTH001,
@@ -26,36 +31,66 @@ pub enum ErrorCode {
     #[doc = "Bad request"]
     BADREQUEST(String),
 
+    #[doc = "File storage error"]
+    STORAGE001(String),
+
+    #[doc = "Licensed plant count limit exceeded"]
+    LIMIT001(String),
+
+    #[doc = "Plant is within the pre-harvest interval of a treatment"]
+    PHI001(String),
+
+    #[doc = "No organization is selected or the user is no member of it"]
+    ORG001,
+
+    #[doc = "Request fields failed validation, messages are keyed by field name"]
+    VALIDATION(BTreeMap<String, String>),
+
     #[doc = "Unknown error"]
     UNKNOWN,
 }
 
 #[doc = "Application error model"]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub code: ErrorCode,
+    #[doc = "Id of the failed request, also sent as `X-Request-Id`"]
+    #[serde(rename = "requestId", default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl ErrorResponse {
     #[allow(dead_code)]
-    #[doc = "Create new error response"]
+    #[doc = "Create new error response for the request currently handled"]
     pub fn new(code: ErrorCode) -> Self {
-        Self { code }
+        Self {
+            code,
+            request_id: current_request_id(),
+        }
     }
 
     #[allow(dead_code)]
     #[doc = "Mapping error responses to status codes"]
     pub fn build(code: ErrorCode) -> HttpResponse {
-        match code {
+        let response = match code {
             ErrorCode::AUTH001 => HttpResponse::NotFound(),
             ErrorCode::AUTH002 => HttpResponse::Unauthorized(),
             ErrorCode::INTERNAL001 => HttpResponse::InternalServerError(),
             ErrorCode::DATABASE001(_) => HttpResponse::InternalServerError(),
             ErrorCode::DATABASE002 => HttpResponse::NotFound(),
             ErrorCode::BADREQUEST(_) => HttpResponse::BadRequest(),
+            ErrorCode::STORAGE001(_) => HttpResponse::InternalServerError(),
+            ErrorCode::LIMIT001(_) => HttpResponse::Conflict(),
+            ErrorCode::PHI001(_) => HttpResponse::Conflict(),
+            ErrorCode::ORG001 => HttpResponse::Forbidden(),
+            ErrorCode::VALIDATION(_) => HttpResponse::UnprocessableEntity(),
             ErrorCode::UNKNOWN => HttpResponse::ImATeapot(),
         }
-        .json(json!(ErrorResponse::new(code)))
+        .json(json!(ErrorResponse::new(code.clone())));
+        if response.status().is_server_error() {
+            tracing::error!(code = ?code, "Request failed");
+        }
+        response
     }
 }
 
@@ -4,3 +4,5 @@
 
 pub(crate) mod dto;
 pub(crate) mod error;
+pub(crate) mod schema;
+pub(crate) mod validation;
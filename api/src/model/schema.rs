@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::borrow::Cow;
+
+use utoipa::{
+    openapi::{
+        schema::{KnownFormat, ObjectBuilder, Schema, SchemaFormat, Type},
+        RefOr,
+    },
+    PartialSchema, ToSchema,
+};
+
+use crate::{
+    model::dto::{
+        AttachmentLinks, Batch, Genetic, Harvest, Location, Lot, NutrientProduct, Plant,
+        PlantLimit, Role, SeedPack,
+    },
+    prisma::{
+        attachment, batch, destruction, feed_recipe, feed_schedule, feeding_log, genetic, harvest,
+        incident, incident_follow_up, location, lot, nutrient_product, plant, plant_history,
+        plant_limit, role, sale, seed_germination, seed_pack, task, task_template, treatment, user,
+        AttachmentKind, DestructionReason, GeneticType, IncidentKind, IncidentSeverity,
+        IncidentStatus, PlantStage, SeedType, TaskStatus,
+    },
+};
+
+#[doc = "Schemas of the enums generated by prisma, listed with their serialized values"]
+macro_rules! enum_schema {
+    ($($name:ident [$($value:literal),+ $(,)?]),+ $(,)?) => {$(
+        impl PartialSchema for $name {
+            fn schema() -> RefOr<Schema> {
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .enum_values(Some([$($value),+]))
+                    .into()
+            }
+        }
+
+        impl ToSchema for $name {}
+    )+};
+}
+
+#[doc = "Schemas of the records and partial models generated by prisma.
+They are documented as plain objects, the fields follow the prisma schema."]
+macro_rules! object_schema {
+    ($($ty:ty => $name:literal: $description:literal),+ $(,)?) => {$(
+        impl PartialSchema for $ty {
+            fn schema() -> RefOr<Schema> {
+                ObjectBuilder::new()
+                    .schema_type(Type::Object)
+                    .description(Some($description))
+                    .into()
+            }
+        }
+
+        impl ToSchema for $ty {
+            fn name() -> Cow<'static, str> {
+                Cow::Borrowed($name)
+            }
+        }
+    )+};
+}
+
+enum_schema!(
+    AttachmentKind["PHOTO", "DOCUMENT"],
+    DestructionReason["PEST", "MOLD", "MALE", "COMPLIANCE", "OTHER"],
+    GeneticType["INDICA", "SATIVA", "HYBRID"],
+    IncidentKind["PEST", "DISEASE", "DEFICIENCY", "OTHER"],
+    IncidentSeverity["LOW", "MEDIUM", "HIGH", "CRITICAL"],
+    IncidentStatus["OPEN", "MONITORING", "RESOLVED"],
+    PlantStage[
+        "SEEDLING",
+        "VEGETATIVE",
+        "FLOWERING",
+        "HARVEST",
+        "DRIED",
+        "CURED",
+        "PACKAGED",
+        "SOLD",
+        "DESTROYED",
+    ],
+    SeedType["FEMINIZED", "REGULAR"],
+    TaskStatus["OPEN", "IN_PROGRESS", "DONE", "CANCELLED"],
+);
+
+object_schema!(
+    attachment::Data => "Attachment": "Uploaded photo or document",
+    batch::Data => "Batch": "Batch of plants",
+    destruction::Data => "Destruction": "Destruction record of a plant",
+    feed_recipe::Data => "FeedRecipe": "Feed recipe with its items",
+    feed_schedule::Data => "FeedSchedule": "Feed schedule with its entries",
+    feeding_log::Data => "FeedingLog": "Logged feeding of a plant",
+    genetic::Data => "Genetic": "Genetic with its cultivation profile",
+    harvest::Data => "Harvest": "Harvest of a plant",
+    incident::Data => "Incident": "Pest or disease incident, lists include plants, locations, treatments and follow-ups",
+    incident_follow_up::Data => "IncidentFollowUp": "Follow-up note of an incident",
+    location::Data => "Location": "Location plants are grown in",
+    lot::Data => "Lot": "Product lot, lists include harvests and sales",
+    nutrient_product::Data => "NutrientProduct": "Nutrient product used in feed recipes",
+    plant::Data => "Plant": "Plant",
+    plant_history::Data => "PlantHistory": "History entry of a plant",
+    plant_limit::Data => "PlantLimit": "Licensed plant count limit",
+    role::Data => "Role": "User role",
+    sale::Data => "Sale": "Sale from a lot",
+    seed_germination::Data => "SeedGermination": "Seeds taken from a pack",
+    seed_pack::Data => "SeedPack": "Pack of seeds",
+    task::Data => "Task": "Task",
+    task_template::Data => "TaskTemplate": "Recurring or stage triggered task template",
+    treatment::Data => "Treatment": "Treatment applied against an incident",
+    user::Data => "User": "User account",
+    AttachmentLinks => "AttachmentLinksInput": "Entities of an attachment: plantId, geneticId, harvestId, historyId, destructionId",
+    Batch => "BatchInput": "Batch fields to set: name, feedScheduleId",
+    Genetic => "GeneticInput": "Genetic fields to set: name, flowerDays, code, namingTemplate, femaleParentId, maleParentId, generation, breeder, source, geneticType, thcMin, thcMax, cbdMin, cbdMax, terpenes, expectedYield, stretchFactor, autoflower, tags",
+    Harvest => "HarvestInput": "Harvest fields to set: wetWeight, dryWeight, note",
+    Location => "LocationInput": "Location fields to set: name, description",
+    Lot => "LotInput": "Lot fields to set: code, note",
+    NutrientProduct => "NutrientProductInput": "Nutrient product fields to set: name, manufacturer, unit",
+    Plant => "PlantInput": "Plant fields to set: name, geneticId, motherId, batchId, feedScheduleId, locationId",
+    PlantLimit => "PlantLimitInput": "Plant limit fields to set: locationId, stage, maxPlants, note",
+    Role => "RoleInput": "Role fields to set: name, isDefault",
+    SeedPack => "SeedPackInput": "Seed pack fields to set: geneticId, label, source, purchasedAt, seedType, count, remaining, locationId, note",
+);
+
+#[doc = "Raw file content of downloads like labels, reports and attachments"]
+pub struct Binary;
+
+impl PartialSchema for Binary {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .format(Some(SchemaFormat::KnownFormat(KnownFormat::Binary)))
+            .into()
+    }
+}
+
+impl ToSchema for Binary {}
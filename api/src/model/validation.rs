@@ -0,0 +1,504 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::collections::BTreeMap;
+
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        dto::{
+            auth::{RegisterRequest, RoleRegisterRequest},
+            feeding::{
+                FeedingLogRequest, RecipeItemRequest, RecipeRequest, ScheduleEntryRequest,
+                ScheduleRequest,
+            },
+            incident::{IncidentRequest, IncidentUpdateRequest},
+            organization::{MemberRequest, OrganizationRequest},
+            task::{TaskRequest, TaskTemplateRequest, TaskUpdateRequest},
+            Batch, Genetic, Harvest, HarvestRequest, Location, NutrientProduct, Plant, PlantLimit,
+            Role, SeedPack,
+        },
+        error::ErrorCode,
+    },
+    prisma::user,
+    service::genetic::is_valid_generation,
+};
+
+#[doc = "Collects the failed rules of a request as messages keyed by the serialized field name.
+Only the first failed rule of a field is kept."]
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: BTreeMap<String, String>,
+}
+
+impl Validator {
+    pub fn check(&mut self, field: &str, valid: bool, message: &str) {
+        if !valid {
+            self.errors
+                .entry(field.to_string())
+                .or_insert_with(|| message.to_string());
+        }
+    }
+
+    pub fn required<T>(&mut self, field: &str, value: &Option<T>, message: &str) {
+        self.check(field, value.is_some(), message);
+    }
+
+    #[doc = "Surrounding whitespace does not count towards the length"]
+    pub fn min_length(&mut self, field: &str, value: &str, min: usize, message: &str) {
+        self.check(field, value.trim().chars().count() >= min, message);
+    }
+
+    pub fn min<T: PartialOrd>(&mut self, field: &str, value: T, min: T, message: &str) {
+        self.check(field, value >= min, message);
+    }
+
+    pub fn between(&mut self, field: &str, value: f64, min: f64, max: f64, message: &str) {
+        self.check(field, (min..=max).contains(&value), message);
+    }
+
+    pub fn positive(&mut self, field: &str, value: f64, message: &str) {
+        self.check(field, value > 0.0, message);
+    }
+
+    pub fn email(&mut self, field: &str, value: &str, message: &str) {
+        self.check(field, is_email(value), message);
+    }
+
+    pub fn uuid(&mut self, field: &str, value: &str, message: &str) {
+        self.check(field, Uuid::parse_str(value).is_ok(), message);
+    }
+
+    #[doc = "Check a nested value, its fields are prefixed with the path, e.g. `items.0.dosePerLitre`"]
+    pub fn nested(&mut self, path: &str, value: &impl Validate) {
+        let mut nested = Validator::default();
+        value.check(&mut nested);
+        for (field, message) in nested.errors {
+            self.errors
+                .entry(format!("{}.{}", path, field))
+                .or_insert(message);
+        }
+    }
+
+    #[doc = "Messages of the failed rules, for callers reporting them next to other data"]
+    pub fn into_errors(self) -> BTreeMap<String, String> {
+        self.errors
+    }
+
+    pub fn finish(self) -> Result<(), ErrorCode> {
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(ErrorCode::VALIDATION(self.errors)),
+        }
+    }
+}
+
+#[doc = "Rules of a request model, they mirror the `@zod` rules of the prisma schema.
+Partial models only check the fields they set."]
+pub trait Validate {
+    fn check(&self, validator: &mut Validator);
+
+    fn validate(&self) -> Result<(), ErrorCode> {
+        let mut validator = Validator::default();
+        self.check(&mut validator);
+        validator.finish()
+    }
+}
+
+#[doc = "Plain address check, a local part and a dotted domain without whitespace"]
+pub fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    let labels: Vec<&str> = domain.split('.').collect();
+    !local.is_empty()
+        && !value.chars().any(char::is_whitespace)
+        && labels.len() > 1
+        && labels
+            .iter()
+            .all(|label| !label.is_empty() && !label.contains('@'))
+        && labels.last().is_some_and(|tld| tld.len() > 1)
+}
+
+impl Validate for RegisterRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.min_length(
+            "displayName",
+            &self.display_name,
+            2,
+            "Display name must be at least 2 characters long",
+        );
+        validator.email("email", &self.email, "Invalid email address");
+        // Only edits may send an empty password to keep the current one
+        validator.min_length(
+            "password",
+            &self.password,
+            8,
+            "Password must be at least 8 characters long",
+        );
+        for (index, role) in self.roles.iter().flatten().enumerate() {
+            validator.nested(&format!("roles.{}", index), role);
+        }
+    }
+}
+
+impl Validate for RoleRegisterRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.min("roleId", self.role_id, 1, "Role ID needs to be set");
+        validator.uuid("assignedBy", &self.assigned_by, "Assigner must be set");
+    }
+}
+
+impl Validate for OrganizationRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.min_length(
+            "name",
+            &self.name,
+            2,
+            "Organization name must be at least 2 characters long",
+        );
+    }
+}
+
+impl Validate for MemberRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.uuid("userId", &self.user_id, "User ID must be set");
+        validator.min("roleId", self.role_id, 1, "Role ID needs to be set");
+    }
+}
+
+impl Validate for user::Data {
+    fn check(&self, validator: &mut Validator) {
+        validator.min_length(
+            "displayName",
+            &self.display_name,
+            2,
+            "Display name must be at least 2 characters long",
+        );
+        validator.email("email", &self.email, "Invalid email address");
+        if !self.password.is_empty() {
+            validator.min_length(
+                "password",
+                &self.password,
+                8,
+                "Password must be at least 8 characters long",
+            );
+        }
+    }
+}
+
+impl Validate for Role {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(name) = &self.name {
+            validator.min_length(
+                "name",
+                name,
+                2,
+                "Role name must be at least 2 characters long",
+            );
+        }
+    }
+}
+
+impl Validate for Genetic {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(name) = &self.name {
+            validator.min_length(
+                "name",
+                name,
+                1,
+                "Genetic name must be at least 1 character long",
+            );
+        }
+        if let Some(flower_days) = self.flower_days {
+            validator.min(
+                "flowerDays",
+                flower_days,
+                1,
+                "Flower days must be at least 1",
+            );
+        }
+        if let Some(Some(code)) = &self.code {
+            validator.check(
+                "code",
+                (1..=12).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphanumeric()),
+                "Genetic code must be 1 to 12 letters or digits",
+            );
+        }
+        if let Some(Some(generation)) = &self.generation {
+            validator.check(
+                "generation",
+                is_valid_generation(generation),
+                "Generation must be P, F1-F99, BX, BX1-BX9, S1-S9 or IBL",
+            );
+        }
+        for (field, value, message) in [
+            ("thcMin", self.thc_min, "THC must be between 0 and 100"),
+            ("thcMax", self.thc_max, "THC must be between 0 and 100"),
+            ("cbdMin", self.cbd_min, "CBD must be between 0 and 100"),
+            ("cbdMax", self.cbd_max, "CBD must be between 0 and 100"),
+        ] {
+            if let Some(Some(value)) = value {
+                validator.between(field, value, 0.0, 100.0, message);
+            }
+        }
+        if let Some(Some(expected_yield)) = self.expected_yield {
+            validator.min(
+                "expectedYield",
+                expected_yield,
+                0.0,
+                "Expected yield must not be negative",
+            );
+        }
+        if let Some(Some(stretch_factor)) = self.stretch_factor {
+            validator.positive(
+                "stretchFactor",
+                stretch_factor,
+                "Stretch factor must be positive",
+            );
+        }
+    }
+}
+
+impl Validate for Plant {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(name) = &self.name {
+            validator.min_length(
+                "name",
+                name,
+                1,
+                "Plant name must be at least 1 character long",
+            );
+        }
+        if let Some(genetic_id) = &self.genetic_id {
+            validator.uuid("geneticId", genetic_id, "Genetic ID must be set");
+        }
+    }
+}
+
+impl Validate for Batch {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(name) = &self.name {
+            validator.min_length(
+                "name",
+                name,
+                1,
+                "Batch name must be at least 1 character long",
+            );
+        }
+    }
+}
+
+impl Validate for Location {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(name) = &self.name {
+            validator.min_length(
+                "name",
+                name,
+                1,
+                "Location name must be at least 1 character long",
+            );
+        }
+    }
+}
+
+impl Validate for TaskRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.min_length(
+            "title",
+            &self.title,
+            1,
+            "Task title must be at least 1 character long",
+        );
+    }
+}
+
+impl Validate for TaskUpdateRequest {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(title) = &self.title {
+            validator.min_length(
+                "title",
+                title,
+                1,
+                "Task title must be at least 1 character long",
+            );
+        }
+    }
+}
+
+impl Validate for TaskTemplateRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.min_length(
+            "title",
+            &self.title,
+            1,
+            "Task title must be at least 1 character long",
+        );
+        if let Some(interval_days) = self.interval_days {
+            validator.min(
+                "intervalDays",
+                interval_days,
+                1,
+                "Interval must be at least 1 day",
+            );
+        }
+        validator.min(
+            "offsetDays",
+            self.offset_days,
+            0,
+            "Offset can not be negative",
+        );
+    }
+}
+
+impl Validate for NutrientProduct {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(name) = &self.name {
+            validator.min_length(
+                "name",
+                name,
+                1,
+                "Product name must be at least 1 character long",
+            );
+        }
+    }
+}
+
+impl Validate for RecipeRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.min_length(
+            "name",
+            &self.name,
+            1,
+            "Recipe name must be at least 1 character long",
+        );
+        for (index, item) in self.items.iter().enumerate() {
+            validator.nested(&format!("items.{}", index), item);
+        }
+    }
+}
+
+impl Validate for RecipeItemRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.positive(
+            "dosePerLitre",
+            self.dose_per_litre,
+            "Dose must be greater than 0",
+        );
+    }
+}
+
+impl Validate for ScheduleRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.min_length(
+            "name",
+            &self.name,
+            1,
+            "Schedule name must be at least 1 character long",
+        );
+        for (index, entry) in self.entries.iter().enumerate() {
+            validator.nested(&format!("entries.{}", index), entry);
+        }
+    }
+}
+
+impl Validate for ScheduleEntryRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.min("week", self.week, 1, "Week must be at least 1");
+    }
+}
+
+impl Validate for FeedingLogRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.positive("volume", self.volume, "Volume must be greater than 0");
+        if let Some(ec) = self.ec {
+            validator.min("ec", ec, 0.0, "EC can not be negative");
+        }
+        if let Some(ph) = self.ph {
+            validator.between("ph", ph, 0.0, 14.0, "pH must be between 0 and 14");
+        }
+    }
+}
+
+fn check_weights(validator: &mut Validator, wet_weight: Option<f64>, dry_weight: Option<f64>) {
+    if let Some(wet_weight) = wet_weight {
+        validator.positive("wetWeight", wet_weight, "Wet weight must be greater than 0");
+    }
+    if let Some(dry_weight) = dry_weight {
+        validator.positive("dryWeight", dry_weight, "Dry weight must be greater than 0");
+    }
+}
+
+impl Validate for HarvestRequest {
+    fn check(&self, validator: &mut Validator) {
+        check_weights(validator, self.wet_weight, self.dry_weight);
+    }
+}
+
+impl Validate for Harvest {
+    fn check(&self, validator: &mut Validator) {
+        check_weights(
+            validator,
+            self.wet_weight.flatten(),
+            self.dry_weight.flatten(),
+        );
+    }
+}
+
+impl Validate for SeedPack {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(genetic_id) = &self.genetic_id {
+            validator.uuid("geneticId", genetic_id, "Genetic ID must be set");
+        }
+        if let Some(count) = self.count {
+            validator.min("count", count, 1, "A seed pack contains at least 1 seed");
+        }
+        if let Some(remaining) = self.remaining {
+            validator.min(
+                "remaining",
+                remaining,
+                0,
+                "Remaining seeds must not be negative",
+            );
+        }
+    }
+}
+
+impl Validate for PlantLimit {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(max_plants) = self.max_plants {
+            validator.min(
+                "maxPlants",
+                max_plants,
+                0,
+                "Plant limit must not be negative",
+            );
+        }
+    }
+}
+
+impl Validate for IncidentRequest {
+    fn check(&self, validator: &mut Validator) {
+        validator.min_length(
+            "issue",
+            &self.issue,
+            1,
+            "Observed issue must be at least 1 character long",
+        );
+    }
+}
+
+impl Validate for IncidentUpdateRequest {
+    fn check(&self, validator: &mut Validator) {
+        if let Some(issue) = &self.issue {
+            validator.min_length(
+                "issue",
+                issue,
+                1,
+                "Observed issue must be at least 1 character long",
+            );
+        }
+    }
+}
@@ -0,0 +1,306 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::{
+            attachment::{AttachmentQuery, UploadRequest},
+            AttachmentLinks,
+        },
+        error::{ErrorCode, ErrorResponse},
+        schema::Binary,
+    },
+    prisma::{attachment, PrismaClient},
+    service,
+    storage::Storage,
+};
+use actix_multipart::Multipart;
+use actix_web::{
+    delete, get, guard,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    patch, post, web, HttpResponse, Responder,
+};
+use futures_util::StreamExt;
+use utoipa::OpenApi;
+
+#[doc = "Largest accepted upload in bytes"]
+const MAX_UPLOAD_SIZE: usize = 20 * 1024 * 1024;
+
+#[allow(dead_code)]
+pub fn attachment_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/attachments")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_attachments)
+            .service(get_attachment_by_id)
+            .service(get_attachment_file)
+            .service(get_attachment_thumbnail)
+            .service(upload_attachment)
+            .service(edit_attachment_links)
+            .service(delete_attachment),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_attachments,
+    get_attachment_by_id,
+    get_attachment_file,
+    get_attachment_thumbnail,
+    upload_attachment,
+    edit_attachment_links,
+    delete_attachment,
+))]
+pub struct AttachmentApi;
+
+#[doc = "Read the multipart form with a `file` part and optional `plantId`,
+`geneticId`, `harvestId`, `historyId` and `destructionId` parts"]
+async fn read_upload(mut payload: Multipart) -> Result<UploadRequest, ErrorCode> {
+    let mut upload = UploadRequest::default();
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| ErrorCode::BADREQUEST(e.to_string()))?;
+        let name = field.name().unwrap_or_default().to_string();
+        if name == "file" {
+            upload.file_name = field
+                .content_disposition()
+                .and_then(|disposition| disposition.get_filename())
+                .unwrap_or("upload")
+                .to_string();
+            upload.content_type = field
+                .content_type()
+                .map(|mime| mime.essence_str().to_string());
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| ErrorCode::BADREQUEST(e.to_string()))?;
+            if upload.bytes.len() + bytes.len() + chunk.len() > MAX_UPLOAD_SIZE {
+                return Err(ErrorCode::BADREQUEST(format!(
+                    "File is larger than {} MB",
+                    MAX_UPLOAD_SIZE / 1024 / 1024
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        let value = || Some(String::from_utf8_lossy(&bytes).trim().to_string());
+        match name.as_str() {
+            "file" => upload.bytes = bytes,
+            "plantId" => upload.links.plant_id = value(),
+            "geneticId" => upload.links.genetic_id = value(),
+            "harvestId" => upload.links.harvest_id = value(),
+            "historyId" => upload.links.history_id = value(),
+            "destructionId" => upload.links.destruction_id = value(),
+            _ => (),
+        }
+    }
+
+    Ok(upload)
+}
+
+fn file_response(file_name: &str, content_type: String, bytes: Vec<u8>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(file_name.to_string())],
+        })
+        .body(bytes)
+}
+
+#[utoipa::path(
+    params(AttachmentQuery),
+    responses(
+        (
+            status = 200,
+            description = "Attachments linked to all given entities",
+            body = Vec<attachment::Data>,
+        ),
+    )
+)]
+#[get("")]
+async fn get_attachments(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    query: web::Query<AttachmentQuery>,
+) -> impl Responder {
+    match service::attachment::get_attachments(&data, &tenant.organization_id, query.into_inner())
+        .await
+    {
+        Ok(attachments) => HttpResponse::Ok().json(attachments),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Attachment", body = attachment::Data)))]
+#[get("/{id}")]
+async fn get_attachment_by_id(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::attachment::get_attachment_by_id(&data, &tenant.organization_id, id.into_inner())
+        .await
+    {
+        Ok(attachment) => HttpResponse::Ok().json(attachment),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "Stored file with its original content type",
+            content_type = "application/octet-stream",
+            body = Binary,
+        ),
+    )
+)]
+#[get("/{id}/file")]
+async fn get_attachment_file(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    storage: web::Data<dyn Storage>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::attachment::get_attachment_file(
+        &data,
+        &tenant.organization_id,
+        storage.get_ref(),
+        id.into_inner(),
+        false,
+    )
+    .await
+    {
+        Ok((attachment, content_type, bytes)) => {
+            file_response(&attachment.file_name, content_type, bytes)
+        }
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "Thumbnail of a photo",
+            content_type = "image/jpeg",
+            body = Binary,
+        ),
+    )
+)]
+#[get("/{id}/thumbnail")]
+async fn get_attachment_thumbnail(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    storage: web::Data<dyn Storage>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::attachment::get_attachment_file(
+        &data,
+        &tenant.organization_id,
+        storage.get_ref(),
+        id.into_inner(),
+        true,
+    )
+    .await
+    {
+        Ok((attachment, content_type, bytes)) => file_response(
+            &format!("thumbnail-{}.jpg", attachment.id),
+            content_type,
+            bytes,
+        ),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    request_body(
+        content = Object,
+        content_type = "multipart/form-data",
+        description = "The `file` with the ids of the linked entities as in `AttachmentLinksInput`"
+    ),
+    responses(
+        (status = 201, description = "Uploaded attachment", body = attachment::Data),
+    )
+)]
+#[post("")]
+async fn upload_attachment(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    storage: web::Data<dyn Storage>,
+    payload: Multipart,
+) -> impl Responder {
+    let upload = match read_upload(payload).await {
+        Ok(upload) => upload,
+        Err(e) => return ErrorResponse::build(e),
+    };
+    match service::attachment::upload_attachment(
+        &data,
+        &tenant.organization_id,
+        storage.get_ref(),
+        upload,
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(attachment) => HttpResponse::Created().json(attachment),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Attachment with its new links", body = attachment::Data),
+    )
+)]
+#[patch("/{id}")]
+async fn edit_attachment_links(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<AttachmentLinks>,
+) -> impl Responder {
+    match service::attachment::edit_attachment_links(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(attachment) => HttpResponse::Ok().json(attachment),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Attachment and its files deleted")))]
+#[delete("/{id}")]
+async fn delete_attachment(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    storage: web::Data<dyn Storage>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::attachment::delete_attachment(
+        &data,
+        &tenant.organization_id,
+        storage.get_ref(),
+        id.into_inner(),
+    )
+    .await
+    {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
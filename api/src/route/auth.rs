@@ -2,14 +2,19 @@
  * Copyright (c) Johannes Grimm 2024.
  */
 
+use crate::middleware::tenant::ORGANIZATION_KEY;
 use crate::model::dto::auth::{LoginRequest, RegisterRequest};
-use crate::model::error::ErrorResponse;
+use crate::model::error::{ErrorCode, ErrorResponse};
 use crate::prisma::{user, PrismaClient};
 use crate::service::authentication::{login_user, register_user};
+use crate::service::metrics::{metrics, TimedQuery};
+use crate::service::organization::first_organization;
 
 use actix_identity::Identity;
+use actix_session::Session;
 use actix_web::web::Json;
 use actix_web::{get, post, web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use utoipa::OpenApi;
 
 #[allow(dead_code)]
 pub fn auth_controller_init(cfg: &mut web::ServiceConfig) {
@@ -22,28 +27,61 @@ pub fn auth_controller_init(cfg: &mut web::ServiceConfig) {
     );
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(login, logout, register, profile))]
+pub struct AuthApi;
+
+#[utoipa::path(
+    security(()),
+    responses(
+        (
+            status = 200,
+            description = "Logged in user,
+            the session cookie is set and works in the organization the user joined first",
+            body = user::Data,
+        ),
+    )
+)]
 #[post("/login")]
 async fn login(
     body: Json<LoginRequest>,
     req: HttpRequest,
+    session: Session,
     data: web::Data<PrismaClient>,
 ) -> impl Responder {
-    let login_result = login_user(body.into_inner(), data).await;
+    let login_result = login_user(body.into_inner(), data.clone()).await;
+    metrics().observe_login(login_result.is_ok());
     match login_result {
         Ok(user) => {
+            let organization_id = match first_organization(&data, &user.id).await {
+                Ok(organization_id) => organization_id,
+                Err(e) => return ErrorResponse::build(e),
+            };
             Identity::login(&req.extensions(), user.id.clone()).unwrap();
+            if let Some(organization_id) = organization_id {
+                if session.insert(ORGANIZATION_KEY, organization_id).is_err() {
+                    return ErrorResponse::build(ErrorCode::INTERNAL001);
+                }
+            }
             HttpResponse::Ok().json(user)
         }
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(responses((status = 200, description = "Session ended")))]
 #[post("/logout")]
 async fn logout(ident: Identity) -> impl Responder {
     ident.logout();
     HttpResponse::Ok().finish()
 }
 
+#[utoipa::path(
+    security(()),
+    responses(
+        (status = 200, description = "Registered user", body = user::Data),
+    )
+)]
 #[post("/register")]
 async fn register(body: Json<RegisterRequest>, data: web::Data<PrismaClient>) -> impl Responder {
     let register_result = register_user(&body.into_inner(), &data).await;
@@ -53,6 +91,12 @@ async fn register(body: Json<RegisterRequest>, data: web::Data<PrismaClient>) ->
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Logged in user", body = user::Data),
+        (status = 401, description = "Not logged in"),
+    )
+)]
 #[get("/profile")]
 async fn profile(usr: Option<Identity>, data: web::Data<PrismaClient>) -> impl Responder {
     if let Some(usr) = usr {
@@ -60,6 +104,7 @@ async fn profile(usr: Option<Identity>, data: web::Data<PrismaClient>) -> impl R
             .user()
             .find_unique(user::id::equals(usr.id().unwrap()))
             .exec()
+            .timed("user", "find_unique")
             .await
             .unwrap();
         HttpResponse::Ok().json(usr.unwrap())
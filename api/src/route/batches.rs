@@ -0,0 +1,98 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{dto::Batch, error::ErrorResponse},
+    prisma::{batch, PrismaClient},
+    service,
+};
+use actix_web::{delete, get, guard, patch, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn batch_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/batches")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_batches)
+            .service(get_batch_by_id)
+            .service(create_batch)
+            .service(delete_batch)
+            .service(edit_batch),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_batches, get_batch_by_id, create_batch, delete_batch, edit_batch))]
+pub struct BatchApi;
+
+#[utoipa::path(responses((status = 200, description = "Batches", body = Vec<batch::Data>)))]
+#[get("")]
+async fn get_batches(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::batch::get_batches(&data, &tenant.organization_id).await {
+        Ok(batches) => HttpResponse::Ok().json(batches),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Batch", body = batch::Data)))]
+#[get("/{id}")]
+async fn get_batch_by_id(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::batch::get_batch_by_id(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(batch) => HttpResponse::Ok().json(batch),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 201, description = "Created batch", body = batch::Data)))]
+#[post("")]
+async fn create_batch(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<Batch>,
+) -> impl Responder {
+    match service::batch::create_batch(&data, &tenant.organization_id, body.into_inner()).await {
+        Ok(batch) => HttpResponse::Created().json(batch),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Updated batch", body = batch::Data)))]
+#[patch("/{id}")]
+async fn edit_batch(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<Batch>,
+) -> impl Responder {
+    match service::batch::edit_batch(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(batch) => HttpResponse::Ok().json(batch),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Batch deleted")))]
+#[delete("/{id}")]
+async fn delete_batch(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::batch::delete_batch(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
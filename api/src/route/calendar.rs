@@ -0,0 +1,79 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::auth::{identity_id, verify_token},
+    model::error::ErrorResponse,
+    prisma::PrismaClient,
+    service,
+};
+use actix_identity::Identity;
+use actix_web::{get, post, web, HttpResponse, Responder};
+use serde_json::json;
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn calendar_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    // The feed itself is authenticated by its token, calendar apps can not log in
+    cfg.service(
+        web::scope("/calendar")
+            .service(regenerate_calendar_token)
+            .service(get_calendar_feed),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(regenerate_calendar_token, get_calendar_feed))]
+pub struct CalendarApi;
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "New calendar `token` and the `path` of its feed",
+            body = Object,
+        ),
+    )
+)]
+#[post("/token", guard = "verify_token")]
+async fn regenerate_calendar_token(
+    identity: Identity,
+    data: web::Data<PrismaClient>,
+) -> impl Responder {
+    let user_id = match identity_id(&identity) {
+        Ok(user_id) => user_id,
+        Err(e) => return ErrorResponse::build(e),
+    };
+    match service::calendar::regenerate_calendar_token(&data, user_id).await {
+        Ok(token) => HttpResponse::Ok().json(json!({
+            "token": token,
+            "path": format!("/api/calendar/{}.ics", token),
+        })),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    security(()),
+    responses(
+        (
+            status = 200,
+            description = "iCalendar feed of the tasks assigned to the token owner",
+            content_type = "text/calendar",
+            body = String,
+        ),
+    )
+)]
+#[get("/{token}.ics")]
+async fn get_calendar_feed(
+    data: web::Data<PrismaClient>,
+    token: web::Path<String>,
+) -> impl Responder {
+    match service::calendar::get_calendar_feed(&data, token.into_inner()).await {
+        Ok(feed) => HttpResponse::Ok()
+            .content_type("text/calendar; charset=utf-8")
+            .body(feed),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
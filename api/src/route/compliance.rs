@@ -0,0 +1,123 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::{compliance::LimitUsage, PlantLimit},
+        error::ErrorResponse,
+    },
+    prisma::{plant_limit, PrismaClient},
+    service,
+};
+use actix_web::{delete, get, guard, patch, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn compliance_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/compliance")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_compliance_dashboard)
+            .service(get_plant_limits)
+            .service(create_plant_limit)
+            .service(edit_plant_limit)
+            .service(delete_plant_limit),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_compliance_dashboard,
+    get_plant_limits,
+    create_plant_limit,
+    edit_plant_limit,
+    delete_plant_limit,
+))]
+pub struct ComplianceApi;
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Usage of every configured limit", body = Vec<LimitUsage>),
+    )
+)]
+#[get("")]
+async fn get_compliance_dashboard(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::compliance::get_compliance_dashboard(&data, &tenant.organization_id).await {
+        Ok(usage) => HttpResponse::Ok().json(usage),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Configured plant limits", body = Vec<plant_limit::Data>),
+    )
+)]
+#[get("/limits")]
+async fn get_plant_limits(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::compliance::get_plant_limits(&data, &tenant.organization_id).await {
+        Ok(limits) => HttpResponse::Ok().json(limits),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Created plant limit", body = plant_limit::Data),
+    )
+)]
+#[post("/limits")]
+async fn create_plant_limit(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<PlantLimit>,
+) -> impl Responder {
+    match service::compliance::create_plant_limit(&data, &tenant.organization_id, body.into_inner())
+        .await
+    {
+        Ok(limit) => HttpResponse::Created().json(limit),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Updated plant limit", body = plant_limit::Data),
+    )
+)]
+#[patch("/limits/{id}")]
+async fn edit_plant_limit(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<PlantLimit>,
+) -> impl Responder {
+    match service::compliance::edit_plant_limit(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(limit) => HttpResponse::Ok().json(limit),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Plant limit deleted")))]
+#[delete("/limits/{id}")]
+async fn delete_plant_limit(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::compliance::delete_plant_limit(&data, &tenant.organization_id, id.into_inner())
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
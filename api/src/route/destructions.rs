@@ -0,0 +1,96 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::{
+            destruction::{WasteLogEntry, WasteLogQuery},
+            report::ReportFormat,
+        },
+        error::{ErrorCode, ErrorResponse},
+    },
+    prisma::PrismaClient,
+    service,
+};
+use actix_web::{
+    get, guard,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web, HttpResponse, Responder,
+};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn destruction_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/destructions")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_waste_log)
+            .service(get_destruction_by_id),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_waste_log, get_destruction_by_id))]
+pub struct DestructionApi;
+
+#[utoipa::path(
+    params(WasteLogQuery),
+    responses(
+        (
+            status = 200,
+            description = "Waste log in the requested format",
+            content(
+                (Vec<WasteLogEntry> = "application/json"),
+                (String = "text/csv"),
+            ),
+        ),
+    )
+)]
+#[get("")]
+async fn get_waste_log(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    query: web::Query<WasteLogQuery>,
+) -> impl Responder {
+    let entries =
+        match service::destruction::get_waste_log(&data, &tenant.organization_id, &query).await {
+            Ok(entries) => entries,
+            Err(e) => return ErrorResponse::build(e),
+        };
+    match query.format {
+        ReportFormat::Json => HttpResponse::Ok().json(entries),
+        ReportFormat::Csv => match service::destruction::waste_log_csv(&entries) {
+            Ok(csv) => HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .insert_header(ContentDisposition {
+                    disposition: DispositionType::Attachment,
+                    parameters: vec![DispositionParam::Filename("waste-log.csv".to_string())],
+                })
+                .body(csv),
+            Err(_) => ErrorResponse::build(ErrorCode::INTERNAL001),
+        },
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Destruction record", body = WasteLogEntry)))]
+#[get("/{id}")]
+async fn get_destruction_by_id(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::destruction::get_destruction_by_id(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+    )
+    .await
+    {
+        Ok(destruction) => HttpResponse::Ok().json(destruction),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
@@ -0,0 +1,301 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::{
+            feeding::{
+                FeedingLogQuery, FeedingLogRequest, FeedingPlan, RecipeRequest, ScheduleRequest,
+            },
+            NutrientProduct,
+        },
+        error::ErrorResponse,
+    },
+    prisma::{feed_recipe, feed_schedule, feeding_log, nutrient_product, PrismaClient},
+    service,
+};
+use actix_web::{delete, get, guard, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn feeding_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/feeding")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_products)
+            .service(create_product)
+            .service(delete_product)
+            .service(get_recipes)
+            .service(create_recipe)
+            .service(delete_recipe)
+            .service(get_schedules)
+            .service(get_schedule_by_id)
+            .service(create_schedule)
+            .service(delete_schedule)
+            .service(get_plant_feeding_plan)
+            .service(get_batch_feeding_plan)
+            .service(get_feeding_logs)
+            .service(log_feeding),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_products,
+    create_product,
+    delete_product,
+    get_recipes,
+    create_recipe,
+    delete_recipe,
+    get_schedules,
+    get_schedule_by_id,
+    create_schedule,
+    delete_schedule,
+    get_plant_feeding_plan,
+    get_batch_feeding_plan,
+    get_feeding_logs,
+    log_feeding,
+))]
+pub struct FeedingApi;
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Nutrient products", body = Vec<nutrient_product::Data>),
+    )
+)]
+#[get("/products")]
+async fn get_products(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::feeding::get_products(&data, &tenant.organization_id).await {
+        Ok(products) => HttpResponse::Ok().json(products),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Created nutrient product", body = nutrient_product::Data),
+    )
+)]
+#[post("/products")]
+async fn create_product(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<NutrientProduct>,
+) -> impl Responder {
+    match service::feeding::create_product(&data, &tenant.organization_id, body.into_inner()).await
+    {
+        Ok(product) => HttpResponse::Created().json(product),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Nutrient product deleted")))]
+#[delete("/products/{id}")]
+async fn delete_product(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::feeding::delete_product(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "Feed recipes with their items",
+            body = Vec<feed_recipe::Data>,
+        ),
+    )
+)]
+#[get("/recipes")]
+async fn get_recipes(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::feeding::get_recipes(&data, &tenant.organization_id).await {
+        Ok(recipes) => HttpResponse::Ok().json(recipes),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Created feed recipe", body = feed_recipe::Data),
+    )
+)]
+#[post("/recipes")]
+async fn create_recipe(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<RecipeRequest>,
+) -> impl Responder {
+    match service::feeding::create_recipe(&data, &tenant.organization_id, body.into_inner()).await {
+        Ok(recipe) => HttpResponse::Created().json(recipe),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Feed recipe deleted")))]
+#[delete("/recipes/{id}")]
+async fn delete_recipe(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::feeding::delete_recipe(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Feed schedules", body = Vec<feed_schedule::Data>),
+    )
+)]
+#[get("/schedules")]
+async fn get_schedules(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::feeding::get_schedules(&data, &tenant.organization_id).await {
+        Ok(schedules) => HttpResponse::Ok().json(schedules),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Feed schedule with its entries", body = feed_schedule::Data),
+    )
+)]
+#[get("/schedules/{id}")]
+async fn get_schedule_by_id(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::feeding::get_schedule_by_id(&data, &tenant.organization_id, id.into_inner())
+        .await
+    {
+        Ok(schedule) => HttpResponse::Ok().json(schedule),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Created feed schedule", body = feed_schedule::Data),
+    )
+)]
+#[post("/schedules")]
+async fn create_schedule(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<ScheduleRequest>,
+) -> impl Responder {
+    match service::feeding::create_schedule(&data, &tenant.organization_id, body.into_inner()).await
+    {
+        Ok(schedule) => HttpResponse::Created().json(schedule),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Feed schedule deleted")))]
+#[delete("/schedules/{id}")]
+async fn delete_schedule(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::feeding::delete_schedule(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "What the plant should receive today", body = FeedingPlan),
+    )
+)]
+#[get("/plants/{id}/today")]
+async fn get_plant_feeding_plan(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::feeding::get_plant_feeding_plan(&data, &tenant.organization_id, id.into_inner())
+        .await
+    {
+        Ok(plan) => HttpResponse::Ok().json(plan),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "What the plants of the batch should receive today",
+            body = Vec<FeedingPlan>,
+        ),
+    )
+)]
+#[get("/batches/{id}/today")]
+async fn get_batch_feeding_plan(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::feeding::get_batch_feeding_plan(&data, &tenant.organization_id, id.into_inner())
+        .await
+    {
+        Ok(plans) => HttpResponse::Ok().json(plans),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    params(FeedingLogQuery),
+    responses(
+        (status = 200, description = "Feedings of the plant", body = Vec<feeding_log::Data>),
+    )
+)]
+#[get("/logs")]
+async fn get_feeding_logs(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    query: web::Query<FeedingLogQuery>,
+) -> impl Responder {
+    match service::feeding::get_feeding_logs(
+        &data,
+        &tenant.organization_id,
+        query.into_inner().plant_id,
+    )
+    .await
+    {
+        Ok(logs) => HttpResponse::Ok().json(logs),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 201, description = "Logged feeding", body = feeding_log::Data)))]
+#[post("/logs")]
+async fn log_feeding(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<FeedingLogRequest>,
+) -> impl Responder {
+    match service::feeding::log_feeding(
+        &data,
+        &tenant.organization_id,
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(log) => HttpResponse::Created().json(log),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
@@ -3,66 +3,255 @@
  */
 
 use crate::{
-    middleware::auth::verify_token,
-    model::{dto::Genetic, error::ErrorResponse},
-    prisma::PrismaClient,
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::{
+            analytics::{AnalyticsQuery, GeneticAnalytics},
+            bulk_import::GeneticImportPreview,
+            genetic_search::GeneticQuery,
+            pedigree::{Pedigree, PedigreeQuery},
+            Genetic,
+        },
+        error::ErrorResponse,
+    },
+    prisma::{genetic, PrismaClient},
     service,
 };
 use actix_web::{delete, get, guard, patch, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
 
 #[allow(dead_code)]
 pub fn genetic_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
     cfg.service(
         web::scope("/genetics")
             .guard(guard::fn_guard(verify_token))
+            .app_data(web::PayloadConfig::new(service::bulk_import::MAX_CSV_SIZE))
+            .service(preview_genetic_import)
+            .service(import_genetics)
+            .service(get_all_genetic_analytics)
             .service(get_genetics)
             .service(get_genetic_by_id)
+            .service(get_genetic_pedigree)
+            .service(get_genetic_analytics)
             .service(create_genetic)
             .service(delete_genetic)
             .service(edit_genetic),
     );
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    preview_genetic_import,
+    import_genetics,
+    get_all_genetic_analytics,
+    get_genetics,
+    get_genetic_by_id,
+    get_genetic_pedigree,
+    get_genetic_analytics,
+    create_genetic,
+    delete_genetic,
+    edit_genetic,
+))]
+pub struct GeneticApi;
+
+#[utoipa::path(
+    params(GeneticQuery),
+    responses(
+        (status = 200, description = "Genetics matching the query", body = Vec<genetic::Data>),
+    )
+)]
 #[get("")]
-async fn get_genetics(data: web::Data<PrismaClient>) -> impl Responder {
-    match service::genetic::get_genetics(&data).await {
+async fn get_genetics(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    query: web::Query<GeneticQuery>,
+) -> impl Responder {
+    match service::genetic::get_genetics(&data, &tenant.organization_id, query.into_inner()).await {
         Ok(genetics) => HttpResponse::Ok().json(genetics),
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(responses((status = 200, description = "Genetic", body = genetic::Data)))]
 #[get("/{id}")]
-async fn get_genetic_by_id(data: web::Data<PrismaClient>, id: web::Path<String>) -> impl Responder {
-    match service::genetic::get_genetic_by_id(&data, id.into_inner()).await {
+async fn get_genetic_by_id(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::genetic::get_genetic_by_id(&data, &tenant.organization_id, id.into_inner()).await
+    {
         Ok(genetics) => HttpResponse::Ok().json(genetics),
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(responses((status = 201, description = "Created genetic", body = genetic::Data)))]
 #[post("")]
-async fn create_genetic(data: web::Data<PrismaClient>, body: web::Json<Genetic>) -> impl Responder {
-    match service::genetic::create_genetic(&data, body.into_inner()).await {
+async fn create_genetic(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<Genetic>,
+) -> impl Responder {
+    match service::genetic::create_genetic(&data, &tenant.organization_id, body.into_inner()).await
+    {
         Ok(genetics) => HttpResponse::Created().json(genetics),
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(
+    request_body(content = String, content_type = "text/csv"),
+    responses(
+        (
+            status = 200,
+            description = "Genetics the file would create and the errors of its rows",
+            body = GeneticImportPreview,
+        ),
+    )
+)]
+#[post("/import/preview")]
+async fn preview_genetic_import(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: String,
+) -> impl Responder {
+    match service::bulk_import::preview_genetics(&data, &tenant.organization_id, body).await {
+        Ok(preview) => HttpResponse::Ok().json(preview),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    request_body(content = String, content_type = "text/csv"),
+    responses(
+        (status = 201, description = "Created genetics", body = Vec<genetic::Data>),
+        (status = 422, description = "Errors of the rows, nothing was created", body = ErrorResponse),
+    )
+)]
+#[post("/import")]
+async fn import_genetics(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: String,
+) -> impl Responder {
+    match service::bulk_import::import_genetics(&data, &tenant.organization_id, body).await {
+        Ok(genetics) => HttpResponse::Created().json(genetics),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Updated genetic", body = genetic::Data)))]
 #[patch("/{id}")]
 async fn edit_genetic(
+    tenant: Tenant,
+
     data: web::Data<PrismaClient>,
     id: web::Path<String>,
     body: web::Json<Genetic>,
 ) -> impl Responder {
-    match service::genetic::edit_genetic(&data, id.into_inner(), body.into_inner()).await {
+    match service::genetic::edit_genetic(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
         Ok(genetics) => HttpResponse::Ok().json(genetics),
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(responses((status = 200, description = "Genetic deleted")))]
 #[delete("/{id}")]
-async fn delete_genetic(data: web::Data<PrismaClient>, id: web::Path<String>) -> impl Responder {
-    match service::genetic::delete_genetic(&data, id.into_inner()).await {
+async fn delete_genetic(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::genetic::delete_genetic(&data, &tenant.organization_id, id.into_inner()).await {
         Ok(genetics) => HttpResponse::Ok().json(genetics),
         Err(e) => ErrorResponse::build(e),
     }
 }
+
+#[utoipa::path(
+    params(PedigreeQuery),
+    responses(
+        (status = 200, description = "Family tree of the genetic", body = Pedigree),
+    )
+)]
+#[get("/{id}/pedigree")]
+async fn get_genetic_pedigree(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    query: web::Query<PedigreeQuery>,
+) -> impl Responder {
+    match service::genetic::get_pedigree(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        query.depth,
+    )
+    .await
+    {
+        Ok(pedigree) => HttpResponse::Ok().json(pedigree),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "Performance of every genetic", body = Vec<GeneticAnalytics>),
+    )
+)]
+#[get("/analytics")]
+async fn get_all_genetic_analytics(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    query: web::Query<AnalyticsQuery>,
+) -> impl Responder {
+    match service::analytics::get_all_genetic_analytics(
+        &data,
+        &tenant.organization_id,
+        query.into_inner(),
+    )
+    .await
+    {
+        Ok(analytics) => HttpResponse::Ok().json(analytics),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "Performance of the genetic", body = GeneticAnalytics),
+    )
+)]
+#[get("/{id}/analytics")]
+async fn get_genetic_analytics(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    query: web::Query<AnalyticsQuery>,
+) -> impl Responder {
+    match service::analytics::get_genetic_analytics(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        query.into_inner(),
+    )
+    .await
+    {
+        Ok(analytics) => HttpResponse::Ok().json(analytics),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
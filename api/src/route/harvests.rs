@@ -0,0 +1,96 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::{Harvest, HarvestRequest},
+        error::ErrorResponse,
+    },
+    prisma::{harvest, PrismaClient},
+    service,
+};
+use actix_web::{get, guard, patch, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn harvest_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/harvests")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_harvests)
+            .service(get_harvest_by_id)
+            .service(create_harvest)
+            .service(edit_harvest),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_harvests, get_harvest_by_id, create_harvest, edit_harvest))]
+pub struct HarvestApi;
+
+#[utoipa::path(responses((status = 200, description = "Harvests", body = Vec<harvest::Data>)))]
+#[get("")]
+async fn get_harvests(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::harvest::get_harvests(&data, &tenant.organization_id).await {
+        Ok(harvests) => HttpResponse::Ok().json(harvests),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Harvest", body = harvest::Data)))]
+#[get("/{id}")]
+async fn get_harvest_by_id(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::harvest::get_harvest_by_id(&data, &tenant.organization_id, id.into_inner()).await
+    {
+        Ok(harvest) => HttpResponse::Ok().json(harvest),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 201, description = "Recorded harvest", body = harvest::Data)))]
+#[post("")]
+async fn create_harvest(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<HarvestRequest>,
+) -> impl Responder {
+    match service::harvest::create_harvest(
+        &data,
+        &tenant.organization_id,
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(harvest) => HttpResponse::Created().json(harvest),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Updated harvest", body = harvest::Data)))]
+#[patch("/{id}")]
+async fn edit_harvest(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<Harvest>,
+) -> impl Responder {
+    match service::harvest::edit_harvest(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(harvest) => HttpResponse::Ok().json(harvest),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
@@ -2,10 +2,51 @@
  * Copyright (c) Johannes Grimm 2024.
  */
 
-use actix_web::{get, HttpResponse, Responder};
+use actix_web::{get, web, HttpResponse, Responder};
+use utoipa::OpenApi;
 
+use crate::{
+    model::dto::health::{HealthStatus, Readiness},
+    prisma::PrismaClient,
+    server::redis_address,
+    service,
+    storage::Storage,
+};
+
+#[derive(OpenApi)]
+#[openapi(paths(health_check, liveness, readiness))]
+pub struct HealthCheckApi;
+
+#[utoipa::path(security(()), responses((status = 200, description = "Service is up")))]
 #[tracing::instrument(name = "Checking the application health")]
 #[get("/health_check")]
 pub async fn health_check() -> impl Responder {
     HttpResponse::Ok().finish()
 }
+
+#[doc = "The process is running and serving requests, dependencies are not probed"]
+#[utoipa::path(security(()), responses((status = 200, description = "Service is alive")))]
+#[get("/health/live")]
+pub async fn liveness() -> impl Responder {
+    HttpResponse::Ok().finish()
+}
+
+#[doc = "Probe the database, the session store and the storage"]
+#[utoipa::path(
+    security(()),
+    responses(
+        (status = 200, description = "Service is ready, maybe degraded", body = Readiness),
+        (status = 503, description = "A critical dependency failed", body = Readiness),
+    )
+)]
+#[get("/health/ready")]
+pub async fn readiness(
+    data: web::Data<PrismaClient>,
+    storage: web::Data<dyn Storage>,
+) -> impl Responder {
+    let readiness = service::health::get_readiness(&data, &**storage, &redis_address()).await;
+    match readiness.status {
+        HealthStatus::Down => HttpResponse::ServiceUnavailable().json(readiness),
+        _ => HttpResponse::Ok().json(readiness),
+    }
+}
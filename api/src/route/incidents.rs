@@ -0,0 +1,229 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::incident::{
+            FollowUpRequest, IncidentQuery, IncidentRequest, IncidentUpdateRequest, ResolveRequest,
+            TreatmentRequest,
+        },
+        error::ErrorResponse,
+    },
+    prisma::{incident, incident_follow_up, treatment, PrismaClient},
+    service,
+};
+use actix_web::{delete, get, guard, patch, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn incident_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/incidents")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_incidents)
+            .service(get_incident_by_id)
+            .service(create_incident)
+            .service(edit_incident)
+            .service(delete_incident)
+            .service(add_treatment)
+            .service(add_follow_up)
+            .service(resolve_incident),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_incidents,
+    get_incident_by_id,
+    create_incident,
+    edit_incident,
+    delete_incident,
+    add_treatment,
+    add_follow_up,
+    resolve_incident,
+))]
+pub struct IncidentApi;
+
+#[utoipa::path(
+    params(IncidentQuery),
+    responses(
+        (
+            status = 200,
+            description = "Incidents with their plants,
+            locations,
+            treatments and follow-ups",
+            body = Vec<incident::Data>,
+        ),
+    )
+)]
+#[get("")]
+async fn get_incidents(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    query: web::Query<IncidentQuery>,
+) -> impl Responder {
+    match service::incident::get_incidents(&data, &tenant.organization_id, query.into_inner()).await
+    {
+        Ok(incidents) => HttpResponse::Ok().json(incidents),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "Incident with its plants,
+            locations,
+            treatments and follow-ups",
+            body = incident::Data,
+        ),
+    )
+)]
+#[get("/{id}")]
+async fn get_incident_by_id(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::incident::get_incident_by_id(&data, &tenant.organization_id, id.into_inner())
+        .await
+    {
+        Ok(incident) => HttpResponse::Ok().json(incident),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 201, description = "Reported incident", body = incident::Data)))]
+#[post("")]
+async fn create_incident(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<IncidentRequest>,
+) -> impl Responder {
+    match service::incident::create_incident(
+        &data,
+        &tenant.organization_id,
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(incident) => HttpResponse::Created().json(incident),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Updated incident", body = incident::Data)))]
+#[patch("/{id}")]
+async fn edit_incident(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<IncidentUpdateRequest>,
+) -> impl Responder {
+    match service::incident::edit_incident(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(incident) => HttpResponse::Ok().json(incident),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Incident deleted")))]
+#[delete("/{id}")]
+async fn delete_incident(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::incident::delete_incident(&data, &tenant.organization_id, id.into_inner()).await
+    {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Recorded treatment", body = treatment::Data),
+    )
+)]
+#[post("/{id}/treatments")]
+async fn add_treatment(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<TreatmentRequest>,
+) -> impl Responder {
+    match service::incident::add_treatment(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(treatment) => HttpResponse::Created().json(treatment),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Recorded follow-up", body = incident_follow_up::Data),
+    )
+)]
+#[post("/{id}/follow-ups")]
+async fn add_follow_up(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<FollowUpRequest>,
+) -> impl Responder {
+    match service::incident::add_follow_up(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(follow_up) => HttpResponse::Created().json(follow_up),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Resolved incident", body = incident::Data)))]
+#[post("/{id}/resolve")]
+async fn resolve_incident(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<ResolveRequest>,
+) -> impl Responder {
+    match service::incident::resolve_incident(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(incident) => HttpResponse::Ok().json(incident),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
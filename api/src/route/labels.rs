@@ -0,0 +1,127 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::label::{LabelQuery, ResolveRequest},
+        error::ErrorResponse,
+        schema::Binary,
+    },
+    prisma::{plant, PrismaClient},
+    service::{self, label::RenderedLabels},
+};
+use actix_web::{
+    get, guard,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    post, web, HttpResponse, Responder,
+};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn label_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/labels")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_plant_labels)
+            .service(get_batch_labels)
+            .service(resolve_label),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_plant_labels, get_batch_labels, resolve_label))]
+pub struct LabelApi;
+
+fn labels_response(labels: RenderedLabels) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(labels.content_type)
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(labels.file_name)],
+        })
+        .body(labels.bytes)
+}
+
+#[utoipa::path(
+    params(LabelQuery),
+    responses(
+        (
+            status = 200,
+            description = "Label of the plant",
+            content((Binary = "application/pdf"), (String = "application/zpl")),
+        ),
+    )
+)]
+#[get("/plants/{id}")]
+async fn get_plant_labels(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    query: web::Query<LabelQuery>,
+) -> impl Responder {
+    match service::label::get_plant_labels(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        query.into_inner(),
+    )
+    .await
+    {
+        Ok(labels) => labels_response(labels),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    params(LabelQuery),
+    responses(
+        (
+            status = 200,
+            description = "Labels of the plants of the batch",
+            content((Binary = "application/pdf"), (String = "application/zpl")),
+        ),
+    )
+)]
+#[get("/batches/{id}")]
+async fn get_batch_labels(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    query: web::Query<LabelQuery>,
+) -> impl Responder {
+    match service::label::get_batch_labels(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        query.into_inner(),
+    )
+    .await
+    {
+        Ok(labels) => labels_response(labels),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Plant of the scanned label", body = plant::Data),
+    )
+)]
+#[post("/resolve")]
+async fn resolve_label(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    body: web::Json<ResolveRequest>,
+) -> impl Responder {
+    match service::label::resolve_label(&data, &tenant.organization_id, body.into_inner().payload)
+        .await
+    {
+        Ok(plant) => HttpResponse::Ok().json(plant),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{dto::Location, error::ErrorResponse},
+    prisma::{location, PrismaClient},
+    service,
+};
+use actix_web::{delete, get, guard, patch, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn location_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/locations")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_locations)
+            .service(get_location_by_id)
+            .service(create_location)
+            .service(delete_location)
+            .service(edit_location),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_locations,
+    get_location_by_id,
+    create_location,
+    delete_location,
+    edit_location,
+))]
+pub struct LocationApi;
+
+#[utoipa::path(responses((status = 200, description = "Locations", body = Vec<location::Data>)))]
+#[get("")]
+async fn get_locations(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::location::get_locations(&data, &tenant.organization_id).await {
+        Ok(locations) => HttpResponse::Ok().json(locations),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Location", body = location::Data)))]
+#[get("/{id}")]
+async fn get_location_by_id(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::location::get_location_by_id(&data, &tenant.organization_id, id.into_inner())
+        .await
+    {
+        Ok(location) => HttpResponse::Ok().json(location),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 201, description = "Created location", body = location::Data)))]
+#[post("")]
+async fn create_location(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<Location>,
+) -> impl Responder {
+    match service::location::create_location(&data, &tenant.organization_id, body.into_inner())
+        .await
+    {
+        Ok(location) => HttpResponse::Created().json(location),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Updated location", body = location::Data)))]
+#[patch("/{id}")]
+async fn edit_location(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<Location>,
+) -> impl Responder {
+    match service::location::edit_location(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(location) => HttpResponse::Ok().json(location),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Location deleted")))]
+#[delete("/{id}")]
+async fn delete_location(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::location::delete_location(&data, &tenant.organization_id, id.into_inner()).await
+    {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
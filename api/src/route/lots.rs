@@ -0,0 +1,148 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::{
+            lot::{CreateLotRequest, LotQuery, SaleRequest},
+            Lot,
+        },
+        error::ErrorResponse,
+    },
+    prisma::{lot, sale, PrismaClient},
+    service,
+};
+use actix_web::{delete, get, guard, patch, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn lot_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/lots")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_lots)
+            .service(get_lot_by_id)
+            .service(create_lot)
+            .service(edit_lot)
+            .service(delete_lot)
+            .service(record_sale),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_lots, get_lot_by_id, create_lot, edit_lot, delete_lot, record_sale))]
+pub struct LotApi;
+
+#[utoipa::path(
+    params(LotQuery),
+    responses(
+        (status = 200, description = "Lots with their harvests and sales", body = Vec<lot::Data>),
+    )
+)]
+#[get("")]
+async fn get_lots(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    query: web::Query<LotQuery>,
+) -> impl Responder {
+    match service::lot::get_lots(&data, &tenant.organization_id, query.into_inner()).await {
+        Ok(lots) => HttpResponse::Ok().json(lots),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Lot with its harvests and sales", body = lot::Data),
+    )
+)]
+#[get("/{id}")]
+async fn get_lot_by_id(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::lot::get_lot_by_id(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(lot) => HttpResponse::Ok().json(lot),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 201, description = "Packaged lot", body = lot::Data)))]
+#[post("")]
+async fn create_lot(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<CreateLotRequest>,
+) -> impl Responder {
+    match service::lot::create_lot(
+        &data,
+        &tenant.organization_id,
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(lot) => HttpResponse::Created().json(lot),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Updated lot", body = lot::Data)))]
+#[patch("/{id}")]
+async fn edit_lot(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<Lot>,
+) -> impl Responder {
+    match service::lot::edit_lot(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(lot) => HttpResponse::Ok().json(lot),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Lot deleted")))]
+#[delete("/{id}")]
+async fn delete_lot(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::lot::delete_lot(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 201, description = "Recorded sale", body = sale::Data)))]
+#[post("/{id}/sales")]
+async fn record_sale(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<SaleRequest>,
+) -> impl Responder {
+    match service::lot::record_sale(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(sale) => HttpResponse::Created().json(sale),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
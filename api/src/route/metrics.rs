@@ -0,0 +1,32 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::{get, web, HttpResponse, Responder};
+
+use crate::{
+    prisma::PrismaClient,
+    server::redis_address,
+    service::metrics::{metrics, refresh},
+};
+
+#[doc = "Served next to `/api` at the root, where Prometheus looks by default"]
+#[utoipa::path(
+    tag = "metrics",
+    security(()),
+    responses(
+        (
+            status = 200,
+            description = "Metrics in the Prometheus text format",
+            body = String,
+            content_type = "text/plain",
+        ),
+    )
+)]
+#[get("/metrics")]
+pub async fn get_metrics(data: web::Data<PrismaClient>) -> impl Responder {
+    refresh(&data, &redis_address()).await;
+    HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(metrics().render())
+}
@@ -2,9 +2,26 @@
  * Copyright (c) Johannes Grimm 2024.
  */
 
+pub(crate) mod attachments;
 pub(crate) mod auth;
+pub(crate) mod batches;
+pub(crate) mod calendar;
+pub(crate) mod compliance;
+pub(crate) mod destructions;
+pub(crate) mod feeding;
 pub(crate) mod genetics;
+pub(crate) mod harvests;
 pub(crate) mod health_check;
+pub(crate) mod incidents;
+pub(crate) mod labels;
+pub(crate) mod locations;
+pub(crate) mod lots;
+pub(crate) mod metrics;
+pub(crate) mod openapi;
+pub(crate) mod organizations;
 pub(crate) mod plants;
+pub(crate) mod reports;
 pub(crate) mod roles;
+pub(crate) mod seeds;
+pub(crate) mod tasks;
 pub(crate) mod users;
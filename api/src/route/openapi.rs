@@ -0,0 +1,121 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    model::error::ErrorResponse,
+    route::{
+        attachments::AttachmentApi, auth::AuthApi, batches::BatchApi, calendar::CalendarApi,
+        compliance::ComplianceApi, destructions::DestructionApi, feeding::FeedingApi,
+        genetics::GeneticApi, harvests::HarvestApi, health_check::HealthCheckApi,
+        incidents::IncidentApi, labels::LabelApi, locations::LocationApi, lots::LotApi,
+        metrics::get_metrics, organizations::OrganizationApi, plants::PlantApi, reports::ReportApi,
+        roles::RoleApi, seeds::SeedApi, tasks::TaskApi, users::UserApi,
+    },
+};
+use actix_web::{get, web, HttpResponse, Responder};
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, SecurityScheme},
+        Content, Ref, RefOr, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+use utoipa_scalar::{Scalar, Servable};
+
+#[doc = "OpenAPI document of every route served below `/api`"]
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Planters Cycle"),
+    paths(get_metrics),
+    nest(
+        (path = "/api", api = HealthCheckApi, tags = ["health"]),
+        (path = "/api", api = DocsApi, tags = ["docs"]),
+        (path = "/api/auth", api = AuthApi, tags = ["auth"]),
+        (path = "/api/users", api = UserApi, tags = ["users"]),
+        (path = "/api/roles", api = RoleApi, tags = ["roles"]),
+        (path = "/api/organizations", api = OrganizationApi, tags = ["organizations"]),
+        (path = "/api/genetics", api = GeneticApi, tags = ["genetics"]),
+        (path = "/api/plants", api = PlantApi, tags = ["plants"]),
+        (path = "/api/batches", api = BatchApi, tags = ["batches"]),
+        (path = "/api/feeding", api = FeedingApi, tags = ["feeding"]),
+        (path = "/api/locations", api = LocationApi, tags = ["locations"]),
+        (path = "/api/tasks", api = TaskApi, tags = ["tasks"]),
+        (path = "/api/calendar", api = CalendarApi, tags = ["calendar"]),
+        (path = "/api/harvests", api = HarvestApi, tags = ["harvests"]),
+        (path = "/api/attachments", api = AttachmentApi, tags = ["attachments"]),
+        (path = "/api/labels", api = LabelApi, tags = ["labels"]),
+        (path = "/api/seeds", api = SeedApi, tags = ["seeds"]),
+        (path = "/api/destructions", api = DestructionApi, tags = ["destructions"]),
+        (path = "/api/compliance", api = ComplianceApi, tags = ["compliance"]),
+        (path = "/api/lots", api = LotApi, tags = ["lots"]),
+        (path = "/api/incidents", api = IncidentApi, tags = ["incidents"]),
+        (path = "/api/reports", api = ReportApi, tags = ["reports"]),
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&SessionCookie, &ErrorResponses),
+    security(("session" = []))
+)]
+pub struct ApiDoc;
+
+#[derive(OpenApi)]
+#[openapi(paths(get_openapi))]
+struct DocsApi;
+
+#[doc = "Session cookie set by `/api/auth/login`"]
+struct SessionCookie;
+
+impl Modify for SessionCookie {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "session",
+                SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("plnt_auth"))),
+            );
+        }
+    }
+}
+
+#[doc = "Failed requests answer with an `ErrorResponse` and the status of its code"]
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let response = ResponseBuilder::new()
+            .description("Request failed")
+            .content(
+                "application/json",
+                Content::new(Some(RefOr::Ref(Ref::from_schema_name("ErrorResponse")))),
+            )
+            .build();
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.patch,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                operation
+                    .responses
+                    .responses
+                    .entry("default".to_string())
+                    .or_insert_with(|| response.clone().into());
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn openapi_controller_init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_openapi)
+        .service(Scalar::with_url("/docs", ApiDoc::openapi()));
+}
+
+#[utoipa::path(security(()), responses((status = 200, description = "This document", body = Object)))]
+#[get("/openapi.json")]
+async fn get_openapi() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
@@ -0,0 +1,194 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{
+        auth::{identity_id, verify_token},
+        tenant::{Tenant, ORGANIZATION_KEY},
+    },
+    model::{
+        dto::organization::{MemberRequest, OrganizationRequest},
+        error::{ErrorCode, ErrorResponse},
+    },
+    prisma::{organization, users_in_roles, PrismaClient},
+    service,
+};
+use actix_identity::Identity;
+use actix_session::Session;
+use actix_web::{delete, get, guard, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn organization_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/organizations")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_organizations)
+            .service(get_current_organization)
+            .service(get_members)
+            .service(add_member)
+            .service(remove_member)
+            .service(create_organization)
+            .service(select_organization),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_organizations,
+    get_current_organization,
+    get_members,
+    add_member,
+    remove_member,
+    create_organization,
+    select_organization,
+))]
+pub struct OrganizationApi;
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Organizations of the user", body = Vec<organization::Data>),
+    )
+)]
+#[get("")]
+async fn get_organizations(identity: Identity, data: web::Data<PrismaClient>) -> impl Responder {
+    let user_id = match identity_id(&identity) {
+        Ok(user_id) => user_id,
+        Err(e) => return ErrorResponse::build(e),
+    };
+    match service::organization::get_organizations(&data, &user_id).await {
+        Ok(organizations) => HttpResponse::Ok().json(organizations),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Organization selected in the session", body = organization::Data),
+        (status = 403, description = "No organization selected", body = ErrorResponse),
+    )
+)]
+#[get("/current")]
+async fn get_current_organization(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::organization::get_organization(&data, &tenant.user_id, &tenant.organization_id)
+        .await
+    {
+        Ok(organization) => HttpResponse::Ok().json(organization),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "Members of the current organization with their roles",
+            body = Vec<users_in_roles::Data>,
+        ),
+    )
+)]
+#[get("/current/members")]
+async fn get_members(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::organization::get_members(&data, &tenant.organization_id).await {
+        Ok(members) => HttpResponse::Ok().json(members),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Role of the member", body = users_in_roles::Data),
+        (status = 403, description = "Only admins manage members", body = ErrorResponse),
+    )
+)]
+#[post("/current/members")]
+async fn add_member(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<MemberRequest>,
+) -> impl Responder {
+    match service::organization::add_member(
+        &data,
+        &tenant.organization_id,
+        &tenant.user_id,
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(member) => HttpResponse::Created().json(member),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Member removed"),
+        (status = 403, description = "Only admins manage members", body = ErrorResponse),
+    )
+)]
+#[delete("/current/members/{user_id}")]
+async fn remove_member(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    user_id: web::Path<String>,
+) -> impl Responder {
+    match service::organization::remove_member(
+        &data,
+        &tenant.organization_id,
+        &tenant.user_id,
+        user_id.into_inner(),
+    )
+    .await
+    {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Created organization, the user is its admin", body = organization::Data),
+    )
+)]
+#[post("")]
+async fn create_organization(
+    identity: Identity,
+    data: web::Data<PrismaClient>,
+    body: web::Json<OrganizationRequest>,
+) -> impl Responder {
+    let user_id = match identity_id(&identity) {
+        Ok(user_id) => user_id,
+        Err(e) => return ErrorResponse::build(e),
+    };
+    match service::organization::create_organization(&data, user_id, body.into_inner()).await {
+        Ok(organization) => HttpResponse::Created().json(organization),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Organization the session works in now", body = organization::Data),
+        (status = 404, description = "The user is no member of the organization", body = ErrorResponse),
+    )
+)]
+#[post("/{id}/select")]
+async fn select_organization(
+    identity: Identity,
+    session: Session,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    let user_id = match identity_id(&identity) {
+        Ok(user_id) => user_id,
+        Err(e) => return ErrorResponse::build(e),
+    };
+    match service::organization::get_organization(&data, &user_id, &id).await {
+        Ok(organization) => match session.insert(ORGANIZATION_KEY, &organization.id) {
+            Ok(()) => HttpResponse::Ok().json(organization),
+            Err(_) => ErrorResponse::build(ErrorCode::INTERNAL001),
+        },
+        Err(e) => ErrorResponse::build(e),
+    }
+}
@@ -3,81 +3,343 @@
  */
 
 use crate::{
-    middleware::auth::verify_token,
+    middleware::{auth::verify_token, tenant::Tenant},
     model::{
-        dto::{IdModel, Plant},
+        dto::{
+            bulk_import::PlantImportPreview,
+            destruction::{DestroyRequest, WasteLogEntry},
+            incident::PreHarvestInterval,
+            naming::{NamingPreview, NamingRequest, NamingTemplateRequest},
+            IdModel, Plant, StageChangeRequest,
+        },
         error::ErrorResponse,
     },
-    prisma::PrismaClient,
+    prisma::{plant, PrismaClient},
     service,
 };
-use actix_web::{delete, get, guard, patch, post, web, HttpResponse, Responder};
+use actix_web::{delete, get, guard, patch, post, put, web, HttpResponse, Responder};
+use utoipa::OpenApi;
 
 #[allow(dead_code)]
 pub fn plant_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
     cfg.service(
         web::scope("/plants")
             .guard(guard::fn_guard(verify_token))
+            .app_data(web::PayloadConfig::new(service::bulk_import::MAX_CSV_SIZE))
+            .service(preview_plant_import)
+            .service(import_plants)
+            .service(get_naming_template)
+            .service(set_naming_template)
+            .service(preview_plant_name)
             .service(get_plants)
             .service(get_plant_by_id)
             .service(create_plant)
             .service(delete_plant)
             .service(edit_plant)
+            .service(change_plant_stage)
+            .service(destroy_plant)
+            .service(get_pre_harvest_intervals)
             .service(generate_plant_name),
     );
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    preview_plant_import,
+    import_plants,
+    get_naming_template,
+    set_naming_template,
+    preview_plant_name,
+    get_plants,
+    get_plant_by_id,
+    create_plant,
+    delete_plant,
+    edit_plant,
+    change_plant_stage,
+    destroy_plant,
+    get_pre_harvest_intervals,
+    generate_plant_name,
+))]
+pub struct PlantApi;
+
+#[utoipa::path(responses((status = 200, description = "Plants", body = Vec<plant::Data>)))]
 #[get("")]
-async fn get_plants(data: web::Data<PrismaClient>) -> impl Responder {
-    match service::plant::get_plants(&data).await {
+async fn get_plants(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::plant::get_plants(&data, &tenant.organization_id).await {
         Ok(genetics) => HttpResponse::Ok().json(genetics),
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(responses((status = 200, description = "Plant", body = plant::Data)))]
 #[get("/{id}")]
-async fn get_plant_by_id(data: web::Data<PrismaClient>, id: web::Path<String>) -> impl Responder {
-    match service::plant::get_plant_by_id(&data, id.into_inner()).await {
+async fn get_plant_by_id(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::plant::get_plant_by_id(&data, &tenant.organization_id, id.into_inner()).await {
         Ok(plant) => HttpResponse::Ok().json(plant),
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Created plant", body = plant::Data),
+        (status = 409, description = "Plant limit exceeded", body = ErrorResponse),
+    )
+)]
 #[post("")]
-async fn create_plant(data: web::Data<PrismaClient>, body: web::Json<Plant>) -> impl Responder {
-    match service::plant::create_plant(&data, body.into_inner()).await {
+async fn create_plant(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<Plant>,
+) -> impl Responder {
+    match service::plant::create_plant(&data, &tenant.organization_id, body.into_inner()).await {
         Ok(plant) => HttpResponse::Created().json(plant),
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Updated plant", body = plant::Data),
+        (status = 409, description = "Plant limit exceeded", body = ErrorResponse),
+    )
+)]
 #[patch("/{id}")]
 async fn edit_plant(
+    tenant: Tenant,
+
     data: web::Data<PrismaClient>,
     id: web::Path<String>,
     body: web::Json<Plant>,
 ) -> impl Responder {
-    match service::plant::edit_plant(&data, id.into_inner(), body.into_inner()).await {
+    match service::plant::edit_plant(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(plant) => HttpResponse::Ok().json(plant),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    request_body(content = String, content_type = "text/csv"),
+    responses(
+        (
+            status = 200,
+            description = "Plants the file would create and the errors of its rows",
+            body = PlantImportPreview,
+        ),
+    )
+)]
+#[post("/import/preview")]
+async fn preview_plant_import(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: String,
+) -> impl Responder {
+    match service::bulk_import::preview_plants(&data, &tenant.organization_id, body).await {
+        Ok(preview) => HttpResponse::Ok().json(preview),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    request_body(content = String, content_type = "text/csv"),
+    responses(
+        (status = 201, description = "Created plants", body = Vec<plant::Data>),
+        (status = 422, description = "Errors of the rows, nothing was created", body = ErrorResponse),
+        (status = 409, description = "Plant limit exceeded", body = ErrorResponse),
+    )
+)]
+#[post("/import")]
+async fn import_plants(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: String,
+) -> impl Responder {
+    match service::bulk_import::import_plants(&data, &tenant.organization_id, body, tenant.user_id)
+        .await
+    {
+        Ok(plants) => HttpResponse::Created().json(plants),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Plant in its new stage", body = plant::Data),
+        (
+            status = 409,
+            description = "Plant limit exceeded or harvest blocked by a pre-harvest interval",
+            body = ErrorResponse,
+        ),
+    )
+)]
+#[post("/{id}/stage")]
+async fn change_plant_stage(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<StageChangeRequest>,
+) -> impl Responder {
+    match service::plant::change_stage(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner().stage,
+        tenant.user_id,
+    )
+    .await
+    {
         Ok(plant) => HttpResponse::Ok().json(plant),
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(responses((status = 200, description = "Plant deleted")))]
 #[delete("/{id}")]
-async fn delete_plant(data: web::Data<PrismaClient>, id: web::Path<String>) -> impl Responder {
-    match service::plant::delete_plant(&data, id.into_inner()).await {
+async fn delete_plant(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::plant::delete_plant(&data, &tenant.organization_id, id.into_inner()).await {
         Ok(_) => HttpResponse::Ok().finish(),
         Err(e) => ErrorResponse::build(e),
     }
 }
 
+#[utoipa::path(responses((status = 200, description = "Generated plant name", body = String)))]
 #[post("/generatePlantName")]
 async fn generate_plant_name(
+    tenant: Tenant,
+
     data: web::Data<PrismaClient>,
     id: web::Json<IdModel>,
 ) -> impl Responder {
-    match service::plant::generate_plant_name(&data, id.into_inner().id).await {
+    match service::plant::generate_plant_name(&data, &tenant.organization_id, id.into_inner().id)
+        .await
+    {
         Ok(name) => HttpResponse::Ok().json(name),
         Err(e) => ErrorResponse::build(e),
     }
 }
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "Naming template of the organization",
+            body = NamingTemplateRequest,
+        ),
+    )
+)]
+#[get("/naming")]
+async fn get_naming_template(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::naming::get_organization_template(&data, &tenant.organization_id).await {
+        Ok(template) => HttpResponse::Ok().json(NamingTemplateRequest { template }),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Saved naming template", body = NamingTemplateRequest),
+    )
+)]
+#[put("/naming")]
+async fn set_naming_template(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<NamingTemplateRequest>,
+) -> impl Responder {
+    match service::naming::set_organization_template(
+        &data,
+        &tenant.organization_id,
+        body.into_inner().template,
+    )
+    .await
+    {
+        Ok(template) => HttpResponse::Ok().json(NamingTemplateRequest { template }),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Name the template would generate", body = NamingPreview),
+    )
+)]
+#[post("/naming/preview")]
+async fn preview_plant_name(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    body: web::Json<NamingRequest>,
+) -> impl Responder {
+    match service::naming::preview_plant_name(&data, &tenant.organization_id, body.into_inner())
+        .await
+    {
+        Ok(preview) => HttpResponse::Ok().json(preview),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 201, description = "Destruction record", body = WasteLogEntry)))]
+#[post("/{id}/destroy")]
+async fn destroy_plant(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<DestroyRequest>,
+) -> impl Responder {
+    match service::destruction::destroy_plant(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(destruction) => HttpResponse::Created().json(destruction),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "Running pre-harvest intervals,
+            longest first",
+            body = Vec<PreHarvestInterval>,
+        ),
+    )
+)]
+#[get("/{id}/pre-harvest")]
+async fn get_pre_harvest_intervals(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::incident::get_pre_harvest_intervals(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+    )
+    .await
+    {
+        Ok(intervals) => HttpResponse::Ok().json(intervals),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
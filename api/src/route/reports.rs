@@ -0,0 +1,150 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::trace::{TraceFormat, TraceQuery, TraceReport},
+        error::{ErrorCode, ErrorResponse},
+        schema::Binary,
+    },
+    prisma::PrismaClient,
+    service::{self, report, trace},
+};
+use actix_web::{
+    get, guard,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    web, HttpResponse, Responder,
+};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn report_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/reports")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_plant_trace)
+            .service(get_batch_trace)
+            .service(get_lot_trace),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(get_plant_trace, get_batch_trace, get_lot_trace))]
+pub struct ReportApi;
+
+fn attachment(file_name: String) -> ContentDisposition {
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(file_name)],
+    }
+}
+
+#[doc = "Respond with the traceability report in the requested format"]
+fn trace_response(report: TraceReport, format: TraceFormat) -> HttpResponse {
+    let file_name = format!("trace-{}-{}", report.subject, report.subject_name);
+    match format {
+        TraceFormat::Json => HttpResponse::Ok().json(report),
+        TraceFormat::Csv => match trace::trace_csv(&report) {
+            Ok(csv) => HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .insert_header(attachment(format!("{}.csv", file_name)))
+                .body(csv),
+            Err(_) => ErrorResponse::build(ErrorCode::INTERNAL001),
+        },
+        TraceFormat::Pdf => {
+            match report::render_report_pdf("Traceability report", &trace::trace_lines(&report)) {
+                Ok(pdf) => HttpResponse::Ok()
+                    .content_type("application/pdf")
+                    .insert_header(attachment(format!("{}.pdf", file_name)))
+                    .body(pdf),
+                Err(_) => ErrorResponse::build(ErrorCode::INTERNAL001),
+            }
+        }
+    }
+}
+
+#[utoipa::path(
+    params(TraceQuery),
+    responses(
+        (
+            status = 200,
+            description = "Traceability report of the plant",
+            content(
+                (TraceReport = "application/json"),
+                (String = "text/csv"),
+                (Binary = "application/pdf"),
+            ),
+        ),
+    )
+)]
+#[get("/trace/plants/{id}")]
+async fn get_plant_trace(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    query: web::Query<TraceQuery>,
+) -> impl Responder {
+    match service::trace::get_plant_trace(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(report) => trace_response(report, query.format),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    params(TraceQuery),
+    responses(
+        (
+            status = 200,
+            description = "Traceability report of the plants of the batch",
+            content(
+                (TraceReport = "application/json"),
+                (String = "text/csv"),
+                (Binary = "application/pdf"),
+            ),
+        ),
+    )
+)]
+#[get("/trace/batches/{id}")]
+async fn get_batch_trace(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    query: web::Query<TraceQuery>,
+) -> impl Responder {
+    match service::trace::get_batch_trace(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(report) => trace_response(report, query.format),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    params(TraceQuery),
+    responses(
+        (
+            status = 200,
+            description = "Traceability report of the lot and its plants",
+            content(
+                (TraceReport = "application/json"),
+                (String = "text/csv"),
+                (Binary = "application/pdf"),
+            ),
+        ),
+    )
+)]
+#[get("/trace/lots/{id}")]
+async fn get_lot_trace(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    query: web::Query<TraceQuery>,
+) -> impl Responder {
+    match service::trace::get_lot_trace(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(report) => trace_response(report, query.format),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
@@ -3,11 +3,17 @@
  */
 
 use crate::{
-    middleware::auth::verify_token,
-    model::dto::Role,
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::Role,
+        error::{ErrorCode, ErrorResponse},
+        validation::{Validate, Validator},
+    },
     prisma::{role, PrismaClient},
+    service::{self, metrics::TimedQuery},
 };
 use actix_web::{delete, get, guard, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
 
 #[allow(dead_code)]
 pub fn role_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
@@ -22,65 +28,163 @@ pub fn role_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
     );
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(get_roles, get_role_by_id, create_role, delete_role, edit_role))]
+pub struct RoleApi;
+
+#[utoipa::path(responses((status = 200, description = "Roles", body = Vec<role::Data>)))]
 #[get("")]
 async fn get_roles(data: web::Data<PrismaClient>) -> impl Responder {
-    let roles = data.role().find_many(vec![]).exec().await.unwrap();
-    HttpResponse::Ok().json(roles)
+    match data
+        .role()
+        .find_many(vec![])
+        .exec()
+        .timed("role", "find_many")
+        .await
+    {
+        Ok(roles) => HttpResponse::Ok().json(roles),
+        Err(e) => ErrorResponse::build(e.into()),
+    }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Role", body = role::Data),
+        (status = 404, description = "Role not found", body = ErrorResponse),
+    )
+)]
 #[get("/{id}")]
 async fn get_role_by_id(data: web::Data<PrismaClient>, id: web::Path<i32>) -> impl Responder {
-    let role = data
+    match data
         .role()
         .find_unique(role::id::equals(id.into_inner()))
         .exec()
+        .timed("role", "find_unique")
         .await
-        .unwrap();
-    HttpResponse::Ok().json(role.unwrap())
+    {
+        Ok(Some(role)) => HttpResponse::Ok().json(role),
+        Ok(None) => ErrorResponse::build(ErrorCode::DATABASE002),
+        Err(e) => ErrorResponse::build(e.into()),
+    }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Created role", body = role::Data),
+        (
+            status = 403,
+            description = "Not an admin of the default organization",
+            body = ErrorResponse,
+        ),
+    )
+)]
 #[post("")]
-async fn create_role(data: web::Data<PrismaClient>, body: web::Json<Role>) -> impl Responder {
+async fn create_role(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<Role>,
+) -> impl Responder {
+    if let Err(e) =
+        service::organization::require_role_admin(&data, &tenant.user_id, &tenant.organization_id)
+            .await
+    {
+        return ErrorResponse::build(e);
+    }
     let body = body.into_inner();
-    let role = data
+    let mut validator = Validator::default();
+    validator.required("name", &body.name, "Name is required");
+    body.check(&mut validator);
+    if let Err(e) = validator.finish() {
+        return ErrorResponse::build(e);
+    }
+
+    match data
         .role()
         .create(
             body.name.unwrap(),
-            vec![role::is_default::set(body.is_default.unwrap())],
+            vec![role::is_default::set(body.is_default.unwrap_or(false))],
         )
         .exec()
+        .timed("role", "create")
         .await
-        .unwrap();
-    HttpResponse::Ok().json(role)
+    {
+        Ok(role) => HttpResponse::Ok().json(role),
+        Err(e) => ErrorResponse::build(e.into()),
+    }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Updated role", body = role::Data),
+        (
+            status = 403,
+            description = "Not an admin of the default organization",
+            body = ErrorResponse,
+        ),
+        (status = 404, description = "Role not found", body = ErrorResponse),
+    )
+)]
 #[post("/{id}")]
 async fn edit_role(
+    tenant: Tenant,
     data: web::Data<PrismaClient>,
     id: web::Path<i32>,
     body: web::Json<Role>,
 ) -> impl Responder {
-    let role = data
+    if let Err(e) =
+        service::organization::require_role_admin(&data, &tenant.user_id, &tenant.organization_id)
+            .await
+    {
+        return ErrorResponse::build(e);
+    }
+    let body = body.into_inner();
+    if let Err(e) = body.validate() {
+        return ErrorResponse::build(e);
+    }
+
+    match data
         .role()
-        .update_unchecked(
-            role::id::equals(id.into_inner()),
-            body.into_inner().to_params(),
-        )
+        .update_unchecked(role::id::equals(id.into_inner()), body.to_params())
         .exec()
+        .timed("role", "update_unchecked")
         .await
-        .unwrap();
-    HttpResponse::Ok().json(role)
+    {
+        Ok(role) => HttpResponse::Ok().json(role),
+        Err(e) => ErrorResponse::build(e.into()),
+    }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Deleted role", body = role::Data),
+        (
+            status = 403,
+            description = "Not an admin of the default organization",
+            body = ErrorResponse,
+        ),
+        (status = 404, description = "Role not found", body = ErrorResponse),
+    )
+)]
 #[delete("/{id}")]
-async fn delete_role(data: web::Data<PrismaClient>, id: web::Path<i32>) -> impl Responder {
+async fn delete_role(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<i32>,
+) -> impl Responder {
+    if let Err(e) =
+        service::organization::require_role_admin(&data, &tenant.user_id, &tenant.organization_id)
+            .await
+    {
+        return ErrorResponse::build(e);
+    }
     match data
         .role()
         .delete(role::id::equals(id.into_inner()))
         .exec()
+        .timed("role", "delete")
         .await
     {
-        Err(_) => HttpResponse::NotFound().body("Role not found"),
         Ok(role) => HttpResponse::Ok().json(role),
+        Err(e) => ErrorResponse::build(e.into()),
     }
 }
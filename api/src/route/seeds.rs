@@ -0,0 +1,169 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::{
+            seed::{GerminateRequest, GerminationResult, SeedPackQuery, SeedPackSummary},
+            SeedPack,
+        },
+        error::ErrorResponse,
+    },
+    prisma::{seed_pack, PrismaClient},
+    service,
+};
+use actix_web::{delete, get, guard, patch, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn seed_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/seeds")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_seed_packs)
+            .service(get_seed_pack_by_id)
+            .service(create_seed_pack)
+            .service(edit_seed_pack)
+            .service(delete_seed_pack)
+            .service(germinate_seeds),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_seed_packs,
+    get_seed_pack_by_id,
+    create_seed_pack,
+    edit_seed_pack,
+    delete_seed_pack,
+    germinate_seeds,
+))]
+pub struct SeedApi;
+
+#[utoipa::path(
+    params(SeedPackQuery),
+    responses(
+        (
+            status = 200,
+            description = "Seed packs with their germination rate",
+            body = Vec<SeedPackSummary>,
+        ),
+    )
+)]
+#[get("")]
+async fn get_seed_packs(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    query: web::Query<SeedPackQuery>,
+) -> impl Responder {
+    match service::seed::get_seed_packs(&data, &tenant.organization_id, query.into_inner()).await {
+        Ok(packs) => HttpResponse::Ok().json(packs),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Seed pack with its germination rate", body = SeedPackSummary),
+    )
+)]
+#[get("/{id}")]
+async fn get_seed_pack_by_id(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::seed::get_seed_pack_by_id(&data, &tenant.organization_id, id.into_inner()).await
+    {
+        Ok(pack) => HttpResponse::Ok().json(pack),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Created seed pack", body = seed_pack::Data),
+    )
+)]
+#[post("")]
+async fn create_seed_pack(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    body: web::Json<SeedPack>,
+) -> impl Responder {
+    match service::seed::create_seed_pack(&data, &tenant.organization_id, body.into_inner()).await {
+        Ok(pack) => HttpResponse::Created().json(pack),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Updated seed pack", body = seed_pack::Data),
+    )
+)]
+#[patch("/{id}")]
+async fn edit_seed_pack(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<SeedPack>,
+) -> impl Responder {
+    match service::seed::edit_seed_pack(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(pack) => HttpResponse::Ok().json(pack),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Seed pack deleted")))]
+#[delete("/{id}")]
+async fn delete_seed_pack(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::seed::delete_seed_pack(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Germination with its seedlings", body = GerminationResult),
+        (status = 409, description = "Plant limit exceeded", body = ErrorResponse),
+    )
+)]
+#[post("/{id}/germinate")]
+async fn germinate_seeds(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<GerminateRequest>,
+) -> impl Responder {
+    match service::seed::germinate_seeds(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(result) => HttpResponse::Created().json(result),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
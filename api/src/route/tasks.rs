@@ -0,0 +1,196 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use crate::{
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::task::{TaskQuery, TaskRequest, TaskTemplateRequest, TaskUpdateRequest},
+        error::ErrorResponse,
+    },
+    prisma::{task, task_template, PrismaClient},
+    service,
+};
+use actix_web::{delete, get, guard, patch, post, web, HttpResponse, Responder};
+use utoipa::OpenApi;
+
+#[allow(dead_code)]
+pub fn task_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
+    cfg.service(
+        web::scope("/tasks")
+            .guard(guard::fn_guard(verify_token))
+            .service(get_my_tasks_today)
+            .service(get_task_templates)
+            .service(create_task_template)
+            .service(delete_task_template)
+            .service(get_tasks)
+            .service(get_task_by_id)
+            .service(create_task)
+            .service(delete_task)
+            .service(edit_task),
+    );
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_my_tasks_today,
+    get_task_templates,
+    create_task_template,
+    delete_task_template,
+    get_tasks,
+    get_task_by_id,
+    create_task,
+    delete_task,
+    edit_task,
+))]
+pub struct TaskApi;
+
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "Open tasks of the logged in user due today",
+            body = Vec<task::Data>,
+        ),
+    )
+)]
+#[get("/today")]
+async fn get_my_tasks_today(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::task::get_my_tasks_today(&data, &tenant.organization_id, tenant.user_id).await {
+        Ok(tasks) => HttpResponse::Ok().json(tasks),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Task templates", body = Vec<task_template::Data>),
+    )
+)]
+#[get("/templates")]
+async fn get_task_templates(tenant: Tenant, data: web::Data<PrismaClient>) -> impl Responder {
+    match service::task::get_task_templates(&data, &tenant.organization_id).await {
+        Ok(templates) => HttpResponse::Ok().json(templates),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    responses(
+        (status = 201, description = "Created task template", body = task_template::Data),
+    )
+)]
+#[post("/templates")]
+async fn create_task_template(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    body: web::Json<TaskTemplateRequest>,
+) -> impl Responder {
+    match service::task::create_task_template(&data, &tenant.organization_id, body.into_inner())
+        .await
+    {
+        Ok(template) => HttpResponse::Created().json(template),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Task template deleted")))]
+#[delete("/templates/{id}")]
+async fn delete_task_template(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::task::delete_task_template(&data, &tenant.organization_id, id.into_inner()).await
+    {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(
+    params(TaskQuery),
+    responses(
+        (status = 200, description = "Tasks matching the query", body = Vec<task::Data>),
+    )
+)]
+#[get("")]
+async fn get_tasks(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    query: web::Query<TaskQuery>,
+) -> impl Responder {
+    match service::task::get_tasks(&data, &tenant.organization_id, query.into_inner()).await {
+        Ok(tasks) => HttpResponse::Ok().json(tasks),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Task", body = task::Data)))]
+#[get("/{id}")]
+async fn get_task_by_id(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::task::get_task_by_id(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(task) => HttpResponse::Ok().json(task),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 201, description = "Created task", body = task::Data)))]
+#[post("")]
+async fn create_task(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    body: web::Json<TaskRequest>,
+) -> impl Responder {
+    match service::task::create_task(
+        &data,
+        &tenant.organization_id,
+        body.into_inner(),
+        tenant.user_id,
+    )
+    .await
+    {
+        Ok(task) => HttpResponse::Created().json(task),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Updated task", body = task::Data)))]
+#[patch("/{id}")]
+async fn edit_task(
+    tenant: Tenant,
+
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+    body: web::Json<TaskUpdateRequest>,
+) -> impl Responder {
+    match service::task::edit_task(
+        &data,
+        &tenant.organization_id,
+        id.into_inner(),
+        body.into_inner(),
+    )
+    .await
+    {
+        Ok(task) => HttpResponse::Ok().json(task),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
+
+#[utoipa::path(responses((status = 200, description = "Task deleted")))]
+#[delete("/{id}")]
+async fn delete_task(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match service::task::delete_task(&data, &tenant.organization_id, id.into_inner()).await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => ErrorResponse::build(e),
+    }
+}
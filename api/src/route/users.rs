@@ -3,15 +3,22 @@
  */
 
 use crate::{
-    middleware::auth::verify_token,
-    model::dto::auth::RegisterRequest,
+    middleware::{auth::verify_token, tenant::Tenant},
+    model::{
+        dto::auth::RegisterRequest,
+        error::{ErrorCode, ErrorResponse},
+    },
     prisma::{user, users_in_roles, PrismaClient},
-    service::user::{create_new_user, edit_user_by_id},
+    service::{
+        metrics::TimedQuery,
+        organization::is_admin,
+        user::{create_new_user, delete_user_by_id, edit_user_by_id, in_organization},
+    },
 };
-use actix_identity::Identity;
 use actix_web::{delete, get, guard, post, web, HttpResponse, Responder};
 use prisma_client_rust::or;
 use serde::Deserialize;
+use utoipa::{IntoParams, OpenApi};
 
 pub fn user_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
     cfg.service(
@@ -26,73 +33,134 @@ pub fn user_controller_init(cfg: &mut actix_web::web::ServiceConfig) {
     );
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(OpenApi)]
+#[openapi(paths(
+    count_users,
+    get_users,
+    get_user_by_id,
+    create_user,
+    delete_user,
+    edit_user
+))]
+pub struct UserApi;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct UserSearchQuery {
     query: String,
 }
+#[utoipa::path(
+    params(UserSearchQuery),
+    responses(
+        (
+            status = 200,
+            description = "Number of matching users",
+            content_type = "text/plain",
+            body = String,
+        ),
+    )
+)]
 #[post("/count")]
 async fn count_users(
+    tenant: Tenant,
     data: web::Data<PrismaClient>,
     query: web::Query<UserSearchQuery>,
 ) -> impl Responder {
     let users = data
         .user()
-        .count(vec![or![
-            user::display_name::contains(query.query.clone()),
-            user::email::contains(query.query.clone())
-        ]])
+        .count(vec![
+            in_organization(&tenant.organization_id),
+            or![
+                user::display_name::contains(query.query.clone()),
+                user::email::contains(query.query.clone())
+            ],
+        ])
         .exec()
+        .timed("user", "count")
         .await
         .unwrap();
     HttpResponse::Ok().body(users.to_string())
 }
 
+#[utoipa::path(
+    params(UserSearchQuery),
+    responses(
+        (
+            status = 200,
+            description = "Users matching the query with their roles in the current organization",
+            body = Vec<user::Data>,
+        ),
+    )
+)]
 #[get("")]
 async fn get_users(
+    tenant: Tenant,
     data: web::Data<PrismaClient>,
     query: web::Query<UserSearchQuery>,
 ) -> impl Responder {
     let users = data
         .user()
-        .find_many(vec![or![
-            user::display_name::contains(query.query.clone()),
-            user::email::contains(query.query.clone())
-        ]])
-        .with(user::roles::fetch(vec![]).with(users_in_roles::role::fetch()))
+        .find_many(vec![
+            in_organization(&tenant.organization_id),
+            or![
+                user::display_name::contains(query.query.clone()),
+                user::email::contains(query.query.clone())
+            ],
+        ])
         .select(user::select!({
             id
             display_name
             email
             last_login
             created_at
-            roles(vec![]): select {
+            roles(vec![users_in_roles::organization_id::equals(tenant.organization_id)]): select {
                 user_id
                 role
                 role_id
                 assigned_at
                 assigned_by
+                organization_id
             }
         }))
         .exec()
+        .timed("user", "find_many")
         .await
         .unwrap();
     HttpResponse::Ok().json(users)
 }
 
+#[utoipa::path(
+    responses(
+        (
+            status = 200,
+            description = "User with their roles in the current organization",
+            body = user::Data,
+        ),
+        (status = 404, description = "User not found"),
+    )
+)]
 #[get("/{id}")]
-async fn get_user_by_id(data: web::Data<PrismaClient>, id: web::Path<String>) -> impl Responder {
+async fn get_user_by_id(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
     let user = data
         .user()
-        .find_unique(user::id::equals(id.to_string()))
+        .find_first(vec![
+            user::id::equals(id.to_string()),
+            in_organization(&tenant.organization_id),
+        ])
         .select(user::select!({
             id
             display_name
             email
             last_login
             created_at
-            roles
+            roles(vec![users_in_roles::organization_id::equals(tenant.organization_id)])
         }))
         .exec()
+        .timed("user", "find_first")
         .await
         .unwrap();
 
@@ -102,51 +170,85 @@ async fn get_user_by_id(data: web::Data<PrismaClient>, id: web::Path<String>) ->
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Created user", body = user::Data),
+        (status = 400, description = "User could not be created"),
+        (status = 403, description = "Not an admin of the organization", body = ErrorResponse),
+    )
+)]
 #[post("")]
 async fn create_user(
-    identity: Option<Identity>,
+    tenant: Tenant,
     data: web::Data<PrismaClient>,
     body: web::Json<RegisterRequest>,
 ) -> impl Responder {
-    let ident_id: String = match identity.map(|id| id.id()) {
-        None => "anonymous".to_owned(),
-        Some(Ok(id)) => id,
-        Some(Err(_)) => return HttpResponse::InternalServerError().finish(),
-    };
-    let register_result = create_new_user(ident_id, body.into_inner(), &data).await;
+    match is_admin(&data, &tenant.user_id, &tenant.organization_id).await {
+        Ok(true) => (),
+        Ok(false) => return ErrorResponse::build(ErrorCode::ORG001),
+        Err(e) => return ErrorResponse::build(e),
+    }
+    let register_result = create_new_user(
+        tenant.user_id,
+        &tenant.organization_id,
+        body.into_inner(),
+        &data,
+    )
+    .await;
     match register_result {
         Ok(user) => HttpResponse::Ok().json(user),
+        Err(e @ ErrorCode::VALIDATION(_)) => ErrorResponse::build(e),
         Err(_) => HttpResponse::BadRequest().finish(),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Updated user", body = user::Data),
+        (
+            status = 403,
+            description = "Not an admin, or the credentials of a shared account were changed",
+            body = ErrorResponse,
+        ),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
 #[post("/{id}")]
 async fn edit_user(
-    identity: Option<Identity>,
+    tenant: Tenant,
     data: web::Data<PrismaClient>,
     id: web::Path<String>,
     body: web::Json<user::Data>,
 ) -> impl Responder {
-    let ident_id = match identity.map(|id| id.id()) {
-        None => "anonymous".to_owned(),
-        Some(Ok(id)) => id,
-        Some(Err(_)) => return HttpResponse::InternalServerError().finish(),
-    };
-    match edit_user_by_id(&id.to_string(), &data, body.into_inner(), ident_id).await {
-        Err(_) => HttpResponse::NotFound().body("User not found"),
+    match edit_user_by_id(
+        &id.to_string(),
+        &data,
+        body.into_inner(),
+        tenant.user_id,
+        &tenant.organization_id,
+    )
+    .await
+    {
+        Err(e) => ErrorResponse::build(e),
         Ok(usr) => HttpResponse::Ok().json(usr),
     }
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "User removed from the organization", body = user::Data),
+        (status = 403, description = "Not an admin of the organization", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    )
+)]
 #[delete("/{id}")]
-async fn delete_user(data: web::Data<PrismaClient>, id: web::Path<String>) -> impl Responder {
-    match data
-        .user()
-        .delete(user::id::equals(id.to_string()))
-        .exec()
-        .await
-    {
-        Err(_) => HttpResponse::NotFound().body("User not found"),
+async fn delete_user(
+    tenant: Tenant,
+    data: web::Data<PrismaClient>,
+    id: web::Path<String>,
+) -> impl Responder {
+    match delete_user_by_id(&id, &tenant.organization_id, &tenant.user_id, &data).await {
+        Err(e) => ErrorResponse::build(e),
         Ok(usr) => HttpResponse::Ok().json(usr),
     }
 }
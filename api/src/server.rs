@@ -2,13 +2,34 @@
  * Copyright (c) Johannes Grimm 2024.
  */
 
+use crate::middleware::metrics::RequestMetrics;
+use crate::middleware::request_id::RequestTracing;
 use crate::prisma::PrismaClient;
+use crate::route::attachments::attachment_controller_init;
 use crate::route::auth::auth_controller_init;
+use crate::route::batches::batch_controller_init;
+use crate::route::calendar::calendar_controller_init;
+use crate::route::compliance::compliance_controller_init;
+use crate::route::destructions::destruction_controller_init;
+use crate::route::feeding::feeding_controller_init;
 use crate::route::genetics::genetic_controller_init;
-use crate::route::health_check::health_check;
+use crate::route::harvests::harvest_controller_init;
+use crate::route::health_check::{health_check, liveness, readiness};
+use crate::route::incidents::incident_controller_init;
+use crate::route::labels::label_controller_init;
+use crate::route::locations::location_controller_init;
+use crate::route::lots::lot_controller_init;
+use crate::route::metrics::get_metrics;
+use crate::route::openapi::openapi_controller_init;
+use crate::route::organizations::organization_controller_init;
 use crate::route::plants::plant_controller_init;
+use crate::route::reports::report_controller_init;
 use crate::route::roles::role_controller_init;
+use crate::route::seeds::seed_controller_init;
+use crate::route::tasks::task_controller_init;
 use crate::route::users::user_controller_init;
+use crate::service::task::spawn_task_scheduler;
+use crate::storage::storage_from_env;
 use actix_identity::{Identity, IdentityMiddleware};
 use actix_session::config::PersistentSession;
 use actix_session::storage::RedisActorSessionStore;
@@ -45,27 +66,61 @@ pub fn get_config(conf: &mut ServiceConfig) {
     conf.service(
         scope("/api")
             .service(health_check)
+            .service(liveness)
+            .service(readiness)
+            .configure(openapi_controller_init)
             .configure(auth_controller_init)
             .configure(user_controller_init)
             .configure(role_controller_init)
+            .configure(organization_controller_init)
             .configure(genetic_controller_init)
-            .configure(plant_controller_init),
+            .configure(plant_controller_init)
+            .configure(batch_controller_init)
+            .configure(feeding_controller_init)
+            .configure(location_controller_init)
+            .configure(task_controller_init)
+            .configure(calendar_controller_init)
+            .configure(harvest_controller_init)
+            .configure(attachment_controller_init)
+            .configure(label_controller_init)
+            .configure(seed_controller_init)
+            .configure(destruction_controller_init)
+            .configure(compliance_controller_init)
+            .configure(lot_controller_init)
+            .configure(incident_controller_init)
+            .configure(report_controller_init),
     );
 }
 
+#[doc = "Address of the Redis server holding the sessions, set by `REDIS_ADDRESS`."]
+pub fn redis_address() -> String {
+    env::var("REDIS_ADDRESS").unwrap_or("127.0.0.1:6379".to_owned())
+}
+
+#[doc = "Seconds in-flight requests get to finish on shutdown, set by `SHUTDOWN_TIMEOUT`."]
+pub fn shutdown_timeout() -> u64 {
+    env::var("SHUTDOWN_TIMEOUT")
+        .ok()
+        .and_then(|timeout| timeout.parse().ok())
+        .unwrap_or(30)
+}
+
 #[doc = "Create the server instance."]
 pub async fn run(tcp_listener: TcpListener, data: PrismaClient) -> Result<Server, std::io::Error> {
     let data = web::Data::new(data);
+    let storage = web::Data::from(storage_from_env());
+    spawn_task_scheduler(data.clone());
 
     let secret_key = env::var("SECRET").expect("SECRET is not set");
     let private_key = actix_web::cookie::Key::from(secret_key.as_bytes());
+    let redis_address = redis_address();
 
     let server = HttpServer::new(move || {
         App::new()
             .wrap(IdentityMiddleware::default())
             .wrap(
                 SessionMiddleware::builder(
-                    RedisActorSessionStore::new("127.0.0.1:6379"),
+                    RedisActorSessionStore::new(redis_address.clone()),
                     private_key.clone(),
                 )
                 .cookie_name("plnt_auth".to_owned())
@@ -73,13 +128,17 @@ pub async fn run(tcp_listener: TcpListener, data: PrismaClient) -> Result<Server
                 .session_lifecycle(PersistentSession::default().session_ttl(Duration::hours(24)))
                 .build(),
             )
+            .wrap(RequestMetrics)
+            .wrap(RequestTracing)
             .wrap(middleware::NormalizePath::trim())
-            .wrap(middleware::Logger::default())
             .app_data(data.clone())
+            .app_data(storage.clone())
             .default_service(web::route().to(not_found))
             .service(index)
+            .service(get_metrics)
             .configure(get_config)
     })
+    .shutdown_timeout(shutdown_timeout())
     .listen(tcp_listener)?
     .run();
 
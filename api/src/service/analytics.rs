@@ -0,0 +1,286 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use chrono::{DateTime, Days, FixedOffset, NaiveDate, NaiveTime};
+use prisma_client_rust::Direction;
+
+use crate::{
+    model::{
+        dto::analytics::{AnalyticsQuery, CloneStats, GeneticAnalytics, StageDays, YieldStats},
+        error::ErrorCode,
+    },
+    prisma::{genetic, plant, plant_history, PlantStage, PrismaClient},
+    service::{genetic::get_genetic_by_id, metrics::TimedQuery},
+};
+
+#[doc = "Stages in the order plants pass them"]
+pub const STAGES: [PlantStage; 9] = [
+    PlantStage::Seedling,
+    PlantStage::Vegetative,
+    PlantStage::Flowering,
+    PlantStage::Harvest,
+    PlantStage::Dried,
+    PlantStage::Cured,
+    PlantStage::Packaged,
+    PlantStage::Sold,
+    PlantStage::Destroyed,
+];
+
+#[doc = "Genetic the analytics are computed for"]
+#[derive(Debug, Clone)]
+pub struct AnalyticsSubject {
+    pub id: String,
+    pub name: String,
+    pub flower_days: i32,
+    pub autoflower: bool,
+}
+
+#[doc = "Stage transitions and harvests of a single plant"]
+#[derive(Debug, Clone)]
+pub struct PlantRecord {
+    pub created_at: DateTime<FixedOffset>,
+    pub stage: PlantStage,
+    pub clone: bool,
+    #[doc = "Stage transitions ordered by date"]
+    pub transitions: Vec<(DateTime<FixedOffset>, PlantStage)>,
+    pub dry_weight: Option<f64>,
+    pub wet_weight: Option<f64>,
+}
+
+impl From<plant::Data> for PlantRecord {
+    fn from(plant: plant::Data) -> Self {
+        let sum = |weights: Vec<Option<f64>>| {
+            let weights: Vec<f64> = weights.into_iter().flatten().collect();
+            (!weights.is_empty()).then(|| weights.iter().sum())
+        };
+        let harvests = plant.harvests.clone().unwrap_or_default();
+
+        Self {
+            created_at: plant.created_at,
+            stage: plant.stage,
+            clone: plant.mother_id.is_some(),
+            transitions: plant
+                .plant_history
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|entry| entry.stage.map(|stage| (entry.created_at, stage)))
+                .collect(),
+            dry_weight: sum(harvests.iter().map(|harvest| harvest.dry_weight).collect()),
+            wet_weight: sum(harvests.iter().map(|harvest| harvest.wet_weight).collect()),
+        }
+    }
+}
+
+fn days_between(from: DateTime<FixedOffset>, to: DateTime<FixedOffset>) -> f64 {
+    (to - from).num_seconds() as f64 / 86_400.0
+}
+
+fn average(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+#[doc = "Days spent in every stage the plant already left. Plants start as seedlings
+when they are created, the current stage is still running and not included."]
+pub fn completed_stage_days(plant: &PlantRecord) -> Vec<(PlantStage, f64)> {
+    let mut stages = vec![];
+    let mut current = (plant.created_at, PlantStage::Seedling);
+    for (date, stage) in &plant.transitions {
+        if *stage == current.1 {
+            continue;
+        }
+        stages.push((current.1, days_between(current.0, *date)));
+        current = (*date, *stage);
+    }
+    stages
+}
+
+fn entered(plant: &PlantRecord, stage: PlantStage) -> Option<DateTime<FixedOffset>> {
+    plant
+        .transitions
+        .iter()
+        .find(|(_, entered)| *entered == stage)
+        .map(|(date, _)| *date)
+}
+
+#[doc = "Aggregate the records of the plants of a genetic"]
+pub fn analyze(subject: &AnalyticsSubject, plants: &[PlantRecord]) -> GeneticAnalytics {
+    let durations: Vec<Vec<(PlantStage, f64)>> = plants.iter().map(completed_stage_days).collect();
+    let stage_days = STAGES
+        .iter()
+        .filter(|stage| **stage != PlantStage::Destroyed)
+        .filter_map(|stage| {
+            let days: Vec<f64> = durations
+                .iter()
+                .flatten()
+                .filter(|(completed, _)| completed == stage)
+                .map(|(_, days)| *days)
+                .collect();
+            average(&days).map(|average_days| StageDays {
+                stage: *stage,
+                average_days,
+                samples: days.len(),
+            })
+        })
+        .collect();
+
+    let flower_days: Vec<f64> = if subject.autoflower {
+        plants
+            .iter()
+            .filter_map(|plant| {
+                entered(plant, PlantStage::Harvest)
+                    .map(|harvest| days_between(plant.created_at, harvest))
+            })
+            .collect()
+    } else {
+        durations
+            .iter()
+            .flatten()
+            .filter(|(stage, _)| *stage == PlantStage::Flowering)
+            .map(|(_, days)| *days)
+            .collect()
+    };
+
+    let dry: Vec<f64> = plants.iter().filter_map(|plant| plant.dry_weight).collect();
+    let wet: Vec<f64> = plants.iter().filter_map(|plant| plant.wet_weight).collect();
+    let yield_stats = YieldStats {
+        harvested_plants: plants
+            .iter()
+            .filter(|plant| plant.dry_weight.is_some() || plant.wet_weight.is_some())
+            .count(),
+        total_dry_weight: dry.iter().sum(),
+        average_dry_weight: average(&dry),
+        average_wet_weight: average(&wet),
+    };
+
+    let destroyed = plants
+        .iter()
+        .filter(|plant| plant.stage == PlantStage::Destroyed)
+        .count();
+
+    // A clone rooted once it moved on to vegetative growth, clones destroyed
+    // before that failed and all others are still rooting
+    let clones: Vec<(bool, &PlantRecord)> = plants
+        .iter()
+        .filter(|plant| plant.clone)
+        .map(|clone| {
+            let rooted = clone
+                .transitions
+                .iter()
+                .any(|(_, stage)| !matches!(stage, PlantStage::Seedling | PlantStage::Destroyed));
+            (rooted, clone)
+        })
+        .collect();
+    let rooted = clones.iter().filter(|(rooted, _)| *rooted).count();
+    let failed = clones
+        .iter()
+        .filter(|(rooted, clone)| !rooted && clone.stage == PlantStage::Destroyed)
+        .count();
+
+    GeneticAnalytics {
+        genetic_id: subject.id.clone(),
+        genetic_name: subject.name.clone(),
+        plants: plants.len(),
+        stage_days,
+        expected_flower_days: subject.flower_days,
+        actual_flower_days: average(&flower_days),
+        yield_stats,
+        destroyed,
+        loss_rate: (!plants.is_empty()).then(|| destroyed as f64 / plants.len() as f64),
+        clones: CloneStats {
+            taken: clones.len(),
+            rooted,
+            failed,
+            success_rate: (rooted + failed > 0).then(|| rooted as f64 / (rooted + failed) as f64),
+        },
+    }
+}
+
+#[doc = "Midnight UTC at the start of the date, for inclusive date range filters"]
+pub fn start_of_day(date: NaiveDate) -> DateTime<FixedOffset> {
+    date.and_time(NaiveTime::MIN).and_utc().fixed_offset()
+}
+
+async fn get_analytics(
+    data: &web::Data<PrismaClient>,
+    genetic: genetic::Data,
+    query: &AnalyticsQuery,
+) -> Result<GeneticAnalytics, ErrorCode> {
+    let mut filter = vec![
+        plant::genetic_id::equals(genetic.id.clone()),
+        plant::organization_id::equals(genetic.organization_id.clone()),
+    ];
+    if let Some(from) = query.from {
+        filter.push(plant::created_at::gte(start_of_day(from)));
+    }
+    if let Some(to) = query.to {
+        filter.push(plant::created_at::lt(start_of_day(to + Days::new(1))));
+    }
+
+    let plants = match data
+        .plant()
+        .find_many(filter)
+        .with(
+            plant::plant_history::fetch(vec![plant_history::stage::not(None)])
+                .order_by(plant_history::created_at::order(Direction::Asc)),
+        )
+        .with(plant::harvests::fetch(vec![]))
+        .exec()
+        .timed("plant", "find_many")
+        .await
+    {
+        Ok(plants) => plants,
+        Err(e) => return Err(e.into()),
+    };
+
+    let records: Vec<PlantRecord> = plants.into_iter().map(PlantRecord::from).collect();
+    Ok(analyze(
+        &AnalyticsSubject {
+            id: genetic.id,
+            name: genetic.name,
+            flower_days: genetic.flower_days,
+            autoflower: genetic.autoflower,
+        },
+        &records,
+    ))
+}
+
+#[doc = "Performance of the plants of a genetic created within the time range"]
+pub async fn get_genetic_analytics(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    query: AnalyticsQuery,
+) -> Result<GeneticAnalytics, ErrorCode> {
+    let genetic = get_genetic_by_id(data, organization_id, id).await?;
+    get_analytics(data, genetic, &query).await
+}
+
+#[doc = "Performance of all genetics for comparing them side by side"]
+pub async fn get_all_genetic_analytics(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    query: AnalyticsQuery,
+) -> Result<Vec<GeneticAnalytics>, ErrorCode> {
+    let genetics = match data
+        .genetic()
+        .find_many(vec![genetic::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .order_by(genetic::name::order(Direction::Asc))
+        .exec()
+        .timed("genetic", "find_many")
+        .await
+    {
+        Ok(genetics) => genetics,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut analytics = vec![];
+    for genetic in genetics {
+        analytics.push(get_analytics(data, genetic, &query).await?);
+    }
+    Ok(analytics)
+}
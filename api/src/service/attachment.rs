@@ -0,0 +1,417 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::io::Cursor;
+
+use actix_web::web;
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use prisma_client_rust::{or, Direction};
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        dto::{
+            attachment::{AttachmentQuery, UploadRequest},
+            AttachmentLinks,
+        },
+        error::ErrorCode,
+    },
+    prisma::{
+        attachment, destruction, genetic, harvest, plant, plant_history, AttachmentKind,
+        PrismaClient,
+    },
+    service::{
+        destruction::in_organization as destruction_in_organization,
+        harvest::in_organization as harvest_in_organization, metrics::TimedQuery,
+    },
+    storage::Storage,
+};
+
+#[doc = "Edge length of the box thumbnails are scaled into"]
+pub const THUMBNAIL_SIZE: u32 = 320;
+
+#[doc = "Content types accepted for document uploads"]
+const DOCUMENT_TYPES: [&str; 3] = ["application/pdf", "text/plain", "text/csv"];
+
+#[doc = "Photo re-encoded without metadata, with a jpeg thumbnail"]
+pub struct ProcessedImage {
+    pub bytes: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+    pub content_type: &'static str,
+}
+
+#[doc = "Decode a photo, apply its EXIF orientation and encode it again.
+Re-encoding drops all metadata, including GPS positions stored by phones."]
+pub fn process_image(bytes: &[u8]) -> Result<ProcessedImage, String> {
+    let reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?;
+    let format = match reader.format() {
+        Some(format @ (ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)) => format,
+        _ => return Err("Unsupported image format".to_string()),
+    };
+
+    let mut decoder = reader.into_decoder().map_err(|e| e.to_string())?;
+    let orientation = decoder.orientation().map_err(|e| e.to_string())?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+    image.apply_orientation(orientation);
+
+    Ok(ProcessedImage {
+        bytes: encode_image(&image, format)?,
+        thumbnail: encode_image(
+            &image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE),
+            ImageFormat::Jpeg,
+        )?,
+        content_type: format.to_mime_type(),
+    })
+}
+
+fn encode_image(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(Vec::new());
+    match format {
+        ImageFormat::Jpeg => image
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut buffer, 90)),
+        format => image.write_to(&mut buffer, format),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(buffer.into_inner())
+}
+
+fn is_image(bytes: &[u8]) -> bool {
+    matches!(
+        image::guess_format(bytes),
+        Ok(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)
+    )
+}
+
+#[doc = "Filter on attachments linked to an entity of the organization"]
+fn in_organization(organization_id: &str) -> attachment::WhereParam {
+    or![
+        attachment::plant::is(vec![plant::organization_id::equals(
+            organization_id.to_string()
+        )]),
+        attachment::genetic::is(vec![genetic::organization_id::equals(
+            organization_id.to_string()
+        )]),
+        attachment::harvest::is(vec![harvest_in_organization(organization_id)]),
+        attachment::history::is(vec![plant_history::organization_id::equals(
+            organization_id.to_string()
+        )]),
+        attachment::destruction::is(vec![destruction_in_organization(organization_id)]),
+    ]
+}
+
+#[doc = "Reject links to entities of other organizations as not found"]
+async fn check_links(
+    data: &PrismaClient,
+    organization_id: &str,
+    links: &AttachmentQuery,
+) -> Result<(), ErrorCode> {
+    let organization_id = organization_id.to_string();
+    let mut found = true;
+    if let Some(id) = &links.plant_id {
+        found &= data
+            .plant()
+            .count(vec![
+                plant::id::equals(id.clone()),
+                plant::organization_id::equals(organization_id.clone()),
+            ])
+            .exec()
+            .timed("plant", "count")
+            .await?
+            > 0;
+    }
+    if let Some(id) = &links.genetic_id {
+        found &= data
+            .genetic()
+            .count(vec![
+                genetic::id::equals(id.clone()),
+                genetic::organization_id::equals(organization_id.clone()),
+            ])
+            .exec()
+            .timed("genetic", "count")
+            .await?
+            > 0;
+    }
+    if let Some(id) = &links.harvest_id {
+        found &= data
+            .harvest()
+            .count(vec![
+                harvest::id::equals(id.clone()),
+                harvest_in_organization(&organization_id),
+            ])
+            .exec()
+            .timed("harvest", "count")
+            .await?
+            > 0;
+    }
+    if let Some(id) = &links.history_id {
+        found &= data
+            .plant_history()
+            .count(vec![
+                plant_history::id::equals(id.clone()),
+                plant_history::organization_id::equals(organization_id.clone()),
+            ])
+            .exec()
+            .timed("plant_history", "count")
+            .await?
+            > 0;
+    }
+    if let Some(id) = &links.destruction_id {
+        found &= data
+            .destruction()
+            .count(vec![
+                destruction::id::equals(id.clone()),
+                destruction_in_organization(&organization_id),
+            ])
+            .exec()
+            .timed("destruction", "count")
+            .await?
+            > 0;
+    }
+    match found {
+        true => Ok(()),
+        false => Err(ErrorCode::DATABASE002),
+    }
+}
+
+pub async fn get_attachments(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    query: AttachmentQuery,
+) -> Result<Vec<attachment::Data>, ErrorCode> {
+    let mut filter = vec![in_organization(organization_id)];
+    if let Some(plant_id) = query.plant_id {
+        filter.push(attachment::plant_id::equals(Some(plant_id)));
+    }
+    if let Some(genetic_id) = query.genetic_id {
+        filter.push(attachment::genetic_id::equals(Some(genetic_id)));
+    }
+    if let Some(harvest_id) = query.harvest_id {
+        filter.push(attachment::harvest_id::equals(Some(harvest_id)));
+    }
+    if let Some(history_id) = query.history_id {
+        filter.push(attachment::history_id::equals(Some(history_id)));
+    }
+    if let Some(destruction_id) = query.destruction_id {
+        filter.push(attachment::destruction_id::equals(Some(destruction_id)));
+    }
+
+    match data
+        .attachment()
+        .find_many(filter)
+        .order_by(attachment::created_at::order(Direction::Desc))
+        .exec()
+        .timed("attachment", "find_many")
+        .await
+    {
+        Ok(attachments) => Ok(attachments),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_attachment_by_id(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<attachment::Data, ErrorCode> {
+    match data
+        .attachment()
+        .find_first(vec![
+            attachment::id::equals(id),
+            in_organization(organization_id),
+        ])
+        .exec()
+        .timed("attachment", "find_first")
+        .await
+    {
+        Ok(Some(attachment)) => Ok(attachment),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Store an uploaded photo or document and link it to its entities.
+Photos are stripped of their metadata and get a thumbnail."]
+pub async fn upload_attachment(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    storage: &dyn Storage,
+    upload: UploadRequest,
+    user_id: String,
+) -> Result<attachment::Data, ErrorCode> {
+    let links = upload.links;
+    if links.plant_id.is_none()
+        && links.genetic_id.is_none()
+        && links.harvest_id.is_none()
+        && links.history_id.is_none()
+        && links.destruction_id.is_none()
+    {
+        return Err(ErrorCode::BADREQUEST(
+            "Attachment needs a plant, genetic, harvest, history or destruction entry".to_string(),
+        ));
+    }
+    if upload.bytes.is_empty() {
+        return Err(ErrorCode::BADREQUEST("File is required".to_string()));
+    }
+    check_links(data, organization_id, &links).await?;
+
+    let (kind, bytes, content_type, thumbnail) = if is_image(&upload.bytes) {
+        let bytes = upload.bytes;
+        let image = match web::block(move || process_image(&bytes)).await {
+            Ok(Ok(image)) => image,
+            Ok(Err(e)) => return Err(ErrorCode::BADREQUEST(e)),
+            Err(_) => return Err(ErrorCode::INTERNAL001),
+        };
+        (
+            AttachmentKind::Photo,
+            image.bytes,
+            image.content_type.to_string(),
+            Some(image.thumbnail),
+        )
+    } else {
+        match upload.content_type {
+            Some(content_type) if DOCUMENT_TYPES.contains(&content_type.as_str()) => {
+                (AttachmentKind::Document, upload.bytes, content_type, None)
+            }
+            _ => {
+                return Err(ErrorCode::BADREQUEST(format!(
+                    "Unsupported file type, allowed are images and {}",
+                    DOCUMENT_TYPES.join(", ")
+                )))
+            }
+        }
+    };
+
+    let storage_key = format!("attachments/{}", Uuid::new_v4());
+    let thumbnail_key = thumbnail
+        .as_ref()
+        .map(|_| format!("{}.thumbnail.jpg", storage_key));
+    let size = bytes.len() as i32;
+
+    if let Err(e) = storage.put(&storage_key, bytes, &content_type).await {
+        return Err(ErrorCode::STORAGE001(e));
+    }
+    if let (Some(key), Some(thumbnail)) = (&thumbnail_key, thumbnail) {
+        if let Err(e) = storage.put(key, thumbnail, "image/jpeg").await {
+            let _ = storage.delete(&storage_key).await;
+            return Err(ErrorCode::STORAGE001(e));
+        }
+    }
+
+    match data
+        .attachment()
+        .create_unchecked(
+            kind,
+            upload.file_name,
+            content_type,
+            size,
+            storage_key.clone(),
+            user_id,
+            vec![
+                attachment::thumbnail_key::set(thumbnail_key.clone()),
+                attachment::plant_id::set(links.plant_id),
+                attachment::genetic_id::set(links.genetic_id),
+                attachment::harvest_id::set(links.harvest_id),
+                attachment::history_id::set(links.history_id),
+                attachment::destruction_id::set(links.destruction_id),
+            ],
+        )
+        .exec()
+        .timed("attachment", "create_unchecked")
+        .await
+    {
+        Ok(attachment) => Ok(attachment),
+        Err(e) => {
+            // Do not keep files nobody can reference
+            let _ = storage.delete(&storage_key).await;
+            if let Some(key) = thumbnail_key {
+                let _ = storage.delete(&key).await;
+            }
+            Err(e.into())
+        }
+    }
+}
+
+#[doc = "Read the stored file or its thumbnail, returns the content type and bytes"]
+pub async fn get_attachment_file(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    storage: &dyn Storage,
+    id: String,
+    thumbnail: bool,
+) -> Result<(attachment::Data, String, Vec<u8>), ErrorCode> {
+    let attachment = get_attachment_by_id(data, organization_id, id).await?;
+    let (key, content_type) = match (thumbnail, &attachment.thumbnail_key) {
+        (false, _) => (
+            attachment.storage_key.clone(),
+            attachment.content_type.clone(),
+        ),
+        (true, Some(key)) => (key.clone(), "image/jpeg".to_string()),
+        (true, None) => return Err(ErrorCode::DATABASE002),
+    };
+
+    match storage.get(&key).await {
+        Ok(bytes) => Ok((attachment, content_type, bytes)),
+        Err(e) => Err(ErrorCode::STORAGE001(e)),
+    }
+}
+
+#[doc = "Change the entities an attachment is linked to, e.g. a history event"]
+pub async fn edit_attachment_links(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    links: AttachmentLinks,
+) -> Result<attachment::Data, ErrorCode> {
+    get_attachment_by_id(data, organization_id, id.clone()).await?;
+    let linked = AttachmentQuery {
+        plant_id: links.plant_id.clone().flatten(),
+        genetic_id: links.genetic_id.clone().flatten(),
+        harvest_id: links.harvest_id.clone().flatten(),
+        history_id: links.history_id.clone().flatten(),
+        destruction_id: links.destruction_id.clone().flatten(),
+    };
+    check_links(data, organization_id, &linked).await?;
+    match data
+        .attachment()
+        .update_unchecked(attachment::id::equals(id), links.to_params())
+        .exec()
+        .timed("attachment", "update_unchecked")
+        .await
+    {
+        Ok(attachment) => Ok(attachment),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_attachment(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    storage: &dyn Storage,
+    id: String,
+) -> Result<(), ErrorCode> {
+    get_attachment_by_id(data, organization_id, id.clone()).await?;
+    let attachment = match data
+        .attachment()
+        .delete(attachment::id::equals(id))
+        .exec()
+        .timed("attachment", "delete")
+        .await
+    {
+        Ok(attachment) => attachment,
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Err(e) = storage.delete(&attachment.storage_key).await {
+        return Err(ErrorCode::STORAGE001(e));
+    }
+    if let Some(key) = attachment.thumbnail_key {
+        if let Err(e) = storage.delete(&key).await {
+            return Err(ErrorCode::STORAGE001(e));
+        }
+    }
+    Ok(())
+}
@@ -5,7 +5,9 @@
 use crate::model::dto::auth::{LoginRequest, RegisterRequest};
 use crate::model::error::ErrorCode;
 use crate::model::error::ErrorCode::{AUTH001, AUTH002};
+use crate::model::validation::Validate;
 use crate::prisma::{user, PrismaClient};
+use crate::service::metrics::TimedQuery;
 use crate::service::user::{find_by_identifier, update_last_login};
 use actix_web::web;
 use argon2::password_hash::rand_core::OsRng;
@@ -31,7 +33,7 @@ pub async fn login_user(
         let hash = match hash {
             Ok(h) => Some(h),
             Err(e) => {
-                println!("Error: {:?}", e);
+                tracing::warn!(user_id = %usr.id, error = %e, "Stored password hash is invalid");
                 None
             }
         };
@@ -61,6 +63,8 @@ pub async fn register_user(
     register_request: &RegisterRequest,
     data: &web::Data<PrismaClient>,
 ) -> Result<user::Data, ErrorCode> {
+    register_request.validate()?;
+
     let salt: SaltString = SaltString::generate(&mut OsRng);
     let pw = &register_request.password;
     let pw = pw.as_bytes();
@@ -85,6 +89,7 @@ pub async fn register_user(
             vec![],
         )
         .exec()
+        .timed("user", "create")
         .await
         .unwrap();
 
@@ -116,6 +121,7 @@ pub async fn change_password(
             vec![user::password::set(hashed_password.clone())],
         )
         .exec()
+        .timed("user", "update")
         .await
         .unwrap();
 
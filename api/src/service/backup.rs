@@ -0,0 +1,1144 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Cursor, Read, Write};
+
+use chrono::Utc;
+use prisma_client_rust::Direction;
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    model::{
+        dto::backup::{
+            ArchiveManifest, GeneticRecord, HistoryRecord, ImportReport, OrganizationRecord,
+            PlantRecord, RoleAssignment, RoleRecord, Snapshot, SnapshotSummary, UserRecord,
+            SNAPSHOT_VERSION,
+        },
+        error::ErrorCode,
+    },
+    prisma::{genetic, organization, plant, plant_history, role, user, PrismaClient},
+    service::{metrics::TimedQuery, organization::DEFAULT_ORGANIZATION_ID, plant::stage_name},
+};
+
+#[doc = "Assigner recorded for roles given by the command line instead of a user"]
+pub const SYSTEM_USER_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+#[doc = "Entry of the archive read by the import, the CSV files are for spreadsheets"]
+pub const ARCHIVE_SNAPSHOT: &str = "snapshot.json";
+pub const ARCHIVE_MANIFEST: &str = "manifest.json";
+
+#[doc = "Imports run in one transaction, large snapshots and CSV files need more than
+the default 5 s"]
+pub const IMPORT_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+impl From<genetic::Data> for GeneticRecord {
+    fn from(genetic: genetic::Data) -> Self {
+        Self {
+            id: genetic.id,
+            name: genetic.name,
+            organization_id: genetic.organization_id,
+            flower_days: genetic.flower_days,
+            code: genetic.code,
+            naming_template: genetic.naming_template,
+            female_parent_id: genetic.female_parent_id,
+            male_parent_id: genetic.male_parent_id,
+            generation: genetic.generation,
+            breeder: genetic.breeder,
+            source: genetic.source,
+            genetic_type: genetic.genetic_type,
+            thc_min: genetic.thc_min,
+            thc_max: genetic.thc_max,
+            cbd_min: genetic.cbd_min,
+            cbd_max: genetic.cbd_max,
+            terpenes: genetic.terpenes,
+            expected_yield: genetic.expected_yield,
+            stretch_factor: genetic.stretch_factor,
+            autoflower: genetic.autoflower,
+            tags: genetic.tags,
+        }
+    }
+}
+
+pub fn summary(snapshot: &Snapshot) -> SnapshotSummary {
+    SnapshotSummary {
+        organizations: snapshot.organizations.len(),
+        roles: snapshot.roles.len(),
+        users: snapshot.users.len(),
+        genetics: snapshot.genetics.len(),
+        plants: snapshot.plants.len(),
+        history: snapshot.history.len(),
+    }
+}
+
+#[doc = "Read organizations, roles, users, genetics, plants and their history.
+Password hashes are left out unless `include_passwords` is set."]
+pub async fn export_snapshot(
+    data: &PrismaClient,
+    include_passwords: bool,
+) -> Result<Snapshot, ErrorCode> {
+    let organizations = data
+        .organization()
+        .find_many(vec![])
+        .order_by(organization::name::order(Direction::Asc))
+        .exec()
+        .timed("organization", "find_many")
+        .await?;
+    let roles = data
+        .role()
+        .find_many(vec![])
+        .order_by(role::id::order(Direction::Asc))
+        .exec()
+        .timed("role", "find_many")
+        .await?;
+    let users = data
+        .user()
+        .find_many(vec![])
+        .with(user::roles::fetch(vec![]))
+        .order_by(user::created_at::order(Direction::Asc))
+        .exec()
+        .timed("user", "find_many")
+        .await?;
+    let genetics = data
+        .genetic()
+        .find_many(vec![])
+        .order_by(genetic::name::order(Direction::Asc))
+        .exec()
+        .timed("genetic", "find_many")
+        .await?;
+    let plants = data
+        .plant()
+        .find_many(vec![])
+        .order_by(plant::created_at::order(Direction::Asc))
+        .exec()
+        .timed("plant", "find_many")
+        .await?;
+    let history = data
+        .plant_history()
+        .find_many(vec![])
+        .order_by(plant_history::created_at::order(Direction::Asc))
+        .exec()
+        .timed("plant_history", "find_many")
+        .await?;
+
+    Ok(Snapshot {
+        version: SNAPSHOT_VERSION,
+        exported_at: Utc::now().fixed_offset(),
+        organizations: organizations
+            .into_iter()
+            .map(|organization| OrganizationRecord {
+                id: organization.id,
+                name: organization.name,
+            })
+            .collect(),
+        roles: roles
+            .into_iter()
+            .map(|role| RoleRecord {
+                id: role.id,
+                name: role.name,
+                is_default: role.is_default,
+                is_admin: role.is_admin,
+            })
+            .collect(),
+        users: users
+            .into_iter()
+            .map(|user| UserRecord {
+                roles: user
+                    .roles
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|role| RoleAssignment {
+                        role_id: role.role_id,
+                        organization_id: role.organization_id,
+                    })
+                    .collect(),
+                id: user.id,
+                display_name: user.display_name,
+                email: user.email,
+                password: (include_passwords && !user.password.is_empty()).then_some(user.password),
+                created_at: user.created_at,
+            })
+            .collect(),
+        genetics: genetics.into_iter().map(GeneticRecord::from).collect(),
+        plants: plants
+            .into_iter()
+            .map(|plant| PlantRecord {
+                id: plant.id,
+                name: plant.name,
+                organization_id: plant.organization_id,
+                genetic_id: plant.genetic_id,
+                stage: plant.stage,
+                mother_id: plant.mother_id,
+                created_at: plant.created_at,
+            })
+            .collect(),
+        history: history
+            .into_iter()
+            .map(|entry| HistoryRecord {
+                id: entry.id,
+                plant_id: entry.plant_id,
+                action: entry.action,
+                stage: entry.stage,
+                user_id: entry.user_id,
+                organization_id: entry.organization_id,
+                created_at: entry.created_at,
+            })
+            .collect(),
+    })
+}
+
+fn csv_file<const N: usize>(
+    header: [&str; N],
+    rows: impl Iterator<Item = [String; N]>,
+) -> Result<Vec<u8>, String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(header).map_err(|e| e.to_string())?;
+    for row in rows {
+        writer.write_record(row).map_err(|e| e.to_string())?;
+    }
+    writer.into_inner().map_err(|e| e.to_string())
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(T::to_string).unwrap_or_default()
+}
+
+#[doc = "One CSV file per kind, lists are joined with `; `. Password hashes are never
+written to them."]
+pub fn snapshot_csv(snapshot: &Snapshot) -> Result<Vec<(&'static str, Vec<u8>)>, String> {
+    Ok(vec![
+        (
+            "organizations.csv",
+            csv_file(
+                ["id", "name"],
+                snapshot
+                    .organizations
+                    .iter()
+                    .map(|organization| [organization.id.clone(), organization.name.clone()]),
+            )?,
+        ),
+        (
+            "roles.csv",
+            csv_file(
+                ["id", "name", "isDefault", "isAdmin"],
+                snapshot.roles.iter().map(|role| {
+                    [
+                        role.id.to_string(),
+                        role.name.clone(),
+                        role.is_default.to_string(),
+                        role.is_admin.to_string(),
+                    ]
+                }),
+            )?,
+        ),
+        (
+            "users.csv",
+            csv_file(
+                ["id", "displayName", "email", "createdAt", "roles"],
+                snapshot.users.iter().map(|user| {
+                    [
+                        user.id.clone(),
+                        user.display_name.clone(),
+                        user.email.clone(),
+                        user.created_at.to_rfc3339(),
+                        user.roles
+                            .iter()
+                            .map(|role| format!("{}:{}", role.organization_id, role.role_id))
+                            .collect::<Vec<_>>()
+                            .join("; "),
+                    ]
+                }),
+            )?,
+        ),
+        (
+            "genetics.csv",
+            csv_file(
+                [
+                    "id",
+                    "name",
+                    "organizationId",
+                    "code",
+                    "flowerDays",
+                    "femaleParentId",
+                    "maleParentId",
+                    "generation",
+                    "breeder",
+                    "source",
+                    "geneticType",
+                    "thcMin",
+                    "thcMax",
+                    "cbdMin",
+                    "cbdMax",
+                    "terpenes",
+                    "expectedYield",
+                    "stretchFactor",
+                    "autoflower",
+                    "tags",
+                ],
+                snapshot.genetics.iter().map(|genetic| {
+                    [
+                        genetic.id.clone(),
+                        genetic.name.clone(),
+                        genetic.organization_id.clone(),
+                        optional(&genetic.code),
+                        genetic.flower_days.to_string(),
+                        optional(&genetic.female_parent_id),
+                        optional(&genetic.male_parent_id),
+                        optional(&genetic.generation),
+                        optional(&genetic.breeder),
+                        optional(&genetic.source),
+                        genetic
+                            .genetic_type
+                            .map(|t| format!("{:?}", t).to_uppercase())
+                            .unwrap_or_default(),
+                        optional(&genetic.thc_min),
+                        optional(&genetic.thc_max),
+                        optional(&genetic.cbd_min),
+                        optional(&genetic.cbd_max),
+                        genetic.terpenes.join("; "),
+                        optional(&genetic.expected_yield),
+                        optional(&genetic.stretch_factor),
+                        genetic.autoflower.to_string(),
+                        genetic.tags.join("; "),
+                    ]
+                }),
+            )?,
+        ),
+        (
+            "plants.csv",
+            csv_file(
+                [
+                    "id",
+                    "name",
+                    "organizationId",
+                    "geneticId",
+                    "stage",
+                    "motherId",
+                    "createdAt",
+                ],
+                snapshot.plants.iter().map(|plant| {
+                    [
+                        plant.id.clone(),
+                        plant.name.clone(),
+                        plant.organization_id.clone(),
+                        plant.genetic_id.clone(),
+                        stage_name(plant.stage),
+                        optional(&plant.mother_id),
+                        plant.created_at.to_rfc3339(),
+                    ]
+                }),
+            )?,
+        ),
+        (
+            "history.csv",
+            csv_file(
+                [
+                    "id",
+                    "plantId",
+                    "action",
+                    "stage",
+                    "userId",
+                    "organizationId",
+                    "createdAt",
+                ],
+                snapshot.history.iter().map(|entry| {
+                    [
+                        entry.id.clone(),
+                        entry.plant_id.clone(),
+                        entry.action.clone(),
+                        entry.stage.map(stage_name).unwrap_or_default(),
+                        entry.user_id.clone(),
+                        entry.organization_id.clone(),
+                        entry.created_at.to_rfc3339(),
+                    ]
+                }),
+            )?,
+        ),
+    ])
+}
+
+#[doc = "Zip archive with a manifest, the snapshot as JSON and a CSV file per kind"]
+pub fn write_archive(snapshot: &Snapshot) -> Result<Vec<u8>, String> {
+    let manifest = ArchiveManifest {
+        version: snapshot.version,
+        exported_at: snapshot.exported_at,
+        passwords: snapshot.users.iter().any(|user| user.password.is_some()),
+        counts: summary(snapshot),
+    };
+
+    let mut files = vec![
+        (
+            ARCHIVE_MANIFEST,
+            serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?,
+        ),
+        (
+            ARCHIVE_SNAPSHOT,
+            serde_json::to_vec_pretty(snapshot).map_err(|e| e.to_string())?,
+        ),
+    ];
+    files.extend(snapshot_csv(snapshot)?);
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut archive = ZipWriter::new(Cursor::new(vec![]));
+    for (name, content) in files {
+        archive
+            .start_file(name, options)
+            .map_err(|e| e.to_string())?;
+        archive.write_all(&content).map_err(|e| e.to_string())?;
+    }
+    Ok(archive.finish().map_err(|e| e.to_string())?.into_inner())
+}
+
+#[doc = "Read the snapshot of an archive, a bare JSON snapshot is accepted as well"]
+pub fn read_archive(bytes: &[u8]) -> Result<Snapshot, String> {
+    let json = if bytes.starts_with(b"PK") {
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+        let mut file = archive
+            .by_name(ARCHIVE_SNAPSHOT)
+            .map_err(|e| format!("{}: {}", ARCHIVE_SNAPSHOT, e))?;
+        let mut json = vec![];
+        file.read_to_end(&mut json).map_err(|e| e.to_string())?;
+        json
+    } else {
+        bytes.to_vec()
+    };
+    serde_json::from_slice(&json).map_err(|e| format!("Not a snapshot: {}", e))
+}
+
+fn check_unique<'a>(
+    errors: &mut BTreeMap<String, String>,
+    kind: &str,
+    field: &str,
+    values: impl Iterator<Item = &'a str>,
+) {
+    let mut seen = HashSet::new();
+    for (index, value) in values.enumerate() {
+        if !seen.insert(value) {
+            errors.insert(
+                format!("{}.{}.{}", kind, index, field),
+                format!("{} {} appears more than once", field, value),
+            );
+        }
+    }
+}
+
+fn check_unique_in_organization<'a>(
+    errors: &mut BTreeMap<String, String>,
+    kind: &str,
+    field: &str,
+    values: impl Iterator<Item = (&'a str, &'a str)>,
+) {
+    let mut seen = HashSet::new();
+    for (index, (organization, value)) in values.enumerate() {
+        if !seen.insert((organization, value)) {
+            errors.insert(
+                format!("{}.{}.{}", kind, index, field),
+                format!(
+                    "{} {} appears more than once in organization {}",
+                    field, value, organization
+                ),
+            );
+        }
+    }
+}
+
+#[doc = "Check a snapshot on its own: version, unique ids and names, and that every
+reference points to a record of the snapshot in the same organization. The default
+organization exists everywhere and need not be listed. Messages are keyed by
+`kind.index.field`."]
+pub fn validate_snapshot(snapshot: &Snapshot) -> BTreeMap<String, String> {
+    let mut errors = BTreeMap::new();
+    if snapshot.version == 0 || snapshot.version > SNAPSHOT_VERSION {
+        errors.insert(
+            "version".to_string(),
+            format!(
+                "Snapshot version {} is not supported, expected up to {}",
+                snapshot.version, SNAPSHOT_VERSION
+            ),
+        );
+    }
+
+    let mut organizations: HashSet<&str> = snapshot
+        .organizations
+        .iter()
+        .map(|organization| organization.id.as_str())
+        .collect();
+    organizations.insert(DEFAULT_ORGANIZATION_ID);
+    let roles: HashSet<i32> = snapshot.roles.iter().map(|role| role.id).collect();
+    let users: HashSet<&str> = snapshot.users.iter().map(|u| u.id.as_str()).collect();
+    let genetics: HashMap<&str, &str> = snapshot
+        .genetics
+        .iter()
+        .map(|g| (g.id.as_str(), g.organization_id.as_str()))
+        .collect();
+    let plants: HashMap<&str, &str> = snapshot
+        .plants
+        .iter()
+        .map(|p| (p.id.as_str(), p.organization_id.as_str()))
+        .collect();
+    let unknown_organization = |errors: &mut BTreeMap<String, String>, key: String, id: &str| {
+        if !organizations.contains(id) {
+            errors.insert(key, format!("Organization {} is not in the snapshot", id));
+        }
+    };
+
+    check_unique(
+        &mut errors,
+        "organizations",
+        "id",
+        snapshot.organizations.iter().map(|o| o.id.as_str()),
+    );
+    check_unique(
+        &mut errors,
+        "organizations",
+        "name",
+        snapshot.organizations.iter().map(|o| o.name.as_str()),
+    );
+    check_unique(
+        &mut errors,
+        "roles",
+        "name",
+        snapshot.roles.iter().map(|role| role.name.as_str()),
+    );
+    check_unique(
+        &mut errors,
+        "users",
+        "id",
+        snapshot.users.iter().map(|u| u.id.as_str()),
+    );
+    check_unique(
+        &mut errors,
+        "users",
+        "email",
+        snapshot.users.iter().map(|u| u.email.as_str()),
+    );
+    check_unique(
+        &mut errors,
+        "genetics",
+        "id",
+        snapshot.genetics.iter().map(|g| g.id.as_str()),
+    );
+    check_unique_in_organization(
+        &mut errors,
+        "genetics",
+        "name",
+        snapshot
+            .genetics
+            .iter()
+            .map(|g| (g.organization_id.as_str(), g.name.as_str())),
+    );
+    check_unique_in_organization(
+        &mut errors,
+        "genetics",
+        "code",
+        snapshot.genetics.iter().filter_map(|g| {
+            g.code
+                .as_deref()
+                .map(|code| (g.organization_id.as_str(), code))
+        }),
+    );
+    check_unique(
+        &mut errors,
+        "plants",
+        "id",
+        snapshot.plants.iter().map(|p| p.id.as_str()),
+    );
+    check_unique_in_organization(
+        &mut errors,
+        "plants",
+        "name",
+        snapshot
+            .plants
+            .iter()
+            .map(|p| (p.organization_id.as_str(), p.name.as_str())),
+    );
+
+    for (index, user) in snapshot.users.iter().enumerate() {
+        for role in &user.roles {
+            if !roles.contains(&role.role_id) {
+                errors.insert(
+                    format!("users.{}.roles", index),
+                    format!("Role {} is not in the snapshot", role.role_id),
+                );
+            }
+            unknown_organization(
+                &mut errors,
+                format!("users.{}.roles", index),
+                &role.organization_id,
+            );
+        }
+    }
+    for (index, genetic) in snapshot.genetics.iter().enumerate() {
+        unknown_organization(
+            &mut errors,
+            format!("genetics.{}.organizationId", index),
+            &genetic.organization_id,
+        );
+        if genetic.name.trim().is_empty() {
+            errors.insert(
+                format!("genetics.{}.name", index),
+                "Genetic name must be at least 1 character long".to_string(),
+            );
+        }
+        if genetic.flower_days < 1 {
+            errors.insert(
+                format!("genetics.{}.flowerDays", index),
+                "Flower days must be at least 1".to_string(),
+            );
+        }
+        for (field, parent) in [
+            ("femaleParentId", &genetic.female_parent_id),
+            ("maleParentId", &genetic.male_parent_id),
+        ] {
+            if let Some(parent) = parent {
+                match genetics.get(parent.as_str()) {
+                    None => {
+                        errors.insert(
+                            format!("genetics.{}.{}", index, field),
+                            format!("Genetic {} is not in the snapshot", parent),
+                        );
+                    }
+                    Some(organization) if *organization != genetic.organization_id => {
+                        errors.insert(
+                            format!("genetics.{}.{}", index, field),
+                            format!("Genetic {} belongs to another organization", parent),
+                        );
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    for (index, plant) in snapshot.plants.iter().enumerate() {
+        unknown_organization(
+            &mut errors,
+            format!("plants.{}.organizationId", index),
+            &plant.organization_id,
+        );
+        if plant.name.trim().is_empty() {
+            errors.insert(
+                format!("plants.{}.name", index),
+                "Plant name must be at least 1 character long".to_string(),
+            );
+        }
+        match genetics.get(plant.genetic_id.as_str()) {
+            None => {
+                errors.insert(
+                    format!("plants.{}.geneticId", index),
+                    format!("Genetic {} is not in the snapshot", plant.genetic_id),
+                );
+            }
+            Some(organization) if *organization != plant.organization_id => {
+                errors.insert(
+                    format!("plants.{}.geneticId", index),
+                    format!(
+                        "Genetic {} belongs to another organization",
+                        plant.genetic_id
+                    ),
+                );
+            }
+            Some(_) => {}
+        }
+        match plant.mother_id.as_deref() {
+            Some(mother) if mother == plant.id => {
+                errors.insert(
+                    format!("plants.{}.motherId", index),
+                    "A plant cannot be its own mother".to_string(),
+                );
+            }
+            Some(mother) => match plants.get(mother) {
+                None => {
+                    errors.insert(
+                        format!("plants.{}.motherId", index),
+                        format!("Plant {} is not in the snapshot", mother),
+                    );
+                }
+                Some(organization) if *organization != plant.organization_id => {
+                    errors.insert(
+                        format!("plants.{}.motherId", index),
+                        format!("Plant {} belongs to another organization", mother),
+                    );
+                }
+                Some(_) => {}
+            },
+            None => {}
+        }
+    }
+    for (index, entry) in snapshot.history.iter().enumerate() {
+        match plants.get(entry.plant_id.as_str()) {
+            None => {
+                errors.insert(
+                    format!("history.{}.plantId", index),
+                    format!("Plant {} is not in the snapshot", entry.plant_id),
+                );
+            }
+            Some(organization) if *organization != entry.organization_id => {
+                errors.insert(
+                    format!("history.{}.organizationId", index),
+                    format!("Plant {} belongs to another organization", entry.plant_id),
+                );
+            }
+            Some(_) => {}
+        }
+        if !users.contains(entry.user_id.as_str()) {
+            errors.insert(
+                format!("history.{}.userId", index),
+                format!("User {} is not in the snapshot", entry.user_id),
+            );
+        }
+    }
+    errors
+}
+
+#[doc = "Records of the target instance an import is matched against"]
+#[derive(Debug, Default)]
+pub struct Existing {
+    #[doc = "Organization ids by name"]
+    pub organizations: HashMap<String, String>,
+    #[doc = "Role ids by name"]
+    pub roles: HashMap<String, i32>,
+    #[doc = "User ids by email"]
+    pub users: HashMap<String, String>,
+    #[doc = "Genetic ids by organization and name"]
+    pub genetics: HashMap<(String, String), String>,
+    #[doc = "Genetic codes with their organization"]
+    pub genetic_codes: HashSet<(String, String)>,
+    #[doc = "Plant ids by organization and name"]
+    pub plants: HashMap<(String, String), String>,
+    #[doc = "Ids in use, records keep their id unless it is taken"]
+    pub ids: HashSet<String>,
+}
+
+async fn existing_records(client: &PrismaClient) -> Result<Existing, ErrorCode> {
+    let mut existing = Existing::default();
+    for organization in client
+        .organization()
+        .find_many(vec![])
+        .exec()
+        .timed("organization", "find_many")
+        .await?
+    {
+        existing.ids.insert(organization.id.clone());
+        existing
+            .organizations
+            .insert(organization.name, organization.id);
+    }
+    for role in client
+        .role()
+        .find_many(vec![])
+        .exec()
+        .timed("role", "find_many")
+        .await?
+    {
+        existing.roles.entry(role.name).or_insert(role.id);
+    }
+    for user in client
+        .user()
+        .find_many(vec![])
+        .exec()
+        .timed("user", "find_many")
+        .await?
+    {
+        existing.ids.insert(user.id.clone());
+        existing.users.insert(user.email, user.id);
+    }
+    for genetic in client
+        .genetic()
+        .find_many(vec![])
+        .exec()
+        .timed("genetic", "find_many")
+        .await?
+    {
+        existing.ids.insert(genetic.id.clone());
+        if let Some(code) = genetic.code {
+            existing
+                .genetic_codes
+                .insert((genetic.organization_id.clone(), code));
+        }
+        existing
+            .genetics
+            .insert((genetic.organization_id, genetic.name), genetic.id);
+    }
+    for plant in client
+        .plant()
+        .find_many(vec![])
+        .exec()
+        .timed("plant", "find_many")
+        .await?
+    {
+        existing.ids.insert(plant.id.clone());
+        existing
+            .plants
+            .insert((plant.organization_id, plant.name), plant.id);
+    }
+    Ok(existing)
+}
+
+#[doc = "Records to write with their ids and references mapped onto the target"]
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    pub organizations: Vec<OrganizationRecord>,
+    pub roles: Vec<RoleRecord>,
+    #[doc = "Snapshot role ids matched to present roles, created roles are added on apply"]
+    pub role_ids: HashMap<i32, i32>,
+    #[doc = "Users to create, the role ids of their `roles` are still the ones of the snapshot"]
+    pub users: Vec<UserRecord>,
+    pub genetics: Vec<GeneticRecord>,
+    pub plants: Vec<PlantRecord>,
+    pub history: Vec<HistoryRecord>,
+    pub report: ImportReport,
+}
+
+#[doc = "The id a created record gets, its own unless another record already uses it"]
+fn free_id(id: &str, existing: &Existing) -> String {
+    if existing.ids.contains(id) {
+        Uuid::new_v4().to_string()
+    } else {
+        id.to_string()
+    }
+}
+
+#[doc = "Decide what an import writes. Organizations and roles are matched by name, users
+by email, genetics and plants by name within their organization; matched records are left
+untouched and references to them point to the present record. History is only imported
+for created plants."]
+pub fn plan_import(snapshot: Snapshot, existing: &Existing) -> Result<ImportPlan, ErrorCode> {
+    let mut errors = validate_snapshot(&snapshot);
+    if !errors.is_empty() {
+        return Err(ErrorCode::VALIDATION(errors));
+    }
+
+    let mut plan = ImportPlan::default();
+    let mut organization_ids = HashMap::from([(
+        DEFAULT_ORGANIZATION_ID.to_string(),
+        DEFAULT_ORGANIZATION_ID.to_string(),
+    )]);
+    for mut organization in snapshot.organizations {
+        match existing.organizations.get(&organization.name) {
+            Some(id) => {
+                organization_ids.insert(organization.id, id.clone());
+                plan.report.existing.organizations += 1;
+            }
+            None => {
+                let id = free_id(&organization.id, existing);
+                organization_ids.insert(organization.id.clone(), id.clone());
+                organization.id = id;
+                plan.organizations.push(organization);
+            }
+        }
+    }
+    plan.report.created.organizations = plan.organizations.len();
+
+    for (index, genetic) in snapshot.genetics.iter().enumerate() {
+        let organization = &organization_ids[&genetic.organization_id];
+        if let Some(code) = &genetic.code {
+            if !existing
+                .genetics
+                .contains_key(&(organization.clone(), genetic.name.clone()))
+                && existing
+                    .genetic_codes
+                    .contains(&(organization.clone(), code.clone()))
+            {
+                errors.insert(
+                    format!("genetics.{}.code", index),
+                    format!("Genetic code {} is used by another genetic", code),
+                );
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ErrorCode::VALIDATION(errors));
+    }
+
+    for role in snapshot.roles {
+        match existing.roles.get(&role.name) {
+            Some(id) => {
+                plan.role_ids.insert(role.id, *id);
+                plan.report.existing.roles += 1;
+            }
+            None => plan.roles.push(role),
+        }
+    }
+    plan.report.created.roles = plan.roles.len();
+
+    let mut user_ids = HashMap::new();
+    for mut user in snapshot.users {
+        match existing.users.get(&user.email) {
+            Some(id) => {
+                user_ids.insert(user.id, id.clone());
+                plan.report.existing.users += 1;
+            }
+            None => {
+                let id = free_id(&user.id, existing);
+                user_ids.insert(user.id.clone(), id.clone());
+                user.id = id;
+                for role in &mut user.roles {
+                    role.organization_id = organization_ids[&role.organization_id].clone();
+                }
+                if user.password.is_none() {
+                    plan.report.without_password += 1;
+                }
+                plan.users.push(user);
+            }
+        }
+    }
+    plan.report.created.users = plan.users.len();
+
+    let mut genetic_ids = HashMap::new();
+    let mut genetics = vec![];
+    for mut genetic in snapshot.genetics {
+        genetic.organization_id = organization_ids[&genetic.organization_id].clone();
+        let key = (genetic.organization_id.clone(), genetic.name.clone());
+        match existing.genetics.get(&key) {
+            Some(id) => {
+                genetic_ids.insert(genetic.id, id.clone());
+                plan.report.existing.genetics += 1;
+            }
+            None => {
+                let id = free_id(&genetic.id, existing);
+                genetic_ids.insert(genetic.id.clone(), id.clone());
+                genetic.id = id;
+                genetics.push(genetic);
+            }
+        }
+    }
+    plan.genetics = genetics
+        .into_iter()
+        .map(|mut genetic| {
+            genetic.female_parent_id = genetic
+                .female_parent_id
+                .map(|parent| genetic_ids[&parent].clone());
+            genetic.male_parent_id = genetic
+                .male_parent_id
+                .map(|parent| genetic_ids[&parent].clone());
+            genetic
+        })
+        .collect();
+    plan.report.created.genetics = plan.genetics.len();
+
+    let mut plant_ids = HashMap::new();
+    let mut plants = vec![];
+    for mut plant in snapshot.plants {
+        plant.organization_id = organization_ids[&plant.organization_id].clone();
+        let key = (plant.organization_id.clone(), plant.name.clone());
+        match existing.plants.get(&key) {
+            Some(id) => {
+                plant_ids.insert(plant.id, id.clone());
+                plan.report.existing.plants += 1;
+            }
+            None => {
+                let id = free_id(&plant.id, existing);
+                plant_ids.insert(plant.id.clone(), id.clone());
+                plant.id = id;
+                plants.push(plant);
+            }
+        }
+    }
+    let created_plants: HashSet<String> = plants.iter().map(|p| p.id.clone()).collect();
+    plan.plants = plants
+        .into_iter()
+        .map(|mut plant| {
+            plant.genetic_id = genetic_ids[&plant.genetic_id].clone();
+            plant.mother_id = plant.mother_id.map(|mother| plant_ids[&mother].clone());
+            plant
+        })
+        .collect();
+    plan.report.created.plants = plan.plants.len();
+
+    for mut entry in snapshot.history {
+        entry.plant_id = plant_ids[&entry.plant_id].clone();
+        if !created_plants.contains(&entry.plant_id) {
+            plan.report.existing.history += 1;
+            continue;
+        }
+        entry.id = Uuid::new_v4().to_string();
+        entry.user_id = user_ids[&entry.user_id].clone();
+        entry.organization_id = organization_ids[&entry.organization_id].clone();
+        plan.history.push(entry);
+    }
+    plan.report.created.history = plan.history.len();
+
+    Ok(plan)
+}
+
+async fn apply_plan(tx: &PrismaClient, plan: ImportPlan) -> Result<(), ErrorCode> {
+    for record in plan.organizations {
+        tx.organization()
+            .create(record.name, vec![organization::id::set(record.id)])
+            .exec()
+            .timed("organization", "create")
+            .await?;
+    }
+
+    let mut role_ids = plan.role_ids;
+    for record in plan.roles {
+        let role = tx
+            .role()
+            .create(
+                record.name,
+                vec![
+                    role::is_default::set(record.is_default),
+                    role::is_admin::set(record.is_admin),
+                ],
+            )
+            .exec()
+            .timed("role", "create")
+            .await?;
+        role_ids.insert(record.id, role.id);
+    }
+
+    for record in plan.users {
+        tx.user()
+            .create(
+                record.display_name,
+                record.email,
+                record.password.unwrap_or_default(),
+                vec![
+                    user::id::set(record.id.clone()),
+                    user::created_at::set(record.created_at),
+                ],
+            )
+            .exec()
+            .timed("user", "create")
+            .await?;
+        for role in record.roles {
+            tx.users_in_roles()
+                .create_unchecked(
+                    record.id.clone(),
+                    role_ids[&role.role_id],
+                    SYSTEM_USER_ID.to_string(),
+                    role.organization_id,
+                    vec![],
+                )
+                .exec()
+                .timed("users_in_roles", "create_unchecked")
+                .await?;
+        }
+    }
+
+    // Parents may come later in the snapshot, so lineage is linked after all exist
+    for record in &plan.genetics {
+        let record = record.clone();
+        tx.genetic()
+            .create_unchecked(
+                record.name,
+                record.flower_days,
+                record.organization_id,
+                vec![
+                    genetic::id::set(record.id),
+                    genetic::code::set(record.code),
+                    genetic::naming_template::set(record.naming_template),
+                    genetic::generation::set(record.generation),
+                    genetic::breeder::set(record.breeder),
+                    genetic::source::set(record.source),
+                    genetic::genetic_type::set(record.genetic_type),
+                    genetic::thc_min::set(record.thc_min),
+                    genetic::thc_max::set(record.thc_max),
+                    genetic::cbd_min::set(record.cbd_min),
+                    genetic::cbd_max::set(record.cbd_max),
+                    genetic::terpenes::set(record.terpenes),
+                    genetic::expected_yield::set(record.expected_yield),
+                    genetic::stretch_factor::set(record.stretch_factor),
+                    genetic::autoflower::set(record.autoflower),
+                    genetic::tags::set(record.tags),
+                ],
+            )
+            .exec()
+            .timed("genetic", "create_unchecked")
+            .await?;
+    }
+    for record in plan.genetics {
+        if record.female_parent_id.is_none() && record.male_parent_id.is_none() {
+            continue;
+        }
+        tx.genetic()
+            .update_unchecked(
+                genetic::id::equals(record.id),
+                vec![
+                    genetic::female_parent_id::set(record.female_parent_id),
+                    genetic::male_parent_id::set(record.male_parent_id),
+                ],
+            )
+            .exec()
+            .timed("genetic", "update_unchecked")
+            .await?;
+    }
+
+    // Same for mothers, which may be cloned from a plant listed after them
+    for record in &plan.plants {
+        let record = record.clone();
+        tx.plant()
+            .create_unchecked(
+                record.name,
+                record.genetic_id,
+                record.organization_id,
+                vec![
+                    plant::id::set(record.id),
+                    plant::stage::set(record.stage),
+                    plant::created_at::set(record.created_at),
+                ],
+            )
+            .exec()
+            .timed("plant", "create_unchecked")
+            .await?;
+    }
+    for record in plan.plants {
+        if let Some(mother_id) = record.mother_id {
+            tx.plant()
+                .update_unchecked(
+                    plant::id::equals(record.id),
+                    vec![plant::mother_id::set(Some(mother_id))],
+                )
+                .exec()
+                .timed("plant", "update_unchecked")
+                .await?;
+        }
+    }
+
+    for record in plan.history {
+        tx.plant_history()
+            .create_unchecked(
+                record.plant_id,
+                record.action,
+                record.user_id,
+                record.organization_id,
+                vec![
+                    plant_history::id::set(record.id),
+                    plant_history::stage::set(record.stage),
+                    plant_history::created_at::set(record.created_at),
+                ],
+            )
+            .exec()
+            .timed("plant_history", "create_unchecked")
+            .await?;
+    }
+    Ok(())
+}
+
+#[doc = "Validate a snapshot and merge it into the instance in one transaction, see
+`plan_import` for how records are matched. A dry run only reports what would be written."]
+pub async fn import_snapshot(
+    data: &PrismaClient,
+    snapshot: Snapshot,
+    dry_run: bool,
+) -> Result<ImportReport, ErrorCode> {
+    if dry_run {
+        let existing = existing_records(data).await?;
+        let mut report = plan_import(snapshot, &existing)?.report;
+        report.dry_run = true;
+        return Ok(report);
+    }
+
+    data._transaction()
+        .with_timeout(IMPORT_TIMEOUT_MS)
+        .run(|tx| async move {
+            let existing = existing_records(&tx).await?;
+            let plan = plan_import(snapshot, &existing)?;
+            let report = plan.report.clone();
+            apply_plan(&tx, plan).await?;
+            Ok::<ImportReport, ErrorCode>(report)
+        })
+        .await
+}
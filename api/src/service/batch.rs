@@ -0,0 +1,157 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+
+use crate::{
+    model::{
+        dto::Batch,
+        error::ErrorCode,
+        validation::{Validate, Validator},
+    },
+    prisma::{batch, plant, PrismaClient},
+    service::{feeding::check_schedules, metrics::TimedQuery},
+};
+
+#[doc = "Reject batches of other organizations as not found"]
+pub async fn check_batches(
+    data: &PrismaClient,
+    organization_id: &str,
+    batch_ids: &[String],
+) -> Result<(), ErrorCode> {
+    let found = data
+        .batch()
+        .count(vec![
+            batch::id::in_vec(batch_ids.to_vec()),
+            batch::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("batch", "count")
+        .await?;
+    match found as usize == batch_ids.len() {
+        true => Ok(()),
+        false => Err(ErrorCode::DATABASE002),
+    }
+}
+
+pub async fn get_batches(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<batch::Data>, ErrorCode> {
+    match data
+        .batch()
+        .find_many(vec![batch::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .exec()
+        .timed("batch", "find_many")
+        .await
+    {
+        Ok(batches) => Ok(batches),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Batch of the organization with its plants"]
+pub async fn get_batch_by_id(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<batch::Data, ErrorCode> {
+    match data
+        .batch()
+        .find_first(vec![
+            batch::id::equals(id),
+            batch::organization_id::equals(organization_id.to_string()),
+        ])
+        .with(
+            batch::plants::fetch(vec![plant::organization_id::equals(
+                organization_id.to_string(),
+            )])
+            .with(plant::genetic::fetch()),
+        )
+        .exec()
+        .timed("batch", "find_first")
+        .await
+    {
+        Ok(Some(batch)) => Ok(batch),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_batch(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    batch: Batch,
+) -> Result<batch::Data, ErrorCode> {
+    let mut validator = Validator::default();
+    validator.required("name", &batch.name, "Name is required");
+    batch.check(&mut validator);
+    validator.finish()?;
+    let feed_schedule_id = batch.feed_schedule_id.flatten();
+    if let Some(feed_schedule_id) = &feed_schedule_id {
+        check_schedules(data, organization_id, &[feed_schedule_id.clone()]).await?;
+    }
+
+    match data
+        .batch()
+        .create_unchecked(
+            batch.name.unwrap(),
+            organization_id.to_string(),
+            vec![batch::feed_schedule_id::set(feed_schedule_id)],
+        )
+        .exec()
+        .timed("batch", "create_unchecked")
+        .await
+    {
+        Ok(batch) => Ok(batch),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn edit_batch(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    batch: Batch,
+) -> Result<batch::Data, ErrorCode> {
+    batch.validate()?;
+    check_batches(data, organization_id, &[id.clone()]).await?;
+    if let Some(Some(feed_schedule_id)) = &batch.feed_schedule_id {
+        check_schedules(data, organization_id, &[feed_schedule_id.clone()]).await?;
+    }
+
+    match data
+        .batch()
+        .update_unchecked(batch::id::equals(id), batch.to_params())
+        .exec()
+        .timed("batch", "update_unchecked")
+        .await
+    {
+        Ok(batch) => Ok(batch),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_batch(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .batch()
+        .delete_many(vec![
+            batch::id::equals(id),
+            batch::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("batch", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
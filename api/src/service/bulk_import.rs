@@ -0,0 +1,719 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use actix_web::web;
+use csv::{ReaderBuilder, StringRecord, Trim};
+
+use crate::{
+    model::{
+        dto::{
+            bulk_import::{
+                GeneticImportPreview, GeneticImportRow, PlantImportPreview, PlantImportRow,
+            },
+            Genetic,
+        },
+        error::ErrorCode,
+        validation::Validator,
+    },
+    prisma::{genetic, location, plant, plant_history, GeneticType, PlantStage, PrismaClient},
+    service::{
+        analytics::STAGES,
+        backup::IMPORT_TIMEOUT_MS,
+        compliance::{check_plant_limits, Placement},
+        genetic::{creates_cycle, normalize_list, validate_range, Lineage},
+        metrics::TimedQuery,
+        plant::stage_name,
+    },
+};
+
+#[doc = "Largest accepted CSV file in bytes"]
+pub const MAX_CSV_SIZE: usize = 5 * 1024 * 1024;
+
+pub const GENETIC_COLUMNS: [&str; 18] = [
+    "name",
+    "flowerDays",
+    "code",
+    "generation",
+    "breeder",
+    "source",
+    "geneticType",
+    "thcMin",
+    "thcMax",
+    "cbdMin",
+    "cbdMax",
+    "terpenes",
+    "expectedYield",
+    "stretchFactor",
+    "autoflower",
+    "tags",
+    "femaleParent",
+    "maleParent",
+];
+
+pub const PLANT_COLUMNS: [&str; 5] = ["name", "genetic", "stage", "mother", "location"];
+
+#[doc = "Names in use in the instance, rows are checked against them"]
+#[derive(Debug, Default)]
+pub struct KnownNames {
+    #[doc = "Genetic ids by name"]
+    pub genetics: HashMap<String, String>,
+    pub genetic_codes: HashSet<String>,
+    #[doc = "Plant ids by name"]
+    pub plants: HashMap<String, String>,
+    #[doc = "Location ids by name"]
+    pub locations: HashMap<String, String>,
+}
+
+struct Sheet {
+    columns: HashMap<&'static str, usize>,
+    rows: Vec<(usize, StringRecord)>,
+}
+
+impl Sheet {
+    #[doc = "Trimmed value of the column, empty cells count as missing"]
+    fn cell<'a>(&self, record: &'a StringRecord, column: &str) -> Option<&'a str> {
+        self.columns
+            .get(column)
+            .and_then(|index| record.get(*index))
+            .filter(|value| !value.is_empty())
+    }
+}
+
+fn column_key(header: &str) -> String {
+    header
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+#[doc = "Read a CSV file with a header row. Headers are matched ignoring case, spaces and
+underscores. Files separated by `;`, as spreadsheets write them in many locales, are
+accepted as well."]
+fn read_sheet(
+    csv: &str,
+    known: &[&'static str],
+    required: &[&'static str],
+) -> Result<Sheet, ErrorCode> {
+    let header = csv.lines().next().unwrap_or_default();
+    let delimiter = match header.contains(';') && !header.contains(',') {
+        true => b';',
+        false => b',',
+    };
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(csv.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| ErrorCode::BADREQUEST(format!("The header row can not be read: {}", e)))?
+        .clone();
+    let mut columns = HashMap::new();
+    for (index, header) in headers.iter().enumerate() {
+        if column_key(header).is_empty() {
+            continue;
+        }
+        let column = known
+            .iter()
+            .find(|column| column_key(column) == column_key(header))
+            .ok_or_else(|| {
+                ErrorCode::BADREQUEST(format!(
+                    "Unknown column {}, expected {}",
+                    header,
+                    known.join(", ")
+                ))
+            })?;
+        if columns.insert(*column, index).is_some() {
+            return Err(ErrorCode::BADREQUEST(format!(
+                "Column {} appears more than once",
+                column
+            )));
+        }
+    }
+    if let Some(column) = required.iter().find(|c| !columns.contains_key(*c)) {
+        return Err(ErrorCode::BADREQUEST(format!(
+            "Column {} is missing",
+            column
+        )));
+    }
+
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record
+            .map_err(|e| ErrorCode::BADREQUEST(format!("The file can not be read: {}", e)))?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let line = record
+            .position()
+            .map_or(0, |position| line_at(csv, position.byte() as usize));
+        rows.push((line, record));
+    }
+    if rows.is_empty() {
+        return Err(ErrorCode::BADREQUEST("The file has no rows".to_string()));
+    }
+    Ok(Sheet { columns, rows })
+}
+
+#[doc = "Line of the record starting at the offset. The reader does not count the blank
+lines it skips and reports their offset as the start of the following record."]
+fn line_at(csv: &str, offset: usize) -> usize {
+    let skipped = csv[offset..].len() - csv[offset..].trim_start_matches(['\r', '\n']).len();
+    csv[..offset + skipped].matches('\n').count() + 1
+}
+
+fn parse_cell<T: FromStr>(
+    validator: &mut Validator,
+    field: &str,
+    value: Option<&str>,
+    message: &str,
+) -> Option<T> {
+    let parsed = value.map(str::parse::<T>);
+    validator.check(field, !matches!(parsed, Some(Err(_))), message);
+    parsed.and_then(Result::ok)
+}
+
+#[doc = "Decimals may use a comma, e.g. `22,5`"]
+fn parse_decimal(validator: &mut Validator, field: &str, value: Option<&str>) -> Option<f64> {
+    let value = value.map(|value| value.replace(',', "."));
+    parse_cell(validator, field, value.as_deref(), "Must be a number")
+}
+
+fn parse_flag(validator: &mut Validator, field: &str, value: Option<&str>) -> bool {
+    match value.map(str::to_lowercase).as_deref() {
+        None | Some("false" | "no" | "0") => false,
+        Some("true" | "yes" | "1" | "x") => true,
+        Some(_) => {
+            validator.check(field, false, "Must be true or false");
+            false
+        }
+    }
+}
+
+fn parse_genetic_type(
+    validator: &mut Validator,
+    field: &str,
+    value: Option<&str>,
+) -> Option<GeneticType> {
+    let genetic_type = match value?.to_uppercase().as_str() {
+        "INDICA" => Some(GeneticType::Indica),
+        "SATIVA" => Some(GeneticType::Sativa),
+        "HYBRID" => Some(GeneticType::Hybrid),
+        _ => None,
+    };
+    validator.required(
+        field,
+        &genetic_type,
+        "Genetic type must be INDICA, SATIVA or HYBRID",
+    );
+    genetic_type
+}
+
+#[doc = "Stages plants can be imported in, destroyed plants need a destruction record"]
+fn importable_stages() -> impl Iterator<Item = PlantStage> {
+    STAGES
+        .into_iter()
+        .filter(|stage| *stage != PlantStage::Destroyed)
+}
+
+fn parse_stage(validator: &mut Validator, field: &str, value: Option<&str>) -> PlantStage {
+    let Some(value) = value else {
+        return PlantStage::Seedling;
+    };
+    let stage = importable_stages().find(|stage| stage_name(*stage) == value.to_uppercase());
+    validator.required(
+        field,
+        &stage,
+        &format!(
+            "Stage must be one of {}",
+            importable_stages()
+                .map(stage_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    );
+    stage.unwrap_or(PlantStage::Seedling)
+}
+
+#[doc = "Terpenes and tags may be separated by commas or semicolons"]
+fn parse_list(value: Option<&str>) -> Vec<String> {
+    value
+        .map(|value| normalize_list(&value.replace(';', ",")))
+        .unwrap_or_default()
+}
+
+#[doc = "The genetic a row creates, checked with the rules of the genetic endpoints"]
+fn genetic_of(row: &GeneticImportRow) -> Genetic {
+    Genetic {
+        name: Some(row.name.clone()),
+        flower_days: Some(row.flower_days),
+        code: Some(row.code.clone()),
+        naming_template: None,
+        female_parent_id: None,
+        male_parent_id: None,
+        generation: Some(row.generation.clone()),
+        breeder: Some(row.breeder.clone()),
+        source: Some(row.source.clone()),
+        genetic_type: Some(row.genetic_type),
+        thc_min: Some(row.thc_min),
+        thc_max: Some(row.thc_max),
+        cbd_min: Some(row.cbd_min),
+        cbd_max: Some(row.cbd_max),
+        terpenes: Some(row.terpenes.clone()),
+        expected_yield: Some(row.expected_yield),
+        stretch_factor: Some(row.stretch_factor),
+        autoflower: Some(row.autoflower),
+        tags: Some(row.tags.clone()),
+    }
+}
+
+fn row_field(line: usize, column: &str) -> String {
+    format!("rows.{}.{}", line, column)
+}
+
+#[doc = "Parse and check a genetics CSV against the instance without writing anything.
+Parents are referenced by name and may be genetics of the instance or of the file."]
+pub fn check_genetic_rows(
+    csv: &str,
+    known: &KnownNames,
+) -> Result<GeneticImportPreview, ErrorCode> {
+    let sheet = read_sheet(csv, &GENETIC_COLUMNS, &["name", "flowerDays"])?;
+    let mut validator = Validator::default();
+    let mut rows = vec![];
+
+    for (line, record) in &sheet.rows {
+        let line = *line;
+        let cell = |column: &str| sheet.cell(record, column);
+        let text = |column: &str| cell(column).map(str::to_string);
+        let mut row = GeneticImportRow {
+            line,
+            name: cell("name").unwrap_or_default().to_string(),
+            flower_days: parse_cell(
+                &mut validator,
+                &row_field(line, "flowerDays"),
+                cell("flowerDays"),
+                "Flower days must be a whole number",
+            )
+            .unwrap_or_default(),
+            code: text("code"),
+            generation: text("generation"),
+            breeder: text("breeder"),
+            source: text("source"),
+            genetic_type: parse_genetic_type(
+                &mut validator,
+                &row_field(line, "geneticType"),
+                cell("geneticType"),
+            ),
+            terpenes: parse_list(cell("terpenes")),
+            autoflower: parse_flag(
+                &mut validator,
+                &row_field(line, "autoflower"),
+                cell("autoflower"),
+            ),
+            tags: parse_list(cell("tags")),
+            female_parent: text("femaleParent"),
+            male_parent: text("maleParent"),
+            ..Default::default()
+        };
+        for (column, value) in [
+            ("thcMin", &mut row.thc_min),
+            ("thcMax", &mut row.thc_max),
+            ("cbdMin", &mut row.cbd_min),
+            ("cbdMax", &mut row.cbd_max),
+            ("expectedYield", &mut row.expected_yield),
+            ("stretchFactor", &mut row.stretch_factor),
+        ] {
+            *value = parse_decimal(&mut validator, &row_field(line, column), cell(column));
+        }
+        validator.nested(&format!("rows.{}", line), &genetic_of(&row));
+        for (column, name, min, max) in [
+            ("thcMin", "THC", row.thc_min, row.thc_max),
+            ("cbdMin", "CBD", row.cbd_min, row.cbd_max),
+        ] {
+            if let Err(message) = validate_range(name, min, max) {
+                validator.check(&row_field(line, column), false, &message);
+            }
+        }
+        rows.push(row);
+    }
+
+    let mut names = HashSet::new();
+    let mut codes = HashSet::new();
+    for row in &rows {
+        let field = row_field(row.line, "name");
+        validator.check(
+            &field,
+            names.insert(row.name.as_str()),
+            &format!("Genetic {} appears more than once", row.name),
+        );
+        validator.check(
+            &field,
+            !known.genetics.contains_key(&row.name),
+            &format!("Genetic {} already exists", row.name),
+        );
+        if let Some(code) = &row.code {
+            let field = row_field(row.line, "code");
+            validator.check(
+                &field,
+                codes.insert(code.as_str()),
+                &format!("Genetic code {} appears more than once", code),
+            );
+            validator.check(
+                &field,
+                !known.genetic_codes.contains(code),
+                &format!("Genetic code {} is already used", code),
+            );
+        }
+    }
+
+    // Lineage of the file by name, genetics of the instance can not descend from it
+    let lineages: HashMap<String, Lineage> = rows
+        .iter()
+        .map(|row| {
+            let lineage = Lineage {
+                id: row.name.clone(),
+                name: row.name.clone(),
+                generation: row.generation.clone(),
+                breeder: row.breeder.clone(),
+                female_parent_id: row.female_parent.clone(),
+                male_parent_id: row.male_parent.clone(),
+            };
+            (row.name.clone(), lineage)
+        })
+        .collect();
+    for row in &rows {
+        for (column, parent) in [
+            ("femaleParent", &row.female_parent),
+            ("maleParent", &row.male_parent),
+        ] {
+            if let Some(parent) = parent {
+                validator.check(
+                    &row_field(row.line, column),
+                    names.contains(parent.as_str()) || known.genetics.contains_key(parent),
+                    &format!("Genetic {} does not exist", parent),
+                );
+            }
+        }
+        let parents: Vec<&str> = [&row.female_parent, &row.male_parent]
+            .into_iter()
+            .filter_map(Option::as_deref)
+            .collect();
+        validator.check(
+            &row_field(row.line, "femaleParent"),
+            !creates_cycle(&lineages, &row.name, &parents),
+            "A genetic can not be its own ancestor",
+        );
+    }
+
+    Ok(GeneticImportPreview {
+        rows,
+        errors: validator.into_errors(),
+    })
+}
+
+#[doc = "Parse and check a plants CSV against the instance without writing anything.
+Mothers are referenced by name and may be plants of the instance or of the file."]
+pub fn check_plant_rows(csv: &str, known: &KnownNames) -> Result<PlantImportPreview, ErrorCode> {
+    let sheet = read_sheet(csv, &PLANT_COLUMNS, &["name", "genetic"])?;
+    let mut validator = Validator::default();
+    let mut rows = vec![];
+
+    for (line, record) in &sheet.rows {
+        let line = *line;
+        let cell = |column: &str| sheet.cell(record, column);
+        let row = PlantImportRow {
+            line,
+            name: cell("name").unwrap_or_default().to_string(),
+            genetic: cell("genetic").unwrap_or_default().to_string(),
+            stage: parse_stage(&mut validator, &row_field(line, "stage"), cell("stage")),
+            mother: cell("mother").map(str::to_string),
+            location: cell("location").map(str::to_string),
+        };
+        validator.min_length(
+            &row_field(line, "name"),
+            &row.name,
+            1,
+            "Plant name must be at least 1 character long",
+        );
+        validator.check(
+            &row_field(line, "genetic"),
+            known.genetics.contains_key(&row.genetic),
+            &format!("Genetic {} does not exist", row.genetic),
+        );
+        if let Some(location) = &row.location {
+            validator.check(
+                &row_field(line, "location"),
+                known.locations.contains_key(location),
+                &format!("Location {} does not exist", location),
+            );
+        }
+        rows.push(row);
+    }
+
+    let mut names = HashSet::new();
+    for row in &rows {
+        let field = row_field(row.line, "name");
+        validator.check(
+            &field,
+            names.insert(row.name.as_str()),
+            &format!("Plant {} appears more than once", row.name),
+        );
+        validator.check(
+            &field,
+            !known.plants.contains_key(&row.name),
+            &format!("Plant {} already exists", row.name),
+        );
+    }
+
+    let mothers: HashMap<&str, &str> = rows
+        .iter()
+        .filter_map(|row| Some((row.name.as_str(), row.mother.as_deref()?)))
+        .collect();
+    for row in &rows {
+        let Some(mother) = row.mother.as_deref() else {
+            continue;
+        };
+        let field = row_field(row.line, "mother");
+        validator.check(
+            &field,
+            names.contains(mother) || known.plants.contains_key(mother),
+            &format!("Plant {} does not exist", mother),
+        );
+        // Follow the mothers of the file, plants of the instance end the chain
+        let mut visited = HashSet::from([row.name.as_str()]);
+        let mut current = Some(mother);
+        while let Some(name) = current {
+            if !visited.insert(name) {
+                validator.check(&field, false, "A plant can not be its own ancestor");
+                break;
+            }
+            current = mothers.get(name).copied();
+        }
+    }
+
+    Ok(PlantImportPreview {
+        rows,
+        errors: validator.into_errors(),
+    })
+}
+
+#[doc = "Names of the genetics, plants and locations of the organization"]
+async fn known_names(
+    client: &PrismaClient,
+    organization_id: &str,
+) -> Result<KnownNames, ErrorCode> {
+    let mut known = KnownNames::default();
+    for genetic in client
+        .genetic()
+        .find_many(vec![genetic::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .exec()
+        .timed("genetic", "find_many")
+        .await?
+    {
+        known.genetic_codes.extend(genetic.code);
+        known.genetics.insert(genetic.name, genetic.id);
+    }
+    for plant in client
+        .plant()
+        .find_many(vec![plant::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .exec()
+        .timed("plant", "find_many")
+        .await?
+    {
+        known.plants.insert(plant.name, plant.id);
+    }
+    for location in client
+        .location()
+        .find_many(vec![location::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .exec()
+        .timed("location", "find_many")
+        .await?
+    {
+        known.locations.insert(location.name, location.id);
+    }
+    Ok(known)
+}
+
+pub async fn preview_genetics(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    csv: String,
+) -> Result<GeneticImportPreview, ErrorCode> {
+    check_genetic_rows(&csv, &known_names(data, organization_id).await?)
+}
+
+pub async fn preview_plants(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    csv: String,
+) -> Result<PlantImportPreview, ErrorCode> {
+    check_plant_rows(&csv, &known_names(data, organization_id).await?)
+}
+
+#[doc = "Create all genetics of a CSV file or none of them when a row is invalid"]
+pub async fn import_genetics(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    csv: String,
+) -> Result<Vec<genetic::Data>, ErrorCode> {
+    let organization_id = organization_id.to_string();
+    data._transaction()
+        .with_timeout(IMPORT_TIMEOUT_MS)
+        .run(|tx| async move {
+            let known = known_names(&tx, &organization_id).await?;
+            let preview = check_genetic_rows(&csv, &known)?;
+            if !preview.errors.is_empty() {
+                return Err(ErrorCode::VALIDATION(preview.errors));
+            }
+
+            let mut ids = known.genetics;
+            let mut created = vec![];
+            for row in &preview.rows {
+                let row = row.clone();
+                let genetic = tx
+                    .genetic()
+                    .create_unchecked(
+                        row.name,
+                        row.flower_days,
+                        organization_id.clone(),
+                        vec![
+                            genetic::code::set(row.code),
+                            genetic::generation::set(row.generation),
+                            genetic::breeder::set(row.breeder),
+                            genetic::source::set(row.source),
+                            genetic::genetic_type::set(row.genetic_type),
+                            genetic::thc_min::set(row.thc_min),
+                            genetic::thc_max::set(row.thc_max),
+                            genetic::cbd_min::set(row.cbd_min),
+                            genetic::cbd_max::set(row.cbd_max),
+                            genetic::terpenes::set(row.terpenes),
+                            genetic::expected_yield::set(row.expected_yield),
+                            genetic::stretch_factor::set(row.stretch_factor),
+                            genetic::autoflower::set(row.autoflower),
+                            genetic::tags::set(row.tags),
+                        ],
+                    )
+                    .exec()
+                    .timed("genetic", "create_unchecked")
+                    .await?;
+                ids.insert(genetic.name.clone(), genetic.id.clone());
+                created.push(genetic);
+            }
+
+            // Parents may be rows further down, so lineage is linked after all exist
+            for (genetic, row) in created.iter_mut().zip(preview.rows) {
+                if row.female_parent.is_none() && row.male_parent.is_none() {
+                    continue;
+                }
+                let parent = |name: Option<String>| name.map(|name| ids[&name].clone());
+                *genetic = tx
+                    .genetic()
+                    .update_unchecked(
+                        genetic::id::equals(genetic.id.clone()),
+                        vec![
+                            genetic::female_parent_id::set(parent(row.female_parent)),
+                            genetic::male_parent_id::set(parent(row.male_parent)),
+                        ],
+                    )
+                    .exec()
+                    .timed("genetic", "update_unchecked")
+                    .await?;
+            }
+            Ok::<Vec<genetic::Data>, ErrorCode>(created)
+        })
+        .await
+}
+
+#[doc = "Create all plants of a CSV file or none of them when a row is invalid or the
+plants would exceed a plant limit. Each plant gets a history entry for the importing user."]
+pub async fn import_plants(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    csv: String,
+    user_id: String,
+) -> Result<Vec<plant::Data>, ErrorCode> {
+    let organization_id = organization_id.to_string();
+    data._transaction()
+        .with_timeout(IMPORT_TIMEOUT_MS)
+        .run(|tx| async move {
+            let known = known_names(&tx, &organization_id).await?;
+            let preview = check_plant_rows(&csv, &known)?;
+            if !preview.errors.is_empty() {
+                return Err(ErrorCode::VALIDATION(preview.errors));
+            }
+
+            let mut ids = known.plants;
+            let mut created = vec![];
+            for row in &preview.rows {
+                let plant = tx
+                    .plant()
+                    .create_unchecked(
+                        row.name.clone(),
+                        known.genetics[&row.genetic].clone(),
+                        organization_id.clone(),
+                        vec![
+                            plant::stage::set(row.stage),
+                            plant::location_id::set(
+                                row.location
+                                    .as_ref()
+                                    .map(|location| known.locations[location].clone()),
+                            ),
+                        ],
+                    )
+                    .exec()
+                    .timed("plant", "create_unchecked")
+                    .await?;
+                tx.plant_history()
+                    .create_unchecked(
+                        plant.id.clone(),
+                        format!("Imported in stage {}", stage_name(row.stage)),
+                        user_id.clone(),
+                        organization_id.clone(),
+                        vec![plant_history::stage::set(Some(row.stage))],
+                    )
+                    .exec()
+                    .timed("plant_history", "create_unchecked")
+                    .await?;
+                ids.insert(plant.name.clone(), plant.id.clone());
+                created.push(plant);
+            }
+
+            // Mothers may be rows further down, so they are linked after all exist
+            for (plant, row) in created.iter_mut().zip(&preview.rows) {
+                if let Some(mother) = &row.mother {
+                    *plant = tx
+                        .plant()
+                        .update_unchecked(
+                            plant::id::equals(plant.id.clone()),
+                            vec![plant::mother_id::set(Some(ids[mother].clone()))],
+                        )
+                        .exec()
+                        .timed("plant", "update_unchecked")
+                        .await?;
+                }
+            }
+
+            // Counting once per placement covers all plants created in it
+            let mut checked = HashSet::new();
+            for plant in &created {
+                let placement = Placement::of(plant);
+                if checked.insert((placement.location_id, stage_name(placement.stage))) {
+                    check_plant_limits(&tx, &organization_id, None, placement).await?;
+                }
+            }
+            Ok::<Vec<plant::Data>, ErrorCode>(created)
+        })
+        .await
+}
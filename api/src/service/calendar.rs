@@ -0,0 +1,262 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{Duration, NaiveDate, Utc};
+use prisma_client_rust::Direction;
+
+use crate::{
+    model::error::ErrorCode,
+    prisma::{
+        organization, plant, plant_history, task, user, users_in_roles, PlantStage, PrismaClient,
+        TaskStatus,
+    },
+    service::{
+        metrics::TimedQuery,
+        plant::{stage_entered_at, stage_name},
+    },
+};
+
+#[doc = "How far back stage milestones are included in the feed"]
+const MILESTONE_DAYS: i64 = 180;
+
+#[doc = "A single all-day event of a calendar feed"]
+#[derive(Debug, Clone)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub date: NaiveDate,
+    pub summary: String,
+    pub description: Option<String>,
+    pub cancelled: bool,
+}
+
+#[doc = "Escape a TEXT value as described in RFC 5545 section 3.3.11"]
+pub fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[doc = "Fold a content line after 75 octets as described in RFC 5545 section 3.1,
+without splitting multi-byte characters"]
+pub fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut octets = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if octets + len > 75 {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += len;
+    }
+    folded
+}
+
+#[doc = "Render the events as an RFC 5545 VCALENDAR"]
+pub fn render_calendar(name: &str, events: &[CalendarEvent]) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Grimm Integrations//Planters Cycle//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!(
+            "DTSTART;VALUE=DATE:{}",
+            event.date.format("%Y%m%d")
+        ));
+        lines.push(format!(
+            "DTEND;VALUE=DATE:{}",
+            (event.date + Duration::days(1)).format("%Y%m%d")
+        ));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        if let Some(description) = &event.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        if event.cancelled {
+            lines.push("STATUS:CANCELLED".to_string());
+        }
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold_line(line) + "\r\n")
+        .collect::<String>()
+}
+
+#[doc = "Create a new feed token for the user, invalidating the previous feed url"]
+pub async fn regenerate_calendar_token(
+    data: &web::Data<PrismaClient>,
+    user_id: String,
+) -> Result<String, ErrorCode> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    match data
+        .user()
+        .update(
+            user::id::equals(user_id),
+            vec![user::calendar_token::set(Some(token.clone()))],
+        )
+        .exec()
+        .timed("user", "update")
+        .await
+    {
+        Ok(_) => Ok(token),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Build the calendar feed of the user owning the token.
+Contains the tasks assigned to the user, the expected end of flowering of all
+flowering plants and the stage transitions of the last months. Tasks and plants are
+taken from all organizations the user belongs to, the feed has no session to select one."]
+pub async fn get_calendar_feed(
+    data: &web::Data<PrismaClient>,
+    token: String,
+) -> Result<String, ErrorCode> {
+    let user = match data
+        .user()
+        .find_unique(user::calendar_token::equals(token))
+        .exec()
+        .timed("user", "find_unique")
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(ErrorCode::DATABASE002),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut events = vec![];
+    let memberships = || {
+        vec![organization::members::some(vec![
+            users_in_roles::user_id::equals(user.id.clone()),
+        ])]
+    };
+
+    let tasks = data
+        .task()
+        .find_many(vec![
+            task::assignee_id::equals(Some(user.id.clone())),
+            task::organization::is(memberships()),
+        ])
+        .with(task::plants::fetch(vec![]))
+        .exec()
+        .timed("task", "find_many")
+        .await?;
+    for task in tasks {
+        let plants: Vec<String> = task
+            .plants
+            .iter()
+            .flatten()
+            .map(|plant| plant.name.clone())
+            .collect();
+        let mut description = task.description.clone().unwrap_or_default();
+        if !plants.is_empty() {
+            if !description.is_empty() {
+                description.push('\n');
+            }
+            description.push_str(&format!("Plants: {}", plants.join(", ")));
+        }
+        events.push(CalendarEvent {
+            uid: format!("task-{}@planters-cycle", task.id),
+            date: task.due_date.date_naive(),
+            summary: match task.status {
+                TaskStatus::Done => format!("✓ {}", task.title),
+                _ => task.title.clone(),
+            },
+            description: (!description.is_empty()).then_some(description),
+            cancelled: matches!(task.status, TaskStatus::Cancelled),
+        });
+    }
+
+    let flowering = data
+        .plant()
+        .find_many(vec![
+            plant::stage::equals(PlantStage::Flowering),
+            plant::organization::is(memberships()),
+        ])
+        .with(plant::genetic::fetch())
+        .exec()
+        .timed("plant", "find_many")
+        .await?;
+    for plant in flowering {
+        let Ok(genetic) = plant.genetic() else {
+            continue;
+        };
+        // The flower days of autoflowers count from seed
+        let (since, description) = if genetic.autoflower {
+            (plant.created_at, "Autoflower planted")
+        } else {
+            (stage_entered_at(data, &plant).await?, "Flowering since")
+        };
+        events.push(CalendarEvent {
+            uid: format!("harvest-{}@planters-cycle", plant.id),
+            date: (since + Duration::days(genetic.flower_days as i64)).date_naive(),
+            summary: format!("Expected harvest: {} ({})", plant.name, genetic.name),
+            description: Some(format!(
+                "{} {}, {} flower days",
+                description,
+                since.date_naive(),
+                genetic.flower_days
+            )),
+            cancelled: false,
+        });
+    }
+
+    let milestones = data
+        .plant_history()
+        .find_many(vec![
+            plant_history::stage::not(None),
+            plant_history::organization::is(memberships()),
+            plant_history::created_at::gte(
+                (Utc::now() - Duration::days(MILESTONE_DAYS)).fixed_offset(),
+            ),
+        ])
+        .order_by(plant_history::created_at::order(Direction::Asc))
+        .with(plant_history::plant::fetch())
+        .exec()
+        .timed("plant_history", "find_many")
+        .await?;
+    for milestone in milestones {
+        let (Some(stage), Ok(plant)) = (milestone.stage, milestone.plant()) else {
+            continue;
+        };
+        events.push(CalendarEvent {
+            uid: format!("stage-{}@planters-cycle", milestone.id),
+            date: milestone.created_at.date_naive(),
+            summary: format!("{} entered {}", plant.name, stage_name(stage)),
+            description: Some(milestone.action.clone()),
+            cancelled: false,
+        });
+    }
+
+    Ok(render_calendar(
+        &format!("Planters Cycle - {}", user.display_name),
+        &events,
+    ))
+}
@@ -0,0 +1,312 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use prisma_client_rust::{raw, Direction, PrismaValue};
+use serde::Deserialize;
+
+use crate::{
+    model::{
+        dto::{compliance::LimitUsage, PlantLimit},
+        error::ErrorCode,
+        validation::{Validate, Validator},
+    },
+    prisma::{plant, plant_limit, PlantStage, PrismaClient},
+    service::{location::check_locations, metrics::TimedQuery, plant::stage_name},
+};
+
+#[doc = "Stages of living plants, counted by limits without a stage"]
+pub const ACTIVE_STAGES: [PlantStage; 3] = [
+    PlantStage::Seedling,
+    PlantStage::Vegetative,
+    PlantStage::Flowering,
+];
+
+#[doc = "Location and stage of a plant, the scope plant limits are checked against"]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement<'a> {
+    pub location_id: Option<&'a str>,
+    pub stage: PlantStage,
+}
+
+impl<'a> Placement<'a> {
+    pub fn of(plant: &'a plant::Data) -> Self {
+        Self {
+            location_id: plant.location_id.as_deref(),
+            stage: plant.stage,
+        }
+    }
+}
+
+#[doc = "Stages counted by a limit, a limit without a stage caps all living plants"]
+pub fn counted_stages(stage: Option<PlantStage>) -> Vec<PlantStage> {
+    match stage {
+        Some(stage) => vec![stage],
+        None => ACTIVE_STAGES.to_vec(),
+    }
+}
+
+#[doc = "Whether a plant at the placement counts towards the limit.
+Limits without a location apply to the whole facility."]
+pub fn limit_applies(limit: &plant_limit::Data, placement: Placement) -> bool {
+    let location = match &limit.location_id {
+        Some(location_id) => placement.location_id == Some(location_id.as_str()),
+        None => true,
+    };
+    location && counted_stages(limit.stage).contains(&placement.stage)
+}
+
+#[doc = "Whether moving a plant from `before` to `after` adds to the count of the limit.
+New plants have no previous placement."]
+pub fn limit_grows(limit: &plant_limit::Data, before: Option<Placement>, after: Placement) -> bool {
+    limit_applies(limit, after) && !before.is_some_and(|before| limit_applies(limit, before))
+}
+
+#[doc = "Human readable scope of a limit, e.g. `Flower room allows at most 12 FLOWERING plants`"]
+pub fn limit_description(limit: &plant_limit::Data, location_name: Option<&str>) -> String {
+    let scope = match location_name {
+        Some(name) => name.to_string(),
+        None => "The facility".to_string(),
+    };
+    let stages = counted_stages(limit.stage)
+        .into_iter()
+        .map(stage_name)
+        .collect::<Vec<String>>()
+        .join("/");
+    format!(
+        "{} allows at most {} {} plants",
+        scope, limit.max_plants, stages
+    )
+}
+
+#[doc = "Usage of the limit with `current` counted plants"]
+pub fn limit_usage(limit: &plant_limit::Data, current: i64) -> LimitUsage {
+    let max_plants = limit.max_plants as i64;
+    LimitUsage {
+        limit_id: limit.id.clone(),
+        location_id: limit.location_id.clone(),
+        location_name: limit
+            .location
+            .as_ref()
+            .and_then(|location| location.as_deref())
+            .map(|location| location.name.clone()),
+        stage: limit.stage,
+        max_plants: limit.max_plants,
+        current,
+        remaining: (max_plants - current).max(0),
+        utilization: (max_plants > 0).then(|| current as f64 / max_plants as f64),
+        exceeded: current > max_plants,
+    }
+}
+
+#[doc = "Number of plants of the organization of the limit counted by it"]
+async fn count_plants(data: &PrismaClient, limit: &plant_limit::Data) -> Result<i64, ErrorCode> {
+    let mut filter = vec![
+        plant::organization_id::equals(limit.organization_id.clone()),
+        plant::stage::in_vec(counted_stages(limit.stage)),
+    ];
+    if let Some(location_id) = &limit.location_id {
+        filter.push(plant::location_id::equals(Some(location_id.clone())));
+    }
+
+    match data
+        .plant()
+        .count(filter)
+        .exec()
+        .timed("plant", "count")
+        .await
+    {
+        Ok(count) => Ok(count),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Deserialize)]
+struct LockedLimit {
+    #[allow(dead_code)]
+    id: String,
+}
+
+#[doc = "Check the limits of the organization after its plants were created or moved inside
+the transaction. The limits are locked before counting, so concurrent transactions wait for
+each other and can not both take the last free slot. Only limits the change adds to are
+checked, plants already over a lowered limit can still move out of it."]
+pub async fn check_plant_limits(
+    tx: &PrismaClient,
+    organization_id: &str,
+    before: Option<Placement<'_>>,
+    after: Placement<'_>,
+) -> Result<(), ErrorCode> {
+    let limits = tx
+        .plant_limit()
+        .find_many(vec![plant_limit::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .with(plant_limit::location::fetch())
+        .exec()
+        .timed("plant_limit", "find_many")
+        .await?;
+    let limits: Vec<plant_limit::Data> = limits
+        .into_iter()
+        .filter(|limit| limit_grows(limit, before, after))
+        .collect();
+    if limits.is_empty() {
+        return Ok(());
+    }
+
+    let _: Vec<LockedLimit> = tx
+        ._query_raw(raw!(
+            r#"SELECT "id" FROM "PlantLimit" WHERE "organizationId" = {}
+            ORDER BY "id" FOR UPDATE"#,
+            PrismaValue::String(organization_id.to_string())
+        ))
+        .exec()
+        .timed("raw", "query_raw")
+        .await?;
+
+    for limit in &limits {
+        let usage = limit_usage(limit, count_plants(tx, limit).await?);
+        if usage.exceeded {
+            return Err(ErrorCode::LIMIT001(limit_description(
+                limit,
+                usage.location_name.as_deref(),
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub async fn get_plant_limits(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<plant_limit::Data>, ErrorCode> {
+    match data
+        .plant_limit()
+        .find_many(vec![plant_limit::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .with(plant_limit::location::fetch())
+        .order_by(plant_limit::created_at::order(Direction::Asc))
+        .exec()
+        .timed("plant_limit", "find_many")
+        .await
+    {
+        Ok(limits) => Ok(limits),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_plant_limit(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    limit: PlantLimit,
+) -> Result<plant_limit::Data, ErrorCode> {
+    let mut validator = Validator::default();
+    validator.required(
+        "maxPlants",
+        &limit.max_plants,
+        "Maximum number of plants is required",
+    );
+    limit.check(&mut validator);
+    validator.finish()?;
+    let max_plants = limit.max_plants.unwrap();
+    if let Some(Some(location_id)) = &limit.location_id {
+        check_locations(data, organization_id, &[location_id.clone()]).await?;
+    }
+
+    match data
+        .plant_limit()
+        .create_unchecked(
+            max_plants,
+            organization_id.to_string(),
+            vec![
+                plant_limit::location_id::set(limit.location_id.flatten()),
+                plant_limit::stage::set(limit.stage.flatten()),
+                plant_limit::note::set(limit.note.flatten()),
+            ],
+        )
+        .exec()
+        .timed("plant_limit", "create_unchecked")
+        .await
+    {
+        Ok(limit) => Ok(limit),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn edit_plant_limit(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    limit: PlantLimit,
+) -> Result<plant_limit::Data, ErrorCode> {
+    limit.validate()?;
+    let existing = data
+        .plant_limit()
+        .count(vec![
+            plant_limit::id::equals(id.clone()),
+            plant_limit::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("plant_limit", "count")
+        .await?;
+    if existing == 0 {
+        return Err(ErrorCode::DATABASE002);
+    }
+    if let Some(Some(location_id)) = &limit.location_id {
+        check_locations(data, organization_id, &[location_id.clone()]).await?;
+    }
+
+    match data
+        .plant_limit()
+        .update_unchecked(plant_limit::id::equals(id), limit.to_params())
+        .exec()
+        .timed("plant_limit", "update_unchecked")
+        .await
+    {
+        Ok(limit) => Ok(limit),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_plant_limit(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .plant_limit()
+        .delete_many(vec![
+            plant_limit::id::equals(id),
+            plant_limit::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("plant_limit", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Current usage of every limit configured in the organization"]
+pub async fn get_compliance_dashboard(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<LimitUsage>, ErrorCode> {
+    let limits = get_plant_limits(data, organization_id).await?;
+
+    let mut usage = vec![];
+    for limit in &limits {
+        let current = count_plants(data, limit).await?;
+        usage.push(limit_usage(limit, current));
+    }
+    usage.sort_by(|a, b| {
+        a.location_name
+            .cmp(&b.location_name)
+            .then_with(|| a.stage.map(stage_name).cmp(&b.stage.map(stage_name)))
+    });
+    Ok(usage)
+}
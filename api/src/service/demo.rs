@@ -0,0 +1,286 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::{
+    model::{dto::backup::SnapshotSummary, error::ErrorCode},
+    prisma::{genetic, plant, plant_history, GeneticType, PlantStage, PrismaClient},
+    service::{metrics::TimedQuery, plant::stage_name},
+};
+
+#[doc = "Genetic of the demo grow, parents are listed before their offspring"]
+pub struct DemoGenetic {
+    pub name: &'static str,
+    pub code: &'static str,
+    pub flower_days: i32,
+    pub genetic_type: GeneticType,
+    pub generation: &'static str,
+    pub breeder: &'static str,
+    pub thc_max: f64,
+    pub autoflower: bool,
+    pub female_parent: Option<&'static str>,
+    pub male_parent: Option<&'static str>,
+}
+
+pub const DEMO_GENETICS: [DemoGenetic; 6] = [
+    DemoGenetic {
+        name: "Sunset Sherbet",
+        code: "SSH",
+        flower_days: 63,
+        genetic_type: GeneticType::Hybrid,
+        generation: "P",
+        breeder: "Cookie Fam",
+        thc_max: 22.0,
+        autoflower: false,
+        female_parent: None,
+        male_parent: None,
+    },
+    DemoGenetic {
+        name: "Thin Mint GSC",
+        code: "TMG",
+        flower_days: 63,
+        genetic_type: GeneticType::Hybrid,
+        generation: "P",
+        breeder: "Cookie Fam",
+        thc_max: 24.0,
+        autoflower: false,
+        female_parent: None,
+        male_parent: None,
+    },
+    DemoGenetic {
+        name: "Gelato",
+        code: "GLT",
+        flower_days: 60,
+        genetic_type: GeneticType::Hybrid,
+        generation: "F1",
+        breeder: "Cookie Fam",
+        thc_max: 25.0,
+        autoflower: false,
+        female_parent: Some("Sunset Sherbet"),
+        male_parent: Some("Thin Mint GSC"),
+    },
+    DemoGenetic {
+        name: "OG Kush",
+        code: "OGK",
+        flower_days: 56,
+        genetic_type: GeneticType::Hybrid,
+        generation: "IBL",
+        breeder: "Imperial Genetics",
+        thc_max: 23.0,
+        autoflower: false,
+        female_parent: None,
+        male_parent: None,
+    },
+    DemoGenetic {
+        name: "Durban Poison",
+        code: "DBP",
+        flower_days: 70,
+        genetic_type: GeneticType::Sativa,
+        generation: "IBL",
+        breeder: "Dutch Passion",
+        thc_max: 20.0,
+        autoflower: false,
+        female_parent: None,
+        male_parent: None,
+    },
+    DemoGenetic {
+        name: "Northern Lights Auto",
+        code: "NLA",
+        flower_days: 49,
+        genetic_type: GeneticType::Indica,
+        generation: "F1",
+        breeder: "Sensi Seeds",
+        thc_max: 18.0,
+        autoflower: true,
+        female_parent: None,
+        male_parent: None,
+    },
+];
+
+#[doc = "Age in days of the demo plants of every genetic"]
+const PLANT_AGES: [i64; 6] = [7, 30, 60, 105, 125, 160];
+
+#[doc = "Plant of the demo grow with the stages it passed"]
+#[derive(Debug, Clone)]
+pub struct DemoPlant {
+    pub name: String,
+    pub genetic: &'static str,
+    #[doc = "Name of the mother plant, clones are taken from it"]
+    pub mother: Option<String>,
+    pub created_at: DateTime<FixedOffset>,
+    #[doc = "Stages with the date they were entered, the first one is the planting"]
+    pub stages: Vec<(DateTime<FixedOffset>, PlantStage)>,
+}
+
+#[doc = "Days after planting each stage is entered"]
+pub fn stage_timeline(flower_days: i32, autoflower: bool) -> Vec<(i64, PlantStage)> {
+    let flowering = if autoflower { 28 } else { 42 };
+    let harvest = flowering + flower_days as i64;
+    vec![
+        (0, PlantStage::Seedling),
+        (if autoflower { 10 } else { 14 }, PlantStage::Vegetative),
+        (flowering, PlantStage::Flowering),
+        (harvest, PlantStage::Harvest),
+        (harvest + 10, PlantStage::Dried),
+        (harvest + 24, PlantStage::Cured),
+    ]
+}
+
+#[doc = "Plants of every demo genetic at different ages, photoperiod genetics are
+cloned from a mother kept in vegetation"]
+pub fn demo_plants(now: DateTime<FixedOffset>) -> Vec<DemoPlant> {
+    let mut plants = vec![];
+    for genetic in &DEMO_GENETICS {
+        let timeline = stage_timeline(genetic.flower_days, genetic.autoflower);
+        let mother = (!genetic.autoflower).then(|| format!("{}-M1", genetic.code));
+        if let Some(mother) = &mother {
+            let planted = now - Duration::days(200);
+            plants.push(DemoPlant {
+                name: mother.clone(),
+                genetic: genetic.name,
+                mother: None,
+                created_at: planted,
+                stages: vec![
+                    (planted, PlantStage::Seedling),
+                    (planted + Duration::days(14), PlantStage::Vegetative),
+                ],
+            });
+        }
+
+        for (index, age) in PLANT_AGES.iter().enumerate() {
+            let planted = now - Duration::days(*age);
+            plants.push(DemoPlant {
+                name: format!("{}-{:03}", genetic.code, index + 1),
+                genetic: genetic.name,
+                mother: mother.clone(),
+                created_at: planted,
+                stages: timeline
+                    .iter()
+                    .filter(|(day, _)| day <= age)
+                    .map(|(day, stage)| (planted + Duration::days(*day), *stage))
+                    .collect(),
+            });
+        }
+    }
+    plants
+}
+
+#[doc = "Fill an organization with genetics and plants across all stages for demos,
+history entries are recorded for the given user"]
+pub async fn seed_demo(
+    data: &PrismaClient,
+    organization_id: &str,
+    user_id: &str,
+    now: DateTime<FixedOffset>,
+) -> Result<SnapshotSummary, ErrorCode> {
+    let names = DEMO_GENETICS
+        .iter()
+        .map(|genetic| genetic.name.to_string())
+        .collect();
+    let present = data
+        .genetic()
+        .count(vec![
+            genetic::name::in_vec(names),
+            genetic::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("genetic", "count")
+        .await?;
+    if present > 0 {
+        return Err(ErrorCode::BADREQUEST(
+            "Demo data is already present".to_string(),
+        ));
+    }
+
+    let organization_id = organization_id.to_string();
+    let user_id = user_id.to_string();
+    data._transaction()
+        .run(|tx| async move {
+            let mut summary = SnapshotSummary::default();
+            let mut genetic_ids: HashMap<&str, String> = HashMap::new();
+            for demo in &DEMO_GENETICS {
+                let parent = |name: Option<&str>| name.and_then(|n| genetic_ids.get(n).cloned());
+                let genetic = tx
+                    .genetic()
+                    .create_unchecked(
+                        demo.name.to_string(),
+                        demo.flower_days,
+                        organization_id.clone(),
+                        vec![
+                            genetic::code::set(Some(demo.code.to_string())),
+                            genetic::genetic_type::set(Some(demo.genetic_type)),
+                            genetic::generation::set(Some(demo.generation.to_string())),
+                            genetic::breeder::set(Some(demo.breeder.to_string())),
+                            genetic::thc_max::set(Some(demo.thc_max)),
+                            genetic::autoflower::set(demo.autoflower),
+                            genetic::female_parent_id::set(parent(demo.female_parent)),
+                            genetic::male_parent_id::set(parent(demo.male_parent)),
+                        ],
+                    )
+                    .exec()
+                    .timed("genetic", "create_unchecked")
+                    .await?;
+                genetic_ids.insert(demo.name, genetic.id);
+                summary.genetics += 1;
+            }
+
+            let mut plant_ids: HashMap<String, String> = HashMap::new();
+            for demo in demo_plants(now) {
+                let (_, stage) = *demo.stages.last().unwrap();
+                let mother_id = demo.mother.as_ref().and_then(|m| plant_ids.get(m).cloned());
+                let plant = tx
+                    .plant()
+                    .create_unchecked(
+                        demo.name.clone(),
+                        genetic_ids[demo.genetic].clone(),
+                        organization_id.clone(),
+                        vec![
+                            plant::stage::set(stage),
+                            plant::created_at::set(demo.created_at),
+                            plant::mother_id::set(mother_id),
+                        ],
+                    )
+                    .exec()
+                    .timed("plant", "create_unchecked")
+                    .await?;
+                summary.plants += 1;
+
+                let mut previous: Option<PlantStage> = None;
+                for (entered, stage) in &demo.stages {
+                    let action = match (previous, &demo.mother) {
+                        (None, Some(mother)) => format!("Cloned from {}", mother),
+                        (None, None) => "Planted from seed".to_string(),
+                        (Some(previous), _) => format!(
+                            "Stage changed from {} to {}",
+                            stage_name(previous),
+                            stage_name(*stage)
+                        ),
+                    };
+                    tx.plant_history()
+                        .create_unchecked(
+                            plant.id.clone(),
+                            action,
+                            user_id.clone(),
+                            organization_id.clone(),
+                            vec![
+                                plant_history::stage::set(Some(*stage)),
+                                plant_history::created_at::set(*entered),
+                            ],
+                        )
+                        .exec()
+                        .timed("plant_history", "create_unchecked")
+                        .await?;
+                    previous = Some(*stage);
+                    summary.history += 1;
+                }
+                plant_ids.insert(demo.name, plant.id);
+            }
+
+            Ok::<SnapshotSummary, ErrorCode>(summary)
+        })
+        .await
+}
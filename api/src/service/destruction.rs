@@ -0,0 +1,250 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use chrono::Days;
+use prisma_client_rust::Direction;
+
+use crate::{
+    model::{
+        dto::destruction::{DestroyRequest, WasteLogEntry, WasteLogQuery},
+        error::ErrorCode,
+    },
+    prisma::{attachment, destruction, plant, user, DestructionReason, PlantStage, PrismaClient},
+    service::{analytics::start_of_day, metrics::TimedQuery, plant::apply_stage_change},
+};
+
+destruction::include!(destruction_with_details {
+    plant: include { genetic }
+    recorded_by: select { display_name }
+    witnesses: select { display_name }
+    attachments: select { id }
+});
+
+#[doc = "Check the fields regulators require for a destruction record"]
+pub fn validate_destruction(request: &DestroyRequest, user_id: &str) -> Result<(), String> {
+    if request.method.trim().is_empty() {
+        return Err("Destruction method is required".to_string());
+    }
+    if request.weight.is_nan() || request.weight <= 0.0 {
+        return Err("Destroyed weight must be greater than 0".to_string());
+    }
+    if !request.witness_ids.iter().any(|witness| witness != user_id) {
+        return Err("At least one witness besides the recording user is required".to_string());
+    }
+    if request.reason == DestructionReason::Other
+        && request
+            .note
+            .as_deref()
+            .unwrap_or_default()
+            .trim()
+            .is_empty()
+    {
+        return Err("A note is required when the reason is OTHER".to_string());
+    }
+    Ok(())
+}
+
+impl From<destruction_with_details::Data> for WasteLogEntry {
+    fn from(destruction: destruction_with_details::Data) -> Self {
+        Self {
+            id: destruction.id,
+            destroyed_at: destruction.destroyed_at,
+            plant_id: destruction.plant.id,
+            plant_name: destruction.plant.name,
+            genetic: destruction.plant.genetic.name,
+            reason: destruction.reason,
+            method: destruction.method,
+            weight: destruction.weight,
+            note: destruction.note,
+            recorded_by: destruction.recorded_by.display_name,
+            witnesses: destruction
+                .witnesses
+                .into_iter()
+                .map(|witness| witness.display_name)
+                .collect(),
+            attachment_ids: destruction
+                .attachments
+                .into_iter()
+                .map(|attachment| attachment.id)
+                .collect(),
+        }
+    }
+}
+
+#[doc = "Render the waste log as CSV with one row per destruction"]
+pub fn waste_log_csv(entries: &[WasteLogEntry]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record([
+            "id",
+            "destroyedAt",
+            "plantId",
+            "plantName",
+            "genetic",
+            "reason",
+            "method",
+            "weightGrams",
+            "recordedBy",
+            "witnesses",
+            "note",
+        ])
+        .map_err(|e| e.to_string())?;
+    for entry in entries {
+        writer
+            .write_record([
+                entry.id.clone(),
+                entry.destroyed_at.to_rfc3339(),
+                entry.plant_id.clone(),
+                entry.plant_name.clone(),
+                entry.genetic.clone(),
+                format!("{:?}", entry.reason).to_uppercase(),
+                entry.method.clone(),
+                entry.weight.to_string(),
+                entry.recorded_by.clone(),
+                entry.witnesses.join("; "),
+                entry.note.clone().unwrap_or_default(),
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+#[doc = "Destroy a plant. The destruction record is written in the same transaction
+as the move to DESTROYED, so no plant is destroyed without a record."]
+pub async fn destroy_plant(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    plant_id: String,
+    request: DestroyRequest,
+    user_id: String,
+) -> Result<WasteLogEntry, ErrorCode> {
+    validate_destruction(&request, &user_id).map_err(ErrorCode::BADREQUEST)?;
+
+    let organization = organization_id.to_string();
+    let id = data
+        ._transaction()
+        .run(|tx| async move {
+            let (_, history) = apply_stage_change(
+                &tx,
+                &organization,
+                &plant_id,
+                PlantStage::Destroyed,
+                &user_id,
+            )
+            .await?;
+            if history.is_none() {
+                return Err(ErrorCode::BADREQUEST(
+                    "Plant is already destroyed".to_string(),
+                ));
+            }
+
+            let mut params = vec![
+                destruction::note::set(request.note),
+                destruction::witnesses::connect(
+                    request
+                        .witness_ids
+                        .into_iter()
+                        .map(user::id::equals)
+                        .collect(),
+                ),
+            ];
+            if let Some(destroyed_at) = request.destroyed_at {
+                params.push(destruction::destroyed_at::set(destroyed_at));
+            }
+            let destruction = tx
+                .destruction()
+                .create(
+                    plant::id::equals(plant_id),
+                    request.reason,
+                    request.method.trim().to_string(),
+                    request.weight,
+                    user::id::equals(user_id),
+                    params,
+                )
+                .exec()
+                .timed("destruction", "create")
+                .await?;
+
+            if !request.attachment_ids.is_empty() {
+                tx.attachment()
+                    .update_many(
+                        vec![attachment::id::in_vec(request.attachment_ids)],
+                        vec![attachment::destruction_id::set(Some(
+                            destruction.id.clone(),
+                        ))],
+                    )
+                    .exec()
+                    .timed("attachment", "update_many")
+                    .await?;
+            }
+            Ok::<String, ErrorCode>(destruction.id)
+        })
+        .await?;
+
+    get_destruction_by_id(data, organization_id, id).await
+}
+
+#[doc = "Filter on destructions of plants of the organization"]
+pub fn in_organization(organization_id: &str) -> destruction::WhereParam {
+    destruction::plant::is(vec![plant::organization_id::equals(
+        organization_id.to_string(),
+    )])
+}
+
+pub async fn get_destruction_by_id(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<WasteLogEntry, ErrorCode> {
+    match data
+        .destruction()
+        .find_first(vec![
+            destruction::id::equals(id),
+            in_organization(organization_id),
+        ])
+        .include(destruction_with_details::include())
+        .exec()
+        .timed("destruction", "find_first")
+        .await
+    {
+        Ok(Some(destruction)) => Ok(destruction.into()),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Destructions within the date range, oldest first"]
+pub async fn get_waste_log(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    query: &WasteLogQuery,
+) -> Result<Vec<WasteLogEntry>, ErrorCode> {
+    let mut filter = vec![in_organization(organization_id)];
+    if let Some(from) = query.from {
+        filter.push(destruction::destroyed_at::gte(start_of_day(from)));
+    }
+    if let Some(to) = query.to {
+        filter.push(destruction::destroyed_at::lt(start_of_day(
+            to + Days::new(1),
+        )));
+    }
+    if let Some(reason) = query.reason {
+        filter.push(destruction::reason::equals(reason));
+    }
+
+    match data
+        .destruction()
+        .find_many(filter)
+        .order_by(destruction::destroyed_at::order(Direction::Asc))
+        .include(destruction_with_details::include())
+        .exec()
+        .timed("destruction", "find_many")
+        .await
+    {
+        Ok(destructions) => Ok(destructions.into_iter().map(WasteLogEntry::from).collect()),
+        Err(e) => Err(e.into()),
+    }
+}
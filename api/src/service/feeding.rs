@@ -0,0 +1,578 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use prisma_client_rust::Direction;
+
+use crate::{
+    model::{
+        dto::{
+            feeding::{FeedingLogRequest, FeedingPlan, RecipeRequest, ScheduleRequest},
+            NutrientProduct,
+        },
+        error::ErrorCode,
+        validation::{Validate, Validator},
+    },
+    prisma::{
+        batch, feed_recipe, feed_recipe_item, feed_schedule, feed_schedule_entry, feeding_log,
+        nutrient_product, organization, plant, PlantStage, PrismaClient,
+    },
+    service::{metrics::TimedQuery, plant::stage_entered_at},
+};
+
+#[doc = "Ids without duplicates, e.g. a recipe used in several weeks of a schedule"]
+fn unique_ids(ids: &[String]) -> Vec<String> {
+    let mut ids = ids.to_vec();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+#[doc = "Reject nutrient products of other organizations as not found"]
+async fn check_products(
+    data: &PrismaClient,
+    organization_id: &str,
+    product_ids: &[String],
+) -> Result<(), ErrorCode> {
+    let product_ids = unique_ids(product_ids);
+    let found = data
+        .nutrient_product()
+        .count(vec![
+            nutrient_product::id::in_vec(product_ids.clone()),
+            nutrient_product::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("nutrient_product", "count")
+        .await?;
+    match found as usize == product_ids.len() {
+        true => Ok(()),
+        false => Err(ErrorCode::DATABASE002),
+    }
+}
+
+#[doc = "Reject feed recipes of other organizations as not found"]
+async fn check_recipes(
+    data: &PrismaClient,
+    organization_id: &str,
+    recipe_ids: &[String],
+) -> Result<(), ErrorCode> {
+    let recipe_ids = unique_ids(recipe_ids);
+    let found = data
+        .feed_recipe()
+        .count(vec![
+            feed_recipe::id::in_vec(recipe_ids.clone()),
+            feed_recipe::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("feed_recipe", "count")
+        .await?;
+    match found as usize == recipe_ids.len() {
+        true => Ok(()),
+        false => Err(ErrorCode::DATABASE002),
+    }
+}
+
+#[doc = "Reject feed schedules of other organizations as not found"]
+pub async fn check_schedules(
+    data: &PrismaClient,
+    organization_id: &str,
+    schedule_ids: &[String],
+) -> Result<(), ErrorCode> {
+    let schedule_ids = unique_ids(schedule_ids);
+    let found = data
+        .feed_schedule()
+        .count(vec![
+            feed_schedule::id::in_vec(schedule_ids.clone()),
+            feed_schedule::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("feed_schedule", "count")
+        .await?;
+    match found as usize == schedule_ids.len() {
+        true => Ok(()),
+        false => Err(ErrorCode::DATABASE002),
+    }
+}
+
+pub async fn get_products(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<nutrient_product::Data>, ErrorCode> {
+    match data
+        .nutrient_product()
+        .find_many(vec![nutrient_product::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .exec()
+        .timed("nutrient_product", "find_many")
+        .await
+    {
+        Ok(products) => Ok(products),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_product(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    product: NutrientProduct,
+) -> Result<nutrient_product::Data, ErrorCode> {
+    let mut validator = Validator::default();
+    validator.required("name", &product.name, "Name is required");
+    product.check(&mut validator);
+    validator.finish()?;
+
+    let mut params = vec![nutrient_product::manufacturer::set(
+        product.manufacturer.flatten(),
+    )];
+    if let Some(unit) = product.unit {
+        params.push(nutrient_product::unit::set(unit));
+    }
+
+    match data
+        .nutrient_product()
+        .create(
+            product.name.unwrap(),
+            organization::id::equals(organization_id.to_string()),
+            params,
+        )
+        .exec()
+        .timed("nutrient_product", "create")
+        .await
+    {
+        Ok(product) => Ok(product),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_product(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .nutrient_product()
+        .delete_many(vec![
+            nutrient_product::id::equals(id),
+            nutrient_product::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("nutrient_product", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_recipes(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<feed_recipe::Data>, ErrorCode> {
+    match data
+        .feed_recipe()
+        .find_many(vec![feed_recipe::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .with(feed_recipe::items::fetch(vec![]).with(feed_recipe_item::product::fetch()))
+        .exec()
+        .timed("feed_recipe", "find_many")
+        .await
+    {
+        Ok(recipes) => Ok(recipes),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_recipe(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    recipe: RecipeRequest,
+) -> Result<feed_recipe::Data, ErrorCode> {
+    recipe.validate()?;
+    let product_ids: Vec<String> = recipe
+        .items
+        .iter()
+        .map(|item| item.product_id.clone())
+        .collect();
+    check_products(data, organization_id, &product_ids).await?;
+
+    let organization = organization_id.to_string();
+    let id = data
+        ._transaction()
+        .run(|tx| async move {
+            let created = tx
+                .feed_recipe()
+                .create(recipe.name, organization::id::equals(organization), vec![])
+                .exec()
+                .timed("feed_recipe", "create")
+                .await?;
+            for item in recipe.items {
+                tx.feed_recipe_item()
+                    .create_unchecked(
+                        created.id.clone(),
+                        item.product_id,
+                        item.dose_per_litre,
+                        vec![],
+                    )
+                    .exec()
+                    .timed("feed_recipe_item", "create_unchecked")
+                    .await?;
+            }
+            Ok::<String, ErrorCode>(created.id)
+        })
+        .await?;
+
+    match data
+        .feed_recipe()
+        .find_unique(feed_recipe::id::equals(id))
+        .with(feed_recipe::items::fetch(vec![]).with(feed_recipe_item::product::fetch()))
+        .exec()
+        .timed("feed_recipe", "find_unique")
+        .await
+    {
+        Ok(Some(recipe)) => Ok(recipe),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_recipe(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .feed_recipe()
+        .delete_many(vec![
+            feed_recipe::id::equals(id),
+            feed_recipe::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("feed_recipe", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_schedules(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<feed_schedule::Data>, ErrorCode> {
+    match data
+        .feed_schedule()
+        .find_many(vec![feed_schedule::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .exec()
+        .timed("feed_schedule", "find_many")
+        .await
+    {
+        Ok(schedules) => Ok(schedules),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_schedule_by_id(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<feed_schedule::Data, ErrorCode> {
+    match data
+        .feed_schedule()
+        .find_first(vec![
+            feed_schedule::id::equals(id),
+            feed_schedule::organization_id::equals(organization_id.to_string()),
+        ])
+        .with(
+            feed_schedule::entries::fetch(vec![])
+                .order_by(feed_schedule_entry::week::order(Direction::Asc))
+                .with(feed_schedule_entry::recipe::fetch()),
+        )
+        .exec()
+        .timed("feed_schedule", "find_first")
+        .await
+    {
+        Ok(Some(schedule)) => Ok(schedule),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_schedule(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    schedule: ScheduleRequest,
+) -> Result<feed_schedule::Data, ErrorCode> {
+    schedule.validate()?;
+    let recipe_ids: Vec<String> = schedule
+        .entries
+        .iter()
+        .map(|entry| entry.recipe_id.clone())
+        .collect();
+    check_recipes(data, organization_id, &recipe_ids).await?;
+
+    let organization = organization_id.to_string();
+    let id = data
+        ._transaction()
+        .run(|tx| async move {
+            let created = tx
+                .feed_schedule()
+                .create(
+                    schedule.name,
+                    organization::id::equals(organization),
+                    vec![],
+                )
+                .exec()
+                .timed("feed_schedule", "create")
+                .await?;
+            for entry in schedule.entries {
+                tx.feed_schedule_entry()
+                    .create_unchecked(
+                        created.id.clone(),
+                        entry.stage,
+                        entry.week,
+                        entry.recipe_id,
+                        vec![],
+                    )
+                    .exec()
+                    .timed("feed_schedule_entry", "create_unchecked")
+                    .await?;
+            }
+            Ok::<String, ErrorCode>(created.id)
+        })
+        .await?;
+
+    get_schedule_by_id(data, organization_id, id).await
+}
+
+pub async fn delete_schedule(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .feed_schedule()
+        .delete_many(vec![
+            feed_schedule::id::equals(id),
+            feed_schedule::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("feed_schedule", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Day and week in stage on the given day, the first seven days are week 1"]
+pub fn stage_progress(stage_entered_at: DateTime<FixedOffset>, today: NaiveDate) -> (i64, i32) {
+    let day_in_stage = (today - stage_entered_at.date_naive()).num_days();
+    (day_in_stage, (day_in_stage.max(0) / 7 + 1) as i32)
+}
+
+#[doc = "Entry of a schedule for the stage and week, the last defined week before it
+when the week has no entry of its own"]
+pub fn entry_for_week(
+    entries: Vec<feed_schedule_entry::Data>,
+    stage: PlantStage,
+    week: i32,
+) -> Option<feed_schedule_entry::Data> {
+    entries
+        .into_iter()
+        .filter(|entry| entry.stage == stage && entry.week <= week)
+        .max_by_key(|entry| entry.week)
+}
+
+#[doc = "Resolve the schedule entry for the plants current stage and week in stage.
+The plants own schedule wins over the one of its batch, weeks without an entry
+keep the recipe of the last defined week."]
+async fn build_feeding_plan(
+    data: &PrismaClient,
+    plant: plant::Data,
+) -> Result<FeedingPlan, ErrorCode> {
+    let schedule_id = plant.feed_schedule_id.clone().or_else(|| {
+        plant
+            .batch()
+            .ok()
+            .flatten()
+            .and_then(|batch| batch.feed_schedule_id.clone())
+    });
+
+    let stage_entered_at = stage_entered_at(data, &plant).await?;
+    let (day_in_stage, week_in_stage) = stage_progress(stage_entered_at, Utc::now().date_naive());
+
+    let recipe = match &schedule_id {
+        Some(schedule_id) => {
+            let entries = data
+                .feed_schedule_entry()
+                .find_many(vec![
+                    feed_schedule_entry::schedule_id::equals(schedule_id.clone()),
+                    feed_schedule_entry::stage::equals(plant.stage),
+                ])
+                .with(feed_schedule_entry::recipe::fetch().with(
+                    feed_recipe::items::fetch(vec![]).with(feed_recipe_item::product::fetch()),
+                ))
+                .exec()
+                .timed("feed_schedule_entry", "find_many")
+                .await?;
+            entry_for_week(entries, plant.stage, week_in_stage)
+                .and_then(|entry| entry.recipe)
+                .map(|recipe| *recipe)
+        }
+        None => None,
+    };
+
+    Ok(FeedingPlan {
+        plant_id: plant.id,
+        plant_name: plant.name,
+        stage: plant.stage,
+        stage_entered_at,
+        day_in_stage,
+        week_in_stage,
+        schedule_id,
+        recipe,
+    })
+}
+
+pub async fn get_plant_feeding_plan(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    plant_id: String,
+) -> Result<FeedingPlan, ErrorCode> {
+    let plant = match data
+        .plant()
+        .find_first(vec![
+            plant::id::equals(plant_id),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .with(plant::batch::fetch())
+        .exec()
+        .timed("plant", "find_first")
+        .await
+    {
+        Ok(Some(plant)) => plant,
+        Ok(None) => return Err(ErrorCode::DATABASE002),
+        Err(e) => return Err(e.into()),
+    };
+
+    build_feeding_plan(data, plant).await
+}
+
+pub async fn get_batch_feeding_plan(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    batch_id: String,
+) -> Result<Vec<FeedingPlan>, ErrorCode> {
+    let plants = match data
+        .plant()
+        .find_many(vec![
+            plant::batch_id::equals(Some(batch_id.clone())),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .with(plant::batch::fetch())
+        .exec()
+        .timed("plant", "find_many")
+        .await
+    {
+        Ok(plants) => plants,
+        Err(e) => return Err(e.into()),
+    };
+    if plants.is_empty()
+        && data
+            .batch()
+            .find_first(vec![
+                batch::id::equals(batch_id),
+                batch::organization_id::equals(organization_id.to_string()),
+            ])
+            .exec()
+            .timed("batch", "find_first")
+            .await?
+            .is_none()
+    {
+        return Err(ErrorCode::DATABASE002);
+    }
+
+    let mut plans = Vec::with_capacity(plants.len());
+    for plant in plants {
+        plans.push(build_feeding_plan(data, plant).await?);
+    }
+    Ok(plans)
+}
+
+pub async fn log_feeding(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    feeding: FeedingLogRequest,
+    user_id: String,
+) -> Result<feeding_log::Data, ErrorCode> {
+    feeding.validate()?;
+    let plants = data
+        .plant()
+        .count(vec![
+            plant::id::equals(feeding.plant_id.clone()),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("plant", "count")
+        .await?;
+    if plants == 0 {
+        return Err(ErrorCode::DATABASE002);
+    }
+    if let Some(recipe_id) = &feeding.recipe_id {
+        check_recipes(data, organization_id, &[recipe_id.clone()]).await?;
+    }
+
+    match data
+        .feeding_log()
+        .create_unchecked(
+            feeding.plant_id,
+            feeding.volume,
+            user_id,
+            vec![
+                feeding_log::recipe_id::set(feeding.recipe_id),
+                feeding_log::ec::set(feeding.ec),
+                feeding_log::ph::set(feeding.ph),
+                feeding_log::note::set(feeding.note),
+            ],
+        )
+        .exec()
+        .timed("feeding_log", "create_unchecked")
+        .await
+    {
+        Ok(log) => Ok(log),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_feeding_logs(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    plant_id: String,
+) -> Result<Vec<feeding_log::Data>, ErrorCode> {
+    match data
+        .feeding_log()
+        .find_many(vec![
+            feeding_log::plant_id::equals(plant_id),
+            feeding_log::plant::is(vec![plant::organization_id::equals(
+                organization_id.to_string(),
+            )]),
+        ])
+        .order_by(feeding_log::created_at::order(Direction::Desc))
+        .with(feeding_log::recipe::fetch())
+        .exec()
+        .timed("feeding_log", "find_many")
+        .await
+    {
+        Ok(logs) => Ok(logs),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -2,50 +2,384 @@
 * Copyright (c) Johannes Grimm 2024.
 */
 
+use std::collections::{HashMap, HashSet};
+
 use actix_web::web;
+use prisma_client_rust::{Direction, QueryMode};
 
 use crate::{
-    model::{dto::Genetic, error::ErrorCode},
+    model::{
+        dto::{
+            genetic_search::GeneticQuery,
+            pedigree::{Pedigree, PedigreeNode},
+            Genetic,
+        },
+        error::ErrorCode,
+        validation::{Validate, Validator},
+    },
     prisma::{genetic, PrismaClient},
+    service::{metrics::TimedQuery, naming::validate_template},
 };
 
-pub async fn get_genetics(data: &web::Data<PrismaClient>) -> Result<Vec<genetic::Data>, ErrorCode> {
-    match data.genetic().find_many(vec![]).exec().await {
+#[doc = "Ancestor generations returned when the pedigree depth is not given"]
+pub const DEFAULT_PEDIGREE_DEPTH: usize = 5;
+
+#[doc = "Deepest pedigree that can be requested"]
+const MAX_PEDIGREE_DEPTH: usize = 20;
+
+#[doc = "Parents and breeding data of a genetic, the part of it the pedigree is built from"]
+#[derive(Debug, Clone)]
+pub struct Lineage {
+    pub id: String,
+    pub name: String,
+    pub generation: Option<String>,
+    pub breeder: Option<String>,
+    pub female_parent_id: Option<String>,
+    pub male_parent_id: Option<String>,
+}
+
+impl From<genetic::Data> for Lineage {
+    fn from(genetic: genetic::Data) -> Self {
+        Self {
+            id: genetic.id,
+            name: genetic.name,
+            generation: genetic.generation,
+            breeder: genetic.breeder,
+            female_parent_id: genetic.female_parent_id,
+            male_parent_id: genetic.male_parent_id,
+        }
+    }
+}
+
+#[doc = "Filial generation like `F1`, `F12`, `BX`, `BX2`, `S1`, `IBL` or `P` for landraces"]
+pub fn is_valid_generation(generation: &str) -> bool {
+    let number = |digits: &str, max_len: usize| {
+        !digits.is_empty()
+            && digits.len() <= max_len
+            && !digits.starts_with('0')
+            && digits.chars().all(|c| c.is_ascii_digit())
+    };
+    match generation {
+        "P" | "BX" | "IBL" => true,
+        _ => {
+            if let Some(digits) = generation.strip_prefix("BX") {
+                number(digits, 1)
+            } else if let Some(digits) = generation.strip_prefix('F') {
+                number(digits, 2)
+            } else if let Some(digits) = generation.strip_prefix('S') {
+                number(digits, 1)
+            } else {
+                false
+            }
+        }
+    }
+}
+
+#[doc = "Check whether making the parents parents of the genetic `id` creates a cycle,
+which is the case when the genetic is one of the parents or one of their ancestors"]
+pub fn creates_cycle(lineages: &HashMap<String, Lineage>, id: &str, parents: &[&str]) -> bool {
+    let mut visited = HashSet::new();
+    let mut queue: Vec<&str> = parents.to_vec();
+    while let Some(current) = queue.pop() {
+        if current == id {
+            return true;
+        }
+        if !visited.insert(current) {
+            continue;
+        }
+        if let Some(lineage) = lineages.get(current) {
+            queue.extend(lineage.female_parent_id.as_deref());
+            queue.extend(lineage.male_parent_id.as_deref());
+        }
+    }
+    false
+}
+
+#[doc = "Build the ancestor tree of a genetic down to the depth"]
+pub fn build_pedigree(
+    lineages: &HashMap<String, Lineage>,
+    id: &str,
+    depth: usize,
+) -> Option<PedigreeNode> {
+    let lineage = lineages.get(id)?;
+    let parent = |parent_id: &Option<String>| match (parent_id, depth) {
+        (Some(parent_id), 1..) => build_pedigree(lineages, parent_id, depth - 1).map(Box::new),
+        _ => None,
+    };
+    let name = |parent_id: &Option<String>| {
+        parent_id
+            .as_ref()
+            .and_then(|parent_id| lineages.get(parent_id))
+            .map(|parent| parent.name.clone())
+    };
+
+    Some(PedigreeNode {
+        id: lineage.id.clone(),
+        name: lineage.name.clone(),
+        generation: lineage.generation.clone(),
+        breeder: lineage.breeder.clone(),
+        cross: match (
+            name(&lineage.female_parent_id),
+            name(&lineage.male_parent_id),
+        ) {
+            (Some(female), Some(male)) if female == male => Some(format!("{} selfed", female)),
+            (Some(female), Some(male)) => Some(format!("{} × {}", female, male)),
+            (Some(female), None) => Some(format!("{} × unknown", female)),
+            (None, Some(male)) => Some(format!("unknown × {}", male)),
+            (None, None) => None,
+        },
+        female: parent(&lineage.female_parent_id),
+        male: parent(&lineage.male_parent_id),
+    })
+}
+
+async fn get_lineages(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<HashMap<String, Lineage>, ErrorCode> {
+    match data
+        .genetic()
+        .find_many(vec![genetic::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .exec()
+        .timed("genetic", "find_many")
+        .await
+    {
+        Ok(genetics) => Ok(genetics
+            .into_iter()
+            .map(|genetic| (genetic.id.clone(), Lineage::from(genetic)))
+            .collect()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Check that the new parents belong to the organization and do not make the genetic
+its own ancestor"]
+async fn validate_lineage(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: Option<&str>,
+    genetic: &Genetic,
+) -> Result<(), ErrorCode> {
+    let parents: Vec<&str> = [&genetic.female_parent_id, &genetic.male_parent_id]
+        .into_iter()
+        .filter_map(|parent| parent.as_ref().and_then(|parent| parent.as_deref()))
+        .collect();
+    if parents.is_empty() {
+        return Ok(());
+    }
+    let lineages = get_lineages(data, organization_id).await?;
+    if let Some(parent) = parents
+        .iter()
+        .find(|parent| !lineages.contains_key(**parent))
+    {
+        return Err(ErrorCode::BADREQUEST(format!(
+            "Genetic {} does not exist",
+            parent
+        )));
+    }
+    let Some(id) = id else {
+        // A new genetic has no offspring yet, so it can not become its own ancestor
+        return Ok(());
+    };
+    if creates_cycle(&lineages, id, &parents) {
+        return Err(ErrorCode::BADREQUEST(
+            "A genetic can not be its own ancestor".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[doc = "Check the naming template used to generate plant names"]
+fn validate_naming(genetic: &Genetic) -> Result<(), ErrorCode> {
+    if let Some(Some(template)) = &genetic.naming_template {
+        validate_template(template)?;
+    }
+    Ok(())
+}
+
+#[doc = "Split a comma separated list into trimmed lowercase values without duplicates"]
+pub fn normalize_list(values: &str) -> Vec<String> {
+    let mut list: Vec<String> = vec![];
+    for value in values.split(',') {
+        let value = value.trim().to_lowercase();
+        if !value.is_empty() && !list.contains(&value) {
+            list.push(value);
+        }
+    }
+    list
+}
+
+#[doc = "Check a percentage range, each bound must be within 0 and 100"]
+pub fn validate_range(name: &str, min: Option<f64>, max: Option<f64>) -> Result<(), String> {
+    for value in [min, max].into_iter().flatten() {
+        if !(0.0..=100.0).contains(&value) {
+            return Err(format!("{} must be between 0 and 100", name));
+        }
+    }
+    match (min, max) {
+        (Some(min), Some(max)) if min > max => {
+            Err(format!("{} minimum must not be above the maximum", name))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[doc = "Check the THC and CBD ranges, unchanged bounds are taken from the current genetic"]
+fn validate_profile(genetic: &Genetic, current: Option<&genetic::Data>) -> Result<(), ErrorCode> {
+    let value = |new: Option<Option<f64>>, old: Option<f64>| match new {
+        Some(new) => new,
+        None => old,
+    };
+    validate_range(
+        "THC",
+        value(genetic.thc_min, current.and_then(|c| c.thc_min)),
+        value(genetic.thc_max, current.and_then(|c| c.thc_max)),
+    )
+    .and(validate_range(
+        "CBD",
+        value(genetic.cbd_min, current.and_then(|c| c.cbd_min)),
+        value(genetic.cbd_max, current.and_then(|c| c.cbd_max)),
+    ))
+    .map_err(ErrorCode::BADREQUEST)
+}
+
+fn normalize_profile(genetic: &mut Genetic) {
+    for list in [&mut genetic.terpenes, &mut genetic.tags]
+        .into_iter()
+        .flatten()
+    {
+        *list = normalize_list(&list.join(","));
+    }
+}
+
+#[doc = "Search the genetics, all given filters have to match"]
+pub async fn get_genetics(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    query: GeneticQuery,
+) -> Result<Vec<genetic::Data>, ErrorCode> {
+    let mut filter = vec![genetic::organization_id::equals(
+        organization_id.to_string(),
+    )];
+    if let Some(search) = query.search.filter(|search| !search.is_empty()) {
+        filter.push(genetic::name::contains(search));
+        filter.push(genetic::name::mode(QueryMode::Insensitive));
+    }
+    if let Some(genetic_type) = query.genetic_type {
+        filter.push(genetic::genetic_type::equals(Some(genetic_type)));
+    }
+    if let Some(autoflower) = query.autoflower {
+        filter.push(genetic::autoflower::equals(autoflower));
+    }
+    if let Some(thc_min) = query.thc_min {
+        filter.push(genetic::thc_max::gte(thc_min));
+    }
+    if let Some(thc_max) = query.thc_max {
+        filter.push(genetic::thc_min::lte(thc_max));
+    }
+    if let Some(cbd_min) = query.cbd_min {
+        filter.push(genetic::cbd_max::gte(cbd_min));
+    }
+    if let Some(cbd_max) = query.cbd_max {
+        filter.push(genetic::cbd_min::lte(cbd_max));
+    }
+    if let Some(terpenes) = query.terpenes {
+        filter.push(genetic::terpenes::has_every(normalize_list(&terpenes)));
+    }
+    if let Some(tags) = query.tags {
+        filter.push(genetic::tags::has_every(normalize_list(&tags)));
+    }
+    if let Some(min_yield) = query.min_yield {
+        filter.push(genetic::expected_yield::gte(min_yield));
+    }
+    if let Some(breeder) = query.breeder {
+        filter.push(genetic::breeder::equals(Some(breeder)));
+    }
+
+    match data
+        .genetic()
+        .find_many(filter)
+        .order_by(genetic::name::order(Direction::Asc))
+        .exec()
+        .timed("genetic", "find_many")
+        .await
+    {
         Ok(genetics) => Ok(genetics),
         Err(e) => Err(e.into()),
     }
 }
 
+#[doc = "Genetic of the organization, genetics of other organizations are not found"]
 pub async fn get_genetic_by_id(
-    data: &web::Data<PrismaClient>,
+    data: &PrismaClient,
+    organization_id: &str,
     id: String,
 ) -> Result<genetic::Data, ErrorCode> {
     match data
         .genetic()
-        .find_unique(genetic::id::equals(id))
+        .find_first(vec![
+            genetic::id::equals(id),
+            genetic::organization_id::equals(organization_id.to_string()),
+        ])
         .exec()
+        .timed("genetic", "find_first")
         .await
     {
-        Ok(genetic) => Ok(genetic.unwrap()),
+        Ok(Some(genetic)) => Ok(genetic),
+        Ok(None) => Err(ErrorCode::DATABASE002),
         Err(e) => Err(e.into()),
     }
 }
 
 pub async fn create_genetic(
     data: &web::Data<PrismaClient>,
-    genetic: Genetic,
+    organization_id: &str,
+    mut genetic: Genetic,
 ) -> Result<genetic::Data, ErrorCode> {
-    if genetic.name.is_none() {
-        return Err(ErrorCode::BADREQUEST("Name is required".to_string()));
-    }
-    if genetic.flower_days.is_none() {
-        return Err(ErrorCode::BADREQUEST("Flower days is required".to_string()));
-    }
+    let mut validator = Validator::default();
+    validator.required("name", &genetic.name, "Name is required");
+    validator.required(
+        "flowerDays",
+        &genetic.flower_days,
+        "Flower days is required",
+    );
+    genetic.check(&mut validator);
+    validator.finish()?;
+    validate_naming(&genetic)?;
+    validate_lineage(data, organization_id, None, &genetic).await?;
+    validate_profile(&genetic, None)?;
+    normalize_profile(&mut genetic);
 
     match data
         .genetic()
-        .create(genetic.name.unwrap(), genetic.flower_days.unwrap(), vec![])
+        .create_unchecked(
+            genetic.name.unwrap(),
+            genetic.flower_days.unwrap(),
+            organization_id.to_string(),
+            vec![
+                genetic::code::set(genetic.code.flatten()),
+                genetic::naming_template::set(genetic.naming_template.flatten()),
+                genetic::female_parent_id::set(genetic.female_parent_id.flatten()),
+                genetic::male_parent_id::set(genetic.male_parent_id.flatten()),
+                genetic::generation::set(genetic.generation.flatten()),
+                genetic::breeder::set(genetic.breeder.flatten()),
+                genetic::source::set(genetic.source.flatten()),
+                genetic::genetic_type::set(genetic.genetic_type.flatten()),
+                genetic::thc_min::set(genetic.thc_min.flatten()),
+                genetic::thc_max::set(genetic.thc_max.flatten()),
+                genetic::cbd_min::set(genetic.cbd_min.flatten()),
+                genetic::cbd_max::set(genetic.cbd_max.flatten()),
+                genetic::terpenes::set(genetic.terpenes.unwrap_or_default()),
+                genetic::expected_yield::set(genetic.expected_yield.flatten()),
+                genetic::stretch_factor::set(genetic.stretch_factor.flatten()),
+                genetic::autoflower::set(genetic.autoflower.unwrap_or(false)),
+                genetic::tags::set(genetic.tags.unwrap_or_default()),
+            ],
+        )
         .exec()
+        .timed("genetic", "create_unchecked")
         .await
     {
         Ok(genetic) => Ok(genetic),
@@ -55,13 +389,22 @@ pub async fn create_genetic(
 
 pub async fn edit_genetic(
     data: &web::Data<PrismaClient>,
+    organization_id: &str,
     id: String,
-    genetic: Genetic,
+    mut genetic: Genetic,
 ) -> Result<genetic::Data, ErrorCode> {
+    genetic.validate()?;
+    validate_naming(&genetic)?;
+    let current = get_genetic_by_id(data, organization_id, id.clone()).await?;
+    validate_lineage(data, organization_id, Some(&id), &genetic).await?;
+    validate_profile(&genetic, Some(&current))?;
+    normalize_profile(&mut genetic);
+
     match data
         .genetic()
         .update_unchecked(genetic::id::equals(id), genetic.to_params())
         .exec()
+        .timed("genetic", "update_unchecked")
         .await
     {
         Ok(genetic) => Ok(genetic),
@@ -69,8 +412,22 @@ pub async fn edit_genetic(
     }
 }
 
-pub async fn delete_genetic(data: &web::Data<PrismaClient>, id: String) -> Result<(), ErrorCode> {
-    match data.genetic().delete(genetic::id::equals(id)).exec().await {
+pub async fn delete_genetic(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .genetic()
+        .delete_many(vec![
+            genetic::id::equals(id),
+            genetic::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("genetic", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
         Ok(_) => Ok(()),
         Err(e) => Err(e.into()),
     }
@@ -79,15 +436,51 @@ pub async fn delete_genetic(data: &web::Data<PrismaClient>, id: String) -> Resul
 #[allow(dead_code)]
 pub async fn check_genetic_name(
     data: &web::Data<PrismaClient>,
+    organization_id: &str,
     name: String,
 ) -> Result<bool, ErrorCode> {
     match data
         .genetic()
-        .count(vec![genetic::name::equals(name)])
+        .count(vec![
+            genetic::name::equals(name),
+            genetic::organization_id::equals(organization_id.to_string()),
+        ])
         .exec()
+        .timed("genetic", "count")
         .await
     {
         Ok(found) => Ok(found > 0),
         Err(e) => Err(e.into()),
     }
 }
+
+#[doc = "Ancestors of the genetic up to the depth and the genetics bred from it"]
+pub async fn get_pedigree(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    depth: Option<usize>,
+) -> Result<Pedigree, ErrorCode> {
+    let lineages = get_lineages(data, organization_id).await?;
+    let Some(genetic) = build_pedigree(
+        &lineages,
+        &id,
+        depth
+            .unwrap_or(DEFAULT_PEDIGREE_DEPTH)
+            .min(MAX_PEDIGREE_DEPTH),
+    ) else {
+        return Err(ErrorCode::DATABASE002);
+    };
+
+    let mut offspring: Vec<PedigreeNode> = lineages
+        .values()
+        .filter(|lineage| {
+            lineage.female_parent_id.as_ref() == Some(&id)
+                || lineage.male_parent_id.as_ref() == Some(&id)
+        })
+        .filter_map(|lineage| build_pedigree(&lineages, &lineage.id, 0))
+        .collect();
+    offspring.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(Pedigree { genetic, offspring })
+}
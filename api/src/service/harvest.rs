@@ -0,0 +1,197 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use prisma_client_rust::Direction;
+
+use crate::{
+    model::{
+        dto::{Harvest, HarvestRequest},
+        error::ErrorCode,
+        validation::Validate,
+    },
+    prisma::{harvest, lot_harvest, plant, PlantStage, PrismaClient},
+    service::{
+        lot::{lock_harvest, WEIGHT_EPSILON},
+        metrics::TimedQuery,
+        plant::apply_stage_change,
+    },
+};
+
+#[doc = "Plants are harvested while FLOWERING, plants already in HARVEST can be
+harvested again"]
+pub fn can_harvest(stage: PlantStage) -> bool {
+    matches!(stage, PlantStage::Flowering | PlantStage::Harvest)
+}
+
+#[doc = "The dry weight of a harvest can not drop below the weight already packaged
+into lots"]
+pub fn check_dry_weight(dry_weight: Option<f64>, packaged: &[f64]) -> Result<(), String> {
+    let packaged: f64 = packaged.iter().sum();
+    if packaged <= 0.0 {
+        return Ok(());
+    }
+    match dry_weight {
+        Some(dry_weight) if dry_weight + WEIGHT_EPSILON >= packaged => Ok(()),
+        _ => Err(format!(
+            "{} g of the harvest are already packaged into lots",
+            packaged
+        )),
+    }
+}
+
+#[doc = "Filter on harvests of plants of the organization"]
+pub fn in_organization(organization_id: &str) -> harvest::WhereParam {
+    harvest::plant::is(vec![plant::organization_id::equals(
+        organization_id.to_string(),
+    )])
+}
+
+pub async fn get_harvests(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<harvest::Data>, ErrorCode> {
+    match data
+        .harvest()
+        .find_many(vec![in_organization(organization_id)])
+        .order_by(harvest::harvested_at::order(Direction::Desc))
+        .with(harvest::plant::fetch())
+        .exec()
+        .timed("harvest", "find_many")
+        .await
+    {
+        Ok(harvests) => Ok(harvests),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_harvest_by_id(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<harvest::Data, ErrorCode> {
+    match data
+        .harvest()
+        .find_first(vec![
+            harvest::id::equals(id),
+            in_organization(organization_id),
+        ])
+        .with(harvest::plant::fetch())
+        .with(harvest::user::fetch())
+        .with(harvest::attachments::fetch(vec![]))
+        .exec()
+        .timed("harvest", "find_first")
+        .await
+    {
+        Ok(Some(harvest)) => Ok(harvest),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Record a harvest of a flowering plant and move the plant to the HARVEST stage"]
+pub async fn create_harvest(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    harvest: HarvestRequest,
+    user_id: String,
+) -> Result<harvest::Data, ErrorCode> {
+    harvest.validate()?;
+
+    let organization_id = organization_id.to_string();
+    data._transaction()
+        .run(|tx| async move {
+            let plant = match tx
+                .plant()
+                .find_first(vec![
+                    plant::id::equals(harvest.plant_id.clone()),
+                    plant::organization_id::equals(organization_id.clone()),
+                ])
+                .exec()
+                .timed("plant", "find_first")
+                .await?
+            {
+                Some(plant) => plant,
+                None => return Err(ErrorCode::DATABASE002),
+            };
+            if !can_harvest(plant.stage) {
+                return Err(ErrorCode::BADREQUEST(format!(
+                    "Plant {} must be FLOWERING before it is harvested",
+                    plant.name
+                )));
+            }
+            apply_stage_change(
+                &tx,
+                &organization_id,
+                &harvest.plant_id,
+                PlantStage::Harvest,
+                &user_id,
+            )
+            .await?;
+            Ok(tx
+                .harvest()
+                .create_unchecked(
+                    harvest.plant_id,
+                    user_id,
+                    vec![
+                        harvest::wet_weight::set(harvest.wet_weight),
+                        harvest::dry_weight::set(harvest.dry_weight),
+                        harvest::note::set(harvest.note),
+                    ],
+                )
+                .exec()
+                .timed("harvest", "create_unchecked")
+                .await?)
+        })
+        .await
+}
+
+#[doc = "Edit a harvest. The harvest is locked like when packaging it, so its dry weight
+never drops below the weight packaged into lots."]
+pub async fn edit_harvest(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    harvest: Harvest,
+) -> Result<harvest::Data, ErrorCode> {
+    harvest.validate()?;
+
+    let organization_id = organization_id.to_string();
+    data._transaction()
+        .run(|tx| async move {
+            lock_harvest(&tx, &id).await?;
+            let found = tx
+                .harvest()
+                .count(vec![
+                    harvest::id::equals(id.clone()),
+                    in_organization(&organization_id),
+                ])
+                .exec()
+                .timed("harvest", "count")
+                .await?;
+            if found == 0 {
+                return Err(ErrorCode::DATABASE002);
+            }
+            if let Some(dry_weight) = harvest.dry_weight {
+                let packaged: Vec<f64> = tx
+                    .lot_harvest()
+                    .find_many(vec![lot_harvest::harvest_id::equals(id.clone())])
+                    .exec()
+                    .timed("lot_harvest", "find_many")
+                    .await?
+                    .into_iter()
+                    .map(|lot| lot.weight)
+                    .collect();
+                check_dry_weight(dry_weight, &packaged).map_err(ErrorCode::BADREQUEST)?;
+            }
+
+            Ok(tx
+                .harvest()
+                .update_unchecked(harvest::id::equals(id), harvest.to_params())
+                .exec()
+                .timed("harvest", "update_unchecked")
+                .await?)
+        })
+        .await
+}
@@ -0,0 +1,119 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::{future::Future, time::Instant};
+
+use prisma_client_rust::raw;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::{timeout, Duration},
+};
+
+use crate::{
+    model::dto::health::{DependencyHealth, HealthStatus, Readiness},
+    prisma::PrismaClient,
+    storage::Storage,
+};
+
+#[doc = "Time a single dependency gets to answer before it counts as down"]
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[doc = "Run a probe and time it, probes exceeding `PROBE_TIMEOUT` fail.
+The readiness endpoint is public, so the error is only logged and not returned."]
+pub async fn probe<F>(name: &str, critical: bool, check: F) -> DependencyHealth
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+    let result = match timeout(PROBE_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("No answer within {} ms", PROBE_TIMEOUT.as_millis())),
+    };
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    if let Err(e) = &result {
+        tracing::warn!(dependency = name, critical, error = %e, latency_ms, "Health probe failed");
+    }
+    DependencyHealth {
+        name: name.to_string(),
+        status: match result {
+            Ok(()) => HealthStatus::Up,
+            Err(_) => HealthStatus::Down,
+        },
+        critical,
+        latency_ms,
+    }
+}
+
+#[doc = "Down when a critical dependency failed, degraded when any other failed"]
+pub fn overall_status(checks: &[DependencyHealth]) -> HealthStatus {
+    let failed = |critical: bool| {
+        checks
+            .iter()
+            .any(|check| check.critical == critical && check.status != HealthStatus::Up)
+    };
+    if failed(true) {
+        HealthStatus::Down
+    } else if failed(false) {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Up
+    }
+}
+
+pub async fn check_database(data: &PrismaClient) -> Result<(), String> {
+    data._query_raw::<serde_json::Value>(raw!("SELECT 1"))
+        .exec()
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[doc = "Send an inline command to Redis and read the first line of the reply"]
+pub async fn redis_command(address: &str, command: &str) -> Result<String, String> {
+    let mut stream = TcpStream::connect(address)
+        .await
+        .map_err(|e| e.to_string())?;
+    stream
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut reply = [0; 64];
+    let read = stream.read(&mut reply).await.map_err(|e| e.to_string())?;
+    let reply = String::from_utf8_lossy(&reply[..read]);
+    match reply.lines().next() {
+        Some(line) if line.starts_with('-') => Err(line[1..].to_string()),
+        Some(line) => Ok(line.to_string()),
+        None => Err("Connection closed without a reply".to_string()),
+    }
+}
+
+#[doc = "Send a `PING` to the Redis server holding the sessions"]
+pub async fn check_redis(address: &str) -> Result<(), String> {
+    match redis_command(address, "PING").await?.as_str() {
+        "+PONG" => Ok(()),
+        reply => Err(format!("Unexpected reply {:?}", reply)),
+    }
+}
+
+#[doc = "Probe every dependency concurrently.
+The database and the session store are critical, without storage only uploads fail."]
+pub async fn get_readiness(
+    data: &PrismaClient,
+    storage: &dyn Storage,
+    redis_address: &str,
+) -> Readiness {
+    let storage_name = format!("storage:{}", storage.name());
+    let (database, sessions, storage) = tokio::join!(
+        probe("database", true, check_database(data)),
+        probe("sessions", true, check_redis(redis_address)),
+        probe(&storage_name, false, storage.health()),
+    );
+    let checks = vec![database, sessions, storage];
+    Readiness {
+        status: overall_status(&checks),
+        checks,
+    }
+}
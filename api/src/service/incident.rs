@@ -0,0 +1,462 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use chrono::{Duration, Utc};
+use prisma_client_rust::{
+    chrono::{DateTime, FixedOffset},
+    or, Direction,
+};
+
+use crate::{
+    model::{
+        dto::incident::{
+            FollowUpRequest, IncidentQuery, IncidentRequest, IncidentUpdateRequest,
+            PreHarvestInterval, ResolveRequest, TreatmentRequest,
+        },
+        error::ErrorCode,
+        validation::Validate,
+    },
+    prisma::{
+        incident, incident_follow_up, location, plant, treatment, user, IncidentStatus,
+        PrismaClient,
+    },
+    service::{location::check_locations, metrics::TimedQuery, plant::check_plants},
+};
+
+incident::include!(incident_with_details {
+    reported_by: select { display_name }
+    plants
+    locations
+    treatments: include { applied_by: select { display_name } }
+    follow_ups: include { user: select { display_name } }
+});
+
+#[doc = "End of the pre-harvest interval of a treatment"]
+pub fn pre_harvest_ends_at(
+    applied_at: DateTime<FixedOffset>,
+    pre_harvest_days: i32,
+) -> DateTime<FixedOffset> {
+    applied_at + Duration::days(pre_harvest_days.max(0) as i64)
+}
+
+#[doc = "Pre-harvest intervals of the treatments still running at `now`, longest first"]
+pub fn running_intervals(
+    treatments: &[treatment::Data],
+    now: DateTime<FixedOffset>,
+) -> Vec<PreHarvestInterval> {
+    let mut intervals: Vec<PreHarvestInterval> = treatments
+        .iter()
+        .map(|treatment| PreHarvestInterval {
+            treatment_id: treatment.id.clone(),
+            incident_id: treatment.incident_id.clone(),
+            product: treatment.product.clone(),
+            applied_at: treatment.applied_at,
+            ends_at: pre_harvest_ends_at(treatment.applied_at, treatment.pre_harvest_days),
+        })
+        .filter(|interval| interval.ends_at > now)
+        .collect();
+    intervals.sort_by(|a, b| b.ends_at.cmp(&a.ends_at));
+    intervals
+}
+
+fn validate_incident(plant_ids: &[String], location_ids: &[String]) -> Result<(), ErrorCode> {
+    if plant_ids.is_empty() && location_ids.is_empty() {
+        return Err(ErrorCode::BADREQUEST(
+            "An incident affects at least one plant or location".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[doc = "Filter on incidents of plants or locations of the organization"]
+fn in_organization(organization_id: &str) -> incident::WhereParam {
+    or![
+        incident::plants::some(vec![plant::organization_id::equals(
+            organization_id.to_string()
+        )]),
+        incident::locations::some(vec![location::organization_id::equals(
+            organization_id.to_string()
+        )]),
+    ]
+}
+
+#[doc = "Reject incidents of other organizations as not found"]
+async fn check_incident(
+    data: &PrismaClient,
+    organization_id: &str,
+    id: &str,
+) -> Result<(), ErrorCode> {
+    let found = data
+        .incident()
+        .count(vec![
+            incident::id::equals(id.to_string()),
+            in_organization(organization_id),
+        ])
+        .exec()
+        .timed("incident", "count")
+        .await?;
+    match found {
+        0 => Err(ErrorCode::DATABASE002),
+        _ => Ok(()),
+    }
+}
+
+#[doc = "Running pre-harvest intervals of treatments on the plant or its current location"]
+pub async fn get_pre_harvest_intervals(
+    data: &PrismaClient,
+    organization_id: &str,
+    plant_id: String,
+) -> Result<Vec<PreHarvestInterval>, ErrorCode> {
+    let plant = match data
+        .plant()
+        .find_first(vec![
+            plant::id::equals(plant_id.clone()),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("plant", "find_first")
+        .await
+    {
+        Ok(Some(plant)) => plant,
+        Ok(None) => return Err(ErrorCode::DATABASE002),
+        Err(e) => return Err(e.into()),
+    };
+
+    let affected = match plant.location_id {
+        Some(location_id) => or![
+            incident::plants::some(vec![plant::id::equals(plant_id)]),
+            incident::locations::some(vec![location::id::equals(location_id)]),
+        ],
+        None => incident::plants::some(vec![plant::id::equals(plant_id)]),
+    };
+    match data
+        .treatment()
+        .find_many(vec![
+            treatment::pre_harvest_days::gt(0),
+            treatment::incident::is(vec![affected]),
+        ])
+        .exec()
+        .timed("treatment", "find_many")
+        .await
+    {
+        Ok(treatments) => Ok(running_intervals(&treatments, Utc::now().fixed_offset())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Refuse the harvest of a plant that was treated within the pre-harvest interval.
+Resolving the incident does not end the interval."]
+pub async fn check_pre_harvest_interval(
+    tx: &PrismaClient,
+    organization_id: &str,
+    plant_id: &str,
+) -> Result<(), ErrorCode> {
+    match get_pre_harvest_intervals(tx, organization_id, plant_id.to_string())
+        .await?
+        .first()
+    {
+        Some(interval) => Err(ErrorCode::PHI001(format!(
+            "Treated with {} on {}, harvest is blocked until {}",
+            interval.product,
+            interval.applied_at.format("%Y-%m-%d"),
+            interval.ends_at.format("%Y-%m-%d %H:%M")
+        ))),
+        None => Ok(()),
+    }
+}
+
+pub async fn get_incidents(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    query: IncidentQuery,
+) -> Result<Vec<incident_with_details::Data>, ErrorCode> {
+    let mut filter = vec![in_organization(organization_id)];
+    if let Some(status) = query.status {
+        filter.push(incident::status::equals(status));
+    }
+    if let Some(plant_id) = query.plant_id {
+        filter.push(incident::plants::some(vec![plant::id::equals(plant_id)]));
+    }
+    if let Some(location_id) = query.location_id {
+        filter.push(incident::locations::some(vec![location::id::equals(
+            location_id,
+        )]));
+    }
+
+    match data
+        .incident()
+        .find_many(filter)
+        .order_by(incident::observed_at::order(Direction::Desc))
+        .include(incident_with_details::include())
+        .exec()
+        .timed("incident", "find_many")
+        .await
+    {
+        Ok(incidents) => Ok(incidents),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_incident_by_id(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<incident_with_details::Data, ErrorCode> {
+    match data
+        .incident()
+        .find_first(vec![
+            incident::id::equals(id),
+            in_organization(organization_id),
+        ])
+        .include(incident_with_details::include())
+        .exec()
+        .timed("incident", "find_first")
+        .await
+    {
+        Ok(Some(incident)) => Ok(incident),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_incident(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    incident: IncidentRequest,
+    user_id: String,
+) -> Result<incident::Data, ErrorCode> {
+    incident.validate()?;
+    validate_incident(&incident.plant_ids, &incident.location_ids)?;
+    check_plants(data, organization_id, &incident.plant_ids).await?;
+    check_locations(data, organization_id, &incident.location_ids).await?;
+
+    let mut params = vec![
+        incident::description::set(incident.description),
+        incident::plants::connect(
+            incident
+                .plant_ids
+                .into_iter()
+                .map(plant::id::equals)
+                .collect(),
+        ),
+        incident::locations::connect(
+            incident
+                .location_ids
+                .into_iter()
+                .map(location::id::equals)
+                .collect(),
+        ),
+    ];
+    if let Some(observed_at) = incident.observed_at {
+        params.push(incident::observed_at::set(observed_at));
+    }
+
+    match data
+        .incident()
+        .create(
+            incident.issue,
+            incident.kind,
+            incident.severity,
+            user::id::equals(user_id),
+            params,
+        )
+        .exec()
+        .timed("incident", "create")
+        .await
+    {
+        Ok(incident) => Ok(incident),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn edit_incident(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    incident: IncidentUpdateRequest,
+) -> Result<incident::Data, ErrorCode> {
+    incident.validate()?;
+    check_incident(data, organization_id, &id).await?;
+    if let Some(plant_ids) = &incident.plant_ids {
+        check_plants(data, organization_id, plant_ids).await?;
+    }
+    if let Some(location_ids) = &incident.location_ids {
+        check_locations(data, organization_id, location_ids).await?;
+    }
+
+    let mut params = vec![];
+    if let Some(issue) = incident.issue {
+        params.push(incident::issue::set(issue));
+    }
+    if let Some(kind) = incident.kind {
+        params.push(incident::kind::set(kind));
+    }
+    if let Some(severity) = incident.severity {
+        params.push(incident::severity::set(severity));
+    }
+    if let Some(description) = incident.description {
+        params.push(incident::description::set(Some(description)));
+    }
+    if let Some(plant_ids) = incident.plant_ids {
+        params.push(incident::plants::set(
+            plant_ids.into_iter().map(plant::id::equals).collect(),
+        ));
+    }
+    if let Some(location_ids) = incident.location_ids {
+        params.push(incident::locations::set(
+            location_ids.into_iter().map(location::id::equals).collect(),
+        ));
+    }
+
+    match data
+        .incident()
+        .update(incident::id::equals(id), params)
+        .exec()
+        .timed("incident", "update")
+        .await
+    {
+        Ok(incident) => Ok(incident),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_incident(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    check_incident(data, organization_id, &id).await?;
+    match data
+        .incident()
+        .delete(incident::id::equals(id))
+        .exec()
+        .timed("incident", "delete")
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Record a treatment, its pre-harvest interval blocks the harvest of the affected plants"]
+pub async fn add_treatment(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    treatment: TreatmentRequest,
+    user_id: String,
+) -> Result<treatment::Data, ErrorCode> {
+    if treatment.product.trim().is_empty() || treatment.dose.trim().is_empty() {
+        return Err(ErrorCode::BADREQUEST(
+            "Product and dose are required".to_string(),
+        ));
+    }
+    if treatment.pre_harvest_days < 0 {
+        return Err(ErrorCode::BADREQUEST(
+            "Pre-harvest interval must not be negative".to_string(),
+        ));
+    }
+
+    let mut params = vec![
+        treatment::pre_harvest_days::set(treatment.pre_harvest_days),
+        treatment::note::set(treatment.note),
+    ];
+    if let Some(applied_at) = treatment.applied_at {
+        params.push(treatment::applied_at::set(applied_at));
+    }
+    check_incident(data, organization_id, &id).await?;
+
+    match data
+        .treatment()
+        .create_unchecked(id, treatment.product, treatment.dose, user_id, params)
+        .exec()
+        .timed("treatment", "create_unchecked")
+        .await
+    {
+        Ok(treatment) => Ok(treatment),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn add_follow_up(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    follow_up: FollowUpRequest,
+    user_id: String,
+) -> Result<incident_follow_up::Data, ErrorCode> {
+    if follow_up.note.trim().is_empty() {
+        return Err(ErrorCode::BADREQUEST("Note is required".to_string()));
+    }
+    if follow_up.status == Some(IncidentStatus::Resolved) {
+        return Err(ErrorCode::BADREQUEST(
+            "Incidents are resolved with a resolution".to_string(),
+        ));
+    }
+    check_incident(data, organization_id, &id).await?;
+
+    data._transaction()
+        .run(|tx| async move {
+            if let Some(status) = follow_up.status {
+                tx.incident()
+                    .update(
+                        incident::id::equals(id.clone()),
+                        vec![
+                            incident::status::set(status),
+                            incident::resolved_at::set(None),
+                        ],
+                    )
+                    .exec()
+                    .timed("incident", "update")
+                    .await?;
+            }
+            Ok(tx
+                .incident_follow_up()
+                .create_unchecked(
+                    id,
+                    follow_up.note,
+                    user_id,
+                    vec![incident_follow_up::status::set(follow_up.status)],
+                )
+                .exec()
+                .timed("incident_follow_up", "create_unchecked")
+                .await?)
+        })
+        .await
+}
+
+pub async fn resolve_incident(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    request: ResolveRequest,
+) -> Result<incident::Data, ErrorCode> {
+    if request.resolution.trim().is_empty() {
+        return Err(ErrorCode::BADREQUEST("Resolution is required".to_string()));
+    }
+    check_incident(data, organization_id, &id).await?;
+
+    match data
+        .incident()
+        .update(
+            incident::id::equals(id),
+            vec![
+                incident::status::set(IncidentStatus::Resolved),
+                incident::resolution::set(Some(request.resolution)),
+                incident::resolved_at::set(Some(
+                    request
+                        .resolved_at
+                        .unwrap_or_else(|| Utc::now().fixed_offset()),
+                )),
+            ],
+        )
+        .exec()
+        .timed("incident", "update")
+        .await
+    {
+        Ok(incident) => Ok(incident),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,371 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::env;
+
+use actix_web::web;
+use chrono::NaiveDate;
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfLayerReference, Rect};
+use qrcode::{Color, QrCode};
+
+use crate::{
+    model::{
+        dto::label::{LabelFormat, LabelLayout, LabelQuery},
+        error::ErrorCode,
+    },
+    prisma::{plant, PrismaClient},
+    service::{metrics::TimedQuery, plant::get_plant_by_id},
+};
+
+#[doc = "Prefix of QR payloads when no `LABEL_BASE_URL` is configured"]
+const PAYLOAD_PREFIX: &str = "planters-cycle:plant:";
+
+#[doc = "Content printed on a single plant label"]
+#[derive(Debug, Clone)]
+pub struct PlantLabel {
+    pub name: String,
+    pub genetic: String,
+    pub created: NaiveDate,
+    pub payload: String,
+}
+
+impl PlantLabel {
+    pub fn from_plant(plant: &plant::Data) -> Self {
+        Self {
+            name: plant.name.clone(),
+            genetic: plant
+                .genetic()
+                .map(|genetic| genetic.name.clone())
+                .unwrap_or_default(),
+            created: plant.created_at.date_naive(),
+            payload: qr_payload(&plant.id),
+        }
+    }
+}
+
+#[doc = "Rendered labels with their content type"]
+pub struct RenderedLabels {
+    pub content_type: &'static str,
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+#[doc = "Sheet geometry of a label layout in millimetres"]
+pub struct SheetGeometry {
+    pub page_width: f32,
+    pub page_height: f32,
+    pub columns: usize,
+    pub rows: usize,
+    pub label_width: f32,
+    pub label_height: f32,
+    pub left: f32,
+    pub top: f32,
+    pub horizontal_pitch: f32,
+    pub vertical_pitch: f32,
+}
+
+impl LabelLayout {
+    pub fn geometry(&self) -> SheetGeometry {
+        match self {
+            LabelLayout::L7160 => SheetGeometry {
+                page_width: 210.0,
+                page_height: 297.0,
+                columns: 3,
+                rows: 7,
+                label_width: 63.5,
+                label_height: 38.1,
+                left: 7.2,
+                top: 15.1,
+                horizontal_pitch: 66.0,
+                vertical_pitch: 38.1,
+            },
+            LabelLayout::L7163 => SheetGeometry {
+                page_width: 210.0,
+                page_height: 297.0,
+                columns: 2,
+                rows: 7,
+                label_width: 99.1,
+                label_height: 38.1,
+                left: 4.65,
+                top: 15.1,
+                horizontal_pitch: 101.6,
+                vertical_pitch: 38.1,
+            },
+            LabelLayout::Avery5160 => SheetGeometry {
+                page_width: 215.9,
+                page_height: 279.4,
+                columns: 3,
+                rows: 10,
+                label_width: 66.7,
+                label_height: 25.4,
+                left: 4.8,
+                top: 12.7,
+                horizontal_pitch: 69.85,
+                vertical_pitch: 25.4,
+            },
+        }
+    }
+}
+
+#[doc = "Content of the QR code of a plant.
+Links into the ui when `LABEL_BASE_URL` is set, so phones can open the plant directly."]
+pub fn qr_payload(plant_id: &str) -> String {
+    match env::var("LABEL_BASE_URL") {
+        Ok(base) if !base.is_empty() => {
+            format!(
+                "{}/dashboard/plants/{}",
+                base.trim_end_matches('/'),
+                plant_id
+            )
+        }
+        _ => format!("{}{}", PAYLOAD_PREFIX, plant_id),
+    }
+}
+
+#[doc = "Extract the plant id from a scanned QR payload.
+Accepts payloads created by `qr_payload`, links containing `/plants/{id}` and bare ids."]
+pub fn parse_qr_payload(payload: &str) -> Option<String> {
+    let payload = payload.trim();
+    let id = if let Some(id) = payload.strip_prefix(PAYLOAD_PREFIX) {
+        id
+    } else if let Some((_, rest)) = payload.rsplit_once("/plants/") {
+        rest.split(['/', '?', '#']).next().unwrap_or_default()
+    } else if payload.contains([':', '/', ' ']) {
+        return None;
+    } else {
+        payload
+    };
+
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+#[doc = "Escape ZPL field data, to be used together with `^FH_`"]
+pub fn zpl_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '^' => escaped.push_str("_5E"),
+            '~' => escaped.push_str("_7E"),
+            '_' => escaped.push_str("_5F"),
+            '\r' | '\n' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[doc = "Render one 2 x 1 inch label per plant for 203 dpi thermal printers"]
+pub fn render_zpl(labels: &[PlantLabel]) -> String {
+    labels
+        .iter()
+        .map(|label| {
+            [
+                "^XA".to_string(),
+                "^CI28".to_string(),
+                "^PW406^LL203".to_string(),
+                format!("^FO10,10^BQN,2,4^FH_^FDQA,{}^FS", zpl_field(&label.payload)),
+                format!("^FO190,30^A0N,40,40^FH_^FD{}^FS", zpl_field(&label.name)),
+                format!(
+                    "^FO190,85^A0N,24,24^FB206,2,0,L^FH_^FD{}^FS",
+                    zpl_field(&label.genetic)
+                ),
+                format!("^FO190,150^A0N,22,22^FD{}^FS", label.created),
+                "^XZ".to_string(),
+            ]
+            .join("\n")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[doc = "Cut the text so it fits into the width, assuming an average Helvetica glyph width"]
+fn fit_text(text: &str, size: f32, width: f32) -> String {
+    let max_chars = (width / (size * 0.3528 * 0.55)).floor() as usize;
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    cut.push_str("...");
+    cut
+}
+
+fn draw_qr_code(layer: &PdfLayerReference, code: &QrCode, x: f32, y: f32, size: f32) {
+    let width = code.width();
+    let module = size / width as f32;
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != Color::Dark {
+            continue;
+        }
+        let (column, row) = ((i % width) as f32, (i / width) as f32);
+        layer.add_rect(Rect::new(
+            Mm(x + column * module),
+            Mm(y + size - (row + 1.0) * module),
+            Mm(x + (column + 1.0) * module),
+            Mm(y + size - row * module),
+        ));
+    }
+}
+
+fn draw_label(
+    layer: &PdfLayerReference,
+    font: &IndirectFontRef,
+    label: &PlantLabel,
+    x: f32,
+    y: f32,
+    geometry: &SheetGeometry,
+) -> Result<(), String> {
+    let padding = 3.0;
+    let qr_size = geometry.label_height - 2.0 * padding;
+    let code = QrCode::new(label.payload.as_bytes()).map_err(|e| e.to_string())?;
+    draw_qr_code(layer, &code, x + padding, y + padding, qr_size);
+
+    let text_x = x + 2.0 * padding + qr_size;
+    let text_width = geometry.label_width - qr_size - 3.0 * padding;
+    let top = y + geometry.label_height - padding;
+    layer.use_text(
+        fit_text(&label.name, 14.0, text_width),
+        14.0,
+        Mm(text_x),
+        Mm(top - 5.0),
+        font,
+    );
+    layer.use_text(
+        fit_text(&label.genetic, 8.0, text_width),
+        8.0,
+        Mm(text_x),
+        Mm(top - 10.0),
+        font,
+    );
+    layer.use_text(
+        label.created.to_string(),
+        8.0,
+        Mm(text_x),
+        Mm(top - 14.0),
+        font,
+    );
+    Ok(())
+}
+
+#[doc = "Render the labels onto sheets of the layout, leaving the first `skip`
+labels of the first sheet empty so partially used sheets can be reused"]
+pub fn render_pdf(
+    labels: &[PlantLabel],
+    layout: LabelLayout,
+    skip: usize,
+) -> Result<Vec<u8>, String> {
+    let geometry = layout.geometry();
+    let per_page = geometry.columns * geometry.rows;
+    let skip = skip % per_page;
+    let pages = (skip + labels.len()).div_ceil(per_page).max(1);
+
+    let (document, first_page, first_layer) = PdfDocument::new(
+        "Plant labels",
+        Mm(geometry.page_width),
+        Mm(geometry.page_height),
+        "Labels",
+    );
+    let font = document
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| e.to_string())?;
+
+    let mut layers = vec![document.get_page(first_page).get_layer(first_layer)];
+    for _ in 1..pages {
+        let (page, layer) =
+            document.add_page(Mm(geometry.page_width), Mm(geometry.page_height), "Labels");
+        layers.push(document.get_page(page).get_layer(layer));
+    }
+
+    for (i, label) in labels.iter().enumerate() {
+        let position = skip + i;
+        let slot = position % per_page;
+        let (column, row) = (slot % geometry.columns, slot / geometry.columns);
+        let x = geometry.left + column as f32 * geometry.horizontal_pitch;
+        let y = geometry.page_height
+            - geometry.top
+            - row as f32 * geometry.vertical_pitch
+            - geometry.label_height;
+        draw_label(&layers[position / per_page], &font, label, x, y, &geometry)?;
+    }
+
+    document.save_to_bytes().map_err(|e| e.to_string())
+}
+
+#[doc = "Render the labels in the requested format"]
+pub fn render_labels(
+    labels: &[PlantLabel],
+    query: &LabelQuery,
+    name: &str,
+) -> Result<RenderedLabels, ErrorCode> {
+    match query.format {
+        LabelFormat::Pdf => match render_pdf(labels, query.layout, query.skip) {
+            Ok(bytes) => Ok(RenderedLabels {
+                content_type: "application/pdf",
+                file_name: format!("{}.pdf", name),
+                bytes,
+            }),
+            Err(_) => Err(ErrorCode::INTERNAL001),
+        },
+        LabelFormat::Zpl => Ok(RenderedLabels {
+            content_type: "application/zpl",
+            file_name: format!("{}.zpl", name),
+            bytes: render_zpl(labels).into_bytes(),
+        }),
+    }
+}
+
+pub async fn get_plant_labels(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    query: LabelQuery,
+) -> Result<RenderedLabels, ErrorCode> {
+    let plant = get_plant_by_id(data, organization_id, id).await?;
+    render_labels(
+        &[PlantLabel::from_plant(&plant)],
+        &query,
+        &format!("label-{}", plant.name),
+    )
+}
+
+#[doc = "Render the labels of all plants of the organization in a batch, ordered by name"]
+pub async fn get_batch_labels(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    query: LabelQuery,
+) -> Result<RenderedLabels, ErrorCode> {
+    let plants = match data
+        .plant()
+        .find_many(vec![
+            plant::batch_id::equals(Some(id.clone())),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .with(plant::genetic::fetch())
+        .exec()
+        .timed("plant", "find_many")
+        .await
+    {
+        Ok(plants) => plants,
+        Err(e) => return Err(e.into()),
+    };
+    if plants.is_empty() {
+        return Err(ErrorCode::BADREQUEST("Batch has no plants".to_string()));
+    }
+
+    let mut labels: Vec<PlantLabel> = plants.iter().map(PlantLabel::from_plant).collect();
+    labels.sort_by(|a, b| a.name.cmp(&b.name));
+    render_labels(&labels, &query, &format!("labels-{}", id))
+}
+
+#[doc = "Find the plant a scanned label belongs to"]
+pub async fn resolve_label(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    payload: String,
+) -> Result<plant::Data, ErrorCode> {
+    match parse_qr_payload(&payload) {
+        Some(id) => get_plant_by_id(data, organization_id, id).await,
+        None => Err(ErrorCode::BADREQUEST("Unknown label payload".to_string())),
+    }
+}
@@ -0,0 +1,150 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+
+use crate::{
+    model::{
+        dto::Location,
+        error::ErrorCode,
+        validation::{Validate, Validator},
+    },
+    prisma::{location, organization, plant, PrismaClient},
+    service::metrics::TimedQuery,
+};
+
+#[doc = "Reject locations of other organizations as not found"]
+pub async fn check_locations(
+    data: &PrismaClient,
+    organization_id: &str,
+    location_ids: &[String],
+) -> Result<(), ErrorCode> {
+    let found = data
+        .location()
+        .count(vec![
+            location::id::in_vec(location_ids.to_vec()),
+            location::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("location", "count")
+        .await?;
+    match found as usize == location_ids.len() {
+        true => Ok(()),
+        false => Err(ErrorCode::DATABASE002),
+    }
+}
+
+pub async fn get_locations(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<location::Data>, ErrorCode> {
+    match data
+        .location()
+        .find_many(vec![location::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .exec()
+        .timed("location", "find_many")
+        .await
+    {
+        Ok(locations) => Ok(locations),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Location of the organization with the plants placed in it"]
+pub async fn get_location_by_id(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<location::Data, ErrorCode> {
+    match data
+        .location()
+        .find_first(vec![
+            location::id::equals(id),
+            location::organization_id::equals(organization_id.to_string()),
+        ])
+        .with(
+            location::plants::fetch(vec![plant::organization_id::equals(
+                organization_id.to_string(),
+            )])
+            .with(plant::genetic::fetch()),
+        )
+        .exec()
+        .timed("location", "find_first")
+        .await
+    {
+        Ok(Some(location)) => Ok(location),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_location(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    location: Location,
+) -> Result<location::Data, ErrorCode> {
+    let mut validator = Validator::default();
+    validator.required("name", &location.name, "Name is required");
+    location.check(&mut validator);
+    validator.finish()?;
+
+    match data
+        .location()
+        .create(
+            location.name.unwrap(),
+            organization::id::equals(organization_id.to_string()),
+            vec![location::description::set(location.description.flatten())],
+        )
+        .exec()
+        .timed("location", "create")
+        .await
+    {
+        Ok(location) => Ok(location),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn edit_location(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    location: Location,
+) -> Result<location::Data, ErrorCode> {
+    location.validate()?;
+    check_locations(data, organization_id, &[id.clone()]).await?;
+
+    match data
+        .location()
+        .update_unchecked(location::id::equals(id), location.to_params())
+        .exec()
+        .timed("location", "update_unchecked")
+        .await
+    {
+        Ok(location) => Ok(location),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_location(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .location()
+        .delete_many(vec![
+            location::id::equals(id),
+            location::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("location", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
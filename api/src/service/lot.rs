@@ -0,0 +1,478 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::collections::HashSet;
+
+use actix_web::web;
+use chrono::{Local, NaiveDate};
+use prisma_client_rust::{raw, Direction, PrismaValue};
+use serde::Deserialize;
+
+use crate::{
+    model::{
+        dto::{
+            lot::{CreateLotRequest, LotHarvestRequest, LotQuery, SaleRequest},
+            Lot,
+        },
+        error::ErrorCode,
+    },
+    prisma::{harvest, lot, lot_harvest, plant, sale, PlantStage, PrismaClient},
+    service::{
+        harvest::in_organization as harvest_in_organization, metrics::TimedQuery,
+        naming::next_sequence, plant::apply_stage_change,
+    },
+};
+
+#[doc = "Weights are compared with this tolerance in grams"]
+pub const WEIGHT_EPSILON: f64 = 0.001;
+
+lot::include!(lot_with_details {
+    packaged_by: select { display_name }
+    harvests: include {
+        harvest: include {
+            plant: select { id name stage }
+        }
+    }
+    sales: include { user: select { display_name } }
+});
+
+harvest::include!(harvest_with_lots {
+    plant
+    lots: include { lot: select { remaining } }
+});
+
+#[doc = "Packaged weight of a harvest and the weight left in each lot it went into"]
+#[derive(Debug, Clone)]
+pub struct HarvestUsage {
+    pub dry_weight: Option<f64>,
+    #[doc = "`(packaged weight, remaining weight of the lot)` per lot"]
+    pub lots: Vec<(f64, f64)>,
+}
+
+#[doc = "Lot codes are 1 to 32 letters, digits, `-`, `_` or `.`"]
+pub fn validate_lot_code(code: &str) -> Result<(), String> {
+    let valid = !code.is_empty()
+        && code.chars().count() <= 32
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    match valid {
+        true => Ok(()),
+        false => Err("Lot code must be 1 to 32 letters, digits, '-', '_' or '.'".to_string()),
+    }
+}
+
+#[doc = "Generated code of the `sequence`th lot of the day"]
+pub fn lot_code(date: NaiveDate, sequence: i64) -> String {
+    format!("LOT-{}-{:03}", date.format("%Y%m%d"), sequence)
+}
+
+#[doc = "Key of the sequence numbering the generated lot codes of the organization on the day"]
+pub fn lot_sequence_key(organization_id: &str, date: NaiveDate) -> String {
+    format!("lot:{}:{}", organization_id, date.format("%Y%m%d"))
+}
+
+#[doc = "Check the harvests of a new lot and return its total weight"]
+pub fn validate_allocations(harvests: &[LotHarvestRequest]) -> Result<f64, String> {
+    if harvests.is_empty() {
+        return Err("A lot is packaged from at least one harvest".to_string());
+    }
+    let mut seen = HashSet::new();
+    for allocation in harvests {
+        if allocation.weight.is_nan() || allocation.weight <= 0.0 {
+            return Err("Packaged weight must be greater than 0".to_string());
+        }
+        if !seen.insert(allocation.harvest_id.as_str()) {
+            return Err("Every harvest can only be listed once".to_string());
+        }
+    }
+    Ok(harvests.iter().map(|allocation| allocation.weight).sum())
+}
+
+#[doc = "Dry weight of the harvest that is not packaged yet, `None` before it is weighed dry"]
+pub fn available_weight(dry_weight: Option<f64>, packaged: &[f64]) -> Option<f64> {
+    dry_weight.map(|dry_weight| (dry_weight - packaged.iter().sum::<f64>()).max(0.0))
+}
+
+#[doc = "A plant is sold once all of its dry weight is packaged and every lot
+containing it is sold out"]
+pub fn plant_sold_out(harvests: &[HarvestUsage]) -> bool {
+    !harvests.is_empty()
+        && harvests.iter().all(|harvest| {
+            let packaged: Vec<f64> = harvest.lots.iter().map(|(weight, _)| *weight).collect();
+            matches!(
+                available_weight(harvest.dry_weight, &packaged),
+                Some(available) if available <= WEIGHT_EPSILON
+            ) && harvest
+                .lots
+                .iter()
+                .all(|(_, remaining)| *remaining <= WEIGHT_EPSILON)
+        })
+}
+
+#[doc = "Filter on lots of the organization"]
+fn in_organization(organization_id: &str) -> lot::WhereParam {
+    lot::organization_id::equals(organization_id.to_string())
+}
+
+pub async fn get_lots(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    query: LotQuery,
+) -> Result<Vec<lot_with_details::Data>, ErrorCode> {
+    let mut filter = vec![in_organization(organization_id)];
+    if query.available == Some(true) {
+        filter.push(lot::remaining::gt(WEIGHT_EPSILON));
+    }
+
+    match data
+        .lot()
+        .find_many(filter)
+        .order_by(lot::packaged_at::order(Direction::Desc))
+        .include(lot_with_details::include())
+        .exec()
+        .timed("lot", "find_many")
+        .await
+    {
+        Ok(lots) => Ok(lots),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_lot_by_id(
+    data: &PrismaClient,
+    organization_id: &str,
+    id: String,
+) -> Result<lot_with_details::Data, ErrorCode> {
+    match data
+        .lot()
+        .find_first(vec![lot::id::equals(id), in_organization(organization_id)])
+        .include(lot_with_details::include())
+        .exec()
+        .timed("lot", "find_first")
+        .await
+    {
+        Ok(Some(lot)) => Ok(lot),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Deserialize)]
+struct LockedHarvest {
+    #[allow(dead_code)]
+    id: String,
+}
+
+#[doc = "Lock the harvest row until the transaction ends, so its dry weight and the
+weight packaged from it can not change concurrently"]
+pub async fn lock_harvest(tx: &PrismaClient, id: &str) -> Result<(), ErrorCode> {
+    let _: Vec<LockedHarvest> = tx
+        ._query_raw(raw!(
+            r#"SELECT "id" FROM "Harvest" WHERE "id" = {} FOR UPDATE"#,
+            PrismaValue::String(id.to_string())
+        ))
+        .exec()
+        .timed("raw", "query_raw")
+        .await?;
+    Ok(())
+}
+
+#[doc = "Package weight of cured harvests into a new lot and move their plants to
+PACKAGED. The harvests are locked, so concurrent lots can not package the same weight."]
+pub async fn create_lot(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    request: CreateLotRequest,
+    user_id: String,
+) -> Result<lot_with_details::Data, ErrorCode> {
+    let weight = validate_allocations(&request.harvests).map_err(ErrorCode::BADREQUEST)?;
+    if let Some(code) = &request.code {
+        validate_lot_code(code).map_err(ErrorCode::BADREQUEST)?;
+    }
+
+    let organization = organization_id.to_string();
+    let id = data
+        ._transaction()
+        .run(|tx| async move {
+            let mut allocations = request.harvests;
+            allocations.sort_by(|a, b| a.harvest_id.cmp(&b.harvest_id));
+
+            let mut plant_ids = vec![];
+            for allocation in &allocations {
+                lock_harvest(&tx, &allocation.harvest_id).await?;
+                let harvest = match tx
+                    .harvest()
+                    .find_first(vec![
+                        harvest::id::equals(allocation.harvest_id.clone()),
+                        harvest_in_organization(&organization),
+                    ])
+                    .include(harvest_with_lots::include())
+                    .exec()
+                    .timed("harvest", "find_first")
+                    .await?
+                {
+                    Some(harvest) => harvest,
+                    None => return Err(ErrorCode::DATABASE002),
+                };
+
+                if !matches!(
+                    harvest.plant.stage,
+                    PlantStage::Cured | PlantStage::Packaged
+                ) {
+                    return Err(ErrorCode::BADREQUEST(format!(
+                        "Plant {} must be CURED before it is packaged",
+                        harvest.plant.name
+                    )));
+                }
+                let packaged: Vec<f64> = harvest.lots.iter().map(|lot| lot.weight).collect();
+                let Some(available) = available_weight(harvest.dry_weight, &packaged) else {
+                    return Err(ErrorCode::BADREQUEST(format!(
+                        "The harvest of plant {} has no dry weight",
+                        harvest.plant.name
+                    )));
+                };
+                if allocation.weight > available + WEIGHT_EPSILON {
+                    return Err(ErrorCode::BADREQUEST(format!(
+                        "Only {} g of the harvest of plant {} are left to package",
+                        available, harvest.plant.name
+                    )));
+                }
+                if !plant_ids.contains(&harvest.plant_id) {
+                    plant_ids.push(harvest.plant_id);
+                }
+            }
+
+            let code = match request.code {
+                Some(code) => code,
+                None => {
+                    let today = Local::now().date_naive();
+                    let sequence =
+                        next_sequence(&tx, &lot_sequence_key(&organization, today)).await?;
+                    lot_code(today, sequence)
+                }
+            };
+            let lot = tx
+                .lot()
+                .create_unchecked(
+                    code,
+                    weight,
+                    weight,
+                    user_id.clone(),
+                    organization.clone(),
+                    vec![lot::note::set(request.note)],
+                )
+                .exec()
+                .timed("lot", "create_unchecked")
+                .await?;
+            for allocation in allocations {
+                tx.lot_harvest()
+                    .create_unchecked(
+                        lot.id.clone(),
+                        allocation.harvest_id,
+                        allocation.weight,
+                        vec![],
+                    )
+                    .exec()
+                    .timed("lot_harvest", "create_unchecked")
+                    .await?;
+            }
+            for plant_id in plant_ids {
+                apply_stage_change(
+                    &tx,
+                    &organization,
+                    &plant_id,
+                    PlantStage::Packaged,
+                    &user_id,
+                )
+                .await?;
+            }
+            Ok(lot.id)
+        })
+        .await?;
+
+    get_lot_by_id(data, organization_id, id).await
+}
+
+pub async fn edit_lot(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    lot: Lot,
+) -> Result<lot::Data, ErrorCode> {
+    if let Some(code) = &lot.code {
+        validate_lot_code(code).map_err(ErrorCode::BADREQUEST)?;
+    }
+    get_lot_by_id(data, organization_id, id.clone()).await?;
+
+    match data
+        .lot()
+        .update_unchecked(lot::id::equals(id), lot.to_params())
+        .exec()
+        .timed("lot", "update_unchecked")
+        .await
+    {
+        Ok(lot) => Ok(lot),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Delete a lot without sales. Its weight becomes available for packaging again,
+the plants stay PACKAGED."]
+pub async fn delete_lot(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    let organization_id = organization_id.to_string();
+    data._transaction()
+        .run(|tx| async move {
+            get_lot_by_id(&tx, &organization_id, id.clone()).await?;
+            let sales = tx
+                .sale()
+                .count(vec![sale::lot_id::equals(id.clone())])
+                .exec()
+                .timed("sale", "count")
+                .await?;
+            if sales > 0 {
+                return Err(ErrorCode::BADREQUEST(
+                    "Lots with sales can not be deleted".to_string(),
+                ));
+            }
+            tx.lot()
+                .delete(lot::id::equals(id))
+                .exec()
+                .timed("lot", "delete")
+                .await?;
+            Ok(())
+        })
+        .await
+}
+
+#[doc = "Move the plants of the lot to SOLD once all of their product is sold"]
+async fn mark_sold_plants(
+    tx: &PrismaClient,
+    organization_id: &str,
+    lot: &lot::Data,
+    user_id: &str,
+) -> Result<(), ErrorCode> {
+    let plants = tx
+        .plant()
+        .find_many(vec![
+            plant::organization_id::equals(organization_id.to_string()),
+            plant::stage::equals(PlantStage::Packaged),
+            plant::harvests::some(vec![harvest::lots::some(vec![
+                lot_harvest::lot_id::equals(lot.id.clone()),
+            ])]),
+        ])
+        .exec()
+        .timed("plant", "find_many")
+        .await?;
+
+    for plant in plants {
+        let harvests = tx
+            .harvest()
+            .find_many(vec![harvest::plant_id::equals(plant.id.clone())])
+            .include(harvest_with_lots::include())
+            .exec()
+            .timed("harvest", "find_many")
+            .await?;
+        let usage: Vec<HarvestUsage> = harvests
+            .into_iter()
+            .map(|harvest| HarvestUsage {
+                dry_weight: harvest.dry_weight,
+                lots: harvest
+                    .lots
+                    .into_iter()
+                    .map(|packaged| (packaged.weight, packaged.lot.remaining))
+                    .collect(),
+            })
+            .collect();
+        if plant_sold_out(&usage) {
+            apply_stage_change(tx, organization_id, &plant.id, PlantStage::Sold, user_id).await?;
+        }
+    }
+    Ok(())
+}
+
+#[doc = "Record a sale from the lot. The lot is only decremented when enough product
+is left, plants whose product is completely sold move to SOLD."]
+pub async fn record_sale(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    request: SaleRequest,
+    user_id: String,
+) -> Result<sale::Data, ErrorCode> {
+    if request.weight.is_nan() || request.weight <= 0.0 {
+        return Err(ErrorCode::BADREQUEST(
+            "Sold weight must be greater than 0".to_string(),
+        ));
+    }
+
+    let organization_id = organization_id.to_string();
+    data._transaction()
+        .run(|tx| async move {
+            let taken = tx
+                .lot()
+                .update_many(
+                    vec![
+                        lot::id::equals(id.clone()),
+                        in_organization(&organization_id),
+                        lot::remaining::gte(request.weight - WEIGHT_EPSILON),
+                    ],
+                    vec![lot::remaining::decrement(request.weight)],
+                )
+                .exec()
+                .timed("lot", "update_many")
+                .await?;
+            let mut lot = match tx
+                .lot()
+                .find_first(vec![
+                    lot::id::equals(id.clone()),
+                    in_organization(&organization_id),
+                ])
+                .exec()
+                .timed("lot", "find_first")
+                .await?
+            {
+                Some(lot) => lot,
+                None => return Err(ErrorCode::DATABASE002),
+            };
+            if taken == 0 {
+                return Err(ErrorCode::BADREQUEST(format!(
+                    "Only {} g left in lot {}",
+                    lot.remaining, lot.code
+                )));
+            }
+            if lot.remaining != 0.0 && lot.remaining.abs() <= WEIGHT_EPSILON {
+                lot = tx
+                    .lot()
+                    .update(lot::id::equals(id.clone()), vec![lot::remaining::set(0.0)])
+                    .exec()
+                    .timed("lot", "update")
+                    .await?;
+            }
+
+            let mut params = vec![
+                sale::customer::set(request.customer),
+                sale::reference::set(request.reference),
+            ];
+            if let Some(sold_at) = request.sold_at {
+                params.push(sale::sold_at::set(sold_at));
+            }
+            let sale = tx
+                .sale()
+                .create_unchecked(id, request.weight, user_id.clone(), params)
+                .exec()
+                .timed("sale", "create_unchecked")
+                .await?;
+
+            if lot.remaining <= WEIGHT_EPSILON {
+                mark_sold_plants(&tx, &organization_id, &lot, &user_id).await?;
+            }
+            Ok(sale)
+        })
+        .await
+}
@@ -0,0 +1,198 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::OnceLock,
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use actix_web::http::{Method, StatusCode};
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+use crate::{
+    prisma::{plant, PrismaClient},
+    service::{analytics::STAGES, health::redis_command, plant::stage_name},
+};
+
+#[doc = "Route label of requests no resource matched, keeps the label set bounded"]
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+#[doc = "Collectors exported on `/metrics`"]
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_duration: HistogramVec,
+    pub db_duration: HistogramVec,
+    pub logins: IntCounterVec,
+    pub active_sessions: IntGauge,
+    pub plants: IntGaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let metrics = Self {
+            registry: Registry::new_custom(Some("planters_cycle".to_string()), None).unwrap(),
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "Handled HTTP requests"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "Time until the response head was ready",
+                ),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            db_duration: HistogramVec::new(
+                HistogramOpts::new("db_query_duration_seconds", "Duration of database queries")
+                    .buckets(vec![
+                        0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+                    ]),
+                &["model", "action"],
+            )
+            .unwrap(),
+            logins: IntCounterVec::new(
+                Opts::new("logins_total", "Login attempts by result"),
+                &["result"],
+            )
+            .unwrap(),
+            active_sessions: IntGauge::new("active_sessions", "Sessions held by Redis").unwrap(),
+            plants: IntGaugeVec::new(Opts::new("plants", "Plants per stage"), &["stage"]).unwrap(),
+        };
+
+        let registry = &metrics.registry;
+        registry
+            .register(Box::new(metrics.http_requests.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(metrics.http_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(metrics.db_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(metrics.logins.clone())).unwrap();
+        registry
+            .register(Box::new(metrics.active_sessions.clone()))
+            .unwrap();
+        registry.register(Box::new(metrics.plants.clone())).unwrap();
+        metrics
+    }
+
+    #[doc = "Count a handled request, `route` is the matched pattern like `/api/plants/{id}`"]
+    pub fn observe_request(
+        &self,
+        method: &Method,
+        route: Option<&str>,
+        status: StatusCode,
+        elapsed: Duration,
+    ) {
+        let labels = [
+            method.as_str(),
+            route.unwrap_or(UNMATCHED_ROUTE),
+            status.as_str(),
+        ];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_login(&self, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        self.logins.with_label_values(&[result]).inc();
+    }
+
+    #[doc = "Encode every collector in the Prometheus text format"]
+    pub fn render(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+#[doc = "Collectors of the running application"]
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+#[doc = "Update the gauges read from the database and the session store before a scrape.
+Failed sources are logged and keep their last value, the scrape itself never fails."]
+pub async fn refresh(data: &PrismaClient, redis_address: &str) {
+    let metrics = metrics();
+
+    for stage in STAGES {
+        match data
+            .plant()
+            .count(vec![plant::stage::equals(stage)])
+            .exec()
+            .timed("plant", "count")
+            .await
+        {
+            Ok(count) => metrics
+                .plants
+                .with_label_values(&[&stage_name(stage)])
+                .set(count),
+            Err(e) => tracing::warn!(error = %e, "Counting plants for the metrics failed"),
+        }
+    }
+
+    // Redis only holds the sessions, so the size of the database is the session count
+    match redis_command(redis_address, "DBSIZE").await {
+        Ok(reply) => match reply.trim_start_matches(':').parse() {
+            Ok(sessions) => metrics.active_sessions.set(sessions),
+            Err(_) => tracing::warn!(reply = ?reply, "Unexpected DBSIZE reply"),
+        },
+        Err(e) => tracing::warn!(error = %e, "Counting sessions for the metrics failed"),
+    }
+}
+
+#[doc = "Database query timed into `db_query_duration_seconds`"]
+pub struct Timed<F> {
+    query: Pin<Box<F>>,
+    model: &'static str,
+    action: &'static str,
+    started: Instant,
+}
+
+impl<F: Future> Future for Timed<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let output = ready!(self.query.as_mut().poll(cx));
+        metrics()
+            .db_duration
+            .with_label_values(&[self.model, self.action])
+            .observe(self.started.elapsed().as_secs_f64());
+        Poll::Ready(output)
+    }
+}
+
+#[doc = "Time a query, e.g. `data.plant().find_many(vec![]).exec().timed(\"plant\", \"find_many\")`"]
+pub trait TimedQuery: Future + Sized {
+    fn timed(self, model: &'static str, action: &'static str) -> Timed<Self> {
+        Timed {
+            query: Box::pin(self),
+            model,
+            action,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl<F: Future> TimedQuery for F {}
@@ -2,7 +2,30 @@
  * Copyright (c) Johannes Grimm 2024.
  */
 
+pub(crate) mod analytics;
+pub(crate) mod attachment;
 pub(crate) mod authentication;
+pub(crate) mod backup;
+pub(crate) mod batch;
+pub(crate) mod bulk_import;
+pub(crate) mod calendar;
+pub(crate) mod compliance;
+pub(crate) mod demo;
+pub(crate) mod destruction;
+pub(crate) mod feeding;
 pub(crate) mod genetic;
+pub(crate) mod harvest;
+pub(crate) mod health;
+pub(crate) mod incident;
+pub(crate) mod label;
+pub(crate) mod location;
+pub(crate) mod lot;
+pub(crate) mod metrics;
+pub(crate) mod naming;
+pub(crate) mod organization;
 pub(crate) mod plant;
+pub(crate) mod report;
+pub(crate) mod seed;
+pub(crate) mod task;
+pub(crate) mod trace;
 pub(crate) mod user;
@@ -0,0 +1,422 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use chrono::{Datelike, Local, NaiveDate};
+use prisma_client_rust::{raw, PrismaValue};
+use serde::Deserialize;
+
+use crate::{
+    model::{
+        dto::naming::{NamingPreview, NamingRequest},
+        error::ErrorCode,
+    },
+    prisma::{batch, genetic, name_sequence, plant, setting, PrismaClient},
+    service::metrics::TimedQuery,
+};
+
+#[doc = "Template used when neither the genetic nor the settings define one.
+Numbers all plants with an uppercase hex counter like the original naming."]
+pub const DEFAULT_TEMPLATE: &str = "{SEQ:X}";
+
+#[doc = "Prefix of the setting keys of the naming templates of the organizations"]
+const TEMPLATE_SETTING: &str = "plantNameTemplate";
+
+#[doc = "How many sequence values are tried before giving up on a free name"]
+const MAX_ATTEMPTS: usize = 100;
+
+#[doc = "Part of a parsed naming template"]
+#[derive(Debug, Clone, PartialEq)]
+pub enum NamingToken {
+    Text(String),
+    GeneticCode,
+    Genetic,
+    Mother,
+    Batch,
+    Year,
+    Month,
+    Day,
+    Sequence { width: usize, hex: bool },
+}
+
+#[doc = "Values the placeholders of a template are replaced with"]
+#[derive(Debug, Clone)]
+pub struct NamingContext {
+    pub genetic_code: String,
+    pub genetic: String,
+    pub mother: Option<String>,
+    pub batch: Option<String>,
+    pub date: NaiveDate,
+}
+
+#[doc = "Parse a template like `{GENETIC_CODE}-{YEAR}-{SEQ:04}`.
+Supported placeholders are `GENETIC_CODE`, `GENETIC`, `MOTHER`, `BATCH`, `YEAR`,
+`MONTH`, `DAY` and exactly one `SEQ`, optionally formatted as `SEQ:04`
+(zero padded), `SEQ:X` (hex) or `SEQ:04X`."]
+pub fn parse_template(template: &str) -> Result<Vec<NamingToken>, String> {
+    let mut tokens = vec![];
+    let mut text = String::new();
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                if !closed {
+                    return Err("Unclosed placeholder in template".to_string());
+                }
+                if !text.is_empty() {
+                    tokens.push(NamingToken::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(parse_placeholder(&placeholder)?);
+            }
+            '}' => return Err("Unopened placeholder in template".to_string()),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        tokens.push(NamingToken::Text(text));
+    }
+
+    match tokens
+        .iter()
+        .filter(|token| matches!(token, NamingToken::Sequence { .. }))
+        .count()
+    {
+        1 => Ok(tokens),
+        _ => Err("Template must contain exactly one {SEQ} placeholder".to_string()),
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<NamingToken, String> {
+    let token = match placeholder {
+        "GENETIC_CODE" => NamingToken::GeneticCode,
+        "GENETIC" => NamingToken::Genetic,
+        "MOTHER" => NamingToken::Mother,
+        "BATCH" => NamingToken::Batch,
+        "YEAR" => NamingToken::Year,
+        "MONTH" => NamingToken::Month,
+        "DAY" => NamingToken::Day,
+        "SEQ" => NamingToken::Sequence {
+            width: 0,
+            hex: false,
+        },
+        _ => {
+            let format = placeholder
+                .strip_prefix("SEQ:")
+                .ok_or(format!("Unknown placeholder {{{}}}", placeholder))?;
+            let (width, hex) = match format.strip_suffix('X') {
+                Some(width) => (width, true),
+                None => (format, false),
+            };
+            let width = match width {
+                "" => 0,
+                width => width
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|width| *width <= 12)
+                    .ok_or(format!("Invalid sequence format {{{}}}", placeholder))?,
+            };
+            NamingToken::Sequence { width, hex }
+        }
+    };
+    Ok(token)
+}
+
+#[doc = "Short uppercase code of a genetic without one, e.g. `NL` for Northern Lights
+or `GEL` for Gelato"]
+pub fn derive_genetic_code(name: &str) -> String {
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let code: String = match words.as_slice() {
+        [word] => word.chars().take(3).collect(),
+        words => words
+            .iter()
+            .filter_map(|word| word.chars().next())
+            .take(6)
+            .collect(),
+    };
+    code.to_uppercase()
+}
+
+#[doc = "Render the tokens with the sequence value.
+Without a value the sequence is rendered as `#`, which is used as the key of the
+counter so every distinct prefix like `NL-2024-` is numbered on its own."]
+pub fn render_name(
+    tokens: &[NamingToken],
+    context: &NamingContext,
+    sequence: Option<i64>,
+) -> Result<String, String> {
+    let mut name = String::new();
+    for token in tokens {
+        match token {
+            NamingToken::Text(text) => name.push_str(text),
+            NamingToken::GeneticCode => name.push_str(&context.genetic_code),
+            NamingToken::Genetic => name.push_str(&context.genetic),
+            NamingToken::Mother => match &context.mother {
+                Some(mother) => name.push_str(mother),
+                None => return Err("Template uses {MOTHER} but no mother is set".to_string()),
+            },
+            NamingToken::Batch => match &context.batch {
+                Some(batch) => name.push_str(batch),
+                None => return Err("Template uses {BATCH} but no batch is set".to_string()),
+            },
+            NamingToken::Year => name.push_str(&context.date.year().to_string()),
+            NamingToken::Month => name.push_str(&format!("{:02}", context.date.month())),
+            NamingToken::Day => name.push_str(&format!("{:02}", context.date.day())),
+            NamingToken::Sequence { width, hex } => match (sequence, hex) {
+                (None, _) => name.push('#'),
+                (Some(value), true) => name.push_str(&format!("{:0width$X}", value)),
+                (Some(value), false) => name.push_str(&format!("{:0width$}", value)),
+            },
+        }
+    }
+    Ok(name)
+}
+
+#[doc = "Setting key of the naming template of the organization"]
+pub fn template_setting(organization_id: &str) -> String {
+    format!("{}:{}", TEMPLATE_SETTING, organization_id)
+}
+
+#[doc = "Key of the sequence numbering the plants of the organization whose names only
+differ in the sequence, the key is the name rendered without a sequence value"]
+pub fn plant_sequence_key(organization_id: &str, key: &str) -> String {
+    format!("plant:{}:{}", organization_id, key)
+}
+
+#[doc = "Naming template of the organization, used for genetics without their own template"]
+pub async fn get_organization_template(
+    data: &PrismaClient,
+    organization_id: &str,
+) -> Result<String, ErrorCode> {
+    match data
+        .setting()
+        .find_unique(setting::key::equals(template_setting(organization_id)))
+        .exec()
+        .timed("setting", "find_unique")
+        .await
+    {
+        Ok(Some(setting)) => Ok(setting.value),
+        Ok(None) => Ok(DEFAULT_TEMPLATE.to_string()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn set_organization_template(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    template: String,
+) -> Result<String, ErrorCode> {
+    validate_template(&template)?;
+
+    match data
+        .setting()
+        .upsert(
+            setting::key::equals(template_setting(organization_id)),
+            setting::create(template_setting(organization_id), template.clone(), vec![]),
+            vec![setting::value::set(template)],
+        )
+        .exec()
+        .timed("setting", "upsert")
+        .await
+    {
+        Ok(setting) => Ok(setting.value),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn validate_template(template: &str) -> Result<Vec<NamingToken>, ErrorCode> {
+    parse_template(template).map_err(ErrorCode::BADREQUEST)
+}
+
+#[doc = "Template and placeholder values for a new plant of the request"]
+async fn naming_context(
+    data: &PrismaClient,
+    organization_id: &str,
+    request: &NamingRequest,
+) -> Result<(String, NamingContext), ErrorCode> {
+    let genetic = match data
+        .genetic()
+        .find_first(vec![
+            genetic::id::equals(request.genetic_id.clone()),
+            genetic::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("genetic", "find_first")
+        .await
+    {
+        Ok(Some(genetic)) => genetic,
+        Ok(None) => return Err(ErrorCode::DATABASE002),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mother = match &request.mother_id {
+        Some(id) => match data
+            .plant()
+            .find_first(vec![
+                plant::id::equals(id.clone()),
+                plant::organization_id::equals(organization_id.to_string()),
+            ])
+            .exec()
+            .timed("plant", "find_first")
+            .await
+        {
+            Ok(Some(mother)) => Some(mother.name),
+            Ok(None) => return Err(ErrorCode::DATABASE002),
+            Err(e) => return Err(e.into()),
+        },
+        None => None,
+    };
+    let batch = match &request.batch_id {
+        Some(id) => match data
+            .batch()
+            .find_first(vec![
+                batch::id::equals(id.clone()),
+                batch::organization_id::equals(organization_id.to_string()),
+            ])
+            .exec()
+            .timed("batch", "find_first")
+            .await
+        {
+            Ok(Some(batch)) => Some(batch.name),
+            Ok(None) => return Err(ErrorCode::DATABASE002),
+            Err(e) => return Err(e.into()),
+        },
+        None => None,
+    };
+
+    let template = match &genetic.naming_template {
+        Some(template) => template.clone(),
+        None => get_organization_template(data, organization_id).await?,
+    };
+    let context = NamingContext {
+        genetic_code: genetic
+            .code
+            .clone()
+            .unwrap_or_else(|| derive_genetic_code(&genetic.name)),
+        genetic: genetic.name,
+        mother,
+        batch,
+        date: Local::now().date_naive(),
+    };
+    Ok((template, context))
+}
+
+#[derive(Deserialize)]
+struct SequenceValue {
+    value: i64,
+}
+
+#[doc = "Atomically increment the counter of the key and return the new value.
+The upsert runs as a single statement, so concurrent requests never get the same value."]
+pub async fn next_sequence(data: &PrismaClient, key: &str) -> Result<i64, ErrorCode> {
+    let values: Vec<SequenceValue> = data
+        ._query_raw(raw!(
+            r#"INSERT INTO "NameSequence" ("key", "value") VALUES ({}, 1)
+            ON CONFLICT ("key") DO UPDATE SET "value" = "NameSequence"."value" + 1
+            RETURNING "value""#,
+            PrismaValue::String(key.to_string())
+        ))
+        .exec()
+        .timed("raw", "query_raw")
+        .await?;
+
+    match values.first() {
+        Some(sequence) => Ok(sequence.value),
+        None => Err(ErrorCode::INTERNAL001),
+    }
+}
+
+async fn name_taken(
+    data: &PrismaClient,
+    organization_id: &str,
+    name: &str,
+) -> Result<bool, ErrorCode> {
+    match data
+        .plant()
+        .count(vec![
+            plant::name::equals(name.to_string()),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("plant", "count")
+        .await
+    {
+        Ok(count) => Ok(count > 0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Generate the next free plant name, consuming a sequence value.
+Names that are already taken, e.g. by manually named plants, are skipped."]
+pub async fn generate_plant_name(
+    data: &PrismaClient,
+    organization_id: &str,
+    request: NamingRequest,
+) -> Result<String, ErrorCode> {
+    let (template, context) = naming_context(data, organization_id, &request).await?;
+    let tokens = validate_template(&template)?;
+    let key = render_name(&tokens, &context, None).map_err(ErrorCode::BADREQUEST)?;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let sequence = next_sequence(data, &plant_sequence_key(organization_id, &key)).await?;
+        let name = render_name(&tokens, &context, Some(sequence)).map_err(ErrorCode::BADREQUEST)?;
+        if !name_taken(data, organization_id, &name).await? {
+            return Ok(name);
+        }
+    }
+    Err(ErrorCode::BADREQUEST(format!(
+        "No free plant name found for template {}",
+        template
+    )))
+}
+
+#[doc = "Show the name the next plant would get without consuming a sequence value"]
+pub async fn preview_plant_name(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    request: NamingRequest,
+) -> Result<NamingPreview, ErrorCode> {
+    let (configured, context) = naming_context(data, organization_id, &request).await?;
+    let template = request.template.unwrap_or(configured);
+    let tokens = validate_template(&template)?;
+    let key = render_name(&tokens, &context, None).map_err(ErrorCode::BADREQUEST)?;
+
+    let mut sequence = match data
+        .name_sequence()
+        .find_unique(name_sequence::key::equals(plant_sequence_key(
+            organization_id,
+            &key,
+        )))
+        .exec()
+        .timed("name_sequence", "find_unique")
+        .await
+    {
+        Ok(Some(sequence)) => sequence.value as i64,
+        Ok(None) => 0,
+        Err(e) => return Err(e.into()),
+    };
+    for _ in 0..MAX_ATTEMPTS {
+        sequence += 1;
+        let name = render_name(&tokens, &context, Some(sequence)).map_err(ErrorCode::BADREQUEST)?;
+        if !name_taken(data, organization_id, &name).await? {
+            return Ok(NamingPreview { template, name });
+        }
+    }
+    Err(ErrorCode::BADREQUEST(format!(
+        "No free plant name found for template {}",
+        template
+    )))
+}
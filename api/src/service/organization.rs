@@ -0,0 +1,283 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use chrono::Utc;
+use prisma_client_rust::Direction;
+
+use crate::{
+    model::{
+        dto::organization::{MemberRequest, OrganizationRequest},
+        error::ErrorCode,
+        validation::Validate,
+    },
+    prisma::{organization, role, users_in_roles, PrismaClient},
+    service::metrics::TimedQuery,
+};
+
+#[doc = "Organization the data created before organizations existed was moved to"]
+pub const DEFAULT_ORGANIZATION_ID: &str = "00000000-0000-0000-0000-000000000001";
+
+#[doc = "Name the admin role is created with. Admin rights come from the `isAdmin` flag
+of the role, the name can be changed and does not grant anything."]
+pub const ADMIN_ROLE: &str = "Admin";
+
+users_in_roles::include!(member_with_details {
+    user: select { id display_name email }
+    role
+});
+
+#[doc = "Whether the user holds any role in the organization"]
+pub async fn is_member(
+    data: &PrismaClient,
+    user_id: &str,
+    organization_id: &str,
+) -> Result<bool, ErrorCode> {
+    let roles = data
+        .users_in_roles()
+        .count(vec![
+            users_in_roles::user_id::equals(user_id.to_string()),
+            users_in_roles::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("users_in_roles", "count")
+        .await?;
+    Ok(roles > 0)
+}
+
+#[doc = "Whether the user holds the admin role in the organization"]
+pub async fn is_admin(
+    data: &PrismaClient,
+    user_id: &str,
+    organization_id: &str,
+) -> Result<bool, ErrorCode> {
+    let roles = data
+        .users_in_roles()
+        .count(vec![
+            users_in_roles::user_id::equals(user_id.to_string()),
+            users_in_roles::organization_id::equals(organization_id.to_string()),
+            users_in_roles::role::is(vec![role::is_admin::equals(true)]),
+        ])
+        .exec()
+        .timed("users_in_roles", "count")
+        .await?;
+    Ok(roles > 0)
+}
+
+#[doc = "Roles are shared by all organizations, only admins working in the default
+organization manage them"]
+pub async fn require_role_admin(
+    data: &PrismaClient,
+    user_id: &str,
+    organization_id: &str,
+) -> Result<(), ErrorCode> {
+    if organization_id != DEFAULT_ORGANIZATION_ID
+        || !is_admin(data, user_id, organization_id).await?
+    {
+        return Err(ErrorCode::ORG001);
+    }
+    Ok(())
+}
+
+#[doc = "The admin role, created when it does not exist yet"]
+pub async fn admin_role(data: &PrismaClient) -> Result<role::Data, ErrorCode> {
+    let existing = data
+        .role()
+        .find_first(vec![role::is_admin::equals(true)])
+        .order_by(role::id::order(Direction::Asc))
+        .exec()
+        .timed("role", "find_first")
+        .await?;
+    match existing {
+        Some(admin) => Ok(admin),
+        None => Ok(data
+            .role()
+            .create(ADMIN_ROLE.to_string(), vec![role::is_admin::set(true)])
+            .exec()
+            .timed("role", "create")
+            .await?),
+    }
+}
+
+#[doc = "Organization with the name, created when it does not exist yet"]
+pub async fn organization_named(
+    data: &PrismaClient,
+    name: &str,
+) -> Result<organization::Data, ErrorCode> {
+    let existing = data
+        .organization()
+        .find_unique(organization::name::equals(name.to_string()))
+        .exec()
+        .timed("organization", "find_unique")
+        .await?;
+    match existing {
+        Some(organization) => Ok(organization),
+        None => Ok(data
+            .organization()
+            .create(name.to_string(), vec![])
+            .exec()
+            .timed("organization", "create")
+            .await?),
+    }
+}
+
+#[doc = "Organization a user works in after logging in, the one they joined first"]
+pub async fn first_organization(
+    data: &PrismaClient,
+    user_id: &str,
+) -> Result<Option<String>, ErrorCode> {
+    let membership = data
+        .users_in_roles()
+        .find_first(vec![users_in_roles::user_id::equals(user_id.to_string())])
+        .order_by(users_in_roles::assigned_at::order(Direction::Asc))
+        .exec()
+        .timed("users_in_roles", "find_first")
+        .await?;
+    Ok(membership.map(|membership| membership.organization_id))
+}
+
+#[doc = "Organizations the user belongs to"]
+pub async fn get_organizations(
+    data: &web::Data<PrismaClient>,
+    user_id: &str,
+) -> Result<Vec<organization::Data>, ErrorCode> {
+    Ok(data
+        .organization()
+        .find_many(vec![organization::members::some(vec![
+            users_in_roles::user_id::equals(user_id.to_string()),
+        ])])
+        .order_by(organization::name::order(Direction::Asc))
+        .exec()
+        .timed("organization", "find_many")
+        .await?)
+}
+
+#[doc = "Organization the user belongs to, other organizations are reported as not found"]
+pub async fn get_organization(
+    data: &web::Data<PrismaClient>,
+    user_id: &str,
+    id: &str,
+) -> Result<organization::Data, ErrorCode> {
+    if !is_member(data, user_id, id).await? {
+        return Err(ErrorCode::DATABASE002);
+    }
+    data.organization()
+        .find_unique(organization::id::equals(id.to_string()))
+        .exec()
+        .timed("organization", "find_unique")
+        .await?
+        .ok_or(ErrorCode::DATABASE002)
+}
+
+#[doc = "Create an organization, its creator joins it with the admin role"]
+pub async fn create_organization(
+    data: &web::Data<PrismaClient>,
+    user_id: String,
+    request: OrganizationRequest,
+) -> Result<organization::Data, ErrorCode> {
+    request.validate()?;
+    data._transaction()
+        .run(|tx| async move {
+            let organization = tx
+                .organization()
+                .create(request.name.trim().to_string(), vec![])
+                .exec()
+                .timed("organization", "create")
+                .await?;
+            let admin = admin_role(&tx).await?;
+            tx.users_in_roles()
+                .create_unchecked(
+                    user_id.clone(),
+                    admin.id,
+                    user_id,
+                    organization.id.clone(),
+                    vec![],
+                )
+                .exec()
+                .timed("users_in_roles", "create_unchecked")
+                .await?;
+            Ok::<organization::Data, ErrorCode>(organization)
+        })
+        .await
+}
+
+#[doc = "Members of the organization with the roles they hold in it"]
+pub async fn get_members(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<member_with_details::Data>, ErrorCode> {
+    Ok(data
+        .users_in_roles()
+        .find_many(vec![users_in_roles::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .order_by(users_in_roles::assigned_at::order(Direction::Asc))
+        .include(member_with_details::include())
+        .exec()
+        .timed("users_in_roles", "find_many")
+        .await?)
+}
+
+#[doc = "Give a user a role in the organization, only admins of it may do so"]
+pub async fn add_member(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    assigner_id: &str,
+    request: MemberRequest,
+) -> Result<users_in_roles::Data, ErrorCode> {
+    request.validate()?;
+    if !is_admin(data, assigner_id, organization_id).await? {
+        return Err(ErrorCode::ORG001);
+    }
+    let existing = data
+        .users_in_roles()
+        .find_unique(users_in_roles::user_id_role_id_organization_id(
+            request.user_id.clone(),
+            request.role_id,
+            organization_id.to_string(),
+        ))
+        .exec()
+        .timed("users_in_roles", "find_unique")
+        .await?;
+    if let Some(existing) = existing {
+        return Ok(existing);
+    }
+    Ok(data
+        .users_in_roles()
+        .create_unchecked(
+            request.user_id,
+            request.role_id,
+            assigner_id.to_string(),
+            organization_id.to_string(),
+            vec![users_in_roles::assigned_at::set(Utc::now().fixed_offset())],
+        )
+        .exec()
+        .timed("users_in_roles", "create_unchecked")
+        .await?)
+}
+
+#[doc = "Remove all roles of a user in the organization, only admins of it may do so"]
+pub async fn remove_member(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    remover_id: &str,
+    user_id: String,
+) -> Result<(), ErrorCode> {
+    if !is_admin(data, remover_id, organization_id).await? {
+        return Err(ErrorCode::ORG001);
+    }
+    let removed = data
+        .users_in_roles()
+        .delete_many(vec![
+            users_in_roles::user_id::equals(user_id),
+            users_in_roles::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("users_in_roles", "delete_many")
+        .await?;
+    match removed {
+        0 => Err(ErrorCode::DATABASE002),
+        _ => Ok(()),
+    }
+}
@@ -3,35 +3,161 @@
  */
 
 use actix_web::web;
+use prisma_client_rust::{
+    chrono::{DateTime, FixedOffset},
+    prisma_errors::query_engine::UniqueKeyViolation,
+    Direction, QueryError,
+};
 
 use crate::{
-    model::{dto::Plant, error::ErrorCode},
-    prisma::{plant, plant_history, PrismaClient},
+    model::{
+        dto::{naming::NamingRequest, Plant},
+        error::ErrorCode,
+        validation::{Validate, Validator},
+    },
+    prisma::{plant, plant_history, PlantStage, PrismaClient},
+    service::{
+        batch::check_batches,
+        compliance::{check_plant_limits, Placement},
+        feeding::check_schedules,
+        genetic::get_genetic_by_id,
+        incident::check_pre_harvest_interval,
+        location::check_locations,
+        metrics::TimedQuery,
+        naming,
+        task::create_stage_tasks,
+    },
 };
 
-pub async fn generate_plant_name(
-    data: &web::Data<PrismaClient>,
-    id: String,
-) -> Result<String, ErrorCode> {
-    let c = match data
+#[doc = "How often a generated name is regenerated when a concurrent create took it"]
+const NAME_RETRIES: usize = 3;
+
+#[doc = "Failure of a single attempt to create a plant"]
+enum CreateError {
+    NameTaken(ErrorCode),
+    Failed(ErrorCode),
+}
+
+impl From<QueryError> for CreateError {
+    fn from(error: QueryError) -> Self {
+        match error.is_prisma_error::<UniqueKeyViolation>() {
+            true => CreateError::NameTaken(error.into()),
+            false => CreateError::Failed(error.into()),
+        }
+    }
+}
+
+impl From<ErrorCode> for CreateError {
+    fn from(error: ErrorCode) -> Self {
+        CreateError::Failed(error)
+    }
+}
+
+#[doc = "Database name of a plant stage, e.g. `FLOWERING`"]
+pub fn stage_name(stage: PlantStage) -> String {
+    format!("{:?}", stage).to_uppercase()
+}
+
+#[doc = "Reject genetics, mother plants, batches and locations of other organizations
+as not found"]
+async fn check_relations(
+    data: &PrismaClient,
+    organization_id: &str,
+    genetic_id: Option<&String>,
+    mother_id: Option<&String>,
+    batch_id: Option<&String>,
+    location_id: Option<&String>,
+    feed_schedule_id: Option<&String>,
+) -> Result<(), ErrorCode> {
+    if let Some(genetic_id) = genetic_id {
+        get_genetic_by_id(data, organization_id, genetic_id.clone()).await?;
+    }
+    if let Some(mother_id) = mother_id {
+        find_plant(data, organization_id, mother_id).await?;
+    }
+    if let Some(batch_id) = batch_id {
+        check_batches(data, organization_id, &[batch_id.clone()]).await?;
+    }
+    if let Some(location_id) = location_id {
+        check_locations(data, organization_id, &[location_id.clone()]).await?;
+    }
+    if let Some(feed_schedule_id) = feed_schedule_id {
+        check_schedules(data, organization_id, &[feed_schedule_id.clone()]).await?;
+    }
+    Ok(())
+}
+
+#[doc = "Reject plants of other organizations as not found"]
+pub async fn check_plants(
+    data: &PrismaClient,
+    organization_id: &str,
+    plant_ids: &[String],
+) -> Result<(), ErrorCode> {
+    let found = data
         .plant()
-        .count(vec![plant::genetic_id::equals(id)])
+        .count(vec![
+            plant::id::in_vec(plant_ids.to_vec()),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
         .exec()
-        .await
+        .timed("plant", "count")
+        .await?;
+    match found as usize == plant_ids.len() {
+        true => Ok(()),
+        false => Err(ErrorCode::DATABASE002),
+    }
+}
+
+#[doc = "Plant of the organization without its relations"]
+async fn find_plant(
+    data: &PrismaClient,
+    organization_id: &str,
+    id: &str,
+) -> Result<plant::Data, ErrorCode> {
+    match data
+        .plant()
+        .find_first(vec![
+            plant::id::equals(id.to_string()),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("plant", "find_first")
+        .await?
     {
-        Ok(g) => g,
-        Err(e) => return Err(e.into()),
-    };
+        Some(plant) => Ok(plant),
+        None => Err(ErrorCode::DATABASE002),
+    }
+}
 
-    Ok(format!("{:X}", c + 1))
+#[doc = "Generate the next name from the naming template of the genetic"]
+pub async fn generate_plant_name(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<String, ErrorCode> {
+    naming::generate_plant_name(
+        data,
+        organization_id,
+        NamingRequest {
+            genetic_id: id,
+            ..Default::default()
+        },
+    )
+    .await
 }
 
-pub async fn get_plants(data: &web::Data<PrismaClient>) -> Result<Vec<plant::Data>, ErrorCode> {
+pub async fn get_plants(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<plant::Data>, ErrorCode> {
     match data
         .plant()
-        .find_many(vec![])
+        .find_many(vec![plant::organization_id::equals(
+            organization_id.to_string(),
+        )])
         .with(plant::genetic::fetch())
         .exec()
+        .timed("plant", "find_many")
         .await
     {
         Ok(plants) => Ok(plants),
@@ -41,14 +167,23 @@ pub async fn get_plants(data: &web::Data<PrismaClient>) -> Result<Vec<plant::Dat
 
 pub async fn get_plant_by_id(
     data: &web::Data<PrismaClient>,
+    organization_id: &str,
     id: String,
 ) -> Result<plant::Data, ErrorCode> {
     match data
         .plant()
-        .find_unique(plant::id::equals(id))
+        .find_first(vec![
+            plant::id::equals(id),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
         .with(plant::genetic::fetch())
-        .with(plant::plant_history::fetch(vec![]).with(plant_history::user::fetch()))
+        .with(
+            plant::plant_history::fetch(vec![])
+                .with(plant_history::user::fetch())
+                .with(plant_history::attachments::fetch(vec![])),
+        )
         .exec()
+        .timed("plant", "find_first")
         .await
     {
         Ok(plant) => match plant {
@@ -59,47 +194,250 @@ pub async fn get_plant_by_id(
     }
 }
 
+#[doc = "Create a plant. Without a name the next name of the naming template is
+used, a generated name taken by a concurrent create is replaced by a fresh one.
+Clones are created like any other plant and count towards the plant limits."]
 pub async fn create_plant(
     data: &web::Data<PrismaClient>,
+    organization_id: &str,
     plant: Plant,
 ) -> Result<plant::Data, ErrorCode> {
-    if plant.name.is_none() {
-        return Err(ErrorCode::BADREQUEST("Name is required".to_string()));
-    }
-    if plant.genetic_id.is_none() {
-        return Err(ErrorCode::BADREQUEST("Genetic ID is required".to_string()));
-    }
+    let mut validator = Validator::default();
+    validator.required("geneticId", &plant.genetic_id, "Genetic ID is required");
+    plant.check(&mut validator);
+    validator.finish()?;
+    let genetic_id = plant.genetic_id.unwrap();
+    let mother_id = plant.mother_id.flatten();
+    let batch_id = plant.batch_id.flatten();
+    let feed_schedule_id = plant.feed_schedule_id.flatten();
+    let location_id = plant.location_id.flatten();
+    check_relations(
+        data,
+        organization_id,
+        Some(&genetic_id),
+        mother_id.as_ref(),
+        batch_id.as_ref(),
+        location_id.as_ref(),
+        feed_schedule_id.as_ref(),
+    )
+    .await?;
 
-    match data
-        .plant()
-        .create_unchecked(plant.name.unwrap(), plant.genetic_id.unwrap(), vec![])
-        .exec()
-        .await
-    {
-        Ok(plant) => Ok(plant),
-        Err(e) => return Err(e.into()),
+    let mut attempt = 0;
+    loop {
+        let name = match &plant.name {
+            Some(name) => name.clone(),
+            None => {
+                naming::generate_plant_name(
+                    data,
+                    organization_id,
+                    NamingRequest {
+                        genetic_id: genetic_id.clone(),
+                        mother_id: mother_id.clone(),
+                        batch_id: batch_id.clone(),
+                        template: None,
+                    },
+                )
+                .await?
+            }
+        };
+
+        let params = vec![
+            plant::mother_id::set(mother_id.clone()),
+            plant::batch_id::set(batch_id.clone()),
+            plant::feed_schedule_id::set(feed_schedule_id.clone()),
+            plant::location_id::set(location_id.clone()),
+        ];
+        let genetic_id = genetic_id.clone();
+        let organization_id = organization_id.to_string();
+        let created = data
+            ._transaction()
+            .run(|tx| async move {
+                let plant = tx
+                    .plant()
+                    .create_unchecked(name, genetic_id, organization_id, params)
+                    .exec()
+                    .timed("plant", "create_unchecked")
+                    .await?;
+                check_plant_limits(&tx, &plant.organization_id, None, Placement::of(&plant))
+                    .await?;
+                Ok::<plant::Data, CreateError>(plant)
+            })
+            .await;
+
+        match created {
+            Ok(plant) => return Ok(plant),
+            Err(CreateError::NameTaken(_)) if plant.name.is_none() && attempt < NAME_RETRIES => {
+                attempt += 1;
+            }
+            Err(CreateError::NameTaken(e) | CreateError::Failed(e)) => return Err(e),
+        }
     }
 }
 
+#[doc = "Edit a plant, moving it to another location is checked against the plant limits"]
 pub async fn edit_plant(
     data: &web::Data<PrismaClient>,
+    organization_id: &str,
     id: String,
     plant: Plant,
 ) -> Result<plant::Data, ErrorCode> {
+    plant.validate()?;
+    check_relations(
+        data,
+        organization_id,
+        plant.genetic_id.as_ref(),
+        plant.mother_id.as_ref().and_then(Option::as_ref),
+        plant.batch_id.as_ref().and_then(Option::as_ref),
+        plant.location_id.as_ref().and_then(Option::as_ref),
+        plant.feed_schedule_id.as_ref().and_then(Option::as_ref),
+    )
+    .await?;
+    let organization_id = organization_id.to_string();
+    data._transaction()
+        .run(|tx| async move {
+            let current = find_plant(&tx, &organization_id, &id).await?;
+            let edited = tx
+                .plant()
+                .update_unchecked(plant::id::equals(id), plant.to_params())
+                .exec()
+                .timed("plant", "update_unchecked")
+                .await?;
+            check_plant_limits(
+                &tx,
+                &organization_id,
+                Some(Placement::of(&current)),
+                Placement::of(&edited),
+            )
+            .await?;
+            Ok(edited)
+        })
+        .await
+}
+
+pub async fn delete_plant(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
     match data
         .plant()
-        .update_unchecked(plant::id::equals(id), plant.to_params())
+        .delete_many(vec![
+            plant::id::equals(id),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
         .exec()
+        .timed("plant", "delete_many")
         .await
     {
-        Ok(plant) => Ok(plant),
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
         Err(e) => Err(e.into()),
     }
 }
 
-pub async fn delete_plant(data: &web::Data<PrismaClient>, id: String) -> Result<(), ErrorCode> {
-    match data.plant().delete(plant::id::equals(id)).exec().await {
-        Ok(_) => Ok(()),
+#[doc = "Move a plant to another stage, record the transition in its history and
+schedule the tasks of templates triggered by the new stage"]
+pub async fn change_stage(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    stage: PlantStage,
+    user_id: String,
+) -> Result<plant::Data, ErrorCode> {
+    if stage == PlantStage::Destroyed {
+        return Err(ErrorCode::BADREQUEST(
+            "Destroying a plant requires a destruction record".to_string(),
+        ));
+    }
+
+    let organization_id = organization_id.to_string();
+    data._transaction()
+        .run(|tx| async move {
+            let (plant, _) =
+                apply_stage_change(&tx, &organization_id, &id, stage, &user_id).await?;
+            Ok(plant)
+        })
+        .await
+}
+
+#[doc = "Stage transition for use inside a transaction.
+Returns the updated plant and the history entry, which is `None` when the plant
+already was in the requested stage. Plants of other organizations are not found."]
+pub async fn apply_stage_change(
+    tx: &PrismaClient,
+    organization_id: &str,
+    id: &str,
+    stage: PlantStage,
+    user_id: &str,
+) -> Result<(plant::Data, Option<plant_history::Data>), ErrorCode> {
+    let current = find_plant(tx, organization_id, id).await?;
+    if current.stage == stage {
+        return Ok((current, None));
+    }
+    if current.stage == PlantStage::Destroyed {
+        return Err(ErrorCode::BADREQUEST(
+            "Destroyed plants can not change their stage".to_string(),
+        ));
+    }
+    if stage == PlantStage::Harvest {
+        check_pre_harvest_interval(tx, organization_id, id).await?;
+    }
+
+    let plant = tx
+        .plant()
+        .update(
+            plant::id::equals(id.to_string()),
+            vec![plant::stage::set(stage)],
+        )
+        .exec()
+        .timed("plant", "update")
+        .await?;
+    check_plant_limits(
+        tx,
+        organization_id,
+        Some(Placement::of(&current)),
+        Placement::of(&plant),
+    )
+    .await?;
+    let history = tx
+        .plant_history()
+        .create_unchecked(
+            id.to_string(),
+            format!(
+                "Stage changed from {} to {}",
+                stage_name(current.stage),
+                stage_name(stage)
+            ),
+            user_id.to_string(),
+            organization_id.to_string(),
+            vec![plant_history::stage::set(Some(stage))],
+        )
+        .exec()
+        .timed("plant_history", "create_unchecked")
+        .await?;
+    create_stage_tasks(tx, &plant, stage, user_id).await?;
+
+    Ok((plant, Some(history)))
+}
+
+#[doc = "Date the plant entered its current stage, falls back to the creation date"]
+pub async fn stage_entered_at(
+    data: &PrismaClient,
+    plant: &plant::Data,
+) -> Result<DateTime<FixedOffset>, ErrorCode> {
+    match data
+        .plant_history()
+        .find_first(vec![
+            plant_history::plant_id::equals(plant.id.clone()),
+            plant_history::stage::equals(Some(plant.stage)),
+        ])
+        .order_by(plant_history::created_at::order(Direction::Desc))
+        .exec()
+        .timed("plant_history", "find_first")
+        .await
+    {
+        Ok(Some(history)) => Ok(history.created_at),
+        Ok(None) => Ok(plant.created_at),
         Err(e) => Err(e.into()),
     }
 }
@@ -0,0 +1,75 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument};
+
+#[doc = "Line of a rendered report"]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportLine {
+    Heading(String),
+    Text(String),
+}
+
+#[doc = "Break the text into lines of at most `max_chars` characters at spaces"]
+pub fn wrap_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split(' ') {
+        let mut word = word.to_string();
+        while word.chars().count() > max_chars {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let rest = word.chars().skip(max_chars).collect();
+            lines.push(word.chars().take(max_chars).collect());
+            word = rest;
+        }
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[doc = "Render the lines onto A4 pages"]
+pub fn render_report_pdf(title: &str, lines: &[ReportLine]) -> Result<Vec<u8>, String> {
+    let (width, height, margin) = (210.0, 297.0, 15.0);
+    let (document, page, layer) = PdfDocument::new(title, Mm(width), Mm(height), "Report");
+    let regular = document
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| e.to_string())?;
+    let bold = document
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(|e| e.to_string())?;
+
+    let mut layer = document.get_page(page).get_layer(layer);
+    let mut y = height - margin;
+    for line in lines {
+        let (text, size, font, spacing): (&str, f32, &IndirectFontRef, f32) = match line {
+            ReportLine::Heading(text) => (text, 11.0, &bold, 3.0),
+            ReportLine::Text(text) => (text, 9.0, &regular, 0.0),
+        };
+        let line_height = size * 0.3528 * 1.4;
+        let max_chars = ((width - 2.0 * margin) / (size * 0.3528 * 0.5)).floor() as usize;
+        y -= spacing;
+        for wrapped in wrap_text(text, max_chars) {
+            if y - line_height < margin {
+                let (page, next) = document.add_page(Mm(width), Mm(height), "Report");
+                layer = document.get_page(page).get_layer(next);
+                y = height - margin;
+            }
+            y -= line_height;
+            layer.use_text(wrapped, size, Mm(margin), Mm(y), font);
+        }
+    }
+
+    document.save_to_bytes().map_err(|e| e.to_string())
+}
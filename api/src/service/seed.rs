@@ -0,0 +1,357 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use actix_web::web;
+use prisma_client_rust::Direction;
+
+use crate::{
+    model::{
+        dto::{
+            naming::NamingRequest,
+            seed::{GerminateRequest, GerminationResult, SeedPackQuery, SeedPackSummary},
+            SeedPack,
+        },
+        error::ErrorCode,
+        validation::{Validate, Validator},
+    },
+    prisma::{
+        genetic, plant, plant_history, seed_germination, seed_pack, PlantStage, PrismaClient,
+    },
+    service::{
+        batch::check_batches,
+        compliance::{check_plant_limits, Placement},
+        genetic::get_genetic_by_id,
+        location::check_locations,
+        metrics::TimedQuery,
+        naming,
+    },
+};
+
+#[doc = "Share of sown seeds that germinated, `None` before the first germination"]
+pub fn germination_rate(sown: i32, germinated: i32) -> Option<f64> {
+    (sown > 0).then(|| germinated as f64 / sown as f64)
+}
+
+fn summarize(mut pack: seed_pack::Data) -> SeedPackSummary {
+    let (sown, germinated) = pack.germinations.take().unwrap_or_default().iter().fold(
+        (0, 0),
+        |(sown, germinated), germination| {
+            (sown + germination.sown, germinated + germination.germinated)
+        },
+    );
+    SeedPackSummary {
+        pack,
+        sown,
+        germinated,
+        germination_rate: germination_rate(sown, germinated),
+    }
+}
+
+fn validate_counts(count: i32, remaining: i32) -> Result<(), ErrorCode> {
+    if remaining > count {
+        return Err(ErrorCode::BADREQUEST(
+            "Remaining seeds must not exceed the pack size".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[doc = "Filter on seed packs of genetics of the organization"]
+fn in_organization(organization_id: &str) -> seed_pack::WhereParam {
+    seed_pack::genetic::is(vec![genetic::organization_id::equals(
+        organization_id.to_string(),
+    )])
+}
+
+pub async fn get_seed_packs(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    query: SeedPackQuery,
+) -> Result<Vec<SeedPackSummary>, ErrorCode> {
+    let mut filter = vec![in_organization(organization_id)];
+    if let Some(genetic_id) = query.genetic_id {
+        filter.push(seed_pack::genetic_id::equals(genetic_id));
+    }
+    if query.available == Some(true) {
+        filter.push(seed_pack::remaining::gt(0));
+    }
+
+    match data
+        .seed_pack()
+        .find_many(filter)
+        .with(seed_pack::genetic::fetch())
+        .with(seed_pack::germinations::fetch(vec![]))
+        .order_by(seed_pack::created_at::order(Direction::Desc))
+        .exec()
+        .timed("seed_pack", "find_many")
+        .await
+    {
+        Ok(packs) => Ok(packs.into_iter().map(summarize).collect()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_seed_pack_by_id(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<SeedPackSummary, ErrorCode> {
+    match data
+        .seed_pack()
+        .find_first(vec![
+            seed_pack::id::equals(id),
+            in_organization(organization_id),
+        ])
+        .with(seed_pack::genetic::fetch())
+        .with(seed_pack::location::fetch())
+        .with(
+            seed_pack::germinations::fetch(vec![])
+                .order_by(seed_germination::created_at::order(Direction::Desc)),
+        )
+        .exec()
+        .timed("seed_pack", "find_first")
+        .await
+    {
+        Ok(Some(pack)) => Ok(summarize(pack)),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_seed_pack(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    pack: SeedPack,
+) -> Result<seed_pack::Data, ErrorCode> {
+    let mut validator = Validator::default();
+    validator.required("geneticId", &pack.genetic_id, "Genetic ID is required");
+    validator.required("count", &pack.count, "Seed count is required");
+    pack.check(&mut validator);
+    validator.finish()?;
+    let genetic_id = pack.genetic_id.unwrap();
+    let count = pack.count.unwrap();
+    let remaining = pack.remaining.unwrap_or(count);
+    validate_counts(count, remaining)?;
+    get_genetic_by_id(data, organization_id, genetic_id.clone()).await?;
+    if let Some(Some(location_id)) = &pack.location_id {
+        check_locations(data, organization_id, &[location_id.clone()]).await?;
+    }
+
+    let mut params = vec![
+        seed_pack::label::set(pack.label.flatten()),
+        seed_pack::source::set(pack.source.flatten()),
+        seed_pack::purchased_at::set(pack.purchased_at.flatten()),
+        seed_pack::location_id::set(pack.location_id.flatten()),
+        seed_pack::note::set(pack.note.flatten()),
+    ];
+    if let Some(seed_type) = pack.seed_type {
+        params.push(seed_pack::seed_type::set(seed_type));
+    }
+
+    match data
+        .seed_pack()
+        .create_unchecked(genetic_id, count, remaining, params)
+        .exec()
+        .timed("seed_pack", "create_unchecked")
+        .await
+    {
+        Ok(pack) => Ok(pack),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn edit_seed_pack(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    pack: SeedPack,
+) -> Result<seed_pack::Data, ErrorCode> {
+    pack.validate()?;
+    let current = get_seed_pack_by_id(data, organization_id, id.clone())
+        .await?
+        .pack;
+    if let Some(genetic_id) = &pack.genetic_id {
+        get_genetic_by_id(data, organization_id, genetic_id.clone()).await?;
+    }
+    if let Some(Some(location_id)) = &pack.location_id {
+        check_locations(data, organization_id, &[location_id.clone()]).await?;
+    }
+    validate_counts(
+        pack.count.unwrap_or(current.count),
+        pack.remaining.unwrap_or(current.remaining),
+    )?;
+
+    match data
+        .seed_pack()
+        .update_unchecked(seed_pack::id::equals(id), pack.to_params())
+        .exec()
+        .timed("seed_pack", "update_unchecked")
+        .await
+    {
+        Ok(pack) => Ok(pack),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_seed_pack(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .seed_pack()
+        .delete_many(vec![
+            seed_pack::id::equals(id),
+            in_organization(organization_id),
+        ])
+        .exec()
+        .timed("seed_pack", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Take seeds from the pack and create a SEEDLING plant for every seed that
+germinated, as long as the plant limits allow them. The pack is only decremented when
+enough seeds are left, so concurrent germinations can not take the same seeds."]
+pub async fn germinate_seeds(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    request: GerminateRequest,
+    user_id: String,
+) -> Result<GerminationResult, ErrorCode> {
+    let germinated = request.germinated.unwrap_or(request.count);
+    if request.count < 1 {
+        return Err(ErrorCode::BADREQUEST(
+            "At least 1 seed has to be germinated".to_string(),
+        ));
+    }
+    if !(0..=request.count).contains(&germinated) {
+        return Err(ErrorCode::BADREQUEST(
+            "Germinated seeds must be between 0 and the sown seeds".to_string(),
+        ));
+    }
+    if let Some(batch_id) = &request.batch_id {
+        check_batches(data, organization_id, &[batch_id.clone()]).await?;
+    }
+    if let Some(location_id) = &request.location_id {
+        check_locations(data, organization_id, &[location_id.clone()]).await?;
+    }
+
+    let organization_id = organization_id.to_string();
+    data._transaction()
+        .run(|tx| async move {
+            let taken = tx
+                .seed_pack()
+                .update_many(
+                    vec![
+                        seed_pack::id::equals(id.clone()),
+                        in_organization(&organization_id),
+                        seed_pack::remaining::gte(request.count),
+                    ],
+                    vec![seed_pack::remaining::decrement(request.count)],
+                )
+                .exec()
+                .timed("seed_pack", "update_many")
+                .await?;
+            let pack = match tx
+                .seed_pack()
+                .find_first(vec![
+                    seed_pack::id::equals(id),
+                    in_organization(&organization_id),
+                ])
+                .exec()
+                .timed("seed_pack", "find_first")
+                .await?
+            {
+                Some(pack) => pack,
+                None => return Err(ErrorCode::DATABASE002),
+            };
+            if taken == 0 {
+                return Err(ErrorCode::BADREQUEST(format!(
+                    "Only {} seeds left in the pack",
+                    pack.remaining
+                )));
+            }
+
+            let germination = tx
+                .seed_germination()
+                .create_unchecked(
+                    pack.id.clone(),
+                    request.count,
+                    germinated,
+                    user_id.clone(),
+                    vec![],
+                )
+                .exec()
+                .timed("seed_germination", "create_unchecked")
+                .await?;
+
+            let mut plants = vec![];
+            for _ in 0..germinated {
+                let name = naming::generate_plant_name(
+                    &tx,
+                    &organization_id,
+                    NamingRequest {
+                        genetic_id: pack.genetic_id.clone(),
+                        batch_id: request.batch_id.clone(),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                let plant = tx
+                    .plant()
+                    .create_unchecked(
+                        name,
+                        pack.genetic_id.clone(),
+                        organization_id.clone(),
+                        vec![
+                            plant::stage::set(PlantStage::Seedling),
+                            plant::seed_pack_id::set(Some(pack.id.clone())),
+                            plant::germination_id::set(Some(germination.id.clone())),
+                            plant::batch_id::set(request.batch_id.clone()),
+                            plant::location_id::set(request.location_id.clone()),
+                        ],
+                    )
+                    .exec()
+                    .timed("plant", "create_unchecked")
+                    .await?;
+                tx.plant_history()
+                    .create_unchecked(
+                        plant.id.clone(),
+                        format!(
+                            "Germinated from seed pack {}",
+                            pack.label.as_deref().unwrap_or(&pack.id)
+                        ),
+                        user_id.clone(),
+                        organization_id.clone(),
+                        vec![plant_history::stage::set(Some(PlantStage::Seedling))],
+                    )
+                    .exec()
+                    .timed("plant_history", "create_unchecked")
+                    .await?;
+                plants.push(plant);
+            }
+            check_plant_limits(
+                &tx,
+                &organization_id,
+                None,
+                Placement {
+                    location_id: request.location_id.as_deref(),
+                    stage: PlantStage::Seedling,
+                },
+            )
+            .await?;
+
+            Ok(GerminationResult {
+                germination,
+                plants,
+            })
+        })
+        .await
+}
@@ -0,0 +1,614 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::{env, time};
+
+use actix_web::web;
+use chrono::{Duration, Utc};
+use prisma_client_rust::{
+    chrono::{DateTime, FixedOffset},
+    Direction,
+};
+
+use crate::{
+    model::{
+        dto::task::{TaskQuery, TaskRequest, TaskTemplateRequest, TaskUpdateRequest},
+        error::ErrorCode,
+        validation::Validate,
+    },
+    prisma::{
+        batch, location, organization, plant, task, task_template, user, PlantStage, PrismaClient,
+        TaskStatus,
+    },
+    service::{
+        batch::check_batches, location::check_locations, metrics::TimedQuery, plant::check_plants,
+    },
+};
+
+#[doc = "Start of the next day in UTC, everything due before is due today"]
+pub fn end_of_today() -> DateTime<FixedOffset> {
+    (Utc::now().date_naive() + Duration::days(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .fixed_offset()
+}
+
+#[doc = "Filter on the linked plants, plants of other organizations are left out even
+when a task was linked to them"]
+fn organization_plants(organization_id: &str) -> Vec<plant::WhereParam> {
+    vec![plant::organization_id::equals(organization_id.to_string())]
+}
+
+pub async fn get_tasks(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    query: TaskQuery,
+) -> Result<Vec<task::Data>, ErrorCode> {
+    let mut filter = vec![task::organization_id::equals(organization_id.to_string())];
+    if let Some(status) = query.status {
+        filter.push(task::status::equals(status));
+    }
+    if let Some(assignee_id) = query.assignee_id {
+        filter.push(task::assignee_id::equals(Some(assignee_id)));
+    }
+    if let Some(plant_id) = query.plant_id {
+        filter.push(task::plants::some(vec![plant::id::equals(plant_id)]));
+    }
+
+    match data
+        .task()
+        .find_many(filter)
+        .order_by(task::due_date::order(Direction::Asc))
+        .with(task::assignee::fetch())
+        .with(task::plants::fetch(organization_plants(organization_id)))
+        .exec()
+        .timed("task", "find_many")
+        .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Open tasks of the user which are due today or overdue"]
+pub async fn get_my_tasks_today(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    user_id: String,
+) -> Result<Vec<task::Data>, ErrorCode> {
+    match data
+        .task()
+        .find_many(vec![
+            task::organization_id::equals(organization_id.to_string()),
+            task::assignee_id::equals(Some(user_id)),
+            task::status::in_vec(vec![TaskStatus::Open, TaskStatus::InProgress]),
+            task::due_date::lt(end_of_today()),
+        ])
+        .order_by(task::due_date::order(Direction::Asc))
+        .with(task::plants::fetch(organization_plants(organization_id)))
+        .with(task::batches::fetch(vec![]))
+        .with(task::locations::fetch(vec![]))
+        .exec()
+        .timed("task", "find_many")
+        .await
+    {
+        Ok(tasks) => Ok(tasks),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_task_by_id(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<task::Data, ErrorCode> {
+    match data
+        .task()
+        .find_first(vec![
+            task::id::equals(id),
+            task::organization_id::equals(organization_id.to_string()),
+        ])
+        .with(task::assignee::fetch())
+        .with(task::created_by::fetch())
+        .with(task::plants::fetch(organization_plants(organization_id)))
+        .with(task::batches::fetch(vec![]))
+        .with(task::locations::fetch(vec![]))
+        .exec()
+        .timed("task", "find_first")
+        .await
+    {
+        Ok(Some(task)) => Ok(task),
+        Ok(None) => Err(ErrorCode::DATABASE002),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_task(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    task: TaskRequest,
+    created_by: String,
+) -> Result<task::Data, ErrorCode> {
+    task.validate()?;
+    check_plants(data, organization_id, &task.plant_ids).await?;
+    check_batches(data, organization_id, &task.batch_ids).await?;
+    check_locations(data, organization_id, &task.location_ids).await?;
+
+    let mut params = vec![
+        task::description::set(task.description),
+        task::created_by::connect(user::id::equals(created_by)),
+        task::plants::connect(task.plant_ids.into_iter().map(plant::id::equals).collect()),
+        task::batches::connect(task.batch_ids.into_iter().map(batch::id::equals).collect()),
+        task::locations::connect(
+            task.location_ids
+                .into_iter()
+                .map(location::id::equals)
+                .collect(),
+        ),
+    ];
+    if let Some(assignee_id) = task.assignee_id {
+        params.push(task::assignee::connect(user::id::equals(assignee_id)));
+    }
+
+    match data
+        .task()
+        .create(
+            task.title,
+            task.due_date,
+            organization::id::equals(organization_id.to_string()),
+            params,
+        )
+        .exec()
+        .timed("task", "create")
+        .await
+    {
+        Ok(task) => Ok(task),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn edit_task(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+    task: TaskUpdateRequest,
+) -> Result<task::Data, ErrorCode> {
+    task.validate()?;
+    get_task_by_id(data, organization_id, id.clone()).await?;
+    if let Some(plant_ids) = &task.plant_ids {
+        check_plants(data, organization_id, plant_ids).await?;
+    }
+    if let Some(batch_ids) = &task.batch_ids {
+        check_batches(data, organization_id, batch_ids).await?;
+    }
+    if let Some(location_ids) = &task.location_ids {
+        check_locations(data, organization_id, location_ids).await?;
+    }
+
+    let mut params = vec![];
+    if let Some(title) = task.title {
+        params.push(task::title::set(title));
+    }
+    if let Some(description) = task.description {
+        params.push(task::description::set(Some(description)));
+    }
+    if let Some(due_date) = task.due_date {
+        params.push(task::due_date::set(due_date));
+    }
+    if let Some(status) = task.status {
+        let completed_at = match status {
+            TaskStatus::Done => Some(Utc::now().fixed_offset()),
+            _ => None,
+        };
+        params.push(task::status::set(status));
+        params.push(task::completed_at::set(completed_at));
+    }
+    match task.assignee_id {
+        Some(assignee_id) if assignee_id.is_empty() => params.push(task::assignee::disconnect()),
+        Some(assignee_id) => params.push(task::assignee::connect(user::id::equals(assignee_id))),
+        None => (),
+    }
+    if let Some(plant_ids) = task.plant_ids {
+        params.push(task::plants::set(
+            plant_ids.into_iter().map(plant::id::equals).collect(),
+        ));
+    }
+    if let Some(batch_ids) = task.batch_ids {
+        params.push(task::batches::set(
+            batch_ids.into_iter().map(batch::id::equals).collect(),
+        ));
+    }
+    if let Some(location_ids) = task.location_ids {
+        params.push(task::locations::set(
+            location_ids.into_iter().map(location::id::equals).collect(),
+        ));
+    }
+
+    match data
+        .task()
+        .update(task::id::equals(id), params)
+        .exec()
+        .timed("task", "update")
+        .await
+    {
+        Ok(task) => Ok(task),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_task(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .task()
+        .delete_many(vec![
+            task::id::equals(id),
+            task::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("task", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn get_task_templates(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+) -> Result<Vec<task_template::Data>, ErrorCode> {
+    match data
+        .task_template()
+        .find_many(vec![task_template::organization_id::equals(
+            organization_id.to_string(),
+        )])
+        .with(task_template::plants::fetch(organization_plants(
+            organization_id,
+        )))
+        .with(task_template::batches::fetch(vec![]))
+        .with(task_template::locations::fetch(vec![]))
+        .exec()
+        .timed("task_template", "find_many")
+        .await
+    {
+        Ok(templates) => Ok(templates),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn create_task_template(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    template: TaskTemplateRequest,
+) -> Result<task_template::Data, ErrorCode> {
+    template.validate()?;
+    check_plants(data, organization_id, &template.plant_ids).await?;
+    check_batches(data, organization_id, &template.batch_ids).await?;
+    check_locations(data, organization_id, &template.location_ids).await?;
+    if template.interval_days.is_none() && template.trigger_stage.is_none() {
+        return Err(ErrorCode::BADREQUEST(
+            "Either an interval or a trigger stage is required".to_string(),
+        ));
+    }
+
+    let next_due_date = template
+        .interval_days
+        .map(|_| template.start_date.unwrap_or(Utc::now().fixed_offset()));
+
+    let mut params = vec![
+        task_template::description::set(template.description),
+        task_template::interval_days::set(template.interval_days),
+        task_template::next_due_date::set(next_due_date),
+        task_template::trigger_stage::set(template.trigger_stage),
+        task_template::offset_days::set(template.offset_days),
+        task_template::plants::connect(
+            template
+                .plant_ids
+                .into_iter()
+                .map(plant::id::equals)
+                .collect(),
+        ),
+        task_template::batches::connect(
+            template
+                .batch_ids
+                .into_iter()
+                .map(batch::id::equals)
+                .collect(),
+        ),
+        task_template::locations::connect(
+            template
+                .location_ids
+                .into_iter()
+                .map(location::id::equals)
+                .collect(),
+        ),
+    ];
+    if let Some(assignee_id) = template.assignee_id {
+        params.push(task_template::assignee::connect(user::id::equals(
+            assignee_id,
+        )));
+    }
+
+    match data
+        .task_template()
+        .create(
+            template.title,
+            organization::id::equals(organization_id.to_string()),
+            params,
+        )
+        .exec()
+        .timed("task_template", "create")
+        .await
+    {
+        Ok(template) => Ok(template),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn delete_task_template(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<(), ErrorCode> {
+    match data
+        .task_template()
+        .delete_many(vec![
+            task_template::id::equals(id),
+            task_template::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("task_template", "delete_many")
+        .await
+    {
+        Ok(0) => Err(ErrorCode::DATABASE002),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[doc = "Due date of the task a recurring template creates now and its next due date.
+Occurrences missed before today are skipped, so they collapse into one task."]
+pub fn roll_forward(
+    due_date: DateTime<FixedOffset>,
+    interval_days: i32,
+    end_of_today: DateTime<FixedOffset>,
+) -> (DateTime<FixedOffset>, DateTime<FixedOffset>) {
+    let interval = Duration::days(interval_days.max(1) as i64);
+    let mut due_date = due_date;
+    while due_date + interval < end_of_today {
+        due_date += interval;
+    }
+    (due_date, due_date + interval)
+}
+
+#[doc = "Minutes between two runs of the recurring task materialization, set by `TASK_INTERVAL`."]
+pub fn task_interval() -> u64 {
+    env::var("TASK_INTERVAL")
+        .ok()
+        .and_then(|minutes| minutes.parse().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(15)
+}
+
+#[doc = "Materialize recurring tasks at startup and every `task_interval` minutes.
+A failed run is logged and retried on the next tick. Spawned on the tokio runtime,
+the server runs outside of an actix system."]
+pub fn spawn_task_scheduler(data: web::Data<PrismaClient>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(time::Duration::from_secs(task_interval() * 60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = materialize_recurring_tasks(&data).await {
+                tracing::warn!(error = ?e, "Materializing recurring tasks failed");
+            }
+        }
+    });
+}
+
+#[doc = "Create the tasks of recurring templates which became due.
+Missed occurrences are collapsed into a single task. The template is claimed by
+moving `nextDueDate` conditionally, so concurrent callers never create duplicates."]
+pub async fn materialize_recurring_tasks(data: &PrismaClient) -> Result<(), ErrorCode> {
+    let end_of_today = end_of_today();
+    let templates = match data
+        .task_template()
+        .find_many(vec![
+            task_template::active::equals(true),
+            task_template::next_due_date::lt(end_of_today),
+        ])
+        .with(task_template::plants::fetch(vec![]))
+        .with(task_template::batches::fetch(vec![]))
+        .with(task_template::locations::fetch(vec![]))
+        .exec()
+        .timed("task_template", "find_many")
+        .await
+    {
+        Ok(templates) => templates,
+        Err(e) => return Err(e.into()),
+    };
+
+    for template in templates {
+        let (Some(previous), Some(interval_days)) =
+            (template.next_due_date, template.interval_days)
+        else {
+            continue;
+        };
+        let (due_date, next_due_date) = roll_forward(previous, interval_days, end_of_today);
+        let previous_due_date = template.next_due_date;
+
+        data._transaction()
+            .run(|tx| async move {
+                let claimed = tx
+                    .task_template()
+                    .update_many(
+                        vec![
+                            task_template::id::equals(template.id.clone()),
+                            task_template::next_due_date::equals(previous_due_date),
+                        ],
+                        vec![task_template::next_due_date::set(Some(next_due_date))],
+                    )
+                    .exec()
+                    .timed("task_template", "update_many")
+                    .await?;
+                if claimed == 0 {
+                    return Ok(());
+                }
+
+                create_task_from_template(&tx, &template, due_date, None, None).await?;
+                Ok::<(), ErrorCode>(())
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[doc = "Plants, batches and locations a template is limited to"]
+#[derive(Debug, Default)]
+pub struct TemplateScope {
+    pub plant_ids: Vec<String>,
+    pub batch_ids: Vec<String>,
+    pub location_ids: Vec<String>,
+}
+
+impl TemplateScope {
+    pub fn of(template: &task_template::Data) -> Self {
+        Self {
+            plant_ids: template
+                .plants
+                .iter()
+                .flatten()
+                .map(|plant| plant.id.clone())
+                .collect(),
+            batch_ids: template
+                .batches
+                .iter()
+                .flatten()
+                .map(|batch| batch.id.clone())
+                .collect(),
+            location_ids: template
+                .locations
+                .iter()
+                .flatten()
+                .map(|location| location.id.clone())
+                .collect(),
+        }
+    }
+
+    #[doc = "Whether a plant is covered, an empty scope covers every plant"]
+    pub fn covers(
+        &self,
+        plant_id: &str,
+        batch_id: Option<&str>,
+        location_id: Option<&str>,
+    ) -> bool {
+        let contains = |ids: &[String], id: Option<&str>| {
+            id.is_some_and(|id| ids.iter().any(|other| other == id))
+        };
+        (self.plant_ids.is_empty() && self.batch_ids.is_empty() && self.location_ids.is_empty())
+            || contains(&self.plant_ids, Some(plant_id))
+            || contains(&self.batch_ids, batch_id)
+            || contains(&self.location_ids, location_id)
+    }
+}
+
+#[doc = "Create the tasks of the templates of the plant's organization triggered by it
+entering `stage`. Templates limited to other plants, batches or locations are skipped.
+Runs inside the transaction of the stage change."]
+pub async fn create_stage_tasks(
+    tx: &PrismaClient,
+    plant: &plant::Data,
+    stage: PlantStage,
+    user_id: &str,
+) -> Result<Vec<task::Data>, ErrorCode> {
+    let templates = tx
+        .task_template()
+        .find_many(vec![
+            task_template::organization_id::equals(plant.organization_id.clone()),
+            task_template::active::equals(true),
+            task_template::trigger_stage::equals(Some(stage)),
+        ])
+        .with(task_template::plants::fetch(vec![]))
+        .with(task_template::batches::fetch(vec![]))
+        .with(task_template::locations::fetch(vec![]))
+        .exec()
+        .timed("task_template", "find_many")
+        .await?;
+
+    let mut tasks = Vec::with_capacity(templates.len());
+    for template in templates {
+        if !TemplateScope::of(&template).covers(
+            &plant.id,
+            plant.batch_id.as_deref(),
+            plant.location_id.as_deref(),
+        ) {
+            continue;
+        }
+        let due_date = Utc::now().fixed_offset() + Duration::days(template.offset_days as i64);
+        tasks.push(
+            create_task_from_template(
+                tx,
+                &template,
+                due_date,
+                Some(plant.id.clone()),
+                Some(user_id.to_string()),
+            )
+            .await?,
+        );
+    }
+    Ok(tasks)
+}
+
+async fn create_task_from_template(
+    tx: &PrismaClient,
+    template: &task_template::Data,
+    due_date: DateTime<FixedOffset>,
+    plant_id: Option<String>,
+    created_by: Option<String>,
+) -> Result<task::Data, ErrorCode> {
+    let scope = TemplateScope::of(template);
+    let mut plant_ids = scope.plant_ids;
+    if let Some(plant_id) = plant_id {
+        if !plant_ids.contains(&plant_id) {
+            plant_ids.push(plant_id);
+        }
+    }
+
+    let mut params = vec![
+        task::description::set(template.description.clone()),
+        task::template::connect(task_template::id::equals(template.id.clone())),
+        task::plants::connect(plant_ids.into_iter().map(plant::id::equals).collect()),
+        task::batches::connect(scope.batch_ids.into_iter().map(batch::id::equals).collect()),
+        task::locations::connect(
+            scope
+                .location_ids
+                .into_iter()
+                .map(location::id::equals)
+                .collect(),
+        ),
+    ];
+    if let Some(assignee_id) = template.assignee_id.clone() {
+        params.push(task::assignee::connect(user::id::equals(assignee_id)));
+    }
+    if let Some(created_by) = created_by {
+        params.push(task::created_by::connect(user::id::equals(created_by)));
+    }
+
+    Ok(tx
+        .task()
+        .create(
+            template.title.clone(),
+            due_date,
+            organization::id::equals(template.organization_id.clone()),
+            params,
+        )
+        .exec()
+        .timed("task", "create")
+        .await?)
+}
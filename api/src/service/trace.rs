@@ -0,0 +1,545 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::collections::HashSet;
+
+use actix_web::web;
+use chrono::{DateTime, FixedOffset, Utc};
+use prisma_client_rust::Direction;
+
+use crate::{
+    model::{
+        dto::trace::{
+            PlantTrace, TraceAncestor, TraceDestruction, TraceEvent, TraceGenetic, TraceHarvest,
+            TraceLot, TraceReport, TraceSale,
+        },
+        error::ErrorCode,
+    },
+    prisma::{batch, plant, PrismaClient},
+    service::{lot::get_lot_by_id, metrics::TimedQuery, plant::stage_name, report::ReportLine},
+};
+
+#[doc = "Mother chains longer than this are cut, protecting against broken data"]
+const MAX_MOTHERS: usize = 50;
+
+plant::include!(plant_trace {
+    genetic
+    batch
+    location
+    seed_pack
+    plant_history: include { user: select { display_name } }
+    harvests: include {
+        user: select { display_name }
+        lots: include { lot: select { code } }
+    }
+    destruction: include {
+        recorded_by: select { display_name }
+        witnesses: select { display_name }
+    }
+});
+
+fn format_time(at: &DateTime<FixedOffset>) -> String {
+    at.format("%Y-%m-%d %H:%M").to_string()
+}
+
+fn format_weight(weight: Option<f64>) -> String {
+    match weight {
+        Some(weight) => format!("{} g", weight),
+        None => "-".to_string(),
+    }
+}
+
+#[doc = "Sorted display names of everyone who recorded, harvested, witnessed, packaged
+or sold something"]
+pub fn involved_users(plants: &[PlantTrace], lot: Option<&TraceLot>) -> Vec<String> {
+    let lot_users = lot.into_iter().flat_map(|lot| {
+        std::iter::once(lot.packaged_by.clone())
+            .chain(lot.sales.iter().map(|sale| sale.user.clone()))
+    });
+    let mut users: Vec<String> = plants
+        .iter()
+        .flat_map(|plant| {
+            let events = plant.events.iter().map(|event| event.user.clone());
+            let harvests = plant.harvests.iter().map(|harvest| harvest.user.clone());
+            let destruction = plant.destruction.iter().flat_map(|destruction| {
+                std::iter::once(destruction.recorded_by.clone())
+                    .chain(destruction.witnesses.iter().cloned())
+            });
+            events
+                .chain(harvests)
+                .chain(destruction)
+                .collect::<Vec<String>>()
+        })
+        .chain(lot_users)
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect();
+    users.sort();
+    users
+}
+
+#[doc = "Mother chain as text, e.g. `M1 (Gelato) < M0 (Gelato)`"]
+pub fn mother_chain(mothers: &[TraceAncestor]) -> String {
+    mothers
+        .iter()
+        .map(|mother| format!("{} ({})", mother.name, mother.genetic))
+        .collect::<Vec<String>>()
+        .join(" < ")
+}
+
+#[doc = "All events of a plant as `(time, kind, description, stage, user)`, oldest first"]
+fn plant_rows(
+    plant: &PlantTrace,
+) -> Vec<(DateTime<FixedOffset>, &'static str, String, String, String)> {
+    let mut rows = vec![];
+    for event in &plant.events {
+        rows.push((
+            event.at,
+            "HISTORY",
+            event.action.clone(),
+            event.stage.map(stage_name).unwrap_or_default(),
+            event.user.clone(),
+        ));
+    }
+    for harvest in &plant.harvests {
+        let mut description = format!(
+            "Wet {}, dry {}",
+            format_weight(harvest.wet_weight),
+            format_weight(harvest.dry_weight)
+        );
+        if !harvest.lots.is_empty() {
+            description.push_str(&format!(", packaged into {}", harvest.lots.join(", ")));
+        }
+        rows.push((
+            harvest.harvested_at,
+            "HARVEST",
+            description,
+            String::new(),
+            harvest.user.clone(),
+        ));
+    }
+    if let Some(destruction) = &plant.destruction {
+        let reason = format!("{:?}", destruction.reason).to_uppercase();
+        rows.push((
+            destruction.destroyed_at,
+            "DESTRUCTION",
+            format!(
+                "{} g, {}, {}, witnessed by {}",
+                destruction.weight,
+                reason,
+                destruction.method,
+                destruction.witnesses.join(", ")
+            ),
+            String::new(),
+            destruction.recorded_by.clone(),
+        ));
+    }
+    rows.sort_by_key(|row| row.0);
+    rows
+}
+
+#[doc = "Packaging and sales of a lot as `(time, kind, description, user)`, oldest first"]
+fn lot_rows(lot: &TraceLot) -> Vec<(DateTime<FixedOffset>, &'static str, String, String)> {
+    let mut rows = vec![(
+        lot.packaged_at,
+        "PACKAGING",
+        format!("Lot {}: {} g packaged", lot.code, lot.weight),
+        lot.packaged_by.clone(),
+    )];
+    for sale in &lot.sales {
+        let mut description = format!("Lot {}: {} g sold", lot.code, sale.weight);
+        if let Some(customer) = &sale.customer {
+            description.push_str(&format!(" to {}", customer));
+        }
+        if let Some(reference) = &sale.reference {
+            description.push_str(&format!(", reference {}", reference));
+        }
+        rows.push((sale.sold_at, "SALE", description, sale.user.clone()));
+    }
+    rows.sort_by_key(|row| row.0);
+    rows
+}
+
+#[doc = "Render the report as CSV with one row per event, lot events have no plant"]
+pub fn trace_csv(report: &TraceReport) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record([
+            "plantId",
+            "plantName",
+            "genetic",
+            "batch",
+            "mothers",
+            "at",
+            "kind",
+            "description",
+            "stage",
+            "user",
+        ])
+        .map_err(|e| e.to_string())?;
+    for plant in &report.plants {
+        for (at, kind, description, stage, user) in plant_rows(plant) {
+            writer
+                .write_record([
+                    plant.id.clone(),
+                    plant.name.clone(),
+                    plant.genetic.name.clone(),
+                    plant.batch.clone().unwrap_or_default(),
+                    mother_chain(&plant.mothers),
+                    at.to_rfc3339(),
+                    kind.to_string(),
+                    description,
+                    stage,
+                    user,
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    for (at, kind, description, user) in report.lot.iter().flat_map(lot_rows) {
+        writer
+            .write_record([
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                at.to_rfc3339(),
+                kind.to_string(),
+                description,
+                String::new(),
+                user,
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+#[doc = "Lines of the PDF report"]
+pub fn trace_lines(report: &TraceReport) -> Vec<ReportLine> {
+    let mut lines = vec![
+        ReportLine::Heading(format!(
+            "Traceability report: {} {}",
+            report.subject, report.subject_name
+        )),
+        ReportLine::Text(format!("Generated {}", format_time(&report.generated_at))),
+    ];
+
+    if let Some(lot) = &report.lot {
+        lines.push(ReportLine::Heading(format!("Lot {}", lot.code)));
+        lines.push(ReportLine::Text(format!(
+            "{} g packaged, {} g remaining",
+            lot.weight, lot.remaining
+        )));
+        for (at, kind, description, user) in lot_rows(lot) {
+            lines.push(ReportLine::Text(format!(
+                "{}  {}: {} ({})",
+                format_time(&at),
+                kind,
+                description,
+                user
+            )));
+        }
+    }
+
+    for plant in &report.plants {
+        lines.push(ReportLine::Heading(format!(
+            "Plant {} ({})",
+            plant.name,
+            stage_name(plant.stage)
+        )));
+        let mut genetic = format!("Genetic: {}", plant.genetic.name);
+        if let Some(code) = &plant.genetic.code {
+            genetic.push_str(&format!(" ({})", code));
+        }
+        if let Some(breeder) = &plant.genetic.breeder {
+            genetic.push_str(&format!(", bred by {}", breeder));
+        }
+        lines.push(ReportLine::Text(genetic));
+        lines.push(ReportLine::Text(format!(
+            "Created {}, batch {}, location {}, seed pack {}",
+            format_time(&plant.created_at),
+            plant.batch.as_deref().unwrap_or("-"),
+            plant.location.as_deref().unwrap_or("-"),
+            plant.seed_pack.as_deref().unwrap_or("-")
+        )));
+        if !plant.mothers.is_empty() {
+            lines.push(ReportLine::Text(format!(
+                "Mother chain: {}",
+                mother_chain(&plant.mothers)
+            )));
+        }
+        for (at, kind, description, stage, user) in plant_rows(plant) {
+            let stage = match stage.is_empty() {
+                true => String::new(),
+                false => format!(" [{}]", stage),
+            };
+            lines.push(ReportLine::Text(format!(
+                "{}  {}{}: {} ({})",
+                format_time(&at),
+                kind,
+                stage,
+                description,
+                user
+            )));
+        }
+    }
+
+    lines.push(ReportLine::Heading("Users involved".to_string()));
+    lines.push(ReportLine::Text(report.users.join(", ")));
+    lines
+}
+
+#[doc = "Mother chain of the plant, starting with the direct mother"]
+async fn get_mothers(
+    data: &PrismaClient,
+    organization_id: &str,
+    mother_id: Option<String>,
+) -> Result<Vec<TraceAncestor>, ErrorCode> {
+    let mut mothers: Vec<TraceAncestor> = vec![];
+    let mut next = mother_id;
+    while let Some(id) = next {
+        if mothers.len() >= MAX_MOTHERS || mothers.iter().any(|mother| mother.id == id) {
+            break;
+        }
+        let mother = match data
+            .plant()
+            .find_first(vec![
+                plant::id::equals(id),
+                plant::organization_id::equals(organization_id.to_string()),
+            ])
+            .with(plant::genetic::fetch())
+            .exec()
+            .timed("plant", "find_first")
+            .await
+        {
+            Ok(Some(mother)) => mother,
+            Ok(None) => break,
+            Err(e) => return Err(e.into()),
+        };
+        mothers.push(TraceAncestor {
+            id: mother.id.clone(),
+            name: mother.name.clone(),
+            genetic: mother
+                .genetic()
+                .map(|genetic| genetic.name.clone())
+                .unwrap_or_default(),
+        });
+        next = mother.mother_id;
+    }
+    Ok(mothers)
+}
+
+async fn trace_plant(
+    data: &PrismaClient,
+    plant: plant_trace::Data,
+) -> Result<PlantTrace, ErrorCode> {
+    let mut events: Vec<TraceEvent> = plant
+        .plant_history
+        .into_iter()
+        .map(|history| TraceEvent {
+            at: history.created_at,
+            action: history.action,
+            stage: history.stage,
+            user: history.user.display_name,
+        })
+        .collect();
+    events.sort_by_key(|event| event.at);
+
+    Ok(PlantTrace {
+        mothers: get_mothers(data, &plant.organization_id, plant.mother_id).await?,
+        id: plant.id,
+        name: plant.name,
+        stage: plant.stage,
+        created_at: plant.created_at,
+        genetic: TraceGenetic {
+            id: plant.genetic.id,
+            name: plant.genetic.name,
+            code: plant.genetic.code,
+            breeder: plant.genetic.breeder,
+        },
+        batch: plant.batch.map(|batch| batch.name),
+        location: plant.location.map(|location| location.name),
+        seed_pack: plant.seed_pack.map(|pack| pack.label.unwrap_or(pack.id)),
+        events,
+        harvests: plant
+            .harvests
+            .into_iter()
+            .map(|harvest| TraceHarvest {
+                id: harvest.id,
+                harvested_at: harvest.harvested_at,
+                wet_weight: harvest.wet_weight,
+                dry_weight: harvest.dry_weight,
+                user: harvest.user.display_name,
+                lots: harvest
+                    .lots
+                    .into_iter()
+                    .map(|packaged| packaged.lot.code)
+                    .collect(),
+            })
+            .collect(),
+        destruction: plant.destruction.map(|destruction| TraceDestruction {
+            id: destruction.id,
+            destroyed_at: destruction.destroyed_at,
+            reason: destruction.reason,
+            method: destruction.method,
+            weight: destruction.weight,
+            recorded_by: destruction.recorded_by.display_name,
+            witnesses: destruction
+                .witnesses
+                .into_iter()
+                .map(|witness| witness.display_name)
+                .collect(),
+        }),
+    })
+}
+
+async fn build_report(
+    data: &PrismaClient,
+    subject: &str,
+    subject_id: String,
+    subject_name: String,
+    lot: Option<TraceLot>,
+    plants: Vec<plant_trace::Data>,
+) -> Result<TraceReport, ErrorCode> {
+    let mut traces = vec![];
+    for plant in plants {
+        traces.push(trace_plant(data, plant).await?);
+    }
+
+    Ok(TraceReport {
+        subject: subject.to_string(),
+        subject_id,
+        subject_name,
+        generated_at: Utc::now().fixed_offset(),
+        users: involved_users(&traces, lot.as_ref()),
+        lot,
+        plants: traces,
+    })
+}
+
+pub async fn get_plant_trace(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<TraceReport, ErrorCode> {
+    let plant = match data
+        .plant()
+        .find_first(vec![
+            plant::id::equals(id),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .include(plant_trace::include())
+        .exec()
+        .timed("plant", "find_first")
+        .await
+    {
+        Ok(Some(plant)) => plant,
+        Ok(None) => return Err(ErrorCode::DATABASE002),
+        Err(e) => return Err(e.into()),
+    };
+
+    build_report(
+        data,
+        "plant",
+        plant.id.clone(),
+        plant.name.clone(),
+        None,
+        vec![plant],
+    )
+    .await
+}
+
+#[doc = "Trace all plants of the organization in a batch, ordered by name"]
+pub async fn get_batch_trace(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<TraceReport, ErrorCode> {
+    let batch = match data
+        .batch()
+        .find_first(vec![
+            batch::id::equals(id.clone()),
+            batch::organization_id::equals(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("batch", "find_first")
+        .await
+    {
+        Ok(Some(batch)) => batch,
+        Ok(None) => return Err(ErrorCode::DATABASE002),
+        Err(e) => return Err(e.into()),
+    };
+    let plants = match data
+        .plant()
+        .find_many(vec![
+            plant::batch_id::equals(Some(id)),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .order_by(plant::name::order(Direction::Asc))
+        .include(plant_trace::include())
+        .exec()
+        .timed("plant", "find_many")
+        .await
+    {
+        Ok(plants) => plants,
+        Err(e) => return Err(e.into()),
+    };
+
+    build_report(data, "batch", batch.id, batch.name, None, plants).await
+}
+
+#[doc = "Trace a lot back to the plants its harvests came from"]
+pub async fn get_lot_trace(
+    data: &web::Data<PrismaClient>,
+    organization_id: &str,
+    id: String,
+) -> Result<TraceReport, ErrorCode> {
+    let lot = get_lot_by_id(data, organization_id, id).await?;
+    let plant_ids: Vec<String> = lot
+        .harvests
+        .iter()
+        .map(|packaged| packaged.harvest.plant.id.clone())
+        .collect();
+    let plants = match data
+        .plant()
+        .find_many(vec![
+            plant::id::in_vec(plant_ids),
+            plant::organization_id::equals(organization_id.to_string()),
+        ])
+        .order_by(plant::name::order(Direction::Asc))
+        .include(plant_trace::include())
+        .exec()
+        .timed("plant", "find_many")
+        .await
+    {
+        Ok(plants) => plants,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut sales: Vec<TraceSale> = lot
+        .sales
+        .into_iter()
+        .map(|sale| TraceSale {
+            id: sale.id,
+            sold_at: sale.sold_at,
+            weight: sale.weight,
+            customer: sale.customer,
+            reference: sale.reference,
+            user: sale.user.display_name,
+        })
+        .collect();
+    sales.sort_by_key(|sale| sale.sold_at);
+    let trace = TraceLot {
+        id: lot.id.clone(),
+        code: lot.code.clone(),
+        weight: lot.weight,
+        remaining: lot.remaining,
+        packaged_at: lot.packaged_at,
+        packaged_by: lot.packaged_by.display_name,
+        sales,
+    };
+
+    build_report(data, "lot", lot.id, lot.code, Some(trace), plants).await
+}
@@ -6,12 +6,57 @@ use std::collections::HashSet;
 
 use crate::model::dto::auth::{RegisterRequest, RoleRegisterRequest};
 use crate::model::error::ErrorCode;
+use crate::model::validation::Validate;
 use crate::prisma::{role, user, users_in_roles, PrismaClient};
+use crate::service::metrics::TimedQuery;
 use actix_web::web;
 use chrono::Utc;
 use prisma_client_rust::or;
 
 use super::authentication::{change_password, register_user};
+use super::organization::{is_admin, remove_member};
+
+#[doc = "Filter on users holding a role in the organization, others are not visible in it"]
+pub fn in_organization(organization_id: &str) -> user::WhereParam {
+    user::roles::some(vec![users_in_roles::organization_id::equals(
+        organization_id.to_string(),
+    )])
+}
+
+#[doc = "User of the organization, users outside of it are reported as not found"]
+pub async fn find_in_organization(
+    id: &str,
+    organization_id: &str,
+    data: &web::Data<PrismaClient>,
+) -> Result<user::Data, ErrorCode> {
+    data.user()
+        .find_first(vec![
+            user::id::equals(id.to_string()),
+            in_organization(organization_id),
+        ])
+        .exec()
+        .timed("user", "find_first")
+        .await?
+        .ok_or(ErrorCode::DATABASE002)
+}
+
+#[doc = "Whether the user also holds roles in organizations other than the given one"]
+async fn in_other_organizations(
+    id: &str,
+    organization_id: &str,
+    data: &web::Data<PrismaClient>,
+) -> Result<bool, ErrorCode> {
+    let roles = data
+        .users_in_roles()
+        .count(vec![
+            users_in_roles::user_id::equals(id.to_string()),
+            users_in_roles::organization_id::not(organization_id.to_string()),
+        ])
+        .exec()
+        .timed("users_in_roles", "count")
+        .await?;
+    Ok(roles > 0)
+}
 
 pub async fn find_by_identifier(
     identifier: &str,
@@ -23,6 +68,7 @@ pub async fn find_by_identifier(
             user::email::equals(identifier.to_string()),
         ]])
         .exec()
+        .timed("user", "find_first")
         .await
         .map_err(|_| ErrorCode::INTERNAL001)
 }
@@ -35,6 +81,7 @@ pub async fn update_last_login(id: &str, data: &web::Data<PrismaClient>) -> Resu
             vec![user::last_login::set(Some(Utc::now().fixed_offset()))],
         )
         .exec()
+        .timed("user", "update")
         .await
         .map_err(|_| ErrorCode::INTERNAL001)
     {
@@ -43,8 +90,10 @@ pub async fn update_last_login(id: &str, data: &web::Data<PrismaClient>) -> Resu
     }
 }
 
+#[doc = "Create a user, the requested and the default roles are assigned in the organization"]
 pub async fn create_new_user(
     assigner_id: String,
+    organization_id: &str,
     user: RegisterRequest,
     data: &web::Data<PrismaClient>,
 ) -> Result<user::Data, ErrorCode> {
@@ -59,6 +108,7 @@ pub async fn create_new_user(
         .role()
         .find_many(vec![role::is_default::equals(true)])
         .exec()
+        .timed("role", "find_many")
         .await
         .map_err(|_| ErrorCode::INTERNAL001)
     {
@@ -84,9 +134,11 @@ pub async fn create_new_user(
                     new_user.id.to_owned(),
                     item.role_id,
                     item.assigned_by,
+                    organization_id.to_string(),
                     vec![],
                 )
                 .exec()
+                .timed("users_in_roles", "create_unchecked")
                 .await
                 .map_err(|_| ErrorCode::INTERNAL001)
             {
@@ -99,6 +151,7 @@ pub async fn create_new_user(
         .user()
         .find_unique(user::id::equals(new_user.id))
         .exec()
+        .timed("user", "find_unique")
         .await
         .map_err(|_| ErrorCode::INTERNAL001)
     {
@@ -108,12 +161,27 @@ pub async fn create_new_user(
 }
 
 user::partial_unchecked!(UserUpdateData { display_name email });
+#[doc = "Edit a user of the organization, only admins of it may do so. The roles replace
+the ones the user holds in it. The name, email and password of accounts which also
+belong to other organizations are left to their owner."]
 pub async fn edit_user_by_id(
     id: &str,
     data: &web::Data<PrismaClient>,
     user: user::Data,
     assigner_id: String,
+    organization_id: &str,
 ) -> Result<user::Data, ErrorCode> {
+    user.validate()?;
+    if !is_admin(data, &assigner_id, organization_id).await? {
+        return Err(ErrorCode::ORG001);
+    }
+    let existing = find_in_organization(id, organization_id, data).await?;
+    let credentials_changed = !user.password.is_empty()
+        || user.email != existing.email
+        || user.display_name != existing.display_name;
+    if credentials_changed && in_other_organizations(id, organization_id, data).await? {
+        return Err(ErrorCode::ORG001);
+    }
     let mut user: user::Data = user.clone();
 
     if !user.password.is_empty() {
@@ -129,15 +197,17 @@ pub async fn edit_user_by_id(
 
     if !user_roles.is_empty() {
         let org_user_roles = match data
-            .user()
-            .find_unique(user::id::equals(id.to_string()))
-            .with(user::roles::fetch(vec![]))
-            .select(user::select!({ roles }))
+            .users_in_roles()
+            .find_many(vec![
+                users_in_roles::user_id::equals(id.to_string()),
+                users_in_roles::organization_id::equals(organization_id.to_string()),
+            ])
             .exec()
+            .timed("users_in_roles", "find_many")
             .await
             .map_err(|_| ErrorCode::INTERNAL001)
         {
-            Ok(u) => u.unwrap().roles,
+            Ok(roles) => roles,
             Err(e) => return Err(e),
         };
 
@@ -154,11 +224,19 @@ pub async fn edit_user_by_id(
             .filter(|item| !previous_items.contains(&item.role_id))
             .collect();
 
+        // Roles are given to the edited user, whoever the body names
         for item in added_roles {
             match data
                 .users_in_roles()
-                .create_unchecked(item.user_id, item.role_id, assigner_id.to_owned(), vec![])
+                .create_unchecked(
+                    id.to_string(),
+                    item.role_id,
+                    assigner_id.to_owned(),
+                    organization_id.to_string(),
+                    vec![],
+                )
                 .exec()
+                .timed("users_in_roles", "create_unchecked")
                 .await
                 .map_err(|_| ErrorCode::INTERNAL001)
             {
@@ -170,8 +248,13 @@ pub async fn edit_user_by_id(
         for item in deleted_roles {
             match data
                 .users_in_roles()
-                .delete(users_in_roles::user_id_role_id(item.user_id, item.role_id))
+                .delete(users_in_roles::user_id_role_id_organization_id(
+                    item.user_id,
+                    item.role_id,
+                    item.organization_id,
+                ))
                 .exec()
+                .timed("users_in_roles", "delete")
                 .await
                 .map_err(|_| ErrorCode::INTERNAL001)
             {
@@ -190,6 +273,7 @@ pub async fn edit_user_by_id(
         .user()
         .update_unchecked(user::id::equals(id.to_string()), user.to_params())
         .exec()
+        .timed("user", "update_unchecked")
         .await
         .map_err(|_| ErrorCode::INTERNAL001)
     {
@@ -197,3 +281,16 @@ pub async fn edit_user_by_id(
         Err(e) => Err(e),
     }
 }
+
+#[doc = "Remove a user from the organization, only admins of it may do so. The account
+itself is kept, it may belong to other organizations."]
+pub async fn delete_user_by_id(
+    id: &str,
+    organization_id: &str,
+    remover_id: &str,
+    data: &web::Data<PrismaClient>,
+) -> Result<user::Data, ErrorCode> {
+    let user = find_in_organization(id, organization_id, data).await?;
+    remove_member(data, organization_id, remover_id, id.to_string()).await?;
+    Ok(user)
+}
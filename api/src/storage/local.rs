@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use tokio::fs;
+
+use super::Storage;
+
+#[doc = "Storage backend writing into a directory of the local filesystem"]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    #[doc = "Resolve a key below the root, rejecting keys escaping it"]
+    fn path(&self, key: &str) -> Result<PathBuf, String> {
+        let relative = Path::new(key);
+        if key.is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(format!("Invalid storage key {key}"));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, bytes: Vec<u8>, _content_type: &str) -> Result<(), String> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        fs::write(path, bytes).await.map_err(|e| e.to_string())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        fs::read(self.path(key)?).await.map_err(|e| e.to_string())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        match fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn health(&self) -> Result<(), String> {
+        fs::create_dir_all(&self.root)
+            .await
+            .map_err(|e| e.to_string())?;
+        let metadata = fs::metadata(&self.root).await.map_err(|e| e.to_string())?;
+        if metadata.permissions().readonly() {
+            return Err(format!("{} is read only", self.root.display()));
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "local"
+    }
+}
@@ -0,0 +1,54 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use std::{env, sync::Arc};
+
+use async_trait::async_trait;
+
+pub(crate) mod local;
+pub(crate) mod s3;
+
+#[doc = "Blob storage for uploaded files"]
+#[async_trait]
+pub trait Storage: Send + Sync {
+    #[doc = "Store the bytes under the key, replacing an existing object"]
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), String>;
+
+    #[doc = "Read the object stored under the key"]
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String>;
+
+    #[doc = "Remove the object, missing objects are not an error"]
+    async fn delete(&self, key: &str) -> Result<(), String>;
+
+    #[doc = "Check that the backend is reachable and writable"]
+    async fn health(&self) -> Result<(), String>;
+
+    #[doc = "Name of the backend used in health reports"]
+    fn name(&self) -> &'static str;
+}
+
+#[doc = "Create the storage configured by `STORAGE_BACKEND` (`local` or `s3`).
+The local backend writes to `STORAGE_PATH`, the s3 backend is configured by the
+`S3_*` variables and works with any S3 compatible server like MinIO."]
+pub fn storage_from_env() -> Arc<dyn Storage> {
+    match env::var("STORAGE_BACKEND")
+        .unwrap_or("local".to_owned())
+        .as_str()
+    {
+        "s3" => Arc::new(
+            s3::S3Storage::new(
+                &env::var("S3_BUCKET").expect("S3_BUCKET is not set"),
+                &env::var("S3_ENDPOINT").expect("S3_ENDPOINT is not set"),
+                &env::var("S3_REGION").unwrap_or("us-east-1".to_owned()),
+                &env::var("S3_ACCESS_KEY").expect("S3_ACCESS_KEY is not set"),
+                &env::var("S3_SECRET_KEY").expect("S3_SECRET_KEY is not set"),
+            )
+            .expect("Failed to configure s3 storage"),
+        ),
+        "local" => Arc::new(local::LocalStorage::new(
+            env::var("STORAGE_PATH").unwrap_or("./uploads".to_owned()),
+        )),
+        backend => panic!("Unknown STORAGE_BACKEND {backend}"),
+    }
+}
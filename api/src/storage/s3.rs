@@ -0,0 +1,74 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use async_trait::async_trait;
+use s3::{creds::Credentials, Bucket, Region};
+
+use super::Storage;
+
+#[doc = "Storage backend for S3 compatible object stores, using path style urls
+so it works with MinIO out of the box"]
+pub struct S3Storage {
+    bucket: Box<Bucket>,
+}
+
+impl S3Storage {
+    pub fn new(
+        bucket: &str,
+        endpoint: &str,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Result<Self, String> {
+        let region = Region::Custom {
+            region: region.to_owned(),
+            endpoint: endpoint.to_owned(),
+        };
+        let credentials = Credentials::new(Some(access_key), Some(secret_key), None, None, None)
+            .map_err(|e| e.to_string())?;
+        let bucket = Bucket::new(bucket, region, credentials)
+            .map_err(|e| e.to_string())?
+            .with_path_style();
+        Ok(Self { bucket })
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), String> {
+        self.bucket
+            .put_object_with_content_type(key, &bytes, content_type)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        self.bucket
+            .get_object(key)
+            .await
+            .map(|response| response.bytes().to_vec())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        self.bucket
+            .delete_object(key)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn health(&self) -> Result<(), String> {
+        self.bucket
+            .location()
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+#[doc = "Keeps the exporters alive, dropping it flushes the spans not yet exported"]
+#[must_use]
+pub struct Telemetry {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Flushing the spans failed: {}", e);
+            }
+        }
+    }
+}
+
+#[doc = "Install a subscriber writing JSON lines to stdout, filtered by `RUST_LOG`.
+`log` records of the dependencies are forwarded to it. Built with the `otlp` feature
+the spans are also exported to `OTEL_EXPORTER_OTLP_ENDPOINT` when it is set."]
+pub fn init() -> Telemetry {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let json = tracing_subscriber::fmt::layer()
+        .json()
+        .with_current_span(true)
+        .with_span_list(false);
+    let subscriber = tracing_subscriber::registry().with(filter).with(json);
+
+    #[cfg(feature = "otlp")]
+    {
+        use opentelemetry::trace::TracerProvider as _;
+
+        let provider = otlp::provider_from_env();
+        let layer = provider.as_ref().map(|provider| {
+            tracing_opentelemetry::layer().with_tracer(provider.tracer("planters_cycle"))
+        });
+        subscriber.with(layer).init();
+        Telemetry { provider }
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    {
+        subscriber.init();
+        Telemetry {}
+    }
+}
+
+#[cfg(feature = "otlp")]
+pub mod otlp {
+    use std::env;
+
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+
+    #[doc = "Batch spans to the collector at the endpoint, e.g. `http://localhost:4317`"]
+    pub fn provider(endpoint: &str) -> Result<TracerProvider, String> {
+        let exporter = SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                "planters_cycle",
+            )]))
+            .build())
+    }
+
+    #[doc = "Exporting is off unless `OTEL_EXPORTER_OTLP_ENDPOINT` is set"]
+    pub fn provider_from_env() -> Option<TracerProvider> {
+        let endpoint = env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+        match provider(&endpoint) {
+            Ok(provider) => Some(provider),
+            Err(e) => {
+                eprintln!("Exporting spans to {} is disabled: {}", endpoint, e);
+                None
+            }
+        }
+    }
+}
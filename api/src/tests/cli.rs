@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use clap::Parser;
+
+    use crate::cli::{password_or_generated, Cli, Command};
+
+    #[test]
+    fn test_parse_commands() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).map(|cli| cli.command);
+
+        assert_eq!(parse(&["planters_cycle"]).unwrap(), None);
+        assert_eq!(
+            parse(&["planters_cycle", "serve", "--bind", "0.0.0.0:80"]).unwrap(),
+            Some(Command::Serve {
+                bind: "0.0.0.0:80".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&["planters_cycle", "migrate"]).unwrap(),
+            Some(Command::Migrate)
+        );
+        assert_eq!(
+            parse(&[
+                "planters_cycle",
+                "create-admin",
+                "--name",
+                "Admin",
+                "--email",
+                "admin@example.com",
+                "--password",
+                "correct horse",
+            ])
+            .unwrap(),
+            Some(Command::CreateAdmin {
+                name: "Admin".to_string(),
+                email: "admin@example.com".to_string(),
+                password: Some("correct horse".to_string()),
+                organization: None,
+            })
+        );
+        assert_eq!(
+            parse(&[
+                "planters_cycle",
+                "seed-demo",
+                "--user",
+                "Admin",
+                "--organization",
+                "Greenhouse",
+            ])
+            .unwrap(),
+            Some(Command::SeedDemo {
+                user: "Admin".to_string(),
+                organization: Some("Greenhouse".to_string()),
+            })
+        );
+        assert_eq!(
+            parse(&["planters_cycle", "export", "backup.zip", "--with-passwords"]).unwrap(),
+            Some(Command::Export {
+                output: PathBuf::from("backup.zip"),
+                with_passwords: true,
+            })
+        );
+        assert_eq!(
+            parse(&["planters_cycle", "import", "backup.zip", "--dry-run"]).unwrap(),
+            Some(Command::Import {
+                input: PathBuf::from("backup.zip"),
+                dry_run: true,
+            })
+        );
+        assert!(parse(&["planters_cycle", "create-admin", "--name", "Admin"]).is_err());
+        assert!(parse(&["planters_cycle", "import"]).is_err());
+        assert!(parse(&["planters_cycle", "drop-database"]).is_err());
+    }
+
+    #[test]
+    fn test_password_or_generated() {
+        assert_eq!(
+            password_or_generated(Some("long enough".to_string())),
+            Ok(("long enough".to_string(), false))
+        );
+        assert!(password_or_generated(Some("short".to_string())).is_err());
+
+        let (generated, was_generated) = password_or_generated(None).unwrap();
+        assert!(was_generated);
+        assert_eq!(generated.len(), 32);
+        assert_ne!(password_or_generated(None).unwrap().0, generated);
+    }
+}
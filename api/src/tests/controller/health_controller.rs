@@ -4,10 +4,10 @@
 
 #[cfg(test)]
 mod tests {
-    use actix_web::{http::StatusCode, test};
+    use actix_web::{http::StatusCode, test, App};
 
     use super::super::{init_app_entry, init_app_state};
-    use crate::route::health_check::health_check;
+    use crate::route::health_check::{health_check, liveness};
 
     #[actix_rt::test]
     async fn test_index_ok_and_message() {
@@ -20,4 +20,13 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[actix_rt::test]
+    async fn test_liveness_without_dependencies() {
+        let app = test::init_service(App::new().service(liveness)).await;
+
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
 }
@@ -0,0 +1,44 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+
+    use crate::{middleware::metrics::RequestMetrics, service::metrics::metrics};
+
+    #[actix_rt::test]
+    async fn test_route_label_is_the_matched_pattern() {
+        let app =
+            test::init_service(App::new().wrap(RequestMetrics).service(
+                web::scope("/api").route("/metered/{id}", web::get().to(HttpResponse::Ok)),
+            ))
+            .await;
+
+        for id in ["1", "2"] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/metered/{}", id))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        let req = test::TestRequest::post().uri("/api/metered").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let requests = &metrics().http_requests;
+        assert_eq!(
+            requests
+                .with_label_values(&["GET", "/api/metered/{id}", "200"])
+                .get(),
+            2
+        );
+        assert_eq!(
+            requests
+                .with_label_values(&["POST", "unmatched", "404"])
+                .get(),
+            1
+        );
+    }
+}
@@ -20,6 +20,11 @@ use super::get_prisma_client;
 pub(crate) mod auth_controller;
 pub(crate) mod health_controller;
 pub(crate) mod index_controller;
+pub(crate) mod metrics_controller;
+pub(crate) mod openapi_controller;
+pub(crate) mod request_id_controller;
+pub(crate) mod role_controller;
+pub(crate) mod tenant_controller;
 
 #[allow(dead_code)]
 async fn init_app_state() -> web::Data<PrismaClient> {
@@ -0,0 +1,272 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use actix_web::{http::StatusCode, test as actix_test, web, App, HttpRequest, HttpResponse};
+    use utoipa::OpenApi;
+
+    use crate::{
+        route::{
+            metrics::get_metrics,
+            openapi::{openapi_controller_init, ApiDoc},
+        },
+        server::get_config,
+    };
+
+    const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+    fn read_source(file: &Path) -> String {
+        fs::read_to_string(file).unwrap_or_else(|_| panic!("Can not read {}", file.display()))
+    }
+
+    fn body_of<'a>(source: &'a str, function: &str) -> Option<&'a str> {
+        let start = source.find(&format!("fn {}(", function))?;
+        let end = source[start..].find("\n}\n")?;
+        Some(&source[start..start + end])
+    }
+
+    #[doc = "Arguments of all `.method(...)` calls, up to the first closing parenthesis"]
+    fn arguments<'a>(body: &'a str, method: &str) -> Vec<&'a str> {
+        let pattern = format!(".{}(", method);
+        body.match_indices(&pattern)
+            .map(|(index, _)| &body[index + pattern.len()..])
+            .filter_map(|rest| rest.split_once(')').map(|(argument, _)| argument.trim()))
+            .collect()
+    }
+
+    fn calls<'a>(body: &'a str, method: &str) -> Vec<&'a str> {
+        arguments(body, method)
+            .into_iter()
+            .filter(|argument| argument.chars().all(|c| c.is_alphanumeric() || c == '_'))
+            .collect()
+    }
+
+    #[doc = "Method and path of the actix attribute of the handler"]
+    fn handler_route(source: &str, handler: &str) -> Option<(String, String)> {
+        let definitions = source.matches(&format!("fn {}(", handler)).count();
+        assert!(
+            definitions <= 1,
+            "Handler {} is defined {} times",
+            handler,
+            definitions
+        );
+
+        let mut route = None;
+        for line in source.lines() {
+            let line = line.trim();
+            for method in METHODS {
+                if let Some(rest) = line.strip_prefix(&format!("#[{}(\"", method)) {
+                    let path = rest.split('"').next().unwrap_or_default();
+                    route = Some((method.to_string(), path.to_string()));
+                }
+            }
+            if line.contains(&format!("fn {}(", handler)) {
+                return route;
+            }
+        }
+        None
+    }
+
+    #[doc = "The only route source the function is found in, ambiguous names fail the test"]
+    fn unique_source<'a>(
+        sources: &'a [String],
+        function: &str,
+        found: impl Fn(&str) -> bool,
+    ) -> &'a str {
+        let matches: Vec<&String> = sources.iter().filter(|source| found(source)).collect();
+        match matches.as_slice() {
+            [source] => source,
+            [] => panic!("{} not found", function),
+            _ => panic!(
+                "{} is ambiguous, it is defined in {} route files",
+                function,
+                matches.len()
+            ),
+        }
+    }
+
+    #[doc = "Every method and path registered by `server::get_config`. The sources are read
+as text, so only the layout used by the controllers is understood: handlers registered with
+`.service(handler)` inside at most one `web::scope`. Anything else fails instead of being
+skipped, and `test_scraped_routes_are_registered` checks the result against the real app."]
+    fn registered_routes() -> Vec<(String, String)> {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let server = read_source(&src.join("server.rs"));
+        let config = body_of(&server, "get_config").expect("get_config not found");
+        let prefix = "/api";
+        assert_eq!(
+            config.matches("scope(").count(),
+            1,
+            "get_config must have one scope"
+        );
+
+        let mut sources = vec![];
+        for entry in fs::read_dir(src.join("route")).unwrap() {
+            sources.push(read_source(&entry.unwrap().path()));
+        }
+
+        let mut routes = vec![];
+        for handler in calls(config, "service") {
+            let source = unique_source(&sources, handler, |source| {
+                handler_route(source, handler).is_some()
+            });
+            let (method, path) = handler_route(source, handler).unwrap();
+            routes.push((method, format!("{}{}", prefix, path)));
+        }
+        for controller in calls(config, "configure") {
+            let source = unique_source(&sources, controller, |source| {
+                body_of(source, controller).is_some()
+            });
+            let init = body_of(source, controller).unwrap();
+            assert!(
+                init.matches("web::scope(").count() <= 1,
+                "{} registers nested or multiple scopes",
+                controller
+            );
+            for unsupported in ["route", "to", "default_service", "configure"] {
+                assert!(
+                    arguments(init, unsupported).is_empty(),
+                    "{} registers routes with .{}(..)",
+                    controller,
+                    unsupported
+                );
+            }
+            for argument in arguments(init, "service") {
+                assert!(
+                    !argument.starts_with("web::resource"),
+                    "{} registers routes with web::resource",
+                    controller
+                );
+            }
+            let scope = init
+                .split_once("web::scope(\"")
+                .and_then(|(_, rest)| rest.split('"').next())
+                .unwrap_or_default();
+            for handler in calls(init, "service") {
+                let (method, path) = handler_route(source, handler)
+                    .unwrap_or_else(|| panic!("Route of {} not found", handler));
+                routes.push((method, format!("{}{}{}", prefix, scope, path)));
+            }
+        }
+        routes
+    }
+
+    #[doc = "Concrete path matching the route pattern"]
+    fn example_path(pattern: &str) -> String {
+        let mut path = String::new();
+        let mut parameter = false;
+        for c in pattern.chars() {
+            match c {
+                '{' => parameter = true,
+                '}' => {
+                    parameter = false;
+                    path.push('1');
+                }
+                c if !parameter => path.push(c),
+                _ => {}
+            }
+        }
+        path
+    }
+
+    #[actix_rt::test]
+    async fn test_scraped_routes_are_registered() {
+        let routes = registered_routes();
+        let documented: Vec<String> = ApiDoc::openapi().paths.paths.into_keys().collect();
+
+        // Registered like in `server::run`. Unknown paths end in the default service, which
+        // sees the resource map of the whole app
+        let app = actix_test::init_service(
+            App::new()
+                .service(get_metrics)
+                .configure(get_config)
+                .default_service(web::to(move |req: HttpRequest| {
+                    let paths: Vec<String> = routes
+                        .iter()
+                        .map(|(_, path)| path.clone())
+                        .chain(documented.clone())
+                        .collect();
+                    async move {
+                        let unknown: Vec<String> = paths
+                            .into_iter()
+                            .filter(|path| !req.resource_map().has_resource(&example_path(path)))
+                            .collect();
+                        HttpResponse::Ok().json(unknown)
+                    }
+                })),
+        )
+        .await;
+
+        let req = actix_test::TestRequest::get().uri("/routes").to_request();
+        let unknown: Vec<String> = actix_test::call_and_read_body_json(&app, req).await;
+        assert!(
+            unknown.is_empty(),
+            "Routes missing in the app: {:?}",
+            unknown
+        );
+    }
+
+    #[test]
+    fn test_openapi_covers_registered_routes() {
+        let routes = registered_routes();
+        assert!(routes.contains(&("get".to_string(), "/api/health_check".to_string())));
+        assert!(routes.contains(&("post".to_string(), "/api/lots/{id}/sales".to_string())));
+
+        let openapi = ApiDoc::openapi();
+        let missing: Vec<String> = routes
+            .iter()
+            .filter(|(method, path)| {
+                let item = openapi.paths.paths.get(path);
+                let operation = item.and_then(|item| match method.as_str() {
+                    "get" => item.get.as_ref(),
+                    "post" => item.post.as_ref(),
+                    "put" => item.put.as_ref(),
+                    "patch" => item.patch.as_ref(),
+                    "delete" => item.delete.as_ref(),
+                    _ => None,
+                });
+                operation.is_none()
+            })
+            .map(|(method, path)| format!("{} {}", method.to_uppercase(), path))
+            .collect();
+        assert!(missing.is_empty(), "Undocumented routes: {:?}", missing);
+    }
+
+    #[test]
+    fn test_openapi_documents_errors() {
+        let openapi = ApiDoc::openapi();
+        let components = openapi.components.as_ref().unwrap();
+        assert!(components.schemas.contains_key("ErrorResponse"));
+        assert!(components.schemas.contains_key("PlantStage"));
+        assert!(components.security_schemes.contains_key("session"));
+
+        let lot = openapi.paths.paths.get("/api/lots/{id}").unwrap();
+        let responses = &lot.get.as_ref().unwrap().responses.responses;
+        assert!(responses.contains_key("200"));
+        assert!(responses.contains_key("default"));
+    }
+
+    #[actix_rt::test]
+    async fn test_openapi_json_and_docs_are_served() {
+        let app = actix_test::init_service(
+            App::new().service(web::scope("/api").configure(openapi_controller_init)),
+        )
+        .await;
+
+        let req = actix_test::TestRequest::get()
+            .uri("/api/openapi.json")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert!(body["paths"]["/api/plants"]["get"].is_object());
+
+        let req = actix_test::TestRequest::get().uri("/api/docs").to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+    use uuid::Uuid;
+
+    use crate::{
+        middleware::request_id::{is_valid_request_id, RequestTracing, REQUEST_ID_HEADER},
+        model::error::{ErrorCode, ErrorResponse},
+    };
+
+    async fn missing() -> HttpResponse {
+        ErrorResponse::build(ErrorCode::DATABASE002)
+    }
+
+    #[actix_rt::test]
+    async fn test_is_valid_request_id() {
+        for id in ["abc-123", "0f8e2c1a.b_c:d", &"a".repeat(128)] {
+            assert!(is_valid_request_id(id), "{}", id);
+        }
+        for id in ["", "a b", "line\nbreak", "ümlaut", &"a".repeat(129)] {
+            assert!(!is_valid_request_id(id), "{}", id);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_request_id_is_echoed_or_generated() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestTracing)
+                .route("/ok", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/ok")
+            .insert_header((REQUEST_ID_HEADER, "client-42"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "client-42");
+
+        for req in [
+            test::TestRequest::get().uri("/ok"),
+            test::TestRequest::get()
+                .uri("/ok")
+                .insert_header((REQUEST_ID_HEADER, "not safe to log")),
+        ] {
+            let resp = test::call_service(&app, req.to_request()).await;
+            let id = resp.headers().get(REQUEST_ID_HEADER).unwrap();
+            assert!(Uuid::parse_str(id.to_str().unwrap()).is_ok());
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_error_response_carries_request_id() {
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestTracing)
+                .route("/missing", web::get().to(missing)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/missing")
+            .insert_header((REQUEST_ID_HEADER, "client-43"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["requestId"], "client-43");
+        assert_eq!(body["code"], "DATABASE002");
+    }
+}
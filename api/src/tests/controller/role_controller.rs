@@ -0,0 +1,99 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_identity::Identity;
+    use actix_session::Session;
+    use actix_web::{
+        http::StatusCode, post, test, web, HttpMessage, HttpRequest, HttpResponse, Responder,
+    };
+    use serde_json::json;
+
+    use super::super::init_app_entry;
+    use crate::{
+        middleware::tenant::ORGANIZATION_KEY,
+        prisma::{role, users_in_roles, PrismaClient},
+        server::get_config,
+        service::organization::DEFAULT_ORGANIZATION_ID,
+    };
+
+    // Logs in as `admin` working in the default organization like the login does
+    #[post("/select")]
+    async fn select(req: HttpRequest, session: Session) -> impl Responder {
+        Identity::login(&req.extensions(), "admin".to_owned()).unwrap();
+        session
+            .insert(ORGANIZATION_KEY, DEFAULT_ORGANIZATION_ID)
+            .unwrap();
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_rt::test]
+    async fn test_roles_are_created_without_default_flag_and_missing_roles_are_not_found() {
+        let (client, mock) = PrismaClient::_mock();
+        mock.expect(
+            client.users_in_roles().count(vec![
+                users_in_roles::user_id::equals("admin".to_owned()),
+                users_in_roles::organization_id::equals(DEFAULT_ORGANIZATION_ID.to_owned()),
+            ]),
+            1,
+        )
+        .await;
+        mock.expect(
+            client.users_in_roles().count(vec![
+                users_in_roles::user_id::equals("admin".to_owned()),
+                users_in_roles::organization_id::equals(DEFAULT_ORGANIZATION_ID.to_owned()),
+                users_in_roles::role::is(vec![role::is_admin::equals(true)]),
+            ]),
+            1,
+        )
+        .await;
+        mock.expect(
+            client
+                .role()
+                .create("Growers".to_owned(), vec![role::is_default::set(false)]),
+            role::Data {
+                id: 7,
+                name: "Growers".to_owned(),
+                is_default: false,
+                is_admin: false,
+                users: None,
+            },
+        )
+        .await;
+        mock.expect(client.role().find_unique(role::id::equals(8)), None)
+            .await;
+        let app = test::init_service(
+            init_app_entry()
+                .app_data(web::Data::new(client))
+                .service(select)
+                .configure(get_config),
+        )
+        .await;
+
+        let req = test::TestRequest::post().uri("/select").to_request();
+        let resp = test::call_service(&app, req).await;
+        let cookie = resp
+            .response()
+            .cookies()
+            .find(|cookie| cookie.name() == "plnt_test")
+            .unwrap()
+            .into_owned();
+
+        let req = test::TestRequest::post()
+            .uri("/api/roles")
+            .cookie(cookie.clone())
+            .set_json(json!({ "name": "Growers" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/api/roles/8")
+            .cookie(cookie)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
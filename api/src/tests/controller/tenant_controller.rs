@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_http::Request;
+    use actix_identity::Identity;
+    use actix_session::Session;
+    use actix_web::{
+        cookie::Cookie,
+        dev::{Service, ServiceResponse},
+        get,
+        http::StatusCode,
+        post, test, web, Error, HttpMessage, HttpRequest, HttpResponse, Responder,
+    };
+
+    use super::super::init_app_entry;
+    use crate::{
+        middleware::tenant::{Tenant, ORGANIZATION_KEY},
+        prisma::{task, users_in_roles, PrismaClient},
+        server::get_config,
+    };
+
+    // Logs in as `user` working in `organization` like the login does
+    #[post("/select")]
+    async fn select(req: HttpRequest, session: Session) -> impl Responder {
+        Identity::login(&req.extensions(), "user".to_owned()).unwrap();
+        session.insert(ORGANIZATION_KEY, "organization").unwrap();
+        HttpResponse::Ok().finish()
+    }
+
+    #[get("/tenant")]
+    async fn current(tenant: Tenant) -> impl Responder {
+        HttpResponse::Ok().body(tenant.organization_id)
+    }
+
+    async fn session_cookie(
+        app: &impl Service<Request, Response = ServiceResponse, Error = Error>,
+    ) -> Cookie<'static> {
+        let req = test::TestRequest::post().uri("/select").to_request();
+        let resp = test::call_service(app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        resp.response()
+            .cookies()
+            .find(|cookie| cookie.name() == "plnt_test")
+            .unwrap()
+            .into_owned()
+    }
+
+    #[actix_rt::test]
+    async fn test_tenant_requires_a_session() {
+        let (client, _mock) = PrismaClient::_mock();
+        let app = test::init_service(
+            init_app_entry()
+                .app_data(web::Data::new(client))
+                .service(current),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/tenant").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[actix_rt::test]
+    async fn test_tenant_requires_membership() {
+        let (client, mock) = PrismaClient::_mock();
+        mock.expect(
+            client.users_in_roles().count(vec![
+                users_in_roles::user_id::equals("user".to_owned()),
+                users_in_roles::organization_id::equals("organization".to_owned()),
+            ]),
+            0,
+        )
+        .await;
+        mock.expect(
+            client.users_in_roles().count(vec![
+                users_in_roles::user_id::equals("user".to_owned()),
+                users_in_roles::organization_id::equals("organization".to_owned()),
+            ]),
+            1,
+        )
+        .await;
+        let app = test::init_service(
+            init_app_entry()
+                .app_data(web::Data::new(client))
+                .service(select)
+                .service(current),
+        )
+        .await;
+
+        let cookie = session_cookie(&app).await;
+
+        // Removed from the organization after selecting it
+        let req = test::TestRequest::get()
+            .uri("/tenant")
+            .cookie(cookie.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::get()
+            .uri("/tenant")
+            .cookie(cookie)
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "organization");
+    }
+
+    #[actix_rt::test]
+    async fn test_tasks_of_other_organizations_are_not_found() {
+        let (client, mock) = PrismaClient::_mock();
+        mock.expect(
+            client.users_in_roles().count(vec![
+                users_in_roles::user_id::equals("user".to_owned()),
+                users_in_roles::organization_id::equals("organization".to_owned()),
+            ]),
+            1,
+        )
+        .await;
+        // The task exists, but only in another organization
+        mock.expect(
+            client.task().delete_many(vec![
+                task::id::equals("task".to_owned()),
+                task::organization_id::equals("organization".to_owned()),
+            ]),
+            0,
+        )
+        .await;
+        let app = test::init_service(
+            init_app_entry()
+                .app_data(web::Data::new(client))
+                .service(select)
+                .configure(get_config),
+        )
+        .await;
+
+        let cookie = session_cookie(&app).await;
+        let req = test::TestRequest::delete()
+            .uri("/api/tasks/task")
+            .cookie(cookie)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,49 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::database::{backoff, bundled_migrations, pending_migrations, MigrationMode};
+
+    #[test]
+    fn test_migration_mode() {
+        assert_eq!(MigrationMode::parse(None), Ok(MigrationMode::Check));
+        assert_eq!(MigrationMode::parse(Some("")), Ok(MigrationMode::Check));
+        assert_eq!(
+            MigrationMode::parse(Some("Apply")),
+            Ok(MigrationMode::Apply)
+        );
+        assert_eq!(MigrationMode::parse(Some("skip")), Ok(MigrationMode::Skip));
+        assert!(MigrationMode::parse(Some("yes")).is_err());
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(0), Duration::from_millis(500));
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+        assert_eq!(backoff(6), Duration::from_secs(30));
+        assert_eq!(backoff(40), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_bundled_migrations() {
+        let bundled = bundled_migrations();
+        assert_eq!(bundled.first(), Some(&"20240510151456_init"));
+        assert!(bundled.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_pending_migrations() {
+        let bundled = ["1_init", "2_plants", "3_lots"];
+        assert_eq!(pending_migrations(&bundled, &[]), bundled);
+        assert_eq!(
+            pending_migrations(&bundled, &["1_init".to_string(), "2_plants".to_string()]),
+            ["3_lots"]
+        );
+        assert!(pending_migrations(&bundled, &bundled.map(String::from)).is_empty());
+    }
+}
@@ -4,7 +4,14 @@
 
 use crate::prisma::PrismaClient;
 
+mod cli;
 mod controller;
+mod database;
+mod server;
+mod service;
+mod storage;
+mod telemetry;
+mod validation;
 
 async fn get_prisma_client() -> PrismaClient {
     PrismaClient::_builder().build().await.unwrap()
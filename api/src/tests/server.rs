@@ -0,0 +1,44 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::{env, net::TcpListener};
+
+    use crate::{
+        prisma::{task_template, PrismaClient},
+        server::run,
+        service::task::end_of_today,
+    };
+
+    // `main` runs on a plain tokio runtime, anything spawned at startup must not need an
+    // actix system or a `LocalSet`
+    #[tokio::test]
+    async fn test_run_starts_under_tokio() {
+        if env::var("SECRET").is_err() {
+            env::set_var("SECRET", "a".repeat(64));
+        }
+        let (client, mock) = PrismaClient::_mock();
+        // The scheduler materializes the recurring tasks right away
+        mock.expect(
+            client
+                .task_template()
+                .find_many(vec![
+                    task_template::active::equals(true),
+                    task_template::next_due_date::lt(end_of_today()),
+                ])
+                .with(task_template::plants::fetch(vec![]))
+                .with(task_template::batches::fetch(vec![]))
+                .with(task_template::locations::fetch(vec![])),
+            vec![],
+        )
+        .await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = run(listener, client).await.unwrap();
+        let handle = server.handle();
+        let (result, _) = tokio::join!(server, handle.stop(false));
+        result.unwrap();
+    }
+}
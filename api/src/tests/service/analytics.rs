@@ -0,0 +1,110 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, FixedOffset};
+
+    use crate::{
+        prisma::PlantStage,
+        service::analytics::{analyze, completed_stage_days, AnalyticsSubject, PlantRecord},
+    };
+
+    fn start() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2024-01-01T00:00:00+00:00").unwrap()
+    }
+
+    fn plant(clone: bool, stages: &[(i64, PlantStage)], dry_weight: Option<f64>) -> PlantRecord {
+        PlantRecord {
+            created_at: start(),
+            stage: stages
+                .last()
+                .map(|(_, stage)| *stage)
+                .unwrap_or(PlantStage::Seedling),
+            clone,
+            transitions: stages
+                .iter()
+                .map(|(day, stage)| (start() + Duration::days(*day), *stage))
+                .collect(),
+            dry_weight,
+            wet_weight: dry_weight.map(|weight| weight * 4.0),
+        }
+    }
+
+    fn subject(autoflower: bool) -> AnalyticsSubject {
+        AnalyticsSubject {
+            id: "g".to_string(),
+            name: "Gelato".to_string(),
+            flower_days: 60,
+            autoflower,
+        }
+    }
+
+    #[test]
+    fn test_completed_stage_days() {
+        let record = plant(
+            false,
+            &[
+                (14, PlantStage::Vegetative),
+                (42, PlantStage::Flowering),
+                (105, PlantStage::Harvest),
+            ],
+            None,
+        );
+        let days: Vec<(PlantStage, f64)> = completed_stage_days(&record);
+        assert_eq!(
+            days,
+            vec![
+                (PlantStage::Seedling, 14.0),
+                (PlantStage::Vegetative, 28.0),
+                (PlantStage::Flowering, 63.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyze() {
+        let plants = vec![
+            plant(
+                false,
+                &[
+                    (10, PlantStage::Vegetative),
+                    (40, PlantStage::Flowering),
+                    (100, PlantStage::Harvest),
+                ],
+                Some(80.0),
+            ),
+            plant(
+                false,
+                &[
+                    (20, PlantStage::Vegetative),
+                    (50, PlantStage::Flowering),
+                    (116, PlantStage::Harvest),
+                ],
+                Some(120.0),
+            ),
+            plant(true, &[(12, PlantStage::Vegetative)], None),
+            plant(true, &[(5, PlantStage::Destroyed)], None),
+            plant(true, &[], None),
+        ];
+
+        let analytics = analyze(&subject(false), &plants);
+        assert_eq!(analytics.plants, 5);
+        assert_eq!(analytics.stage_days[0].stage, PlantStage::Seedling);
+        assert_eq!(analytics.stage_days[0].samples, 4);
+        assert_eq!(analytics.actual_flower_days, Some(63.0));
+        assert_eq!(analytics.yield_stats.harvested_plants, 2);
+        assert_eq!(analytics.yield_stats.total_dry_weight, 200.0);
+        assert_eq!(analytics.yield_stats.average_dry_weight, Some(100.0));
+        assert_eq!(analytics.destroyed, 1);
+        assert_eq!(analytics.loss_rate, Some(0.2));
+        assert_eq!(analytics.clones.taken, 3);
+        assert_eq!(analytics.clones.rooted, 1);
+        assert_eq!(analytics.clones.failed, 1);
+        assert_eq!(analytics.clones.success_rate, Some(0.5));
+
+        let autoflower = analyze(&subject(true), &plants);
+        assert_eq!(autoflower.actual_flower_days, Some(108.0));
+    }
+}
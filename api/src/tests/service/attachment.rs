@@ -0,0 +1,73 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, GenericImageView, ImageFormat, Rgb, RgbImage};
+
+    use crate::service::attachment::{process_image, THUMBNAIL_SIZE};
+
+    #[doc = "Jpeg as phones write it: rotated by EXIF and carrying a description that
+stands in for the GPS position"]
+    fn photo(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, _| match x < width / 2 {
+            true => Rgb([200, 30, 30]),
+            false => Rgb([30, 30, 200]),
+        });
+        let mut jpeg = Cursor::new(vec![]);
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut jpeg, ImageFormat::Jpeg)
+            .unwrap();
+        let jpeg = jpeg.into_inner();
+
+        let mut tiff = b"MM\0*\0\0\0\x08".to_vec();
+        tiff.extend([0, 2]);
+        // Orientation 6, rotate 90 degrees clockwise
+        tiff.extend([0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0]);
+        // ImageDescription, 11 bytes stored after the directory
+        tiff.extend([0x01, 0x0e, 0, 2, 0, 0, 0, 11, 0, 0, 0, 38]);
+        tiff.extend([0, 0, 0, 0]);
+        tiff.extend(b"secret-gps\0");
+
+        let mut exif = vec![0xff, 0xe1];
+        exif.extend(((2 + 6 + tiff.len()) as u16).to_be_bytes());
+        exif.extend(b"Exif\0\0");
+        exif.extend(tiff);
+        [&jpeg[..2], &exif[..], &jpeg[2..]].concat()
+    }
+
+    fn contains(bytes: &[u8], needle: &[u8]) -> bool {
+        bytes.windows(needle.len()).any(|window| window == needle)
+    }
+
+    #[test]
+    fn test_process_image_strips_exif_and_applies_orientation() {
+        let photo = photo(800, 400);
+        assert!(contains(&photo, b"secret-gps"));
+
+        let processed = process_image(&photo).unwrap();
+        assert_eq!(processed.content_type, "image/jpeg");
+        assert!(!contains(&processed.bytes, b"Exif"));
+        assert!(!contains(&processed.bytes, b"secret-gps"));
+
+        let image = image::load_from_memory(&processed.bytes).unwrap();
+        assert_eq!(image.dimensions(), (400, 800));
+    }
+
+    #[test]
+    fn test_process_image_thumbnail() {
+        let processed = process_image(&photo(800, 400)).unwrap();
+        let thumbnail = image::load_from_memory(&processed.thumbnail).unwrap();
+        assert_eq!(thumbnail.dimensions(), (THUMBNAIL_SIZE / 2, THUMBNAIL_SIZE));
+        assert!(!contains(&processed.thumbnail, b"secret-gps"));
+    }
+
+    #[test]
+    fn test_process_image_rejects_other_files() {
+        assert!(process_image(b"%PDF-1.7 not a photo").is_err());
+        assert!(process_image(&[0xff, 0xd8, 0xff]).is_err());
+    }
+}
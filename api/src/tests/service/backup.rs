@@ -0,0 +1,363 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use chrono::{TimeZone, Utc};
+
+    use crate::model::dto::backup::{
+        GeneticRecord, HistoryRecord, OrganizationRecord, PlantRecord, RoleAssignment, RoleRecord,
+        Snapshot, UserRecord, SNAPSHOT_VERSION,
+    };
+    use crate::model::error::ErrorCode;
+    use crate::prisma::PlantStage;
+    use crate::service::backup::{
+        plan_import, read_archive, validate_snapshot, write_archive, Existing, ARCHIVE_MANIFEST,
+    };
+    use crate::service::organization::DEFAULT_ORGANIZATION_ID;
+
+    fn key(organization: &str, name: &str) -> (String, String) {
+        (organization.to_string(), name.to_string())
+    }
+
+    fn genetic(id: &str, name: &str, female_parent_id: Option<&str>) -> GeneticRecord {
+        GeneticRecord {
+            id: id.to_string(),
+            name: name.to_string(),
+            organization_id: "o1".to_string(),
+            flower_days: 63,
+            code: None,
+            naming_template: None,
+            female_parent_id: female_parent_id.map(str::to_string),
+            male_parent_id: None,
+            generation: None,
+            breeder: None,
+            source: None,
+            genetic_type: None,
+            thc_min: None,
+            thc_max: Some(22.5),
+            cbd_min: None,
+            cbd_max: None,
+            terpenes: vec!["Limonene".to_string(), "Myrcene".to_string()],
+            expected_yield: None,
+            stretch_factor: None,
+            autoflower: false,
+            tags: vec![],
+        }
+    }
+
+    fn plant(id: &str, name: &str, genetic_id: &str, mother_id: Option<&str>) -> PlantRecord {
+        PlantRecord {
+            id: id.to_string(),
+            name: name.to_string(),
+            organization_id: "o1".to_string(),
+            genetic_id: genetic_id.to_string(),
+            stage: PlantStage::Vegetative,
+            mother_id: mother_id.map(str::to_string),
+            created_at: Utc
+                .with_ymd_and_hms(2024, 5, 1, 8, 0, 0)
+                .unwrap()
+                .fixed_offset(),
+        }
+    }
+
+    fn snapshot() -> Snapshot {
+        let at = Utc
+            .with_ymd_and_hms(2024, 6, 1, 12, 0, 0)
+            .unwrap()
+            .fixed_offset();
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            exported_at: at,
+            organizations: vec![OrganizationRecord {
+                id: "o1".to_string(),
+                name: "Greenhouse".to_string(),
+            }],
+            roles: vec![RoleRecord {
+                id: 3,
+                name: "Admin".to_string(),
+                is_default: false,
+                is_admin: true,
+            }],
+            users: vec![UserRecord {
+                id: "u1".to_string(),
+                display_name: "Grower".to_string(),
+                email: "grower@example.com".to_string(),
+                password: None,
+                created_at: at,
+                roles: vec![RoleAssignment {
+                    role_id: 3,
+                    organization_id: "o1".to_string(),
+                }],
+            }],
+            genetics: vec![
+                genetic("g2", "Gelato", Some("g1")),
+                genetic("g1", "Sunset Sherbet", None),
+            ],
+            plants: vec![
+                plant("p2", "GLT-001", "g2", Some("p1")),
+                plant("p1", "GLT-M1", "g2", None),
+            ],
+            history: vec![
+                HistoryRecord {
+                    id: "h1".to_string(),
+                    plant_id: "p1".to_string(),
+                    action: "Planted from seed".to_string(),
+                    stage: Some(PlantStage::Seedling),
+                    user_id: "u1".to_string(),
+                    organization_id: "o1".to_string(),
+                    created_at: at,
+                },
+                HistoryRecord {
+                    id: "h2".to_string(),
+                    plant_id: "p2".to_string(),
+                    action: "Cloned from GLT-M1".to_string(),
+                    stage: Some(PlantStage::Seedling),
+                    user_id: "u1".to_string(),
+                    organization_id: "o1".to_string(),
+                    created_at: at,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_validate_snapshot() {
+        assert!(validate_snapshot(&snapshot()).is_empty());
+
+        let mut broken = snapshot();
+        broken.version = SNAPSHOT_VERSION + 1;
+        broken.users[0].roles.push(RoleAssignment {
+            role_id: 9,
+            organization_id: "o1".to_string(),
+        });
+        broken
+            .genetics
+            .push(genetic("g3", "Gelato", Some("missing")));
+        broken.plants[1].mother_id = Some("p1".to_string());
+        broken.plants[0].genetic_id = "missing".to_string();
+        broken.history[0].user_id = "nobody".to_string();
+        broken.history[1].organization_id = DEFAULT_ORGANIZATION_ID.to_string();
+        broken.organizations.push(OrganizationRecord {
+            id: "o2".to_string(),
+            name: "Greenhouse".to_string(),
+        });
+
+        let errors = validate_snapshot(&broken);
+        for key in [
+            "version",
+            "organizations.1.name",
+            "users.0.roles",
+            "genetics.2.name",
+            "genetics.2.femaleParentId",
+            "plants.0.geneticId",
+            "plants.1.motherId",
+            "history.0.userId",
+            "history.1.organizationId",
+        ] {
+            assert!(errors.contains_key(key), "{} missing in {:?}", key, errors);
+        }
+        assert_eq!(errors.len(), 9, "{:?}", errors);
+    }
+
+    #[test]
+    fn test_validate_snapshot_keeps_organizations_apart() {
+        let mut snapshot = snapshot();
+        snapshot.organizations.push(OrganizationRecord {
+            id: "o2".to_string(),
+            name: "Outdoor".to_string(),
+        });
+        // The same names in another organization are fine
+        let mut outdoor = genetic("g3", "Gelato", None);
+        outdoor.organization_id = "o2".to_string();
+        snapshot.genetics.push(outdoor);
+        let mut clone = plant("p3", "GLT-001", "g3", None);
+        clone.organization_id = "o2".to_string();
+        snapshot.plants.push(clone);
+        assert!(validate_snapshot(&snapshot).is_empty());
+
+        snapshot.plants[2].genetic_id = "g2".to_string();
+        snapshot.plants[2].mother_id = Some("p1".to_string());
+        snapshot.genetics[2].organization_id = "missing".to_string();
+        let errors = validate_snapshot(&snapshot);
+        for key in [
+            "genetics.2.organizationId",
+            "plants.2.geneticId",
+            "plants.2.motherId",
+        ] {
+            assert!(errors.contains_key(key), "{} missing in {:?}", key, errors);
+        }
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+    }
+
+    #[test]
+    fn test_plan_import_into_empty_instance_keeps_ids() {
+        let plan = plan_import(snapshot(), &Existing::default()).unwrap();
+
+        assert_eq!(
+            plan.report.created,
+            crate::service::backup::summary(&snapshot())
+        );
+        assert_eq!(plan.report.without_password, 1);
+        assert_eq!(plan.organizations[0].id, "o1");
+        assert_eq!(plan.roles.len(), 1);
+        assert_eq!(plan.users[0].id, "u1");
+        assert_eq!(plan.genetics[0].female_parent_id.as_deref(), Some("g1"));
+        assert_eq!(plan.plants[0].mother_id.as_deref(), Some("p1"));
+        assert!(plan.history.iter().all(|entry| entry.user_id == "u1"));
+    }
+
+    #[test]
+    fn test_plan_import_maps_onto_present_records() {
+        let mut existing = Existing::default();
+        existing
+            .organizations
+            .insert("Greenhouse".to_string(), "present-organization".to_string());
+        existing.roles.insert("Admin".to_string(), 1);
+        existing
+            .users
+            .insert("grower@example.com".to_string(), "present-user".to_string());
+        existing.genetics.insert(
+            key("present-organization", "Sunset Sherbet"),
+            "present-genetic".to_string(),
+        );
+        // Same name in another organization, not a match
+        existing.genetics.insert(
+            key(DEFAULT_ORGANIZATION_ID, "Gelato"),
+            "other-genetic".to_string(),
+        );
+        existing.plants.insert(
+            key("present-organization", "GLT-M1"),
+            "present-mother".to_string(),
+        );
+        // Another record already uses the id of the imported Gelato
+        existing.ids.insert("g2".to_string());
+
+        let plan = plan_import(snapshot(), &existing).unwrap();
+        assert!(plan.organizations.is_empty());
+        assert_eq!(plan.report.existing.organizations, 1);
+        assert!(plan.roles.is_empty());
+        assert_eq!(plan.role_ids.get(&3), Some(&1));
+        assert!(plan.users.is_empty());
+        assert_eq!(plan.report.existing.users, 1);
+        assert_eq!(plan.report.without_password, 0);
+
+        assert_eq!(plan.genetics.len(), 1);
+        let gelato = &plan.genetics[0];
+        assert_ne!(gelato.id, "g2");
+        assert_eq!(gelato.organization_id, "present-organization");
+        assert_eq!(gelato.female_parent_id.as_deref(), Some("present-genetic"));
+
+        assert_eq!(plan.plants.len(), 1);
+        assert_eq!(plan.plants[0].genetic_id, gelato.id);
+        assert_eq!(plan.plants[0].mother_id.as_deref(), Some("present-mother"));
+
+        assert_eq!(plan.history.len(), 1);
+        assert_eq!(plan.history[0].plant_id, "p2");
+        assert_eq!(plan.history[0].user_id, "present-user");
+        assert_eq!(plan.history[0].organization_id, "present-organization");
+        assert_eq!(plan.report.existing.history, 1);
+    }
+
+    #[test]
+    fn test_plan_import_refuses_taken_codes() {
+        let mut snapshot = snapshot();
+        snapshot.genetics[0].code = Some("GLT".to_string());
+        let mut existing = Existing::default();
+        existing.genetic_codes.insert(key("o1", "GLT"));
+
+        match plan_import(snapshot.clone(), &existing) {
+            Err(ErrorCode::VALIDATION(errors)) => {
+                assert!(errors.contains_key("genetics.0.code"), "{:?}", errors)
+            }
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+
+        let mut existing = Existing::default();
+        existing
+            .genetic_codes
+            .insert(key(DEFAULT_ORGANIZATION_ID, "GLT"));
+        assert!(plan_import(snapshot, &existing).is_ok());
+    }
+
+    #[test]
+    fn test_archive_roundtrip() {
+        let mut snapshot = snapshot();
+        snapshot.users[0].password = Some("$argon2id$v=19$hash".to_string());
+        let archive = write_archive(&snapshot).unwrap();
+        assert_eq!(read_archive(&archive).unwrap(), snapshot);
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "genetics.csv",
+                "history.csv",
+                ARCHIVE_MANIFEST,
+                "organizations.csv",
+                "plants.csv",
+                "roles.csv",
+                "snapshot.json",
+                "users.csv",
+            ]
+        );
+        let users = std::io::read_to_string(zip.by_name("users.csv").unwrap()).unwrap();
+        assert!(!users.contains("argon2"), "{}", users);
+        let genetics = std::io::read_to_string(zip.by_name("genetics.csv").unwrap()).unwrap();
+        assert!(genetics.contains("Limonene; Myrcene"), "{}", genetics);
+    }
+
+    #[test]
+    fn test_read_version_one_json() {
+        let mut json = serde_json::json!({
+            "version": 1,
+            "exportedAt": "2024-06-01T12:00:00+00:00",
+            "roles": [{ "id": 3, "name": "Admin", "isDefault": false }],
+            "users": [{
+                "id": "u1",
+                "displayName": "Grower",
+                "email": "grower@example.com",
+                "createdAt": "2024-06-01T12:00:00+00:00",
+                "roleIds": [3],
+            }],
+            "genetics": [],
+            "plants": [],
+            "history": [],
+        });
+        json["genetics"] =
+            serde_json::to_value(vec![genetic("g1", "Sunset Sherbet", None)]).unwrap();
+        json["genetics"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("organizationId");
+        let snapshot = read_archive(json.to_string().as_bytes()).unwrap();
+        assert_eq!(snapshot.version, 1);
+        assert!(snapshot.organizations.is_empty());
+        // Admin rights are never derived from the role name
+        assert!(!snapshot.roles[0].is_admin);
+        assert_eq!(snapshot.users[0].password, None);
+        assert_eq!(
+            snapshot.users[0].roles,
+            [RoleAssignment {
+                role_id: 3,
+                organization_id: DEFAULT_ORGANIZATION_ID.to_string(),
+            }]
+        );
+        assert_eq!(
+            snapshot.genetics[0].organization_id,
+            DEFAULT_ORGANIZATION_ID
+        );
+        assert!(validate_snapshot(&snapshot).is_empty());
+
+        let plan = plan_import(snapshot, &Existing::default()).unwrap();
+        assert_eq!(plan.genetics[0].organization_id, DEFAULT_ORGANIZATION_ID);
+
+        assert!(read_archive(b"PK not a zip").is_err());
+        assert!(read_archive(b"{}").is_err());
+    }
+}
@@ -0,0 +1,49 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web;
+
+    use crate::{
+        model::{dto::Batch, error::ErrorCode},
+        prisma::{batch, PrismaClient},
+        service::batch::{delete_batch, edit_batch},
+    };
+
+    #[actix_rt::test]
+    async fn test_batches_of_other_organizations_are_not_changed() {
+        let (client, mock) = PrismaClient::_mock();
+        mock.expect(
+            client.batch().count(vec![
+                batch::id::in_vec(vec!["batch".to_string()]),
+                batch::organization_id::equals("organization".to_string()),
+            ]),
+            0,
+        )
+        .await;
+        mock.expect(
+            client.batch().delete_many(vec![
+                batch::id::equals("batch".to_string()),
+                batch::organization_id::equals("organization".to_string()),
+            ]),
+            0,
+        )
+        .await;
+        let data = web::Data::new(client);
+
+        let renamed = Batch {
+            name: Some("Renamed".to_string()),
+            feed_schedule_id: None,
+        };
+        assert!(matches!(
+            edit_batch(&data, "organization", "batch".to_string(), renamed).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+        assert!(matches!(
+            delete_batch(&data, "organization", "batch".to_string()).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+    }
+}
@@ -0,0 +1,144 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use crate::model::error::ErrorCode;
+    use crate::prisma::{GeneticType, PlantStage};
+    use crate::service::bulk_import::{check_genetic_rows, check_plant_rows, KnownNames};
+
+    fn known() -> KnownNames {
+        let mut known = KnownNames::default();
+        known
+            .genetics
+            .insert("Sunset Sherbet".to_string(), "g1".to_string());
+        known.genetic_codes.insert("SSH".to_string());
+        known.plants.insert("SSH-M1".to_string(), "p1".to_string());
+        known
+            .locations
+            .insert("Tent 1".to_string(), "l1".to_string());
+        known
+    }
+
+    fn bad_request(result: Result<impl std::fmt::Debug, ErrorCode>) -> String {
+        match result {
+            Err(ErrorCode::BADREQUEST(message)) => message,
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_genetic_rows() {
+        let csv = "Name,Flower Days,Code,Genetic Type,THC Max,Terpenes,Autoflower,Female Parent\n\
+                   Gelato,60,GLT,hybrid,\"25,5\",Limonene; Myrcene,no,Sunset Sherbet\n\
+                   Gelato 41,58,,,,,yes,Gelato\n";
+        let preview = check_genetic_rows(csv, &known()).unwrap();
+
+        assert!(preview.errors.is_empty(), "{:?}", preview.errors);
+        assert_eq!(preview.rows.len(), 2);
+        let gelato = &preview.rows[0];
+        assert_eq!(gelato.line, 2);
+        assert_eq!(gelato.code.as_deref(), Some("GLT"));
+        assert_eq!(gelato.genetic_type, Some(GeneticType::Hybrid));
+        assert_eq!(gelato.thc_max, Some(25.5));
+        assert_eq!(gelato.terpenes, ["limonene", "myrcene"]);
+        assert!(!gelato.autoflower);
+        assert_eq!(preview.rows[1].line, 3);
+        assert!(preview.rows[1].autoflower);
+        assert_eq!(preview.rows[1].female_parent.as_deref(), Some("Gelato"));
+    }
+
+    #[test]
+    fn test_check_genetic_rows_reports_row_errors() {
+        let csv = "name;flowerDays;code;geneticType;thcMin;thcMax;femaleParent;maleParent\n\
+                   Sunset Sherbet;63;;;;;;\n\
+                   Gelato;sixty;SSH;ruderalis;30;20;Unknown;\n\
+                   Gelato;60;;;;;;Gelato\n\
+                   A;60;;;;;B;\n\
+                   B;60;;;;;A;\n";
+        let errors = check_genetic_rows(csv, &known()).unwrap().errors;
+
+        for key in [
+            "rows.2.name",
+            "rows.3.flowerDays",
+            "rows.3.code",
+            "rows.3.geneticType",
+            "rows.3.thcMin",
+            "rows.3.femaleParent",
+            "rows.4.name",
+            "rows.4.femaleParent",
+            "rows.5.femaleParent",
+            "rows.6.femaleParent",
+        ] {
+            assert!(errors.contains_key(key), "{} missing in {:?}", key, errors);
+        }
+        assert_eq!(errors.len(), 10, "{:?}", errors);
+    }
+
+    #[test]
+    fn test_check_plant_rows() {
+        let csv = "name,genetic,stage,mother,location\n\
+                   SSH-001,Sunset Sherbet,,SSH-M1,Tent 1\n\
+                   \n\
+                   SSH-002,Sunset Sherbet,flowering,SSH-003,\n\
+                   SSH-003,Sunset Sherbet,Vegetative,,\n";
+        let preview = check_plant_rows(csv, &known()).unwrap();
+
+        assert!(preview.errors.is_empty(), "{:?}", preview.errors);
+        let stages: Vec<PlantStage> = preview.rows.iter().map(|row| row.stage).collect();
+        assert_eq!(
+            stages,
+            [
+                PlantStage::Seedling,
+                PlantStage::Flowering,
+                PlantStage::Vegetative
+            ]
+        );
+        assert_eq!(preview.rows[0].location.as_deref(), Some("Tent 1"));
+        assert_eq!(preview.rows[1].line, 4);
+        assert_eq!(preview.rows[1].location, None);
+    }
+
+    #[test]
+    fn test_check_plant_rows_reports_row_errors() {
+        let csv = "name,genetic,stage,mother,location\n\
+                   SSH-M1,Sunset Sherbet,,,\n\
+                   P-1,Unknown,destroyed,Missing,Tent 9\n\
+                   P-2,Sunset Sherbet,,P-3,\n\
+                   P-3,Sunset Sherbet,,P-2,\n\
+                   P-3,Sunset Sherbet,,,\n\
+                   ,Sunset Sherbet,,,\n";
+        let errors = check_plant_rows(csv, &known()).unwrap().errors;
+
+        for key in [
+            "rows.2.name",
+            "rows.3.genetic",
+            "rows.3.stage",
+            "rows.3.mother",
+            "rows.3.location",
+            "rows.4.mother",
+            "rows.5.mother",
+            "rows.6.name",
+            "rows.7.name",
+        ] {
+            assert!(errors.contains_key(key), "{} missing in {:?}", key, errors);
+        }
+        assert_eq!(errors.len(), 9, "{:?}", errors);
+    }
+
+    #[test]
+    fn test_malformed_files_are_rejected() {
+        let known = known();
+        assert!(
+            bad_request(check_plant_rows("name,genetic,color\nP,G,red\n", &known))
+                .contains("Unknown column color")
+        );
+        assert!(
+            bad_request(check_plant_rows("name,Name,genetic\n", &known)).contains("more than once")
+        );
+        assert!(bad_request(check_genetic_rows("name\nGelato\n", &known))
+            .contains("flowerDays is missing"));
+        assert!(bad_request(check_genetic_rows("name,flowerDays\n\n", &known)).contains("no rows"));
+    }
+}
@@ -0,0 +1,56 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::service::calendar::{escape_text, fold_line, render_calendar, CalendarEvent};
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(
+            escape_text("Defoliate; top, then\nwater \\ feed"),
+            r"Defoliate\; top\, then\nwater \\ feed"
+        );
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let line = "A".repeat(160);
+        let folded = fold_line(&line);
+        let parts: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].len(), 75);
+        assert!(parts[1].starts_with(' '));
+        assert!(parts.iter().all(|part| part.len() <= 75));
+    }
+
+    #[test]
+    fn test_fold_line_keeps_characters_whole() {
+        let line = "ü".repeat(60);
+        let folded = fold_line(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn test_render_calendar() {
+        let events = vec![CalendarEvent {
+            uid: "task-1@planters-cycle".to_owned(),
+            date: NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+            summary: "Defoliate, day 21".to_owned(),
+            description: None,
+            cancelled: false,
+        }];
+        let calendar = render_calendar("Test", &events);
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20240630\r\n"));
+        assert!(calendar.contains("DTEND;VALUE=DATE:20240701\r\n"));
+        assert!(calendar.contains("SUMMARY:Defoliate\\, day 21\r\n"));
+        assert!(!calendar.contains("DESCRIPTION"));
+    }
+}
@@ -0,0 +1,152 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web;
+    use chrono::DateTime;
+
+    use crate::{
+        model::error::ErrorCode,
+        prisma::{plant_limit, PlantStage, PrismaClient},
+        service::compliance::{
+            delete_plant_limit, limit_applies, limit_description, limit_grows, limit_usage,
+            Placement,
+        },
+    };
+
+    fn limit(
+        location_id: Option<&str>,
+        stage: Option<PlantStage>,
+        max_plants: i32,
+    ) -> plant_limit::Data {
+        plant_limit::Data {
+            id: "limit".to_string(),
+            location: None,
+            location_id: location_id.map(str::to_string),
+            stage,
+            max_plants,
+            note: None,
+            created_at: DateTime::parse_from_rfc3339("2024-06-01T10:00:00+00:00").unwrap(),
+            organization: None,
+            organization_id: "organization".to_string(),
+        }
+    }
+
+    fn placement(location_id: Option<&str>, stage: PlantStage) -> Placement {
+        Placement { location_id, stage }
+    }
+
+    #[test]
+    fn test_limit_applies() {
+        let flowering = limit(Some("room"), Some(PlantStage::Flowering), 10);
+        assert!(limit_applies(
+            &flowering,
+            placement(Some("room"), PlantStage::Flowering)
+        ));
+        assert!(!limit_applies(
+            &flowering,
+            placement(Some("tent"), PlantStage::Flowering)
+        ));
+        assert!(!limit_applies(
+            &flowering,
+            placement(None, PlantStage::Flowering)
+        ));
+        assert!(!limit_applies(
+            &flowering,
+            placement(Some("room"), PlantStage::Vegetative)
+        ));
+
+        let total = limit(None, None, 99);
+        assert!(limit_applies(&total, placement(None, PlantStage::Seedling)));
+        assert!(limit_applies(
+            &total,
+            placement(Some("tent"), PlantStage::Flowering)
+        ));
+        assert!(!limit_applies(
+            &total,
+            placement(Some("tent"), PlantStage::Harvest)
+        ));
+        assert!(!limit_applies(
+            &total,
+            placement(Some("tent"), PlantStage::Destroyed)
+        ));
+    }
+
+    #[test]
+    fn test_limit_grows() {
+        let flowering = limit(None, Some(PlantStage::Flowering), 10);
+        let total = limit(None, None, 99);
+        let vegetative = placement(Some("room"), PlantStage::Vegetative);
+        let flower = placement(Some("room"), PlantStage::Flowering);
+
+        assert!(limit_grows(&flowering, Some(vegetative), flower));
+        assert!(!limit_grows(&total, Some(vegetative), flower));
+        assert!(limit_grows(&total, None, vegetative));
+        assert!(!limit_grows(&flowering, Some(flower), flower));
+
+        let room = limit(Some("room"), None, 5);
+        let moved = placement(Some("room"), PlantStage::Vegetative);
+        assert!(limit_grows(
+            &room,
+            Some(placement(Some("tent"), PlantStage::Vegetative)),
+            moved
+        ));
+        assert!(!limit_grows(
+            &room,
+            Some(moved),
+            placement(Some("tent"), PlantStage::Vegetative)
+        ));
+    }
+
+    #[test]
+    fn test_limit_usage() {
+        let usage = limit_usage(&limit(None, Some(PlantStage::Flowering), 4), 3);
+        assert_eq!(usage.remaining, 1);
+        assert_eq!(usage.utilization, Some(0.75));
+        assert!(!usage.exceeded);
+
+        let usage = limit_usage(&limit(None, Some(PlantStage::Flowering), 4), 5);
+        assert_eq!(usage.remaining, 0);
+        assert!(usage.exceeded);
+
+        let usage = limit_usage(&limit(None, None, 0), 0);
+        assert_eq!(usage.utilization, None);
+        assert!(!usage.exceeded);
+    }
+
+    #[test]
+    fn test_limit_description() {
+        assert_eq!(
+            limit_description(
+                &limit(Some("room"), Some(PlantStage::Flowering), 12),
+                Some("Flower room")
+            ),
+            "Flower room allows at most 12 FLOWERING plants"
+        );
+        assert_eq!(
+            limit_description(&limit(None, None, 99), None),
+            "The facility allows at most 99 SEEDLING/VEGETATIVE/FLOWERING plants"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_limits_of_other_organizations_are_not_deleted() {
+        let (client, mock) = PrismaClient::_mock();
+        mock.expect(
+            client.plant_limit().delete_many(vec![
+                plant_limit::id::equals("limit".to_string()),
+                plant_limit::organization_id::equals("organization".to_string()),
+            ]),
+            0,
+        )
+        .await;
+        let data = web::Data::new(client);
+
+        assert!(matches!(
+            delete_plant_limit(&data, "organization", "limit".to_string()).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+    }
+}
@@ -0,0 +1,66 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::prisma::PlantStage;
+    use crate::service::demo::{demo_plants, stage_timeline, DEMO_GENETICS};
+
+    #[test]
+    fn test_stage_timeline() {
+        let timeline = stage_timeline(60, false);
+        assert_eq!(timeline.first(), Some(&(0, PlantStage::Seedling)));
+        assert_eq!(timeline[2], (42, PlantStage::Flowering));
+        assert_eq!(timeline[3], (102, PlantStage::Harvest));
+        assert_eq!(timeline.last(), Some(&(126, PlantStage::Cured)));
+        assert!(timeline.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let auto = stage_timeline(49, true);
+        assert_eq!(auto[1], (10, PlantStage::Vegetative));
+        assert_eq!(auto[3], (77, PlantStage::Harvest));
+    }
+
+    #[test]
+    fn test_demo_plants_cover_all_stages() {
+        let now = Utc
+            .with_ymd_and_hms(2024, 6, 1, 12, 0, 0)
+            .unwrap()
+            .fixed_offset();
+        let plants = demo_plants(now);
+
+        let stages: HashSet<String> = plants
+            .iter()
+            .map(|plant| format!("{:?}", plant.stages.last().unwrap().1))
+            .collect();
+        assert_eq!(stages.len(), 6, "{:?}", stages);
+
+        let names: HashSet<&str> = plants.iter().map(|plant| plant.name.as_str()).collect();
+        assert_eq!(names.len(), plants.len());
+        assert!(names.contains("GLT-M1"));
+        assert!(!names.contains("NLA-M1"));
+
+        for plant in &plants {
+            assert!(DEMO_GENETICS.iter().any(|g| g.name == plant.genetic));
+            assert_eq!(plant.stages[0].0, plant.created_at);
+            assert!(plant.stages.iter().all(|(entered, _)| *entered <= now));
+            if let Some(mother) = &plant.mother {
+                let mother = plants.iter().position(|p| &p.name == mother).unwrap();
+                let clone = plants.iter().position(|p| p.name == plant.name).unwrap();
+                assert!(mother < clone, "mothers are created before their clones");
+            }
+        }
+        assert_eq!(
+            plants
+                .iter()
+                .find(|p| p.name == "OGK-001")
+                .unwrap()
+                .created_at,
+            now - Duration::days(7)
+        );
+    }
+}
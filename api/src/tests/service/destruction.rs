@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::{
+        model::dto::destruction::{DestroyRequest, WasteLogEntry},
+        prisma::DestructionReason,
+        service::destruction::{validate_destruction, waste_log_csv},
+    };
+
+    fn request() -> DestroyRequest {
+        DestroyRequest {
+            reason: DestructionReason::Mold,
+            method: "Mixed with soil and composted".to_string(),
+            weight: 250.0,
+            witness_ids: vec!["witness".to_string()],
+            destroyed_at: None,
+            note: None,
+            attachment_ids: vec![],
+        }
+    }
+
+    #[test]
+    fn test_validate_destruction() {
+        assert!(validate_destruction(&request(), "recorder").is_ok());
+
+        let mut without_witness = request();
+        without_witness.witness_ids = vec!["recorder".to_string()];
+        assert!(validate_destruction(&without_witness, "recorder").is_err());
+
+        let mut without_weight = request();
+        without_weight.weight = 0.0;
+        assert!(validate_destruction(&without_weight, "recorder").is_err());
+
+        let mut without_method = request();
+        without_method.method = "  ".to_string();
+        assert!(validate_destruction(&without_method, "recorder").is_err());
+
+        let mut other = request();
+        other.reason = DestructionReason::Other;
+        assert!(validate_destruction(&other, "recorder").is_err());
+        other.note = Some("Hermaphrodite".to_string());
+        assert!(validate_destruction(&other, "recorder").is_ok());
+    }
+
+    #[test]
+    fn test_waste_log_csv() {
+        let entry = WasteLogEntry {
+            id: "d1".to_string(),
+            destroyed_at: DateTime::parse_from_rfc3339("2024-06-01T10:00:00+00:00").unwrap(),
+            plant_id: "p1".to_string(),
+            plant_name: "1A".to_string(),
+            genetic: "Gelato".to_string(),
+            reason: DestructionReason::Mold,
+            method: "Shredded, composted".to_string(),
+            weight: 250.5,
+            note: None,
+            recorded_by: "Anna".to_string(),
+            witnesses: vec!["Ben".to_string(), "Cleo".to_string()],
+            attachment_ids: vec![],
+        };
+
+        let csv = waste_log_csv(&[entry]).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("id,destroyedAt,plantId"));
+        assert_eq!(
+            lines[1],
+            "d1,2024-06-01T10:00:00+00:00,p1,1A,Gelato,MOLD,\"Shredded, composted\",250.5,Anna,Ben; Cleo,"
+        );
+    }
+}
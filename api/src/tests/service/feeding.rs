@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web;
+    use chrono::{DateTime, NaiveDate};
+
+    use crate::{
+        model::{
+            dto::feeding::{FeedingLogRequest, ScheduleEntryRequest, ScheduleRequest},
+            error::ErrorCode,
+            validation::Validate,
+        },
+        prisma::{feed_recipe, feed_schedule, feed_schedule_entry, PlantStage, PrismaClient},
+        service::feeding::{create_schedule, delete_schedule, entry_for_week, stage_progress},
+    };
+
+    fn entry(stage: PlantStage, week: i32) -> feed_schedule_entry::Data {
+        feed_schedule_entry::Data {
+            id: format!("{:?}-{}", stage, week),
+            schedule: None,
+            schedule_id: "schedule".to_string(),
+            stage,
+            week,
+            recipe: None,
+            recipe_id: format!("recipe-{}", week),
+        }
+    }
+
+    fn request(volume: f64, ec: Option<f64>, ph: Option<f64>) -> FeedingLogRequest {
+        FeedingLogRequest {
+            plant_id: "plant".to_string(),
+            recipe_id: None,
+            volume,
+            ec,
+            ph,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_stage_progress() {
+        let entered_at = DateTime::parse_from_rfc3339("2024-06-01T22:00:00+02:00").unwrap();
+        let day = |d: u32| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+
+        assert_eq!(stage_progress(entered_at, day(1)), (0, 1));
+        assert_eq!(stage_progress(entered_at, day(7)), (6, 1));
+        assert_eq!(stage_progress(entered_at, day(8)), (7, 2));
+        assert_eq!(stage_progress(entered_at, day(22)), (21, 4));
+        // A stage change recorded in the future still counts as week 1
+        assert_eq!(
+            stage_progress(entered_at, NaiveDate::from_ymd_opt(2024, 5, 30).unwrap()),
+            (-2, 1)
+        );
+    }
+
+    #[test]
+    fn test_entry_for_week() {
+        let entries = || {
+            vec![
+                entry(PlantStage::Flowering, 1),
+                entry(PlantStage::Flowering, 3),
+                entry(PlantStage::Vegetative, 2),
+                entry(PlantStage::Flowering, 6),
+            ]
+        };
+        let pick = |stage, week| entry_for_week(entries(), stage, week).map(|e| e.id);
+
+        assert_eq!(
+            pick(PlantStage::Flowering, 1).as_deref(),
+            Some("Flowering-1")
+        );
+        assert_eq!(
+            pick(PlantStage::Flowering, 2).as_deref(),
+            Some("Flowering-1")
+        );
+        assert_eq!(
+            pick(PlantStage::Flowering, 5).as_deref(),
+            Some("Flowering-3")
+        );
+        assert_eq!(
+            pick(PlantStage::Flowering, 9).as_deref(),
+            Some("Flowering-6")
+        );
+        assert_eq!(pick(PlantStage::Vegetative, 1), None);
+        assert_eq!(pick(PlantStage::Seedling, 4), None);
+    }
+
+    #[test]
+    fn test_validate_feeding_log() {
+        assert!(request(1.5, Some(1.8), Some(6.2)).validate().is_ok());
+        assert!(request(1.5, Some(0.0), Some(0.0)).validate().is_ok());
+
+        match request(0.0, Some(-0.1), Some(14.5)).validate() {
+            Err(ErrorCode::VALIDATION(errors)) => {
+                let keys: Vec<&str> = errors.keys().map(String::as_str).collect();
+                assert_eq!(keys, ["ec", "ph", "volume"]);
+            }
+            other => panic!("Expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_schedules_of_other_organizations_are_not_changed() {
+        let (client, mock) = PrismaClient::_mock();
+        // A recipe fed in several weeks is counted once
+        mock.expect(
+            client.feed_recipe().count(vec![
+                feed_recipe::id::in_vec(vec!["foreign".to_string(), "recipe".to_string()]),
+                feed_recipe::organization_id::equals("organization".to_string()),
+            ]),
+            1,
+        )
+        .await;
+        mock.expect(
+            client.feed_schedule().delete_many(vec![
+                feed_schedule::id::equals("schedule".to_string()),
+                feed_schedule::organization_id::equals("organization".to_string()),
+            ]),
+            0,
+        )
+        .await;
+        let data = web::Data::new(client);
+
+        let entry = |week: i32, recipe_id: &str| ScheduleEntryRequest {
+            stage: PlantStage::Flowering,
+            week,
+            recipe_id: recipe_id.to_string(),
+        };
+        let schedule = ScheduleRequest {
+            name: "Bloom".to_string(),
+            entries: vec![entry(1, "recipe"), entry(2, "recipe"), entry(3, "foreign")],
+        };
+        assert!(matches!(
+            create_schedule(&data, "organization", schedule).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+        assert!(matches!(
+            delete_schedule(&data, "organization", "schedule".to_string()).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::service::genetic::{
+        build_pedigree, creates_cycle, is_valid_generation, normalize_list, validate_range, Lineage,
+    };
+
+    fn lineage(id: &str, female: Option<&str>, male: Option<&str>) -> (String, Lineage) {
+        (
+            id.to_string(),
+            Lineage {
+                id: id.to_string(),
+                name: id.to_uppercase(),
+                generation: None,
+                breeder: None,
+                female_parent_id: female.map(str::to_string),
+                male_parent_id: male.map(str::to_string),
+            },
+        )
+    }
+
+    // a and b are crossed to c, c is selfed to d
+    fn lineages() -> HashMap<String, Lineage> {
+        HashMap::from([
+            lineage("a", None, None),
+            lineage("b", None, None),
+            lineage("c", Some("a"), Some("b")),
+            lineage("d", Some("c"), Some("c")),
+        ])
+    }
+
+    #[test]
+    fn test_is_valid_generation() {
+        for generation in ["P", "F1", "F12", "BX", "BX2", "S1", "IBL"] {
+            assert!(is_valid_generation(generation), "{}", generation);
+        }
+        for generation in ["", "F", "F0", "F100", "BX10", "S", "X1", "f1"] {
+            assert!(!is_valid_generation(generation), "{}", generation);
+        }
+    }
+
+    #[test]
+    fn test_creates_cycle() {
+        let lineages = lineages();
+        assert!(creates_cycle(&lineages, "a", &["a"]));
+        assert!(creates_cycle(&lineages, "a", &["d"]));
+        assert!(creates_cycle(&lineages, "b", &["a", "c"]));
+        assert!(!creates_cycle(&lineages, "d", &["a", "b"]));
+        assert!(!creates_cycle(&lineages, "a", &["b"]));
+    }
+
+    #[test]
+    fn test_build_pedigree() {
+        let lineages = lineages();
+        let pedigree = build_pedigree(&lineages, "d", 5).unwrap();
+        assert_eq!(pedigree.cross.as_deref(), Some("C selfed"));
+
+        let female = pedigree.female.unwrap();
+        assert_eq!(female.cross.as_deref(), Some("A × B"));
+        assert_eq!(female.male.unwrap().name, "B");
+
+        let shallow = build_pedigree(&lineages, "d", 1).unwrap();
+        assert!(shallow.female.unwrap().female.is_none());
+
+        assert!(build_pedigree(&lineages, "missing", 5).is_none());
+    }
+
+    #[test]
+    fn test_normalize_list() {
+        assert_eq!(
+            normalize_list(" Myrcene,limonene, ,MYRCENE"),
+            vec!["myrcene".to_string(), "limonene".to_string()]
+        );
+        assert!(normalize_list("").is_empty());
+    }
+
+    #[test]
+    fn test_validate_range() {
+        assert!(validate_range("THC", Some(18.0), Some(24.0)).is_ok());
+        assert!(validate_range("THC", None, Some(24.0)).is_ok());
+        assert!(validate_range("THC", Some(24.0), Some(18.0)).is_err());
+        assert!(validate_range("CBD", Some(-1.0), None).is_err());
+        assert!(validate_range("CBD", None, Some(101.0)).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        prisma::PlantStage,
+        service::harvest::{can_harvest, check_dry_weight},
+    };
+
+    #[test]
+    fn test_can_harvest() {
+        assert!(can_harvest(PlantStage::Flowering));
+        assert!(can_harvest(PlantStage::Harvest));
+        assert!(!can_harvest(PlantStage::Seedling));
+        assert!(!can_harvest(PlantStage::Vegetative));
+        assert!(!can_harvest(PlantStage::Cured));
+        assert!(!can_harvest(PlantStage::Sold));
+    }
+
+    #[test]
+    fn test_check_dry_weight() {
+        assert!(check_dry_weight(None, &[]).is_ok());
+        assert!(check_dry_weight(Some(80.0), &[50.0, 30.0]).is_ok());
+        assert!(check_dry_weight(Some(79.9995), &[50.0, 30.0]).is_ok());
+        assert!(check_dry_weight(Some(79.0), &[50.0, 30.0]).is_err());
+        // Packaged weight needs a dry weight to come from
+        assert!(check_dry_weight(None, &[50.0]).is_err());
+    }
+}
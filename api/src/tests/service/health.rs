@@ -0,0 +1,87 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use crate::{
+        model::dto::health::{DependencyHealth, HealthStatus},
+        service::health::{check_redis, overall_status, probe},
+    };
+
+    fn check(critical: bool, status: HealthStatus) -> DependencyHealth {
+        DependencyHealth {
+            name: "test".to_string(),
+            status,
+            critical,
+            latency_ms: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_overall_status() {
+        assert_eq!(overall_status(&[]), HealthStatus::Up);
+        assert_eq!(
+            overall_status(&[
+                check(true, HealthStatus::Up),
+                check(false, HealthStatus::Up)
+            ]),
+            HealthStatus::Up
+        );
+        assert_eq!(
+            overall_status(&[
+                check(true, HealthStatus::Up),
+                check(false, HealthStatus::Down)
+            ]),
+            HealthStatus::Degraded
+        );
+        assert_eq!(
+            overall_status(&[
+                check(true, HealthStatus::Down),
+                check(false, HealthStatus::Up)
+            ]),
+            HealthStatus::Down
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_probe() {
+        let up = probe("database", true, async { Ok(()) }).await;
+        assert_eq!(up.status, HealthStatus::Up);
+
+        let down = probe("storage:local", false, async {
+            Err("read only".to_string())
+        })
+        .await;
+        assert_eq!(down.status, HealthStatus::Down);
+        assert!(!down.critical);
+        // The reason is only logged, the public report must not leak it
+        let report = serde_json::to_string(&down).unwrap();
+        assert!(!report.contains("read only"));
+    }
+
+    #[actix_rt::test]
+    async fn test_check_redis() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            for reply in [
+                &b"+PONG\r\n"[..],
+                &b"-NOAUTH Authentication required.\r\n"[..],
+            ] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0; 16];
+                let _ = stream.read(&mut request).await.unwrap();
+                stream.write_all(reply).await.unwrap();
+            }
+        });
+
+        assert!(check_redis(&address).await.is_ok());
+        assert!(check_redis(&address).await.is_err());
+    }
+}
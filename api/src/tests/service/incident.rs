@@ -0,0 +1,63 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::{
+        prisma::treatment,
+        service::incident::{pre_harvest_ends_at, running_intervals},
+    };
+
+    fn treatment(id: &str, applied_at: &str, pre_harvest_days: i32) -> treatment::Data {
+        treatment::Data {
+            id: id.to_string(),
+            incident: None,
+            incident_id: "incident".to_string(),
+            product: format!("Product {}", id),
+            dose: "2 ml/l".to_string(),
+            pre_harvest_days,
+            applied_at: DateTime::parse_from_rfc3339(applied_at).unwrap(),
+            applied_by: None,
+            applied_by_id: "user".to_string(),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_pre_harvest_ends_at() {
+        let applied_at = DateTime::parse_from_rfc3339("2024-06-01T10:00:00+02:00").unwrap();
+        assert_eq!(
+            pre_harvest_ends_at(applied_at, 14),
+            DateTime::parse_from_rfc3339("2024-06-15T10:00:00+02:00").unwrap()
+        );
+        assert_eq!(pre_harvest_ends_at(applied_at, -3), applied_at);
+    }
+
+    #[test]
+    fn test_running_intervals() {
+        let now = DateTime::parse_from_rfc3339("2024-06-10T12:00:00+00:00").unwrap();
+        let treatments = vec![
+            treatment("expired", "2024-06-01T10:00:00+00:00", 7),
+            treatment("short", "2024-06-08T10:00:00+00:00", 3),
+            treatment("long", "2024-06-05T10:00:00+00:00", 21),
+            treatment("none", "2024-06-10T10:00:00+00:00", 0),
+        ];
+
+        let intervals = running_intervals(&treatments, now);
+        let ids: Vec<&str> = intervals
+            .iter()
+            .map(|interval| interval.treatment_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["long", "short"]);
+        assert_eq!(
+            intervals[0].ends_at,
+            DateTime::parse_from_rfc3339("2024-06-26T10:00:00+00:00").unwrap()
+        );
+
+        let at_end = DateTime::parse_from_rfc3339("2024-06-11T10:00:00+00:00").unwrap();
+        assert_eq!(running_intervals(&treatments[1..2], at_end), vec![]);
+    }
+}
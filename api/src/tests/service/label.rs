@@ -0,0 +1,57 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::{
+        model::dto::label::LabelLayout,
+        service::label::{parse_qr_payload, render_pdf, render_zpl, zpl_field, PlantLabel},
+    };
+
+    fn label() -> PlantLabel {
+        PlantLabel {
+            name: "1A".to_string(),
+            genetic: "Northern_Lights^Auto".to_string(),
+            created: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            payload: "planters-cycle:plant:clx1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_qr_payload() {
+        assert_eq!(
+            parse_qr_payload("planters-cycle:plant:clx1"),
+            Some("clx1".to_string())
+        );
+        assert_eq!(
+            parse_qr_payload("https://grow.example/dashboard/plants/clx1?tab=history"),
+            Some("clx1".to_string())
+        );
+        assert_eq!(parse_qr_payload(" clx1\n"), Some("clx1".to_string()));
+        assert_eq!(parse_qr_payload("https://grow.example/genetics/1"), None);
+        assert_eq!(parse_qr_payload(""), None);
+    }
+
+    #[test]
+    fn test_render_zpl() {
+        assert_eq!(zpl_field("a^b~c_d"), "a_5Eb_7Ec_5Fd");
+
+        let zpl = render_zpl(&[label(), label()]);
+        assert_eq!(zpl.matches("^XA").count(), 2);
+        assert_eq!(zpl.matches("^XZ").count(), 2);
+        assert!(zpl.contains("^FDQA,planters-cycle:plant:clx1^FS"));
+        assert!(zpl.contains("Northern_5FLights_5EAuto"));
+    }
+
+    #[test]
+    fn test_render_pdf() {
+        let pdf = render_pdf(&vec![label(); 20], LabelLayout::L7160, 3).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+
+        let empty = render_pdf(&[], LabelLayout::Avery5160, 0).unwrap();
+        assert!(empty.starts_with(b"%PDF"));
+    }
+}
@@ -0,0 +1,49 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web;
+
+    use crate::{
+        model::{dto::Location, error::ErrorCode},
+        prisma::{location, PrismaClient},
+        service::location::{delete_location, edit_location},
+    };
+
+    #[actix_rt::test]
+    async fn test_locations_of_other_organizations_are_not_changed() {
+        let (client, mock) = PrismaClient::_mock();
+        mock.expect(
+            client.location().count(vec![
+                location::id::in_vec(vec!["location".to_string()]),
+                location::organization_id::equals("organization".to_string()),
+            ]),
+            0,
+        )
+        .await;
+        mock.expect(
+            client.location().delete_many(vec![
+                location::id::equals("location".to_string()),
+                location::organization_id::equals("organization".to_string()),
+            ]),
+            0,
+        )
+        .await;
+        let data = web::Data::new(client);
+
+        let renamed = Location {
+            name: Some("Renamed".to_string()),
+            description: None,
+        };
+        assert!(matches!(
+            edit_location(&data, "organization", "location".to_string(), renamed).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+        assert!(matches!(
+            delete_location(&data, "organization", "location".to_string()).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+    }
+}
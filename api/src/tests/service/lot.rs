@@ -0,0 +1,97 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::{
+        model::dto::lot::LotHarvestRequest,
+        service::lot::{
+            available_weight, lot_code, lot_sequence_key, plant_sold_out, validate_allocations,
+            validate_lot_code, HarvestUsage,
+        },
+    };
+
+    fn allocation(harvest_id: &str, weight: f64) -> LotHarvestRequest {
+        LotHarvestRequest {
+            harvest_id: harvest_id.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn test_validate_lot_code() {
+        assert!(validate_lot_code("LOT-20240601-001").is_ok());
+        assert!(validate_lot_code("gel_24.1").is_ok());
+        assert!(validate_lot_code("").is_err());
+        assert!(validate_lot_code("LOT 1").is_err());
+        assert!(validate_lot_code(&"A".repeat(33)).is_err());
+    }
+
+    #[test]
+    fn test_lot_code() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        assert_eq!(lot_code(date, 7), "LOT-20240601-007");
+        assert_eq!(lot_code(date, 1234), "LOT-20240601-1234");
+    }
+
+    #[test]
+    fn test_lot_sequence_key() {
+        let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        assert_ne!(
+            lot_sequence_key("org-a", date),
+            lot_sequence_key("org-b", date)
+        );
+        assert_eq!(lot_sequence_key("org-a", date), "lot:org-a:20240601");
+    }
+
+    #[test]
+    fn test_validate_allocations() {
+        assert_eq!(
+            validate_allocations(&[allocation("h1", 100.0), allocation("h2", 50.5)]),
+            Ok(150.5)
+        );
+        assert!(validate_allocations(&[]).is_err());
+        assert!(validate_allocations(&[allocation("h1", 0.0)]).is_err());
+        assert!(validate_allocations(&[allocation("h1", f64::NAN)]).is_err());
+        assert!(validate_allocations(&[allocation("h1", 10.0), allocation("h1", 5.0)]).is_err());
+    }
+
+    #[test]
+    fn test_available_weight() {
+        assert_eq!(available_weight(Some(100.0), &[]), Some(100.0));
+        assert_eq!(available_weight(Some(100.0), &[30.0, 20.0]), Some(50.0));
+        assert_eq!(available_weight(Some(100.0), &[120.0]), Some(0.0));
+        assert_eq!(available_weight(None, &[]), None);
+    }
+
+    #[test]
+    fn test_plant_sold_out() {
+        let sold = HarvestUsage {
+            dry_weight: Some(100.0),
+            lots: vec![(60.0, 0.0), (40.0, 0.0)],
+        };
+        assert!(plant_sold_out(&[sold.clone()]));
+
+        let unsold_lot = HarvestUsage {
+            dry_weight: Some(100.0),
+            lots: vec![(60.0, 0.0), (40.0, 12.5)],
+        };
+        assert!(!plant_sold_out(&[sold.clone(), unsold_lot]));
+
+        let unpackaged = HarvestUsage {
+            dry_weight: Some(100.0),
+            lots: vec![(60.0, 0.0)],
+        };
+        assert!(!plant_sold_out(&[unpackaged]));
+
+        let not_dried = HarvestUsage {
+            dry_weight: None,
+            lots: vec![],
+        };
+        assert!(!plant_sold_out(&[sold, not_dried]));
+        assert!(!plant_sold_out(&[]));
+    }
+}
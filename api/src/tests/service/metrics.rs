@@ -0,0 +1,58 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::http::{Method, StatusCode};
+
+    use crate::service::metrics::{metrics, Metrics, TimedQuery};
+
+    #[test]
+    fn test_render_exports_collectors() {
+        let metrics = Metrics::new();
+        metrics.observe_request(
+            &Method::GET,
+            Some("/api/plants/{id}"),
+            StatusCode::OK,
+            Duration::from_millis(5),
+        );
+        metrics.observe_request(
+            &Method::GET,
+            None,
+            StatusCode::NOT_FOUND,
+            Duration::from_millis(1),
+        );
+        metrics.observe_login(true);
+        metrics.observe_login(false);
+        metrics.observe_login(false);
+
+        let rendered = metrics.render();
+        for line in [
+            r#"planters_cycle_http_requests_total{method="GET",route="/api/plants/{id}",status="200"} 1"#,
+            r#"planters_cycle_http_requests_total{method="GET",route="unmatched",status="404"} 1"#,
+            r#"planters_cycle_http_request_duration_seconds_count{method="GET",route="/api/plants/{id}",status="200"} 1"#,
+            r#"planters_cycle_logins_total{result="success"} 1"#,
+            r#"planters_cycle_logins_total{result="failure"} 2"#,
+            "planters_cycle_active_sessions 0",
+        ] {
+            assert!(rendered.contains(line), "{} missing in\n{}", line, rendered);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_timed_query_observes_duration() {
+        let histogram = metrics()
+            .db_duration
+            .with_label_values(&["timed_test", "count"]);
+        let before = histogram.get_sample_count();
+
+        let count = async { Ok::<i64, String>(3) }
+            .timed("timed_test", "count")
+            .await;
+        assert_eq!(count, Ok(3));
+        assert_eq!(histogram.get_sample_count(), before + 1);
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::service::naming::{
+        derive_genetic_code, parse_template, plant_sequence_key, render_name, template_setting,
+        NamingContext, NamingToken, DEFAULT_TEMPLATE,
+    };
+
+    fn context() -> NamingContext {
+        NamingContext {
+            genetic_code: "NL".to_string(),
+            genetic: "Northern Lights".to_string(),
+            mother: Some("NL-M1".to_string()),
+            batch: None,
+            date: NaiveDate::from_ymd_opt(2024, 3, 7).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_template() {
+        assert_eq!(
+            parse_template("{GENETIC_CODE}-{SEQ:04}").unwrap(),
+            vec![
+                NamingToken::GeneticCode,
+                NamingToken::Text("-".to_string()),
+                NamingToken::Sequence {
+                    width: 4,
+                    hex: false
+                },
+            ]
+        );
+        assert!(parse_template(DEFAULT_TEMPLATE).is_ok());
+        assert!(parse_template("{GENETIC_CODE}").is_err());
+        assert!(parse_template("{SEQ}-{SEQ}").is_err());
+        assert!(parse_template("{SEQ").is_err());
+        assert!(parse_template("SEQ}").is_err());
+        assert!(parse_template("{UNKNOWN}-{SEQ}").is_err());
+        assert!(parse_template("{SEQ:abc}").is_err());
+    }
+
+    #[test]
+    fn test_render_name() {
+        let tokens = parse_template("{GENETIC_CODE}-{YEAR}{MONTH}-{SEQ:04}").unwrap();
+        assert_eq!(
+            render_name(&tokens, &context(), Some(12)).unwrap(),
+            "NL-202403-0012"
+        );
+        assert_eq!(
+            render_name(&tokens, &context(), None).unwrap(),
+            "NL-202403-#"
+        );
+
+        let tokens = parse_template("{MOTHER}-C{SEQ}").unwrap();
+        assert_eq!(
+            render_name(&tokens, &context(), Some(3)).unwrap(),
+            "NL-M1-C3"
+        );
+
+        let tokens = parse_template(DEFAULT_TEMPLATE).unwrap();
+        assert_eq!(render_name(&tokens, &context(), Some(26)).unwrap(), "1A");
+
+        let tokens = parse_template("{BATCH}-{SEQ}").unwrap();
+        assert!(render_name(&tokens, &context(), Some(1)).is_err());
+    }
+
+    #[test]
+    fn test_derive_genetic_code() {
+        assert_eq!(derive_genetic_code("Northern Lights"), "NL");
+        assert_eq!(derive_genetic_code("Gelato"), "GEL");
+        assert_eq!(derive_genetic_code("white widow #2"), "WW2");
+    }
+
+    #[test]
+    fn test_naming_keys_are_scoped_to_the_organization() {
+        assert_ne!(template_setting("org-a"), template_setting("org-b"));
+        assert_ne!(
+            plant_sequence_key("org-a", "NL-202403-#"),
+            plant_sequence_key("org-b", "NL-202403-#")
+        );
+        assert_eq!(
+            plant_sequence_key("org-a", "NL-202403-#"),
+            "plant:org-a:NL-202403-#"
+        );
+    }
+}
@@ -0,0 +1,41 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        model::error::ErrorCode,
+        prisma::{role, users_in_roles, PrismaClient},
+        service::organization::{require_role_admin, DEFAULT_ORGANIZATION_ID},
+    };
+
+    #[actix_rt::test]
+    async fn test_roles_are_managed_by_admins_of_the_default_organization() {
+        let (client, mock) = PrismaClient::_mock();
+        // Only the admin flag counts, a role renamed to "Admin" grants nothing
+        for roles in [0, 1] {
+            mock.expect(
+                client.users_in_roles().count(vec![
+                    users_in_roles::user_id::equals("user".to_string()),
+                    users_in_roles::organization_id::equals(DEFAULT_ORGANIZATION_ID.to_string()),
+                    users_in_roles::role::is(vec![role::is_admin::equals(true)]),
+                ]),
+                roles,
+            )
+            .await;
+        }
+
+        assert!(matches!(
+            require_role_admin(&client, "user", "organization").await,
+            Err(ErrorCode::ORG001)
+        ));
+        assert!(matches!(
+            require_role_admin(&client, "user", DEFAULT_ORGANIZATION_ID).await,
+            Err(ErrorCode::ORG001)
+        ));
+        assert!(require_role_admin(&client, "user", DEFAULT_ORGANIZATION_ID)
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,351 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web;
+    use chrono::DateTime;
+    use prisma_client_rust::{raw, MockStore, PrismaValue};
+    use serde_json::{json, Value};
+
+    use crate::{
+        model::{dto::seed::GerminateRequest, error::ErrorCode},
+        prisma::{
+            genetic, plant, plant_history, plant_limit, seed_germination, seed_pack, PlantStage,
+            PrismaClient,
+        },
+        service::seed::{germinate_seeds, germination_rate},
+    };
+
+    fn request(count: i32, germinated: Option<i32>) -> GerminateRequest {
+        GerminateRequest {
+            count,
+            germinated,
+            batch_id: None,
+            location_id: None,
+        }
+    }
+
+    fn in_organization() -> seed_pack::WhereParam {
+        seed_pack::genetic::is(vec![genetic::organization_id::equals(
+            "organization".to_string(),
+        )])
+    }
+
+    fn pack(remaining: i32) -> seed_pack::Data {
+        serde_json::from_value(json!({
+            "id": "pack",
+            "geneticId": "genetic",
+            "label": "Pack 1",
+            "seedType": "FEMINIZED",
+            "count": 10,
+            "remaining": remaining,
+            "createdAt": "2024-06-01T12:00:00+00:00",
+        }))
+        .unwrap()
+    }
+
+    fn germination(sown: i32, germinated: i32) -> seed_germination::Data {
+        serde_json::from_value(json!({
+            "id": "germination",
+            "seedPackId": "pack",
+            "sown": sown,
+            "germinated": germinated,
+            "userId": "user",
+            "createdAt": "2024-06-01T12:00:00+00:00",
+        }))
+        .unwrap()
+    }
+
+    fn seedling(name: &str) -> plant::Data {
+        serde_json::from_value(json!({
+            "id": name,
+            "name": name,
+            "geneticId": "genetic",
+            "createdAt": "2024-06-01T12:00:00+00:00",
+            "updatedAt": "2024-06-01T12:00:00+00:00",
+            "stage": "SEEDLING",
+            "seedPackId": "pack",
+            "germinationId": "germination",
+            "organizationId": "organization",
+        }))
+        .unwrap()
+    }
+
+    fn history(plant_id: &str) -> plant_history::Data {
+        serde_json::from_value(json!({
+            "id": format!("history-{}", plant_id),
+            "plantId": plant_id,
+            "action": "Germinated from seed pack Pack 1",
+            "stage": "SEEDLING",
+            "createdAt": "2024-06-01T12:00:00+00:00",
+            "userId": "user",
+            "organizationId": "organization",
+        }))
+        .unwrap()
+    }
+
+    // Expect the seed pack to be decremented by `count` seeds
+    async fn expect_seeds_taken(
+        client: &PrismaClient,
+        mock: &MockStore,
+        count: i32,
+        taken: i64,
+        remaining: i32,
+    ) {
+        mock.expect(
+            client.seed_pack().update_many(
+                vec![
+                    seed_pack::id::equals("pack".to_string()),
+                    in_organization(),
+                    seed_pack::remaining::gte(count),
+                ],
+                vec![seed_pack::remaining::decrement(count)],
+            ),
+            taken,
+        )
+        .await;
+        mock.expect(
+            client.seed_pack().find_first(vec![
+                seed_pack::id::equals("pack".to_string()),
+                in_organization(),
+            ]),
+            Some(pack(remaining)),
+        )
+        .await;
+    }
+
+    // Expect a seedling named by the genetic's template `NL-{SEQ:02}`
+    async fn expect_seedling(client: &PrismaClient, mock: &MockStore, sequence: i64) {
+        let name = format!("NL-{:02}", sequence);
+        mock.expect(
+            client.genetic().find_first(vec![
+                genetic::id::equals("genetic".to_string()),
+                genetic::organization_id::equals("organization".to_string()),
+            ]),
+            Some(
+                serde_json::from_value(json!({
+                    "id": "genetic",
+                    "name": "Northern Lights",
+                    "flowerDays": 56,
+                    "code": "NL",
+                    "namingTemplate": "NL-{SEQ:02}",
+                    "terpenes": [],
+                    "autoflower": false,
+                    "tags": [],
+                    "organizationId": "organization",
+                }))
+                .unwrap(),
+            ),
+        )
+        .await;
+        mock.expect(
+            client._query_raw::<Value>(raw!(
+                r#"INSERT INTO "NameSequence" ("key", "value") VALUES ({}, 1)
+            ON CONFLICT ("key") DO UPDATE SET "value" = "NameSequence"."value" + 1
+            RETURNING "value""#,
+                PrismaValue::String("plant:organization:NL-#".to_string())
+            )),
+            vec![json!({ "value": sequence })],
+        )
+        .await;
+        mock.expect(
+            client.plant().count(vec![
+                plant::name::equals(name.clone()),
+                plant::organization_id::equals("organization".to_string()),
+            ]),
+            0,
+        )
+        .await;
+        mock.expect(
+            client.plant().create_unchecked(
+                name.clone(),
+                "genetic".to_string(),
+                "organization".to_string(),
+                vec![
+                    plant::stage::set(PlantStage::Seedling),
+                    plant::seed_pack_id::set(Some("pack".to_string())),
+                    plant::germination_id::set(Some("germination".to_string())),
+                    plant::batch_id::set(None),
+                    plant::location_id::set(None),
+                ],
+            ),
+            seedling(&name),
+        )
+        .await;
+        mock.expect(
+            client.plant_history().create_unchecked(
+                name.clone(),
+                "Germinated from seed pack Pack 1".to_string(),
+                "user".to_string(),
+                "organization".to_string(),
+                vec![plant_history::stage::set(Some(PlantStage::Seedling))],
+            ),
+            history(&name),
+        )
+        .await;
+    }
+
+    #[test]
+    fn test_germination_rate() {
+        assert_eq!(germination_rate(0, 0), None);
+        assert_eq!(germination_rate(4, 3), Some(0.75));
+        assert_eq!(germination_rate(5, 0), Some(0.0));
+    }
+
+    #[actix_rt::test]
+    async fn test_germinate_seeds_creates_seedlings() {
+        let (client, mock) = PrismaClient::_mock();
+        expect_seeds_taken(&client, &mock, 3, 1, 7).await;
+        mock.expect(
+            client.seed_germination().create_unchecked(
+                "pack".to_string(),
+                3,
+                2,
+                "user".to_string(),
+                vec![],
+            ),
+            germination(3, 2),
+        )
+        .await;
+        for sequence in [1, 2] {
+            expect_seedling(&client, &mock, sequence).await;
+        }
+        mock.expect(
+            client
+                .plant_limit()
+                .find_many(vec![plant_limit::organization_id::equals(
+                    "organization".to_string(),
+                )])
+                .with(plant_limit::location::fetch()),
+            vec![],
+        )
+        .await;
+        let data = web::Data::new(client);
+
+        let result = germinate_seeds(
+            &data,
+            "organization",
+            "pack".to_string(),
+            request(3, Some(2)),
+            "user".to_string(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.germination.sown, 3);
+        assert_eq!(result.germination.germinated, 2);
+        let names: Vec<&str> = result.plants.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["NL-01", "NL-02"]);
+        assert!(result
+            .plants
+            .iter()
+            .all(|plant| plant.stage == PlantStage::Seedling));
+    }
+
+    #[actix_rt::test]
+    async fn test_germinate_seeds_needs_enough_seeds() {
+        let (client, mock) = PrismaClient::_mock();
+        expect_seeds_taken(&client, &mock, 3, 0, 2).await;
+        let data = web::Data::new(client);
+
+        match germinate_seeds(
+            &data,
+            "organization",
+            "pack".to_string(),
+            request(3, None),
+            "user".to_string(),
+        )
+        .await
+        {
+            Err(ErrorCode::BADREQUEST(message)) => {
+                assert_eq!(message, "Only 2 seeds left in the pack")
+            }
+            other => panic!("Expected a bad request, got {:?}", other),
+        }
+
+        // Rejected before any seed is taken
+        for invalid in [request(0, None), request(3, Some(4)), request(3, Some(-1))] {
+            assert!(matches!(
+                germinate_seeds(
+                    &data,
+                    "organization",
+                    "pack".to_string(),
+                    invalid,
+                    "user".to_string()
+                )
+                .await,
+                Err(ErrorCode::BADREQUEST(_))
+            ));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_germinate_seeds_respects_plant_limits() {
+        let (client, mock) = PrismaClient::_mock();
+        expect_seeds_taken(&client, &mock, 1, 1, 9).await;
+        mock.expect(
+            client.seed_germination().create_unchecked(
+                "pack".to_string(),
+                1,
+                1,
+                "user".to_string(),
+                vec![],
+            ),
+            germination(1, 1),
+        )
+        .await;
+        expect_seedling(&client, &mock, 1).await;
+        mock.expect(
+            client
+                .plant_limit()
+                .find_many(vec![plant_limit::organization_id::equals(
+                    "organization".to_string(),
+                )])
+                .with(plant_limit::location::fetch()),
+            vec![plant_limit::Data {
+                id: "limit".to_string(),
+                location: None,
+                location_id: None,
+                stage: Some(PlantStage::Seedling),
+                max_plants: 1,
+                note: None,
+                created_at: DateTime::parse_from_rfc3339("2024-06-01T10:00:00+00:00").unwrap(),
+                organization: None,
+                organization_id: "organization".to_string(),
+            }],
+        )
+        .await;
+        mock.expect(
+            client._query_raw::<Value>(raw!(
+                r#"SELECT "id" FROM "PlantLimit" WHERE "organizationId" = {}
+            ORDER BY "id" FOR UPDATE"#,
+                PrismaValue::String("organization".to_string())
+            )),
+            vec![],
+        )
+        .await;
+        // The new seedling is the second one
+        mock.expect(
+            client.plant().count(vec![
+                plant::organization_id::equals("organization".to_string()),
+                plant::stage::in_vec(vec![PlantStage::Seedling]),
+            ]),
+            2,
+        )
+        .await;
+        let data = web::Data::new(client);
+
+        assert!(matches!(
+            germinate_seeds(
+                &data,
+                "organization",
+                "pack".to_string(),
+                request(1, None),
+                "user".to_string(),
+            )
+            .await,
+            Err(ErrorCode::LIMIT001(_))
+        ));
+    }
+}
@@ -0,0 +1,115 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web;
+    use chrono::DateTime;
+
+    use crate::{
+        model::error::ErrorCode,
+        prisma::{task, task_template, PrismaClient},
+        service::task::{delete_task, delete_task_template, roll_forward, TemplateScope},
+    };
+
+    fn at(value: &str) -> DateTime<chrono::FixedOffset> {
+        DateTime::parse_from_rfc3339(value).unwrap()
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_roll_forward() {
+        let end_of_today = at("2024-06-11T00:00:00+00:00");
+
+        // Due today, nothing was missed
+        assert_eq!(
+            roll_forward(at("2024-06-10T08:00:00+00:00"), 2, end_of_today),
+            (
+                at("2024-06-10T08:00:00+00:00"),
+                at("2024-06-12T08:00:00+00:00")
+            )
+        );
+        // Missed occurrences collapse into the last one before the end of today
+        assert_eq!(
+            roll_forward(at("2024-06-01T08:00:00+00:00"), 2, end_of_today),
+            (
+                at("2024-06-09T08:00:00+00:00"),
+                at("2024-06-11T08:00:00+00:00")
+            )
+        );
+        assert_eq!(
+            roll_forward(at("2024-05-01T08:00:00+00:00"), 7, end_of_today),
+            (
+                at("2024-06-05T08:00:00+00:00"),
+                at("2024-06-12T08:00:00+00:00")
+            )
+        );
+        // Intervals below a day are treated as daily
+        assert_eq!(
+            roll_forward(at("2024-06-08T08:00:00+00:00"), 0, end_of_today),
+            (
+                at("2024-06-10T08:00:00+00:00"),
+                at("2024-06-11T08:00:00+00:00")
+            )
+        );
+    }
+
+    #[test]
+    fn test_template_scope_covers() {
+        let everything = TemplateScope::default();
+        assert!(everything.covers("p1", None, None));
+        assert!(everything.covers("p2", Some("b1"), Some("l1")));
+
+        let batch = TemplateScope {
+            batch_ids: ids(&["b1"]),
+            ..Default::default()
+        };
+        assert!(batch.covers("p1", Some("b1"), None));
+        assert!(!batch.covers("p1", Some("b2"), Some("l1")));
+        assert!(!batch.covers("p1", None, None));
+
+        let mixed = TemplateScope {
+            plant_ids: ids(&["p1"]),
+            batch_ids: vec![],
+            location_ids: ids(&["l1", "l2"]),
+        };
+        assert!(mixed.covers("p1", None, None));
+        assert!(mixed.covers("p2", Some("b1"), Some("l2")));
+        assert!(!mixed.covers("p2", Some("b1"), Some("l3")));
+    }
+
+    #[actix_rt::test]
+    async fn test_tasks_of_other_organizations_are_not_deleted() {
+        let (client, mock) = PrismaClient::_mock();
+        mock.expect(
+            client.task().delete_many(vec![
+                task::id::equals("task".to_string()),
+                task::organization_id::equals("organization".to_string()),
+            ]),
+            0,
+        )
+        .await;
+        mock.expect(
+            client.task_template().delete_many(vec![
+                task_template::id::equals("template".to_string()),
+                task_template::organization_id::equals("organization".to_string()),
+            ]),
+            0,
+        )
+        .await;
+        let data = web::Data::new(client);
+
+        assert!(matches!(
+            delete_task(&data, "organization", "task".to_string()).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+        assert!(matches!(
+            delete_task_template(&data, "organization", "template".to_string()).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::{
+        model::dto::trace::{
+            PlantTrace, TraceAncestor, TraceDestruction, TraceEvent, TraceGenetic, TraceHarvest,
+            TraceLot, TraceReport, TraceSale,
+        },
+        prisma::{DestructionReason, PlantStage},
+        service::{
+            report::{wrap_text, ReportLine},
+            trace::{involved_users, mother_chain, trace_csv, trace_lines},
+        },
+    };
+
+    fn plant() -> PlantTrace {
+        let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap();
+        PlantTrace {
+            id: "p1".to_string(),
+            name: "GEL-1".to_string(),
+            stage: PlantStage::Harvest,
+            created_at: at("2024-03-01T08:00:00+00:00"),
+            genetic: TraceGenetic {
+                id: "g1".to_string(),
+                name: "Gelato".to_string(),
+                code: Some("GEL".to_string()),
+                breeder: None,
+            },
+            batch: Some("Spring".to_string()),
+            location: None,
+            seed_pack: None,
+            mothers: vec![
+                TraceAncestor {
+                    id: "m1".to_string(),
+                    name: "M1".to_string(),
+                    genetic: "Gelato".to_string(),
+                },
+                TraceAncestor {
+                    id: "m0".to_string(),
+                    name: "M0".to_string(),
+                    genetic: "Gelato".to_string(),
+                },
+            ],
+            events: vec![
+                TraceEvent {
+                    at: at("2024-04-01T08:00:00+00:00"),
+                    action: "Stage changed from VEGETATIVE to FLOWERING".to_string(),
+                    stage: Some(PlantStage::Flowering),
+                    user: "Alex".to_string(),
+                },
+                TraceEvent {
+                    at: at("2024-06-01T08:00:00+00:00"),
+                    action: "Stage changed from FLOWERING to HARVEST".to_string(),
+                    stage: Some(PlantStage::Harvest),
+                    user: "Sam".to_string(),
+                },
+            ],
+            harvests: vec![TraceHarvest {
+                id: "h1".to_string(),
+                harvested_at: at("2024-06-01T08:00:01+00:00"),
+                wet_weight: Some(420.0),
+                dry_weight: None,
+                user: "Sam".to_string(),
+                lots: vec![],
+            }],
+            destruction: None,
+        }
+    }
+
+    fn report(plants: Vec<PlantTrace>) -> TraceReport {
+        TraceReport {
+            subject: "plant".to_string(),
+            subject_id: "p1".to_string(),
+            subject_name: "GEL-1".to_string(),
+            generated_at: DateTime::parse_from_rfc3339("2024-07-01T08:00:00+00:00").unwrap(),
+            users: involved_users(&plants, None),
+            lot: None,
+            plants,
+        }
+    }
+
+    #[test]
+    fn test_involved_users() {
+        let mut destroyed = plant();
+        destroyed.destruction = Some(TraceDestruction {
+            id: "d1".to_string(),
+            destroyed_at: DateTime::parse_from_rfc3339("2024-06-02T08:00:00+00:00").unwrap(),
+            reason: DestructionReason::Mold,
+            method: "Composted".to_string(),
+            weight: 100.0,
+            recorded_by: "Sam".to_string(),
+            witnesses: vec!["Kim".to_string()],
+        });
+        assert_eq!(
+            involved_users(&[plant(), destroyed], None),
+            vec!["Alex".to_string(), "Kim".to_string(), "Sam".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_mother_chain() {
+        assert_eq!(mother_chain(&plant().mothers), "M1 (Gelato) < M0 (Gelato)");
+        assert_eq!(mother_chain(&[]), "");
+    }
+
+    #[test]
+    fn test_trace_csv() {
+        let csv = trace_csv(&report(vec![plant()])).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "plantId,plantName,genetic,batch,mothers,at,kind,description,stage,user"
+        );
+        assert!(lines[1].contains("FLOWERING"));
+        assert!(lines[2].contains(",HISTORY,") && lines[2].contains(",HARVEST,Sam"));
+        assert!(lines[3].contains(",HARVEST,\"Wet 420 g, dry -\",,Sam"));
+    }
+
+    #[test]
+    fn test_lot_trace() {
+        let at = |time: &str| DateTime::parse_from_rfc3339(time).unwrap();
+        let lot = TraceLot {
+            id: "l1".to_string(),
+            code: "LOT-20240701-001".to_string(),
+            weight: 100.0,
+            remaining: 0.0,
+            packaged_at: at("2024-07-01T08:00:00+00:00"),
+            packaged_by: "Kim".to_string(),
+            sales: vec![TraceSale {
+                id: "s1".to_string(),
+                sold_at: at("2024-07-02T08:00:00+00:00"),
+                weight: 100.0,
+                customer: Some("Dispensary".to_string()),
+                reference: Some("INV-1".to_string()),
+                user: "Robin".to_string(),
+            }],
+        };
+        let mut report = report(vec![plant()]);
+        report.users = involved_users(&report.plants, Some(&lot));
+        report.lot = Some(lot);
+
+        assert_eq!(report.users, vec!["Alex", "Kim", "Robin", "Sam"]);
+        let csv = trace_csv(&report).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[4].starts_with(",,,,,") && lines[4].contains("PACKAGING"));
+        assert!(lines[5].contains(
+            ",SALE,\"Lot LOT-20240701-001: 100 g sold to Dispensary, reference INV-1\",,Robin"
+        ));
+
+        let lines = trace_lines(&report);
+        assert_eq!(
+            lines[2],
+            ReportLine::Heading("Lot LOT-20240701-001".to_string())
+        );
+    }
+
+    #[test]
+    fn test_trace_lines() {
+        let lines = trace_lines(&report(vec![plant()]));
+
+        assert_eq!(
+            lines[0],
+            ReportLine::Heading("Traceability report: plant GEL-1".to_string())
+        );
+        assert!(lines.contains(&ReportLine::Heading("Plant GEL-1 (HARVEST)".to_string())));
+        assert!(lines.contains(&ReportLine::Text("Genetic: Gelato (GEL)".to_string())));
+        assert!(lines.contains(&ReportLine::Text(
+            "Mother chain: M1 (Gelato) < M0 (Gelato)".to_string()
+        )));
+        assert_eq!(
+            lines.last(),
+            Some(&ReportLine::Text("Alex, Sam".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(wrap_text("a bb ccc dddd", 6), vec!["a bb", "ccc", "dddd"]);
+        assert_eq!(
+            wrap_text("abcdefghij kl", 4),
+            vec!["abcd", "efgh", "ij", "kl"]
+        );
+        assert_eq!(wrap_text("", 4), vec![""]);
+    }
+}
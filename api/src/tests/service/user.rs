@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web;
+
+    use crate::{
+        model::error::ErrorCode,
+        prisma::{role, user, users_in_roles, PrismaClient},
+        service::user::{
+            delete_user_by_id, edit_user_by_id, find_in_organization, in_organization,
+        },
+    };
+
+    fn member() -> user::Data {
+        serde_json::from_value(serde_json::json!({
+            "id": "member",
+            "displayName": "Grower",
+            "email": "grower@example.com",
+            "password": "",
+            "lastLogin": null,
+            "createdAt": "2024-06-01T12:00:00+00:00",
+            "calendarToken": null,
+        }))
+        .unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_users_of_other_organizations_are_not_found() {
+        let (client, mock) = PrismaClient::_mock();
+        // Only the query restricted to the organization is answered, an unfiltered
+        // lookup by id would not match the expectations
+        for _ in 0..2 {
+            mock.expect(
+                client.user().find_first(vec![
+                    user::id::equals("stranger".to_string()),
+                    in_organization("organization"),
+                ]),
+                None,
+            )
+            .await;
+        }
+        let data = web::Data::new(client);
+
+        assert!(matches!(
+            find_in_organization("stranger", "organization", &data).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+        // The user is looked up before anything is deleted
+        assert!(matches!(
+            delete_user_by_id("stranger", "organization", "admin", &data).await,
+            Err(ErrorCode::DATABASE002)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_only_admins_edit_users() {
+        let (client, mock) = PrismaClient::_mock();
+        mock.expect(
+            client.users_in_roles().count(vec![
+                users_in_roles::user_id::equals("grower".to_string()),
+                users_in_roles::organization_id::equals("organization".to_string()),
+                users_in_roles::role::is(vec![role::is_admin::equals(true)]),
+            ]),
+            0,
+        )
+        .await;
+        let data = web::Data::new(client);
+
+        assert!(matches!(
+            edit_user_by_id(
+                "member",
+                &data,
+                member(),
+                "grower".to_string(),
+                "organization"
+            )
+            .await,
+            Err(ErrorCode::ORG001)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_credentials_of_shared_accounts_are_kept() {
+        let (client, mock) = PrismaClient::_mock();
+        mock.expect(
+            client.users_in_roles().count(vec![
+                users_in_roles::user_id::equals("admin".to_string()),
+                users_in_roles::organization_id::equals("organization".to_string()),
+                users_in_roles::role::is(vec![role::is_admin::equals(true)]),
+            ]),
+            1,
+        )
+        .await;
+        mock.expect(
+            client.user().find_first(vec![
+                user::id::equals("member".to_string()),
+                in_organization("organization"),
+            ]),
+            Some(member()),
+        )
+        .await;
+        // The member also works in another organization
+        mock.expect(
+            client.users_in_roles().count(vec![
+                users_in_roles::user_id::equals("member".to_string()),
+                users_in_roles::organization_id::not("organization".to_string()),
+            ]),
+            1,
+        )
+        .await;
+        let data = web::Data::new(client);
+
+        let mut takeover = member();
+        takeover.email = "attacker@example.com".to_string();
+        takeover.password = "new password".to_string();
+        assert!(matches!(
+            edit_user_by_id(
+                "member",
+                &data,
+                takeover,
+                "admin".to_string(),
+                "organization"
+            )
+            .await,
+            Err(ErrorCode::ORG001)
+        ));
+    }
+}
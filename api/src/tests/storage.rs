@@ -0,0 +1,67 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use uuid::Uuid;
+
+    use crate::storage::{local::LocalStorage, s3::S3Storage, Storage};
+
+    #[actix_rt::test]
+    async fn test_local_storage_round_trip() {
+        let root = env::temp_dir().join(format!("planters-cycle-{}", Uuid::new_v4()));
+        let storage = LocalStorage::new(&root);
+
+        storage
+            .put("attachments/test", b"hello".to_vec(), "text/plain")
+            .await
+            .unwrap();
+        assert_eq!(storage.get("attachments/test").await.unwrap(), b"hello");
+
+        storage.delete("attachments/test").await.unwrap();
+        assert!(storage.get("attachments/test").await.is_err());
+        // Deleting a missing object is not an error
+        storage.delete("attachments/test").await.unwrap();
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[actix_rt::test]
+    async fn test_local_storage_rejects_escaping_keys() {
+        let storage = LocalStorage::new(env::temp_dir().join("planters-cycle"));
+
+        assert!(storage.get("../etc/passwd").await.is_err());
+        assert!(storage.get("/etc/passwd").await.is_err());
+        assert!(storage.put("", vec![], "text/plain").await.is_err());
+    }
+
+    #[doc = "Round trip against the MinIO of the docker compose file, run with
+`cargo test -- --ignored` after creating the bucket named by `S3_BUCKET`"]
+    #[actix_rt::test]
+    #[ignore = "needs a running MinIO"]
+    async fn test_s3_storage_round_trip() {
+        let var = |name: &str, default: &str| env::var(name).unwrap_or(default.to_owned());
+        let storage = S3Storage::new(
+            &var("S3_BUCKET", "planters-cycle"),
+            &var("S3_ENDPOINT", "http://localhost:9000"),
+            &var("S3_REGION", "us-east-1"),
+            &var("S3_ACCESS_KEY", "minioadmin"),
+            &var("S3_SECRET_KEY", "minioadmin"),
+        )
+        .unwrap();
+        let key = format!("tests/{}", Uuid::new_v4());
+
+        storage.health().await.unwrap();
+        storage
+            .put(&key, b"hello".to_vec(), "text/plain")
+            .await
+            .unwrap();
+        assert_eq!(storage.get(&key).await.unwrap(), b"hello");
+
+        storage.delete(&key).await.unwrap();
+        assert!(storage.get(&key).await.is_err());
+    }
+}
@@ -0,0 +1,28 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(all(test, feature = "otlp"))]
+mod tests {
+    use std::env;
+
+    use opentelemetry::trace::{Tracer, TracerProvider as _};
+
+    use crate::telemetry::otlp::provider;
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore = "needs a collector, start one with `docker compose --profile tracing up -d jaeger`"]
+    async fn test_spans_reach_local_collector() {
+        let endpoint =
+            env::var("OTEL_EXPORTER_OTLP_ENDPOINT").unwrap_or("http://localhost:4317".to_owned());
+        let provider = provider(&endpoint).unwrap();
+
+        provider
+            .tracer("planters_cycle")
+            .in_span("collector test", |_| {});
+        for result in provider.force_flush() {
+            result.unwrap();
+        }
+        provider.shutdown().unwrap();
+    }
+}
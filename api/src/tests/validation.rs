@@ -0,0 +1,140 @@
+/*
+ * Copyright (c) Johannes Grimm 2024.
+ */
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use serde_json::json;
+
+    use crate::model::{
+        dto::{
+            auth::RegisterRequest,
+            feeding::RecipeRequest,
+            organization::{MemberRequest, OrganizationRequest},
+            Genetic,
+        },
+        error::{ErrorCode, ErrorResponse},
+        validation::{is_email, Validate},
+    };
+
+    fn fields(error: ErrorCode) -> Vec<String> {
+        match error {
+            ErrorCode::VALIDATION(errors) => errors.into_keys().collect(),
+            other => panic!("Expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_is_email() {
+        for email in ["grower@example.com", "a.b+c@mail.example.org"] {
+            assert!(is_email(email), "{}", email);
+        }
+        for email in [
+            "",
+            "grower",
+            "@example.com",
+            "grower@",
+            "grower@example",
+            "a b@c.de",
+            "a@b@c.de",
+        ] {
+            assert!(!is_email(email), "{}", email);
+        }
+    }
+
+    #[test]
+    fn test_register_request() {
+        let request: RegisterRequest = serde_json::from_value(json!({
+            "displayName": "J",
+            "email": "not an address",
+            "password": "short",
+            "roles": [
+                {"roleId": 1, "assignedAt": "2024-06-01T00:00:00Z", "assignedBy": "nobody"},
+            ],
+        }))
+        .unwrap();
+        assert_eq!(
+            fields(request.validate().unwrap_err()),
+            ["displayName", "email", "password", "roles.0.assignedBy"]
+        );
+
+        let request: RegisterRequest = serde_json::from_value(json!({
+            "displayName": "Grower",
+            "email": "grower@example.com",
+            "password": "correct horse",
+        }))
+        .unwrap();
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn test_organization_requests() {
+        let request: OrganizationRequest = serde_json::from_value(json!({"name": "G"})).unwrap();
+        assert_eq!(fields(request.validate().unwrap_err()), ["name"]);
+        let request: OrganizationRequest =
+            serde_json::from_value(json!({"name": "Greenhouse"})).unwrap();
+        assert!(request.validate().is_ok());
+
+        let request: MemberRequest =
+            serde_json::from_value(json!({"userId": "nobody", "roleId": 0})).unwrap();
+        assert_eq!(
+            fields(request.validate().unwrap_err()),
+            ["roleId", "userId"]
+        );
+    }
+
+    #[test]
+    fn test_partial_models_only_check_given_fields() {
+        let genetic: Genetic = serde_json::from_value(json!({})).unwrap();
+        assert!(genetic.validate().is_ok());
+
+        let genetic: Genetic = serde_json::from_value(json!({
+            "name": " ",
+            "flowerDays": 0,
+            "code": "GELATO-41",
+            "generation": "F100",
+            "thcMax": 120.0,
+            "stretchFactor": 0.0,
+        }))
+        .unwrap();
+        assert_eq!(
+            fields(genetic.validate().unwrap_err()),
+            [
+                "code",
+                "flowerDays",
+                "generation",
+                "name",
+                "stretchFactor",
+                "thcMax"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_fields_are_prefixed() {
+        let recipe: RecipeRequest = serde_json::from_value(json!({
+            "name": "Bloom",
+            "items": [
+                {"productId": "p1", "dosePerLitre": 2.0},
+                {"productId": "p2", "dosePerLitre": 0.0},
+            ],
+        }))
+        .unwrap();
+        match recipe.validate() {
+            Err(ErrorCode::VALIDATION(errors)) => assert_eq!(
+                errors.get("items.1.dosePerLitre").map(String::as_str),
+                Some("Dose must be greater than 0")
+            ),
+            other => panic!("Expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validation_error_response() {
+        let recipe: RecipeRequest =
+            serde_json::from_value(json!({"name": "", "items": []})).unwrap();
+        let response = ErrorResponse::build(recipe.validate().unwrap_err());
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}